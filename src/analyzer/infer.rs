@@ -0,0 +1,301 @@
+//! Inference for `let` bindings without a type. A value made only of literals gets a type variable, which is
+//! checked as its literal type while the function it is in is analyzed. Then each use in the MIR of the function
+//! binds the variable to the type it expects, variables used as the same value are joined, and the ones nothing
+//! binds take the default type of their literals.
+use std::collections::HashMap;
+
+use crate::report::{Result, ReportKind};
+use crate::span::{Sp, Span};
+
+use super::{Analyzer, Node, Var, Type, ValId, cmp_ty, concretize, unify, fits, int_range};
+
+pub(super) struct TyVar {
+	link: Option<usize>, // the variable it was joined with, which stands for both
+	kind: Type, // Puint | Pint | Pbool | Pfloat, widened by the literals it is used with
+	ty:   Option<Type>, // what its uses bound it to
+	name: Option<String>, // of the binding it is the type of
+	span: Span,
+}
+
+impl Analyzer<'_> {
+	/// The type a value of `ty` is bound as, a literal type gets a new variable unless `hint` gives it a type
+	pub(super) fn infer_ty(&mut self, ty: &Type, hint: Option<&Type>, span: Span) -> Type {
+		match ty.base() {
+			ty if !ty.is_literal() => ty.clone(),
+			ty if hint.is_some_and(|h| !h.is_literal() && cmp_ty(ty, h)) => concretize(ty, hint),
+			ty @ Type::Infer(..) => ty.clone(),
+			kind => {
+				self.ty_vars.push(TyVar { link: None, kind: kind.clone(), ty: None, name: None, span });
+				Type::Infer(self.ty_vars.len() - 1, Box::new(kind.clone()))
+			},
+		}
+	}
+
+	/// Names the variable `ty` is after the binding it was made for, to report it by
+	pub(super) fn name_var(&mut self, ty: &Type, name: &Sp<&str>) {
+		let Type::Infer(id, _) = ty else { return };
+		let var = &mut self.ty_vars[*id];
+		if var.name.is_none() {
+			var.name = Some(name.elem.to_string());
+			var.span = name.span;
+		}
+	}
+
+	/// Binds the type variables in the body of a function by their uses, then gives each value the type its
+	/// variable ended up with
+	pub(super) fn settle(&mut self, args: &[(ValId, Type)], body: &mut [Node]) -> Result<()> {
+		let mut locals: HashMap<ValId, Type> = args.iter().cloned().collect();
+		locals.extend(body.iter().filter_map(|node| match node {
+			Node::Assign { id, ty, .. } => Some((*id, ty.clone())),
+			_ => None,
+		}));
+
+		let mut imms = Vec::new();
+		for node in body.iter() {
+			for (var, ty) in self.uses(node)? {
+				match var {
+					Var::Local(id) => if let Some(decl) = locals.get(id) { self.join(decl, &ty)?; },
+					Var::Imm(v) => if let Type::Infer(id, _) = ty.base() { imms.push((v, *id)); },
+					Var::FImm(_) | Var::Glob(_) => (),
+				}
+			}
+		}
+
+		// the literals only get checked against a variable once it has a type
+		for (v, id) in imms {
+			let ty = self.resolved(id);
+			if !fits(v, &ty) {
+				let err = ReportKind::TypeError
+					.title(format!("Literal '{v}' does not fit in a '{ty}'"))
+					.span(self.ty_vars[id].span);
+
+				return Err(match int_range(&ty) {
+					Some((min, max)) => err.label(format!("it holds {min} to {max}")),
+					None => err,
+				}.into());
+			}
+		}
+
+		for node in body.iter_mut() {
+			for ty in types(node) {
+				*ty = self.settled(ty);
+			}
+		}
+		Ok(())
+	}
+
+	/// The values `node` reads, with the type each is read as. Joins the types that have to be the same on the way
+	fn uses<'n>(&mut self, node: &'n Node) -> Result<Vec<(&'n Var, Type)>> {
+		Ok(match node {
+			Node::Assign { ty, val, .. } => match &**val {
+				Node::Var(v) => vec![(v, ty.clone())],
+				Node::BinOp { op, ty: oty, lhs, rhs } => {
+					if !op.is_cmp() { self.join(ty, oty)?; }
+					vec![(lhs, oty.clone()), (rhs, oty.clone())]
+				},
+				Node::UnOp { ty: oty, val, .. } => {
+					self.join(ty, oty)?;
+					vec![(val, oty.clone())]
+				},
+				Node::Index { ty: aty, val, index } => {
+					if let Type::Arr(elem, _) = aty.base() { self.join(ty, elem)?; }
+					vec![(val, aty.clone()), (index, Type::Usize)]
+				},
+				Node::Unwrap { ty: oty, val } => {
+					if let Type::Opt(inner) = oty.base() { self.join(ty, inner)?; }
+					vec![(val, oty.clone())]
+				},
+				Node::AddrOf(v) => match ty.base() {
+					Type::Ptr(pointee) => vec![(v, (**pointee).clone())],
+					_ => Vec::new(),
+				},
+				Node::Deref(v) => vec![(v, Type::Ptr(Box::new(ty.clone())))],
+				Node::Offset { ty: pty, ptr, index, .. } => {
+					self.join(ty, pty)?;
+					vec![(ptr, pty.clone()), (&index.0, index.1.clone())]
+				},
+				Node::Diff { ty: pty, lhs, rhs } => vec![(lhs, pty.clone()), (rhs, pty.clone())],
+				Node::FuncCall { args, .. } | Node::StructLit(args) | Node::ArrLit(args)
+					=> args.iter().map(|(v, t)| (v, t.clone())).collect(),
+				Node::EnumLit { val, .. } | Node::OptLit(val) => val.iter().map(|(v, t)| (v, t.clone())).collect(),
+				Node::Field { ty: sty, val, deref, .. } => match deref {
+					true  => vec![(val, Type::Ptr(Box::new(sty.clone())))],
+					false => vec![(val, sty.clone())],
+				},
+				Node::Tag { ty: oty, val } | Node::Payload { ty: oty, val, .. } | Node::IsSome { ty: oty, val }
+					| Node::Decay { ty: oty, val } | Node::Cast { ty: oty, val } => vec![(val, oty.clone())],
+				Node::SliceLit { len, .. } => vec![(len, Type::Usize)],
+				Node::Len(_) | Node::StrLit(_) => Vec::new(),
+				Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. } | Node::Assign { .. }
+					| Node::Global { .. } | Node::Store { .. } | Node::StoreIndex { .. } | Node::StoreField { .. }
+					| Node::Ret(..) | Node::Trap(_) | Node::Label(_) | Node::Jump(_) | Node::Branch { .. }
+					=> unreachable!("not the value of an Assign"),
+			},
+			Node::Store { to, from: (v, ty), deref } => match deref {
+				true  => vec![(v, ty.clone()), (to, Type::Ptr(Box::new(ty.clone())))],
+				false => vec![(v, ty.clone()), (to, ty.clone())],
+			},
+			Node::StoreIndex { to: (to, aty), index, from: (v, ty) } => {
+				if let Type::Arr(elem, _) = aty.base() { self.join(ty, elem)?; }
+				vec![(to, aty.clone()), (index, Type::Usize), (v, ty.clone())]
+			},
+			Node::StoreField { from: (v, ty), .. } | Node::Ret(Some(v), ty) | Node::Branch { cond: (v, ty), .. } => vec![(v, ty.clone())],
+			Node::FuncCall { args, .. } => args.iter().map(|(v, t)| (v, t.clone())).collect(),
+			Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. } | Node::Global { .. }
+				| Node::Ret(None, _) | Node::Trap(_) | Node::Label(_) | Node::Jump(_) | Node::BinOp { .. }
+				| Node::UnOp { .. } | Node::StructLit(_) | Node::Field { .. } | Node::EnumLit { .. } | Node::Tag { .. }
+				| Node::Payload { .. } | Node::OptLit(_) | Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_)
+				| Node::Deref(_) | Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. } | Node::Len(_)
+				| Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. } | Node::StrLit(_) | Node::Var(_) => Vec::new(),
+		})
+	}
+
+	/// Makes the types `a` and `b` of the same value agree, binding the variables in them
+	fn join(&mut self, a: &Type, b: &Type) -> Result<()> {
+		match (a.base(), b.base()) {
+			(Type::Infer(x, k1), Type::Infer(y, k2)) => {
+				self.widen(*x, k1)?;
+				self.widen(*y, k2)?;
+				self.link(*x, *y)
+			},
+			(Type::Infer(x, kind), ty) | (ty, Type::Infer(x, kind)) => {
+				self.widen(*x, kind)?;
+				match ty.is_literal() {
+					true  => self.widen(*x, ty),
+					false => self.bind(*x, ty),
+				}
+			},
+			(Type::Ptr(a) | Type::Arr(a, _), Type::Ptr(b) | Type::Arr(b, _)) | (Type::Opt(a), Type::Opt(b)) => self.join(a, b),
+			_ => Ok(()),
+		}
+	}
+
+	fn root(&self, mut id: usize) -> usize {
+		while let Some(link) = self.ty_vars[id].link { id = link; }
+		id
+	}
+
+	/// The variable `id` is used with a literal of `kind`, like `{int}` once it is added to a negative one
+	fn widen(&mut self, id: usize, kind: &Type) -> Result<()> {
+		let root = self.root(id);
+		let Some(wide) = unify(&self.ty_vars[root].kind, kind) else {
+			return self.conflict(id, &self.ty_vars[root].kind.clone(), kind);
+		};
+
+		self.ty_vars[root].kind = wide;
+		self.check(id)
+	}
+
+	fn bind(&mut self, id: usize, ty: &Type) -> Result<()> {
+		let root = self.root(id);
+		match &self.ty_vars[root].ty {
+			Some(bound) if bound != ty => self.conflict(id, &bound.clone(), ty),
+			Some(_) => Ok(()),
+			None => {
+				self.ty_vars[root].ty = Some(ty.clone());
+				self.check(id)
+			},
+		}
+	}
+
+	fn link(&mut self, x: usize, y: usize) -> Result<()> {
+		let (x, y) = (self.root(x), self.root(y));
+		if x == y { return Ok(()); }
+
+		let (kind, ty) = {
+			let var = &self.ty_vars[y];
+			(var.kind.clone(), var.ty.clone())
+		};
+		self.ty_vars[y].link = Some(x);
+		self.widen(x, &kind)?;
+		match ty {
+			Some(ty) => self.bind(y, &ty),
+			None => Ok(()),
+		}
+	}
+
+	/// The type a variable is bound to has to take its literals
+	fn check(&self, id: usize) -> Result<()> {
+		let var = &self.ty_vars[self.root(id)];
+		match &var.ty {
+			Some(ty) if !cmp_ty(&var.kind, ty) => self.conflict(id, ty, &var.kind),
+			_ => Ok(()),
+		}
+	}
+
+	fn conflict(&self, id: usize, a: &Type, b: &Type) -> Result<()> {
+		// the variable of a binding stands for the values it was joined with
+		let var = match self.ty_vars[id].name {
+			Some(_) => &self.ty_vars[id],
+			None => &self.ty_vars[self.root(id)],
+		};
+
+		let (title, help) = match &var.name {
+			Some(name) => (
+				format!("Cannot infer the type of '{name}'"),
+				format!("Give it a type, like 'let {name}: {a} = ...', and cast it where another is expected"),
+			),
+			None => (String::from("Cannot infer the type of this value"), String::from("Cast it where another type is expected")),
+		};
+
+		ReportKind::TypeError
+			.title(title)
+			.label(format!("it is used as a '{a}' and as a '{b}'"))
+			.span(var.span)
+			.help(help)
+			.as_err()
+	}
+
+	/// What the variable `id` is bound to, or the default of its literals when nothing binds it
+	fn resolved(&self, id: usize) -> Type {
+		let var = &self.ty_vars[self.root(id)];
+		var.ty.clone().unwrap_or_else(|| concretize(&var.kind, None))
+	}
+
+	/// `ty` with its variables replaced by what they resolved to
+	fn settled(&self, ty: &Type) -> Type {
+		match ty {
+			Type::Infer(id, _) => self.resolved(*id),
+			Type::Ptr(ty) => Type::Ptr(Box::new(self.settled(ty))),
+			Type::Mut(ty) => Type::Mut(Box::new(self.settled(ty))),
+			Type::Opt(ty) => Type::Opt(Box::new(self.settled(ty))),
+			Type::Arr(ty, n) => Type::Arr(Box::new(self.settled(ty)), *n),
+			ty => ty.clone(),
+		}
+	}
+}
+
+/// `ty` with the variables in it given the default of their literals, for where a type is needed before they are bound
+pub(super) fn defaulted(ty: &Type) -> Type {
+	match ty {
+		Type::Infer(_, kind) => concretize(kind, None),
+		Type::Ptr(ty) => Type::Ptr(Box::new(defaulted(ty))),
+		Type::Mut(ty) => Type::Mut(Box::new(defaulted(ty))),
+		Type::Opt(ty) => Type::Opt(Box::new(defaulted(ty))),
+		Type::Arr(ty, n) => Type::Arr(Box::new(defaulted(ty)), *n),
+		ty => ty.clone(),
+	}
+}
+
+/// Every type written in `node`
+fn types(node: &mut Node) -> Vec<&mut Type> {
+	match node {
+		Node::Assign { ty, val, .. } => {
+			let mut tys = types(val);
+			tys.push(ty);
+			tys
+		},
+		Node::Store { from: (_, ty), .. } | Node::Ret(_, ty) | Node::Branch { cond: (_, ty), .. }
+			| Node::BinOp { ty, .. } | Node::UnOp { ty, .. } | Node::Field { ty, .. } | Node::Tag { ty, .. }
+			| Node::Payload { ty, .. } | Node::IsSome { ty, .. } | Node::Unwrap { ty, .. } | Node::Index { ty, .. }
+			| Node::Decay { ty, .. } | Node::Cast { ty, .. } | Node::Diff { ty, .. } | Node::Global { ty, .. } => vec![ty],
+		Node::StoreIndex { to: (_, to), from: (_, ty), .. } | Node::StoreField { to: (_, to), from: (_, ty), .. }
+			| Node::Offset { ty: to, index: (_, ty), .. } => vec![to, ty],
+		Node::FuncCall { args, .. } | Node::StructLit(args) | Node::ArrLit(args)
+			=> args.iter_mut().map(|(_, ty)| ty).collect(),
+		Node::EnumLit { val, .. } | Node::OptLit(val) => val.iter_mut().map(|(_, ty)| ty).collect(),
+		Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. } | Node::Trap(_)
+			| Node::Label(_) | Node::Jump(_) | Node::AddrOf(_) | Node::Deref(_) | Node::Len(_) | Node::SliceLit { .. }
+			| Node::StrLit(_) | Node::Var(_) => Vec::new(),
+	}
+}
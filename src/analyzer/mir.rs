@@ -1,6 +1,9 @@
 use std::fmt;
 use colored::Colorize;
 use crate::bigint::IBig;
+use crate::span::Span;
+
+pub use crate::parser::ast::{BinOp, UnOp};
 
 #[derive(Clone, Copy, Default, Debug, Eq, Hash, PartialEq)]
 pub struct ValId(pub u64);
@@ -10,45 +13,165 @@ impl std::ops::Deref for ValId { // FIXME: prob not needed
 	{ &self.0 }
 }
 
+#[derive(Clone, Copy, Default, Debug, Eq, Hash, PartialEq)]
+pub struct LabelId(pub u64); // unique across the whole module
+
+#[derive(Clone, Copy, Default, Debug, Eq, Hash, PartialEq)]
+pub struct TypeId(pub u64);
+
+/// Where a declaration is, the backends have no finer spans to report what they cannot lower at
+#[derive(Clone, Copy, Debug)]
+pub struct Loc {
+	pub file: &'static str,
+	pub span: Span, // of the name
+}
+
 pub enum Node {
 	Func {
 		id:      ValId,
 		export:  bool, // TODO: perhaps remove, the id can be checked in the sym table
 		args:    Vec<(ValId, Type)>, // type cant be Void, Never
 		ret:     Type,
-		body:    Vec<Node>, // Assign | Global | Ret | FuncCall
+		body:    Vec<Self>, // Assign | Global | Ret | FuncCall | Label | Jump | Branch
+		loc:     Loc,
 	},
 	FuncDecl {
 		id:   ValId,
 		args: Vec<Type>,
 		ret:  Type,
+		loc:  Loc,
+	},
+	Struct { // comes before anything using it
+		id:     TypeId,
+		fields: Vec<Type>,
+		loc:    Loc,
+	},
+	Enum { // comes before anything using it
+		id:       TypeId,
+		variants: Vec<Option<Type>>, // the payload of each variant, the tag is the index
+		loc:      Loc,
 	},
 	Assign {
 		id:  ValId,
 		ty:  Type, // type cant be Void, Never
-		val: Box<Node>, // FuncCall | Var
+		val: Box<Self>, // FuncCall | Var | Global | BinOp | UnOp | StructLit | Field | EnumLit | Tag | Payload
+		                // | OptLit | IsSome | Unwrap | AddrOf | Deref | Offset | Diff | ArrLit | Index | Len | Decay | SliceLit
+		                // | Cast
 	},
 	Global {
 		id:  ValId,
 		ty:  Type,
-		val: Box<Node>, // StrLit | Var::Imm | Var::Glob
+		val: Box<Self>, // StrLit | Var::Imm | Var::Glob
 	},
-	// TODO: actually use this lol, @newguy do stores pls
-	Store { // TODO: maybe make work with other types than ptr
-		to:   Var, // Var::Local | Var::Glob
-		from: (Var, Type), // Var::Local | Var::Glob | Var::Imm
+	Store { // writes to a variable declared by an earlier Assign, or an arg
+		to:    Var, // Var::Local, or any pointer when `deref`
+		from:  (Var, Type), // Var::Local | Var::Glob | Var::Imm | Var::FImm
+		deref: bool, // `to` holds a pointer, which is written through instead
+	},
+	StoreIndex { // writes an element of an array declared by an earlier Assign, or of what a slice points to
+		to:    (Var, Type), // the array or slice
+		index: Var, // usize, checked against the length already
+		from:  (Var, Type),
+	},
+	StoreField { // writes a field of a struct declared by an earlier Assign, or of what a pointer points to
+		to:    (Var, Type), // the struct or the pointer to it, and the type of the struct
+		index: usize,
+		from:  (Var, Type),
+		deref: bool, // `to` is a pointer to the struct
 	},
 	Ret(Option<Var>, Type),
+	Trap(&'static str), // stops the program when a check fails, the reason is only used by the interpreter
+	Label(LabelId), // starts a new block, control may fall into it
+	Jump(LabelId),
+	Branch {
+		cond: (Var, Type), // bool
+		then: LabelId,
+		els:  LabelId,
+	},
 	FuncCall {
 		id: Var, // Var::Local | Var::Glob
 		args: Vec<(Var, Type)>,
 	},
+	BinOp { // only as the value of an Assign
+		op:  BinOp,
+		ty:  Type, // of the operands, the result is a bool for comparisons
+		lhs: Var,
+		rhs: Var,
+	},
+	UnOp { // only as the value of an Assign
+		op:  UnOp,
+		ty:  Type,
+		val: Var,
+	},
+	StructLit(Vec<(Var, Type)>), // only as the value of an Assign, in field order
+	Field { // only as the value of an Assign
+		ty:    Type, // of the struct
+		val:   Var,
+		index: usize,
+		deref: bool, // `val` is a pointer to the struct
+	},
+	EnumLit { // only as the value of an Assign
+		variant: usize,
+		val:     Option<(Var, Type)>,
+	},
+	Tag { // only as the value of an Assign, always a u32
+		ty:  Type, // of the enum
+		val: Var, // Var::Local
+	},
+	Payload { // only as the value of an Assign, the tag has to be checked first
+		ty:      Type, // of the enum
+		val:     Var, // Var::Local
+		variant: usize,
+	},
+	OptLit(Option<(Var, Type)>), // only as the value of an Assign, `none` or a value wrapped into an optional
+	IsSome { // only as the value of an Assign, a bool of the Assign's type
+		ty:  Type, // of the optional
+		val: Var, // Var::Local
+	},
+	Unwrap { // only as the value of an Assign, it has to be checked with IsSome first
+		ty:  Type, // of the optional
+		val: Var, // Var::Local
+	},
+	AddrOf(Var), // only as the value of an Assign, Var::Local | Var::Glob
+	Deref(Var), // only as the value of an Assign, reads a value of the Assign's type through the pointer
+	Offset { // only as the value of an Assign, moves a pointer by a number of the values it points to
+		ty:    Type, // of the pointer
+		ptr:   Var,
+		op:    BinOp, // Add | Sub
+		index: (Var, Type), // int
+	},
+	Diff { // only as the value of an Assign, the isize number of values `rhs` is before `lhs`
+		ty:  Type, // of the pointers
+		lhs: Var,
+		rhs: Var,
+	},
+	ArrLit(Vec<(Var, Type)>), // only as the value of an Assign
+	Index { // only as the value of an Assign, reads an element
+		ty:    Type, // of the array or slice
+		val:   Var, // Var::Local | Var::Glob
+		index: Var, // usize, checked against the length already
+	},
+	Len(Var), // only as the value of an Assign, the usize length of a slice
+	Decay { // only as the value of an Assign, a pointer to the first element
+		ty:  Type, // of the array or slice
+		val: Var, // Var::Local | Var::Glob
+	},
+	SliceLit { // only as the value of an Assign
+		ptr: Var,
+		len: Var, // usize
+	},
+	Cast { // only as the value of an Assign, converts the value to the Assign's type
+		ty:  Type, // of the value, a number or pointer
+		val: Var,
+	},
 	StrLit(String), // ?!
 	Var(Var), // ?!
 }
 
+#[derive(Clone)]
 pub enum Var {
 	Imm(IBig),
+	FImm(f64),
 	Local(ValId),
 	Glob(ValId),
 }
@@ -57,20 +180,76 @@ pub enum Var {
 pub enum Type {
 	U(u32), I(u32), B(u32), F(u32),
 	Usize, Isize,
-	Puint, Pint, Pbool, Pfloat,
+	Puint, Pint, Pbool,
+	Pfloat,
 	Void, Never,
-	Ptr(Box<Type>),
-	Arr(Box<Type>, Option<u64>),
-	Mut(Box<Type>),
-	Opt(Box<Type>),
-	Fn(Vec<Type>, Box<Type>),
-	// Ident(,
+	Ptr(Box<Self>),
+	Arr(Box<Self>, Option<u64>),
+	Mut(Box<Self>),
+	Opt(Box<Self>),
+	Fn(Vec<Self>, Box<Self>),
+	Struct(TypeId, String),
+	Enum(TypeId, String),
+	Infer(usize, Box<Self>), // a value of literals whose type comes from its uses, with the literal type it has so far
+}
+
+impl Type {
+	/// `opt *T` is null when it is none, so it needs no tag
+	pub fn has_niche(&self) -> bool {
+		matches!(self.base(), Self::Opt(ty) if matches!(ty.base(), Self::Ptr(_)))
+	}
+
+	/// Strips the outer `mut`, values keep the representation of what they wrap
+	pub fn base(&self) -> &Self {
+		match self {
+			Self::Mut(ty) => ty.base(),
+			ty => ty,
+		}
+	}
+
+	/// The literal type behind a type variable, which is what it is checked as until its function is analyzed
+	pub fn kind(&self) -> &Self {
+		match self.base() {
+			Self::Infer(_, kind) => kind,
+			ty => ty,
+		}
+	}
+
+	pub fn is_literal(&self) -> bool {
+		matches!(self.kind(), Self::Puint | Self::Pint | Self::Pbool | Self::Pfloat)
+	}
+
+	pub fn is_int(&self) -> bool {
+		matches!(self.kind(), Self::U(_) | Self::I(_) | Self::Usize | Self::Isize | Self::Puint | Self::Pint)
+	}
+
+	pub fn is_signed(&self) -> bool {
+		matches!(self.kind(), Self::I(_) | Self::Isize | Self::Pint)
+	}
+
+	pub fn is_float(&self) -> bool {
+		matches!(self.kind(), Self::F(_) | Self::Pfloat)
+	}
+
+	pub fn is_bool(&self) -> bool {
+		matches!(self.kind(), Self::B(_) | Self::Pbool)
+	}
+}
+
+impl Node {
+	/// Where a declaration is, the nodes of a body have no place of their own
+	pub fn loc(&self) -> Option<Loc> {
+		match self {
+			Self::Func { loc, .. } | Self::FuncDecl { loc, .. } | Self::Struct { loc, .. } | Self::Enum { loc, .. } => Some(*loc),
+			_ => None,
+		}
+	}
 }
 
 impl fmt::Display for Node {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
-			Self::Func { id, export, args, ret, body } => {
+			Self::Func { id, export, args, ret, body, .. } => {
 				if *export { write!(f, "{} ", "export".yellow().dimmed())?; }
 
 				write!(f, "{} {}(", "fn".yellow().dimmed(), **id)?;
@@ -93,7 +272,7 @@ impl fmt::Display for Node {
 				}
 				write!(f, "}}")
 			},
-			Self::FuncDecl { id, args, ret } => {
+			Self::FuncDecl { id, args, ret, .. } => {
 				write!(f, "{} {}(", "fn".yellow().dimmed(), **id)?;
 
 				for (i, ty) in args.iter().enumerate() {
@@ -103,12 +282,37 @@ impl fmt::Display for Node {
 
 				write!(f, ") {ret}")
 			},
+			Self::Struct { id, fields, .. } => {
+				write!(f, "{} {id} {{ ", "struct".yellow().dimmed())?;
+				fields.iter().try_for_each(|ty| write!(f, "{ty}, "))?;
+				write!(f, "}}")
+			},
+			Self::Enum { id, variants, .. } => {
+				write!(f, "{} {id} {{ ", "enum".yellow().dimmed())?;
+				variants.iter().try_for_each(|ty| match ty {
+					Some(ty) => write!(f, "{ty}, "),
+					None     => write!(f, "_, "),
+				})?;
+				write!(f, "}}")
+			},
 			Self::Assign { id, ty, val } => write!(f, "%{}: {ty} = {val}", **id),
-			Self::Store { to, from: (from, ty) } 
+			Self::Store { to, from: (from, ty), deref: true } 
+				=> write!(f, "store {ty} {from}, {} @{to}", "ptr".yellow().dimmed()),
+			Self::Store { to, from: (from, ty), .. } 
 				=> write!(f, "store {ty} {from}, {} {to}", "ptr".yellow().dimmed()),
+			Self::StoreIndex { to: (to, aty), index, from: (from, ty) }
+				=> write!(f, "store {ty} {from}, {to}: {aty}[{index}]"),
+			Self::StoreField { to: (to, sty), index, from: (from, ty), deref: true }
+				=> write!(f, "store {ty} {from}, {to}->{index}: {sty}"),
+			Self::StoreField { to: (to, sty), index, from: (from, ty), .. }
+				=> write!(f, "store {ty} {from}, {to}.{index}: {sty}"),
 			Self::Global { id, ty, val } => write!(f, "@{}: {ty} = {val}", **id),
 			Self::Ret(Some(v), ty) => write!(f, "ret {v}: {ty}"),
 			Self::Ret(None, ty) => write!(f, "ret {ty}"),
+			Self::Trap(why) => write!(f, "trap {why:?}"),
+			Self::Label(l) => write!(f, "{}", format!("{l}:").yellow()),
+			Self::Jump(l)  => write!(f, "jmp {l}"),
+			Self::Branch { cond: (v, ty), then, els } => write!(f, "br {v}: {ty}, {then}, {els}"),
 			Self::FuncCall { id, args } => {
 				write!(f, "{id}(")?;
 				for (i, (v, ty)) in args.iter().enumerate() {
@@ -117,6 +321,37 @@ impl fmt::Display for Node {
 				}
 				write!(f, ")")
 			},
+			Self::BinOp { op, ty, lhs, rhs } => write!(f, "{lhs} {op} {rhs}: {ty}"),
+			Self::UnOp { op, ty, val } => write!(f, "{op}{val}: {ty}"),
+			Self::StructLit(fields) => {
+				write!(f, "{{ ")?;
+				fields.iter().try_for_each(|(v, ty)| write!(f, "{v}: {ty}, "))?;
+				write!(f, "}}")
+			},
+			Self::Field { ty, val, index, deref: true } => write!(f, "{val}->{index}: {ty}"),
+			Self::Field { ty, val, index, .. } => write!(f, "{val}.{index}: {ty}"),
+			Self::EnumLit { variant, val: Some((v, ty)) } => write!(f, "#{variant}({v}: {ty})"),
+			Self::EnumLit { variant, val: None } => write!(f, "#{variant}"),
+			Self::Tag { ty, val } => write!(f, "tag {val}: {ty}"),
+			Self::Payload { ty, val, variant } => write!(f, "{val}#{variant}: {ty}"),
+			Self::OptLit(Some((v, ty))) => write!(f, "some({v}: {ty})"),
+			Self::OptLit(None) => write!(f, "{}", "none".yellow().dimmed()),
+			Self::IsSome { ty, val } => write!(f, "is_some {val}: {ty}"),
+			Self::Unwrap { ty, val } => write!(f, "{val}?: {ty}"),
+			Self::AddrOf(v) => write!(f, "&{v}"),
+			Self::Deref(v)  => write!(f, "@{v}"),
+			Self::Offset { ty, ptr, op, index: (i, ity) } => write!(f, "{ptr}: {ty} {op} {i}: {ity}"),
+			Self::Diff { ty, lhs, rhs } => write!(f, "{lhs} - {rhs}: {ty}"),
+			Self::ArrLit(elems) => {
+				write!(f, "[ ")?;
+				elems.iter().try_for_each(|(v, ty)| write!(f, "{v}: {ty}, "))?;
+				write!(f, "]")
+			},
+			Self::Index { ty, val, index } => write!(f, "{val}: {ty}[{index}]"),
+			Self::Len(v) => write!(f, "len {v}"),
+			Self::Decay { ty, val } => write!(f, "decay {val}: {ty}"),
+			Self::SliceLit { ptr, len } => write!(f, "slice {ptr}, {len}"),
+			Self::Cast { ty, val } => write!(f, "cast {val}: {ty}"),
 			Self::StrLit(s) => write!(f, "{}", format!("{s:?}").green()),
 			Self::Var(v)    => write!(f, "{}", v.to_string().cyan()),
 		}
@@ -140,9 +375,11 @@ impl fmt::Display for Type {
 			Self::Never   => String::from("never"),
 			Self::Ptr(ty) => format!("*{ty}"),
 			Self::Arr(ty, None) => format!("[{ty}]"),
-			Self::Arr(ty, Some(n)) => format!("[{ty}; {n}]"),
+			Self::Arr(ty, Some(n)) => format!("[{ty}:{n}]"),
 			Self::Mut(ty) => format!("mut {ty}"),
 			Self::Opt(ty) => format!("opt {ty}"),
+			Self::Struct(_, name) | Self::Enum(_, name) => name.clone(),
+			Self::Infer(_, kind) => return write!(f, "{kind}"),
 			Self::Fn(args, ret) => {
 				write!(f, "{}(", "fn".yellow().dimmed())?;
 				for (i, ty) in args.iter().enumerate() {
@@ -160,12 +397,23 @@ impl fmt::Display for Var {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
 			Self::Imm(v)    => write!(f, "{}", v.to_string().cyan()),
+			Self::FImm(v)   => write!(f, "{}", format!("{v:?}").cyan()),
 			Self::Local(id) => write!(f, "%{}", **id),
 			Self::Glob(id)  => write!(f, "@{}", **id),
 		}
 	}
 }
 
+impl fmt::Display for LabelId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result 
+	{ write!(f, "L{}", self.0) }
+}
+
+impl fmt::Display for TypeId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result 
+	{ write!(f, "s{}", self.0) }
+}
+
 impl fmt::Display for ValId {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result 
 	{ write!(f, "{}", self.0) }
@@ -1,22 +1,84 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
 
-use crate::report::{Result, ReportKind, LogHandler};
-use crate::span::Sp;
+use crate::report::{self, Result, Report, ReportKind, LogHandler};
+use crate::span::{Sp, Span};
+use crate::bigint::IBig;
 use crate::parser::ast;
+use crate::module::Module;
 
 pub mod mir;
-use mir::{Node, ValId, Var, Type};
+mod mono;
+mod overload;
+mod infer;
+use mir::{Node, ValId, LabelId, TypeId, Loc, Var, Type, BinOp, UnOp};
 
 #[derive(Default)]
-pub struct Analyzer {
+pub struct Analyzer<'src> {
 	scope:   Vec<Scope>,
 	symbols: HashMap<ValId, String>,
+	labels:  LabelId,
+	loops:   Vec<(LabelId, LabelId)>, // (continue, break) of the enclosing loops
+	diverged: Option<Span>, // the statement control cannot get past, once the current path has ended
+	types:   HashMap<String, TypeDef>,
+	tyacc:   TypeId,
+	impl_ty: Option<String>, // the type whose impl block is being analyzed
+	aliases: HashMap<&'src str, Sp<ast::Type<'src>>>,
+	bad_aliases: HashSet<&'src str>, // an error was given for them, their uses only point back at it
+
+	generic_fns: HashMap<&'src str, (&'static str, Sp<ast::Node<'src>>)>, // instantiated when called, with their file
+	generic_tys: HashMap<&'src str, (&'static str, Sp<ast::Node<'src>>)>, // instantiated when named, with their file
+	ty_args:     Vec<(&'src str, Type)>, // the params of the generic being instantiated
+	inst_args:   HashMap<TypeId, (&'src str, Vec<Type>)>, // what each instantiated type was made from
+	instances:   Vec<Node>, // made while analyzing the current root, they come before it
+	pending:     Vec<mono::Pending<'src>>, // instances whose body is analyzed after the current root
+	inst_depth:  usize,
+	overloads:   HashMap<&'src str, usize>, // how many functions share each name
+	warnings:    Vec<Report>, // logged once the root they are in is analyzed
+	declared:    HashMap<(&'static str, Span), ValId>, // the functions whose body is yet to be analyzed, by where their name is
+	ty_spans:    HashMap<TypeId, (&'static str, Span)>, // where each type is named
+	file:        &'static str, // of the root being analyzed
+	func:        Option<ValId>, // whose body is being analyzed
+	calls:       HashMap<ValId, HashSet<ValId>>, // the functions each function uses, they count once it is used itself
+	bounds_checks: bool, // whether indexes are checked at runtime
+	ty_vars:     Vec<infer::TyVar>, // of the literals whose type comes from their uses
+}
+
+type Variants = Vec<(String, Option<Type>)>;
+
+#[derive(Debug)]
+enum TypeDef {
+	Struct {
+		id:     TypeId,
+		fields: Vec<(String, Type)>,
+	},
+	Enum {
+		id:       TypeId,
+		variants: Variants,
+	},
+}
+
+impl TypeDef {
+	fn id(&self) -> TypeId {
+		match self {
+			Self::Struct { id, .. } | Self::Enum { id, .. } => *id,
+		}
+	}
+
+	fn ty(&self, name: &str) -> Type {
+		match self {
+			Self::Struct { id, .. } => Type::Struct(*id, name.to_string()),
+			Self::Enum { id, .. }   => Type::Enum(*id, name.to_string()),
+		}
+	}
 }
 
 #[derive(Default, Debug)]
 struct Scope {
 	idacc:  ValId,
 	locals: Vec<(ValId, String, Type)>, 
+	decls:  HashMap<ValId, Span>, // where the named locals were declared
+	used:   HashSet<ValId>, // the locals read so far
 }
 
 impl Scope {
@@ -26,7 +88,7 @@ impl Scope {
 	}
 }
 
-impl Analyzer {
+impl<'src> Analyzer<'src> {
 	#[inline]
 	fn push_new_scope(&mut self) {
 		self.scope.push(Scope::default());
@@ -37,6 +99,20 @@ impl Analyzer {
 		self.scope.pop();
 	}
 
+	/// Blocks share the id counter of their function, so the ids stay unique within it
+	#[inline]
+	fn push_block_scope(&mut self) {
+		let idacc = self.peek_scope().idacc;
+		self.scope.push(Scope { idacc, ..Default::default() });
+	}
+
+	#[inline]
+	fn pop_block_scope(&mut self) {
+		self.check_unused(&[]);
+		let idacc = self.scope.pop().unwrap().idacc;
+		self.peek_scope_mut().idacc = idacc;
+	}
+
 	#[inline]
 	fn peek_scope_mut(&mut self) -> &mut Scope {
 		self.scope.last_mut().unwrap()
@@ -64,302 +140,2566 @@ impl Analyzer {
 			scope.locals.iter().rev().find(|(i,n,t)| f((*i,n,t))).cloned().map(|v| (d, v)))
 	}
 
-	pub fn analyze(ast: Vec<Sp<ast::Node>>, file: &'static str, handler: &LogHandler) -> (Vec<Node>, HashMap<ValId, String>) {
+	pub fn analyze(modules: Vec<Module<'src>>, bounds_checks: bool, handler: &LogHandler) -> (Vec<Node>, HashMap<ValId, String>) {
+		let errors = report::ERR_COUNT.load(Ordering::Relaxed);
 		let mut analyzer = Self {
 			scope: vec![Scope::default()],
-			symbols: HashMap::new(),
+			bounds_checks,
+			..Default::default()
 		};
 
-		(ast.into_iter().fold(Vec::new(), |mut acc, node| {
-			match analyzer.analyze_root(node) {
-				Ok(n)  => acc.push(n),
-				Err(e) => {
-					handler.log(e.file(file));
-					analyzer.scope.truncate(1);
+		// the globals of every module share one namespace, each node keeps the file it is from for its reports
+		let ast: Vec<(&'static str, Sp<ast::Node>)> = modules.into_iter()
+			.flat_map(|m| m.ast.into_iter().map(move |n| (m.file, n)))
+			.collect();
+
+		// aliases are collected up front, so they can be used before they are declared,
+		// and overloads are counted so every one of them is mangled the same way
+		for (file, node) in &ast {
+			match &node.elem {
+				ast::Node::Alias { name, .. } if analyzer.aliases.contains_key(name.elem) => {
+					handler.log(ReportKind::TypeError
+						.title(format!("Type '{}' is already defined", **name))
+						.span(name.span)
+						.file(file));
 				},
-			}; acc
-		}), analyzer.symbols)
-	}
+				ast::Node::Alias { name, ty, .. } => { analyzer.aliases.insert(name.elem, ty.clone()); },
+				ast::Node::Func { name, generics, .. } if generics.is_empty()
+					=> *analyzer.overloads.entry(name.elem).or_default() += 1,
+				_ => (),
+			}
+		}
 
-	fn analyze_root(&mut self, node: Sp<ast::Node>) -> Result<Node> {
-		Ok(match node.elem {
-			ast::Node::Func { name, args, ret, attrs, body } 
-				if attrs.iter().any(|a| matches!(**a, ast::Attrs::Extern)) => {
-				if !body.is_empty() {
-					return ReportKind::SyntaxError // TODO: maybe not SyntaxError
-						.title("Extern functions cannot have a body")
-						.span(node.span)
-						.as_err();
-				}
+		// everything global is declared before any body is analyzed, so it can be used before it appears:
+		// generics and type names first, then the fields of the types, then the signatures of the functions
+		let mut types = Vec::new();
+		let mut funcs = Vec::new();
+		let mut aliases = Vec::new();
+		let mut unused = Vec::new(); // the functions to warn about if nothing calls them
+		for (file, node) in ast {
+			analyzer.file = file;
+			match node.elem {
+				ast::Node::Alias { name, ty, .. } => aliases.push((file, name, ty)),
+				_ if mono::is_generic(&node) => {
+					if matches!(node.elem, ast::Node::Func { .. }) { unused.extend(analyzer.unused_candidate(file, &node)); }
+					if let Err(e) = analyzer.declare_generic(node) { handler.log(e.file(file)); }
+				},
+				ast::Node::Struct { .. } | ast::Node::Enum { .. } => match analyzer.declare_type(&node) {
+					Ok(())  => types.push((file, node)),
+					Err(e) => handler.log(e.file(file)),
+				},
+				_ => funcs.push((file, node)),
+			}
+		}
 
-				if attrs.iter().any(|a| matches!(**a, ast::Attrs::Export)) {
-					return ReportKind::SyntaxError
-						.title("Extern functions cannot be exported")
-						.span(node.span)
-						.as_err();
+		// checked once every type is named, and before anything uses them so each error is only given here
+		for (file, name, ty) in aliases {
+			analyzer.file = file;
+			if let Err(e) = analyzer.analyze_alias(&name, &ty) {
+				handler.log(e.file(file));
+			}
+		}
+
+		let mut acc = Vec::new();
+		let mut root = |analyzer: &mut Self, file, node| {
+			analyzer.file = file;
+			let mut res = analyzer.analyze_root(node);
+			let mut file = file;
+
+			// instances are analyzed one after the other rather than inside their callers, deep ones would overflow the stack
+			loop {
+				acc.append(&mut analyzer.instances);
+				analyzer.warnings.drain(..).for_each(|w| handler.log(w.file(file)));
+
+				match res {
+					Ok(n)  => acc.push(n),
+					Err(e) => {
+						handler.log(e.file(file));
+						analyzer.scope.truncate(1);
+						analyzer.loops.clear();
+					},
 				}
 
-				let id = self.peek_scope_mut().new_id();
-				self.symbols.insert(id, name.elem.to_string());
+				let Some(inst) = analyzer.pending.pop() else { break };
+				file = inst.file;
+				res = analyzer.analyze_instance(inst);
+			}
+		};
 
-				let mut nargs = Vec::new();
-				let mut fargs = Vec::new();
-				for (_, ty) in args {
-					if matches!(*ty, ast::Type::Void) {
-						return ReportKind::TypeError
-							.title("Type 'void' is not allowed as a function argument")
-							.help("Remove the arg, or change the type to '*void'")
+		types.into_iter().for_each(|(file, node)| root(&mut analyzer, file, node));
+
+		// methods are analyzed one by one, so an error in one does not hide the others
+		let mut bodies = Vec::new();
+		for (file, node) in funcs {
+			analyzer.file = file;
+			match node.elem {
+				ast::Node::Impl { ty, .. } if analyzer.generic_tys.contains_key(ty.elem) => {
+					handler.log(ReportKind::TypeError
+						.title(format!("Generic type '{}' cannot have methods yet", *ty))
+						.span(ty.span)
+						.file(file));
+				},
+				ast::Node::Impl { ty, funcs: methods } => {
+					if !analyzer.types.contains_key(ty.elem) {
+						handler.log(ReportKind::UndefinedSym
+							.title(format!("Type '{}' is not defined", *ty))
 							.span(ty.span)
-							.as_err();
+							.file(file));
+						continue;
 					}
 
-					let ty = convert_ast_ty(&ty.elem);
-					fargs.push(ty.clone());
-					nargs.push(ty);
-				}
+					analyzer.impl_ty = Some(ty.elem.to_string());
+					for func in methods {
+						match analyzer.declare_func(&func) {
+							Ok(true) => {
+								unused.extend(analyzer.unused_candidate(file, &func));
+								bodies.push((file, Some(ty.elem), func));
+							},
+							Ok(false) => (),
+							Err(e) => handler.log(e.file(file)),
+						}
+					}
+					analyzer.impl_ty = None;
+				},
+				_ => match analyzer.declare_func(&node) {
+					Ok(true) => {
+						unused.extend(analyzer.unused_candidate(file, &node));
+						bodies.push((file, None, node));
+					},
+					Ok(false) => (),
+					Err(e) => handler.log(e.file(file)),
+				},
+			}
+		}
 
-				let ret = ret.map_or(Type::Void, |t| convert_ast_ty(&t));
+		for (file, ty, node) in bodies {
+			analyzer.impl_ty = ty.map(str::to_string);
+			root(&mut analyzer, file, node);
+		}
+		analyzer.impl_ty = None;
 
-				let ty = Type::Fn(nargs, Box::new(ret.clone()));
-				self.peek_scope_mut().locals.push((id, name.elem.to_string(), ty));
+		acc.append(&mut analyzer.instances);
 
-				Node::FuncDecl {
-					id, ret,
-					args: fargs,
-				}
+		// everything that could call a function has been analyzed by now, instances included,
+		// unless an error cut a body short before its calls
+		if report::ERR_COUNT.load(Ordering::Relaxed) > errors { unused.clear(); }
+		let reached = analyzer.reachable(&unused.iter().filter_map(|(_, id, _)| *id).collect());
+		for (file, id, name) in unused {
+			let used = match id {
+				Some(id) => reached.contains(&id),
+				// a generic is used once one of its instances is
+				None => analyzer.get_global().locals.iter()
+					.any(|(i, n, _)| reached.contains(i) && n.strip_prefix(name.elem).is_some_and(|n| n.starts_with('<'))),
+			};
+			if used { continue; }
+
+			let sym = symbol(name.elem);
+			handler.log(ReportKind::UnusedFunction
+				.title(format!("Function '{sym}' is never used"))
+				.label("nothing calls it")
+				.help(format!("Remove it, or name it '_{sym}' if that is intended"))
+				.span(name.span)
+				.file(file));
+		}
+
+		let acc = match analyzer.order_types(acc) {
+			Ok(acc) => acc,
+			Err(e)  => {
+				handler.log(*e);
+				Vec::new()
 			},
-			ast::Node::Func { name, args, ret, attrs, body } => {
-				let id = self.peek_scope_mut().new_id();
+		};
 
-				if attrs.iter().any(|a| matches!(**a, ast::Attrs::Export)) {
-					self.symbols.insert(id, name.elem.to_string());
-				}
+		(acc, analyzer.symbols)
+	}
 
-				self.push_new_scope();
+	/// Methods live in the global scope as `Type.name` and instances as `name<T, ...>`, both get a mangled symbol.
+	/// Overloads keep their name, but their symbol is mangled from their args
+	fn func_name(&mut self, id: ValId, name: &str, args: &[Type]) -> String {
+		if self.impl_ty.is_none() && self.ty_args.is_empty() {
+			if self.overloads.get(name).is_some_and(|n| *n > 1) && !self.symbols.contains_key(&id) {
+				let args = match args.is_empty() {
+					true  => String::from("v"),
+					false => args.iter().map(|t| self.mangle(t)).collect(),
+				};
+				self.symbols.insert(id, format!("_Z{}{args}", mono::nested(name)));
+			}
+			return name.to_string();
+		}
 
-				let mut nargs = Vec::new();
-				let mut fargs = Vec::new();
-				for (n, ty) in args {
-					if matches!(*ty, ast::Type::Void) {
-						return ReportKind::TypeError
-							.title("Type 'void' is not allowed as a function argument")
-							.help("Remove the arg, or change the type to '*void'")
-							.span(ty.span)
-							.as_err();
-					}
+		let (prefix, owner) = match &self.impl_ty {
+			Some(ty) => (mono::parts(ty), format!("{ty}.")),
+			None     => (String::new(), String::new()),
+		};
+		let args = match self.ty_args.is_empty() {
+			true  => String::new(),
+			false => format!("I{}E", self.ty_args.iter().map(|(_, t)| self.mangle(t)).collect::<String>()),
+		};
 
-					let ty = convert_ast_ty(&ty.elem);
+		let sym = format!("_ZN{prefix}{}{args}", mono::parts(name));
+		let qualified = owner + &self.inst_name(name);
+
+		self.symbols.insert(id, sym + "E");
+		qualified
+	}
+
+	fn analyze_root(&mut self, node: Sp<ast::Node>) -> Result<Node> {
+		Ok(match node.elem {
+			ast::Node::Func { name, args, attrs, body, .. } => {
+				let id = self.declared.remove(&(self.file, name.span)).expect("functions are declared before their body");
+				let loc = Loc { file: self.file, span: name.span };
+				let Some((_, _, Type::Fn(nargs, ret))) = self.get_global().locals.iter().find(|(i, _, _)| *i == id).cloned()
+					else { unreachable!() };
 
-					let id = self.peek_scope_mut().new_id();
-					self.peek_scope_mut().locals.push((id, n.elem.to_string(), ty.clone()));
-					fargs.push((id, ty.clone()));
-					nargs.push(ty);
+				if attrs.iter().any(|a| matches!(**a, ast::Attrs::Extern)) {
+					return Ok(Node::FuncDecl { id, args: nargs, ret: *ret, loc });
 				}
 
-				let ret = ret.map_or(Type::Void, |t| convert_ast_ty(&t));
-				let ty = Type::Fn(nargs, Box::new(ret.clone()));
+				let export = attrs.iter().any(|a| matches!(**a, ast::Attrs::Export));
+				let ret = *ret;
 
-				let scope_len = self.scope.len();
-				self.scope.get_mut(scope_len - 2).unwrap()
-					.locals.push((id, name.elem.to_string(), ty));
+				self.func = Some(id);
+				self.push_new_scope();
 
-				// TODO: try fold? 🥺👉👈
-				let mut nodes = Vec::new();
-				for node in body {
-					nodes.extend(self.analyze_stmt(node, &ret)?);
+				let mut fargs = Vec::new();
+				for ((n, _), ty) in args.into_iter().zip(nargs) {
+					let id = self.new_local(&n, &ty);
+					fargs.push((id, ty));
 				}
 
-				for node in nodes.iter_mut().filter(|n| matches!(n, Node::Ret(_, _))) {
-					let Node::Ret(_, ref mut t) = node 
-						else { unreachable!() };
+				self.diverged = None;
+				let mut nodes = self.analyze_stmts(body, &ret)?;
 
-					if !cmp_ty(&t, &ret) {
-						return ReportKind::TypeError
-							.title("Return type mismatch")
-							// .label(format!("expected '{ret}', found '{t}'"))
-							// .span(node.span) // TODO: span
-							.as_err();
+				// the end of the body is only fine for functions that return nothing
+				if self.diverged.take().is_none() {
+					match ret {
+						Type::Void => (),
+						Type::Never => return ReportKind::TypeError
+							.title(format!("Function '{}' can reach its end", *name))
+							.label("it returns 'never'")
+							.help("End it with a call to another 'never' function, or an endless 'loop'")
+							.span(name.span)
+							.as_err(),
+						_ => return ReportKind::TypeError
+							.title(format!("Function '{}' does not return on every path", *name))
+							.label(format!("expected to return '{ret}'"))
+							.help("Add a 'ret' at its end")
+							.span(name.span)
+							.as_err(),
 					}
-
-					*t = ret.clone();
 				}
 
+				self.settle(&fargs, &mut nodes)?;
+				self.check_unused(&fargs);
 				self.pop_scope();
 				Node::Func {
-					id, ret, 
+					id, ret, loc,
 					body:   nodes,
 					args:   fargs,
-					export: attrs.iter().any(|a| matches!(**a, ast::Attrs::Export)),
+					export,
+				}
+			},
+			ast::Node::Struct { name, fields, .. } => {
+				let tname = self.inst_name(name.elem);
+
+				// registered before the fields are resolved, so they can point back to it
+				let id = match self.types.get(&tname) {
+					Some(def) => def.id(),
+					None => {
+						let id = self.new_type(&tname, name.span)?;
+						self.types.insert(tname.clone(), TypeDef::Struct { id, fields: Vec::new() });
+						id
+					},
+				};
+
+				match self.analyze_fields(id, &name, fields) {
+					Ok(fields) => {
+						let types = fields.iter().map(|(_, t)| t.clone()).collect();
+						self.types.insert(tname, TypeDef::Struct { id, fields });
+						Node::Struct { id, fields: types, loc: Loc { file: self.file, span: name.span } }
+					},
+					Err(e) => {
+						self.types.remove(&tname);
+						return Err(e);
+					},
+				}
+			},
+			ast::Node::Enum { name, variants, .. } => {
+				let tname = self.inst_name(name.elem);
+				let id = match self.types.get(&tname) {
+					Some(def) => def.id(),
+					None => {
+						let id = self.new_type(&tname, name.span)?;
+						self.types.insert(tname.clone(), TypeDef::Enum { id, variants: Vec::new() });
+						id
+					},
+				};
+
+				match self.analyze_variants(id, &name, variants) {
+					Ok(variants) => {
+						let types = variants.iter().map(|(_, t)| t.clone()).collect();
+						self.types.insert(tname, TypeDef::Enum { id, variants });
+						Node::Enum { id, variants: types, loc: Loc { file: self.file, span: name.span } }
+					},
+					Err(e) => {
+						self.types.remove(&tname);
+						return Err(e);
+					},
 				}
 			},
-			_ => todo!(),
+			_ => unreachable!("only functions and types are analyzed as roots"),
 		})
 	}
 
-	fn analyze_stmt(&mut self, node: Sp<ast::Node>, ret: &Type) -> Result<Vec<Node>> {
-		Ok(match node.elem {
-			ast::Node::Ret(None) => vec![Node::Ret(None, Type::Void)],
-			ast::Node::Ret(Some(node)) => {
-				let (ty, n, v) = self.analyze_expr(*node)?;
+	/// Adds the signature of the function `node` to the global scope, its body is analyzed by `analyze_root`.
+	/// False when there is nothing left to analyze, as it names an extern which is already declared
+	fn declare_func(&mut self, node: &Sp<ast::Node>) -> Result<bool> {
+		let ast::Node::Func { name, generics, args, ret, attrs, body } = &node.elem else { unreachable!() };
 
-				let mut nodes = n.map_or(Vec::new(), |n| vec![n]);
+		if !generics.is_empty() && self.ty_args.is_empty() {
+			return ReportKind::TypeError
+				.title("Methods cannot be generic yet")
+				.span(name.span)
+				.as_err();
+		}
 
-				nodes.push(Node::Ret(Some(v), ty));
-				nodes
+		let is_extern = attrs.iter().any(|a| matches!(**a, ast::Attrs::Extern));
+		let export    = attrs.iter().any(|a| matches!(**a, ast::Attrs::Export));
+		if is_extern {
+			if self.impl_ty.is_some() {
+				return ReportKind::SyntaxError
+					.title("Methods cannot be extern")
+					.span(name.span)
+					.as_err();
+			}
+
+			if !body.is_empty() {
+				return ReportKind::SyntaxError // TODO: maybe not SyntaxError
+					.title("Extern functions cannot have a body")
+					.span(node.span)
+					.as_err();
+			}
+
+			if export {
+				return ReportKind::SyntaxError
+					.title("Extern functions cannot be exported")
+					.span(node.span)
+					.as_err();
+			}
+		}
+
+		let mut nargs = Vec::new();
+		for (_, ty) in args {
+			// checked after conversion, aliases can name 'void' too
+			let t = self.convert_ty(ty)?;
+			if t == Type::Void {
+				return ReportKind::TypeError
+					.title("Type 'void' is not allowed as a function argument")
+					.help("Remove the arg, or change the type to '*void'")
+					.span(ty.span)
+					.as_err();
+			}
+			nargs.push(t);
+		}
+
+		let ret = ret.as_ref().map_or(Ok(Type::Void), |t| self.convert_ty(t))?;
+
+		// an extern declared by several modules is the same function in all of them
+		let shared = match is_extern {
+			true  => self.get_global().locals.iter()
+				.find(|(i, _, t)| self.symbols.get(i).is_some_and(|s| s == symbol(name.elem))
+					&& matches!(t, Type::Fn(a, r) if *a == nargs && **r == ret))
+				.map(|(i, _, _)| *i),
+			false => None,
+		};
+
+		// named once the args are known, overloads are told apart by them
+		self.check_overload(name, &nargs, (is_extern || export) && shared.is_none())?;
+		let ty = Type::Fn(nargs, Box::new(ret));
+		if let Some(id) = shared {
+			self.get_global_mut().locals.push((id, name.elem.to_string(), ty));
+			return Ok(false);
+		}
+
+		let id = self.get_global_mut().new_id();
+		if is_extern || export { self.symbols.insert(id, symbol(name.elem).to_string()); }
+		let qualified = match (is_extern, &ty) {
+			(true, _) => name.elem.to_string(),
+			(false, Type::Fn(args, _)) => self.func_name(id, name.elem, args),
+			_ => unreachable!(),
+		};
+
+		self.get_global_mut().locals.push((id, qualified, ty));
+		self.declared.insert((self.file, name.span), id);
+		Ok(true)
+	}
+
+	/// Names the struct or enum `node` without its fields, so types declared before it can hold it
+	fn declare_type(&mut self, node: &Sp<ast::Node>) -> Result<()> {
+		let (ast::Node::Struct { name, .. } | ast::Node::Enum { name, .. }) = &node.elem else { unreachable!() };
+		let id = self.new_type(name.elem, name.span)?;
+
+		let def = match node.elem {
+			ast::Node::Struct { .. } => TypeDef::Struct { id, fields: Vec::new() },
+			_ => TypeDef::Enum { id, variants: Vec::new() },
+		};
+		self.types.insert(name.elem.to_string(), def);
+		Ok(())
+	}
+
+	/// Types come out first, each after the ones it holds, as the backends lay them out in order.
+	/// Holding itself directly is caught at the field, through other types it is caught here
+	fn order_types(&self, nodes: Vec<Node>) -> Result<Vec<Node>> {
+		let (types, funcs): (Vec<Node>, Vec<Node>) = nodes.into_iter()
+			.partition(|n| matches!(n, Node::Struct { .. } | Node::Enum { .. }));
+
+		let ids: Vec<TypeId> = types.iter().map(|n| match n {
+			Node::Struct { id, .. } | Node::Enum { id, .. } => *id,
+			_ => unreachable!(),
+		}).collect();
+		let mut pending: HashMap<TypeId, Node> = ids.iter().copied().zip(types).collect();
+
+		let mut ordered = Vec::new();
+		for id in ids {
+			self.place_type(id, &mut pending, &mut Vec::new(), &mut ordered)?;
+		}
+
+		ordered.extend(funcs);
+		Ok(ordered)
+	}
+
+	/// `path` holds the types being placed, the ones holding `id`
+	fn place_type(&self, id: TypeId, pending: &mut HashMap<TypeId, Node>, path: &mut Vec<TypeId>, ordered: &mut Vec<Node>)
+	-> Result<()> {
+		if let Some(start) = path.iter().position(|p| *p == id) {
+			let def = |id: &TypeId| self.types.iter().find(|(_, def)| def.id() == *id);
+			let name = |id: &TypeId| def(id).map_or_else(String::new, |(n, _)| n.clone());
+			let kind = match def(&id) {
+				Some((_, TypeDef::Enum { .. })) => "Enum",
+				_ => "Struct",
+			};
+			let cycle: Vec<String> = path[start..].iter().chain([&id]).map(|i| format!("'{}'", name(i))).collect();
+
+			return ReportKind::TypeError
+				.title(format!("{kind} '{}' contains itself", name(&id)))
+				.label(format!("{} would make it infinitely large", cycle.join(" -> ")))
+				.help(format!("Point to one of them instead, like '*{}'", name(&path[path.len() - 1])))
+				.span(self.ty_spans[&id].1)
+				.file(self.ty_spans[&id].0)
+				.as_err();
+		}
+
+		// placed already, or it failed to analyze
+		let Some(node) = pending.remove(&id) else { return Ok(()) };
+
+		let mut held = Vec::new();
+		match &node {
+			Node::Struct { fields, .. }   => fields.iter().for_each(|t| held_types(t, &mut held)),
+			Node::Enum { variants, .. } => variants.iter().flatten().for_each(|t| held_types(t, &mut held)),
+			_ => unreachable!(),
+		}
+
+		path.push(id);
+		for held in held {
+			self.place_type(held, pending, path, ordered)?;
+		}
+		path.pop();
+
+		ordered.push(node);
+		Ok(())
+	}
+
+	/// Overloads have to differ in their args, methods cannot be overloaded at all.
+	/// `plain` is whether the function is known to the linker by its own name, only one of them can be
+	fn check_overload(&self, name: &Sp<&str>, args: &[Type], plain: bool) -> Result<()> {
+		let qualified = match &self.impl_ty {
+			Some(ty) => format!("{ty}.{}", **name),
+			None     => name.elem.to_string(),
+		};
+
+		let taken = self.get_global().locals.iter().any(|(_, n, t)| *n == qualified 
+			&& matches!(t, Type::Fn(a, _) if a == args || self.impl_ty.is_some()));
+		if taken {
+			return ReportKind::TypeError
+				.title(format!("Function '{qualified}' is already defined"))
+				.help(match self.impl_ty {
+					Some(_) => "Give the method another name",
+					None    => "Overloads need different argument types",
+				})
+				.span(name.span)
+				.as_err();
+		}
+
+		let sym = symbol(name.elem);
+		if plain && self.symbols.values().any(|s| s == sym) {
+			return ReportKind::TypeError
+				.title(format!("Symbol '{sym}' is already defined"))
+				.help(format!("Only one function called '{sym}' can be extern or exported"))
+				.span(name.span)
+				.as_err();
+		}
+
+		Ok(())
+	}
+
+	/// Aliases are resolved where they are used, this only checks that the alias itself is valid.
+	/// Every alias in a chain or cycle it fails on is marked bad, so the error is only given once
+	fn analyze_alias(&mut self, name: &Sp<&'src str>, ty: &Sp<ast::Type>) -> Result<()> {
+		if self.bad_aliases.contains(name.elem) { return Ok(()); }
+
+		// the aliases being expanded are left in `seen` when it fails
+		let mut seen = vec![name.elem];
+		let res = self.resolve_ty(ty, &mut seen).map(|_| ());
+		if res.is_err() { self.bad_aliases.extend(seen); }
+		res
+	}
+
+	fn new_type(&mut self, name: &str, span: Span) -> Result<TypeId> {
+		if self.types.contains_key(name) || self.aliases.contains_key(name) || self.generic_tys.contains_key(name) {
+			return ReportKind::TypeError
+				.title(format!("Type '{name}' is already defined"))
+				.span(span)
+				.as_err();
+		}
+
+		self.tyacc.0 += 1;
+		self.ty_spans.insert(self.tyacc, (self.file, span));
+		Ok(self.tyacc)
+	}
+
+	fn analyze_fields(&mut self, id: TypeId, name: &Sp<&str>, fields: Vec<(Sp<&str>, Sp<ast::Type>)>)
+	-> Result<Vec<(String, Type)>> {
+		let mut nfields: Vec<(String, Type)> = Vec::new();
+		for (n, ty) in fields {
+			if nfields.iter().any(|(f, _)| f == n.elem) {
+				return ReportKind::TypeError
+					.title(format!("Field '{}' is declared twice", *n))
+					.span(n.span)
+					.as_err();
+			}
+
+			let t = self.convert_ty(&ty)?;
+			if t == Type::Void {
+				return ReportKind::TypeError
+					.title("Type 'void' is not allowed as a field")
+					.help("Remove the field, or change the type to '*void'")
+					.span(ty.span)
+					.as_err();
+			}
+
+			if contains(&t, id) {
+				return ReportKind::TypeError
+					.title(format!("Struct '{}' contains itself", **name))
+					.label("this field would make it infinitely large")
+					.help(format!("Point to it instead, with '*{}'", **name))
+					.span(ty.span)
+					.as_err();
+			}
+
+			nfields.push((n.elem.to_string(), t));
+		}
+
+		Ok(nfields)
+	}
+
+	fn analyze_variants(&mut self, id: TypeId, name: &Sp<&str>, variants: Vec<(Sp<&str>, Option<Sp<ast::Type>>)>)
+	-> Result<Variants> {
+		let mut nvariants: Variants = Vec::new();
+		for (n, ty) in variants {
+			if nvariants.iter().any(|(v, _)| v == n.elem) {
+				return ReportKind::TypeError
+					.title(format!("Variant '{}' is declared twice", *n))
+					.span(n.span)
+					.as_err();
+			}
+
+			let ty = match ty {
+				Some(ty) => {
+					let t = self.convert_ty(&ty)?;
+					if t == Type::Void {
+						return ReportKind::TypeError
+							.title("Type 'void' is not allowed as a payload")
+							.help("Leave out the payload instead")
+							.span(ty.span)
+							.as_err();
+					}
+
+					if contains(&t, id) {
+						return ReportKind::TypeError
+							.title(format!("Enum '{}' contains itself", **name))
+							.label("this payload would make it infinitely large")
+							.help(format!("Point to it instead, with '*{}'", **name))
+							.span(ty.span)
+							.as_err();
+					}
+					Some(t)
+				},
+				None => None,
+			};
+
+			nvariants.push((n.elem.to_string(), ty));
+		}
+
+		Ok(nvariants)
+	}
+
+	fn convert_ty(&mut self, ty: &Sp<ast::Type>) -> Result<Type> {
+		self.resolve_ty(ty, &mut Vec::new())
+	}
+
+	/// `seen` holds the aliases being expanded, to catch the ones which expand to themselves
+	fn resolve_ty(&mut self, ty: &Sp<ast::Type>, seen: &mut Vec<&'src str>) -> Result<Type> {
+		Ok(match &ty.elem {
+			ast::Type::U(n)  => Type::U(*n),
+			ast::Type::I(n)  => Type::I(*n),
+			ast::Type::B(n)  => Type::B(*n),
+			ast::Type::F(n)  => Type::F(*n),
+			ast::Type::Usize => Type::Usize,
+			ast::Type::Isize => Type::Isize,
+			ast::Type::Void  => Type::Void,
+			ast::Type::Never => Type::Never,
+			ast::Type::Ptr(ty)    => Type::Ptr(self.resolve_ty(ty, seen)?.into()),
+			ast::Type::Arr(ty, n) => Type::Arr(self.resolve_ty(ty, seen)?.into(), *n),
+			ast::Type::Mut(ty)    => Type::Mut(self.resolve_ty(ty, seen)?.into()),
+			ast::Type::Opt(ty)    => Type::Opt(self.resolve_ty(ty, seen)?.into()),
+			ast::Type::Fn(args, ret) => Type::Fn(
+				args.iter().map(|t| self.resolve_ty(t, seen)).collect::<Result<_>>()?,
+				Box::new(ret.as_ref().map_or(Ok(Type::Void), |t| self.resolve_ty(t, seen))?)),
+			ast::Type::Ident(name) if self.ty_args.iter().any(|(p, _)| p == name) =>
+				self.ty_args.iter().find(|(p, _)| p == name).unwrap().1.clone(),
+			ast::Type::Ident(name) if self.generic_tys.contains_key(name) => return ReportKind::TypeError
+				.title(format!("Type '{name}' needs type arguments"))
+				.help(format!("Give them, like '{name}<...>'"))
+				.span(ty.span)
+				.as_err(),
+			ast::Type::Ident(name) => match (
+				self.types.get(*name).map(|def| def.ty(name)),
+				self.aliases.get_key_value(*name).map(|(a, t)| (*a, t.clone())),
+			) {
+				(Some(ty), _) => ty,
+				(None, Some((alias, _))) if self.bad_aliases.contains(alias) => return ReportKind::TypeError
+					.title(format!("Type alias '{alias}' cannot be used"))
+					.label("its definition has an error")
+					.span(ty.span)
+					.as_err(),
+				(None, Some((alias, aty))) => {
+					if let Some(start) = seen.iter().position(|a| *a == alias) {
+						let cycle: Vec<String> = seen[start..].iter().chain([&alias])
+							.map(|a| format!("'{a}'"))
+							.collect();
+
+						return ReportKind::CyclicType
+							.title(format!("Type alias '{alias}' expands to itself"))
+							.label(cycle.join(" -> "))
+							.help("Aliases are replaced by what they name, use a struct for recursive types")
+							.span(ty.span)
+							.as_err();
+					}
+
+					seen.push(alias);
+					let t = self.resolve_ty(&aty, seen)?;
+					seen.pop();
+					t
+				},
+				(None, None) => return ReportKind::UndefinedSym
+					.title(format!("Type '{name}' is not defined"))
+					.span(ty.span)
+					.as_err(),
 			},
-			ast::Node::FuncCall { name, args } => {
-				// TODO: do the whole overloading match thing
-				let (depth, (id, _, ty)) = self.find_matching_descending(|(_, n, _)| n == name.elem)
-					.ok_or_else(|| ReportKind::UndefinedSym
-						.title(format!("Function '{}' is not defined", *name))
-						.span(name.span))?;
+			ast::Type::Generic(name, args) => {
+				let args = args.iter().map(|t| self.resolve_ty(t, seen)).collect::<Result<_>>()?;
+				self.instantiate_type(name, args, ty.span)?
+			},
+		})
+	}
 
-				let Type::Fn(fn_args, fn_ret) = ty else {
+	fn analyze_stmt(&mut self, node: Sp<ast::Node>, ret: &Type) -> Result<Vec<Node>> {
+		Ok(match node.elem {
+			ast::Node::Ret(_) if *ret == Type::Never => return ReportKind::TypeError
+				.title("Cannot return from a function returning 'never'")
+				.help("Call another 'never' function instead, or loop forever")
+				.span(node.span)
+				.as_err(),
+			ast::Node::Ret(None) => {
+				if *ret != Type::Void {
 					return ReportKind::TypeError
-						.title(format!("'{}' is not callable", *name))
-						.help("consider changing the type to 'fn(...) ...'")
-						.span(name.span)
+						.title("Missing return value")
+						.label(format!("expected a value of type '{ret}'"))
+						.span(node.span)
 						.as_err();
-				};
+				}
 
-				if fn_args.len() != args.len() {
-					return ReportKind::InvalidArgCount
-						.title(format!("Expected {} arguments, got {}", fn_args.len(), args.len()))
-						.span(node.span)
+				self.diverged = Some(node.span);
+				vec![Node::Ret(None, Type::Void)]
+			},
+			ast::Node::Ret(Some(value)) => {
+				let (vspan, vname) = (value.span, source(&value.elem));
+				let (t, mut nodes, v) = self.analyze_expr(*value, Some(ret))?;
+
+				if !cmp_ty(&t, ret) {
+					if let Some(e) = self.mut_mismatch(vname, &t, ret, vspan).or_else(|| unwrap_mismatch(&t, ret, vspan)) { return Err(e); }
+					return ReportKind::TypeError
+						.title("Return type mismatch")
+						.label(format!("expected '{ret}', found '{t}'"))
+						.span(vspan)
 						.as_err();
 				}
 
-				let id = match depth {
-					0 => Var::Glob(id),
-					_ => Var::Local(id),
+				self.diverged = Some(node.span);
+				nodes.push(Node::Ret(Some(v), ret.clone()));
+				nodes
+			},
+			ast::Node::FuncCall { .. } | ast::Node::MethodCall { .. } => {
+				let (ast::Node::FuncCall { name, .. } | ast::Node::MethodCall { name, .. }) = &node.elem else { unreachable!() };
+				let callee = symbol(name.elem);
+
+				let (ret, mut nodes, call) = match node.elem {
+					ast::Node::FuncCall { name, ty_args, args } => self.analyze_call(&name, &ty_args, args, None, node.span)?,
+					ast::Node::MethodCall { expr, name, args } => self.analyze_method(*expr, &name, args, node.span)?,
+					_ => unreachable!(),
 				};
 
-				let mut nargs = Vec::new();
-				let mut nodes = Vec::new();
-				for (inx, arg) in args.into_iter().enumerate() {
-					let span = arg.span;
-					let (t, n, v) = self.analyze_expr(arg)?;
+				if ret == Type::Never { self.diverged = Some(node.span); }
+				if !matches!(ret, Type::Void | Type::Never) {
+					self.warnings.push(ReportKind::UnusedResult
+						.title(format!("Result of '{callee}' is unused"))
+						.label(format!("it returns '{ret}'"))
+						.help("Assign it to '_' if that is intended, like 'let _ = ...'")
+						.span(node.span));
+				}
 
-					if !cmp_ty(&t, &fn_args[inx]) {
-						return ReportKind::TypeError
-							.title("Type mismatch in function call")
-							.label(format!("expected '{}', found '{t}'", fn_args[inx]))
-							.span(span)
-							.as_err();
-					}
+				nodes.push(match ret {
+					Type::Void | Type::Never => call,
+					_ => Node::Assign {
+						id:  self.new_tmp("__ret", &ret),
+						ty:  ret,
+						val: Box::new(call),
+					},
+				});
 
-					n.map(|n| nodes.push(n));
-					nargs.push((v, fn_args[inx].clone()));
-				}
+				nodes
+			},
+			ast::Node::Assign { name, ty, value } => {
+				let (vspan, vname) = (value.span, source(&value.elem));
+
+				let (ty, mut nodes, v) = match ty {
+					Some(ty) => {
+						let tspan = ty.span;
+						let ty = self.convert_ty(&ty)?;
+						if ty == Type::Void {
+							return ReportKind::TypeError
+								.title("Cannot assign as type 'void'")
+								.span(tspan)
+								.as_err();
+						}
 
-				let call = Node::FuncCall { id, args: nargs };
+						let (t, nodes, v) = self.analyze_expr(*value, Some(&ty))?;
 
-				nodes.push(match *fn_ret {
-					Type::Void => call,
-					_ => {
-						let retid = self.peek_scope_mut().new_id();
-						self.peek_scope_mut().locals.push((retid, "__ret".to_string(), (*fn_ret).clone()));
-						Node::Assign {
-							id:  retid,
-							ty:  (*fn_ret).clone(),
-							val: Box::new(call),
+						if !cmp_ty(&t, &ty) {
+							if let Some(e) = self.mut_mismatch(vname, &t, &ty, vspan).or_else(|| unwrap_mismatch(&t, &ty, vspan)) { return Err(e); }
+							return ReportKind::TypeError
+								.title("Type mismatch in assignment")
+								.label(format!("expected '{ty}', found '{t}'"))
+								.span(node.span)
+								.as_err();
 						}
+						(ty, nodes, v)
 					},
-				});
+					None => {
+						let (t, nodes, v) = self.analyze_expr(*value, None)?;
+						if matches!(t, Type::Void | Type::Never) {
+							return ReportKind::TypeError
+								.title(format!("Cannot assign a value of type '{t}'"))
+								.label("it has no value")
+								.span(vspan)
+								.as_err();
+						}
+						// a value made only of literals takes the type its uses expect
+						let ty = self.infer_ty(&t, None, vspan);
+						self.name_var(&ty, &name);
+						(ty, nodes, v)
+					},
+				};
+
+				let id = self.new_local(&name, &ty);
+
+				// take over the temporary the value was computed into instead of copying it
+				match nodes.last_mut() {
+					Some(Node::Assign { id: tmp, ty: tmp_ty, .. }) 
+						if matches!(v, Var::Local(v) if v == *tmp) => {
+						*tmp    = id;
+						*tmp_ty = ty;
+					},
+					_ => nodes.push(Node::Assign { id, ty, val: Box::new(Node::Var(v)) }),
+				}
 
 				nodes
 			},
-			ast::Node::Assign { name, ty, value } => {
-				let (t, n, v) = self.analyze_expr(*value)?;
+			ast::Node::Store { to, value } if matches!(to.elem, ast::Node::Deref(_)) => {
+				let ast::Node::Deref(ptr) = to.elem else { unreachable!() };
+				let (pspan, pname) = (ptr.span, ident(&ptr.elem));
+				let (pty, mut nodes, p) = self.analyze_expr(*ptr, None)?;
 
-				if matches!(*ty, ast::Type::Void) {
+				let Type::Ptr(pointee) = pty.base() else {
 					return ReportKind::TypeError
-						.title("Cannot assign as type 'void'")
-						.span(ty.span)
+						.title(format!("Cannot dereference a '{pty}', it is not a pointer"))
+						.span(pspan)
 						.as_err();
+				};
+
+				if !matches!(**pointee, Type::Mut(_)) {
+					let err = ReportKind::TypeError
+						.title(format!("Cannot write through a '{}'", pty.base()))
+						.label("it does not allow writes")
+						.span(pspan);
+
+					return Err(match pname.and_then(|n| self.decl_span(n).map(|s| (n, s))) {
+						Some((name, decl)) => err.related(decl, format!("'{name}' is declared here")),
+						None => err,
+					}.help(format!("Only pointers to 'mut' values can be written through, like '*mut {}'", pointee.base())).into());
 				}
 
-				let ty = convert_ast_ty(&ty.elem);
+				let ty = pointee.base().clone();
+				let (vspan, vname) = (value.span, source(&value.elem));
+				let (t, vnodes, v) = self.analyze_expr(*value, Some(&ty))?;
 
 				if !cmp_ty(&t, &ty) {
+					if let Some(e) = self.mut_mismatch(vname, &t, &ty, vspan).or_else(|| unwrap_mismatch(&t, &ty, vspan)) { return Err(e); }
 					return ReportKind::TypeError
 						.title("Type mismatch in assignment")
 						.label(format!("expected '{ty}', found '{t}'"))
-						.span(node.span)
+						.span(vspan)
 						.as_err();
 				}
 
-				let id = self.peek_scope_mut().new_id();
-				self.peek_scope_mut().locals.push((id, name.elem.to_string(), ty.clone()));
-
-				vec![Node::Assign {
-					id, ty, val: Box::new(n.unwrap_or(Node::Var(v))),
-				}]
+				nodes.extend(vnodes);
+				nodes.push(Node::Store { to: p, from: (v, ty), deref: true });
+				nodes
 			},
-			_ => todo!(),
-		})
-	}
+			ast::Node::Store { to, value } if matches!(to.elem, ast::Node::Index { .. }) => {
+				let ast::Node::Index { expr, index } = to.elem else { unreachable!() };
+				let (span, name) = (expr.span, ident(&expr.elem));
+				let (aty, mut nodes, arr, index) = self.analyze_index(*expr, *index)?;
 
-	fn analyze_expr(&mut self, node: Sp<ast::Node>) -> Result<(Type, Option<Node>, Var)> {
-		Ok(match node.elem {
-			ast::Node::StrLit(s) => {
-				let id = self.get_global_mut().new_id();
+				// a sized array is written in place, a slice or pointer writes to what it points to
+				match (aty.base(), name) {
+					(Type::Ptr(pointee), _) if !matches!(**pointee, Type::Mut(_)) => {
+						let err = ReportKind::TypeError
+							.title(format!("Cannot write through a '{}'", aty.base()))
+							.label("it does not allow writes")
+							.span(span);
 
-				let ty = Type::Arr(Type::U(8).into(), Some(s.len() as u64));
-				
-				self.get_global_mut().locals.push((id, format!("__const{id:?}"), ty.clone()));
-				(ty.clone(), Some(Node::Global { 
-					ty, id, 
-					val: Node::StrLit(s).into(),
-				}), Var::Glob(id))
-			},
-			ast::Node::UIntLit(v) => (Type::Puint, None, Var::Imm(v)),
-			ast::Node::SIntLit(v) => (Type::Pint,  None, Var::Imm(v)),
-			ast::Node::Ident(name) => {
-				let (depth, (id, _, ty)) = self.find_matching_descending(|(_, n, _)| n == name)
-					.ok_or_else(|| ReportKind::UndefinedSym
-						.title(format!("'{name}' is not defined"))
-						.span(node.span))?;
+						return Err(match name.and_then(|n| self.decl_span(n).map(|s| (n, s))) {
+							Some((name, decl)) => err.related(decl, format!("'{name}' is declared here")),
+							None => err,
+						}.help(format!("Only pointers to 'mut' values can be written through, like '*mut {}'", pointee.base())).into());
+					},
+					(Type::Arr(_, Some(_)), None) => return ReportKind::TypeError
+						.title("Cannot assign to an element of a temporary array")
+						.help("Store it in a variable first")
+						.span(span)
+						.as_err(),
+					(Type::Arr(_, Some(_)), Some(name)) if !matches!(aty, Type::Mut(_)) => {
+						let err = ReportKind::TypeError
+							.title(format!("Cannot assign to an element of '{name}', it is not mutable"))
+							.span(span);
 
-				(ty, None, match depth {
-					0 => Var::Glob(id),
-					_ => Var::Local(id),
-				})
+						return Err(match self.decl_span(name) {
+							Some(decl) => err.related(decl, format!("'{name}' is declared here")),
+							None => err,
+						}.help(format!("Declare it as 'mut {aty}'")).into());
+					},
+					(Type::Arr(ety, None), _) if !matches!(**ety, Type::Mut(_)) => {
+						let err = ReportKind::TypeError
+							.title(format!("Cannot write through a '{aty}'"))
+							.label("it does not allow writes")
+							.span(span);
+
+						return Err(match name.and_then(|n| self.decl_span(n).map(|s| (n, s))) {
+							Some((name, decl)) => err.related(decl, format!("'{name}' is declared here")),
+							None => err,
+						}.help(format!("Only slices of 'mut' values can be written through, like '[mut {}]'", ety.base())).into());
+					},
+					_ => (),
+				}
+
+				let ty = elem_ty(&aty).base().clone();
+				let (vspan, vname) = (value.span, source(&value.elem));
+				let (t, vnodes, v) = self.analyze_expr(*value, Some(&ty))?;
+
+				if !cmp_ty(&t, &ty) {
+					if let Some(e) = self.mut_mismatch(vname, &t, &ty, vspan).or_else(|| unwrap_mismatch(&t, &ty, vspan)) { return Err(e); }
+					return ReportKind::TypeError
+						.title("Type mismatch in assignment")
+						.label(format!("expected '{ty}', found '{t}'"))
+						.span(vspan)
+						.as_err();
+				}
+
+				nodes.extend(vnodes);
+				match aty.base() {
+					Type::Ptr(_) => {
+						let (_, p) = self.elem_ptr(&aty, arr, index, &mut nodes);
+						nodes.push(Node::Store { to: p, from: (v, ty), deref: true });
+					},
+					_ => nodes.push(Node::StoreIndex { to: (arr, aty.base().clone()), index, from: (v, ty) }),
+				}
+				nodes
 			},
-			_ => todo!(),
-		})
-	}
-}
+			ast::Node::Store { to, value } if matches!(to.elem, ast::Node::Field { .. }) => {
+				let ast::Node::Field { expr, field } = to.elem else { unreachable!() };
+				let (span, name) = (expr.span, ident(&expr.elem));
+				let (sty, mut nodes, s) = self.analyze_expr(*expr, None)?;
+				let (base, deref, index, ty) = self.struct_field(&sty, span, &field)?;
 
-fn convert_ast_ty(ty: &ast::Type) -> Type {
-	match ty {
-		ast::Type::U(n)  => Type::U(*n),
-		ast::Type::I(n)  => Type::I(*n),
-		ast::Type::B(n)  => Type::B(*n),
-		ast::Type::F(n)  => Type::F(*n),
-		ast::Type::Usize => Type::Usize,
-		ast::Type::Isize => Type::Isize,
-		ast::Type::Void  => Type::Void,
-		ast::Type::Never => Type::Never,
-		ast::Type::Ptr(ty)    => Type::Ptr(convert_ast_ty(ty).into()),
-		ast::Type::Arr(ty, n) => Type::Arr(convert_ast_ty(ty).into(), *n),
-		ast::Type::Mut(ty)    => Type::Mut(convert_ast_ty(ty).into()),
-		ast::Type::Opt(ty)    => Type::Opt(convert_ast_ty(&ty).into()),
-		ast::Type::Fn(args, ret) => Type::Fn(
-			args.iter().map(|t| convert_ast_ty(&t)).collect(),
-			Box::new(ret.as_ref().map_or(Type::Void, |t| convert_ast_ty(t)))),
-		ast::Type::Ident(_) => unimplemented!("ident"),
-	}
-}
+				// a struct is written in place, a pointer writes to what it points to
+				match (sty.base(), name) {
+					(Type::Ptr(pointee), _) if !matches!(**pointee, Type::Mut(_)) => {
+						let err = ReportKind::TypeError
+							.title(format!("Cannot write through a '{}'", sty.base()))
+							.label("it does not allow writes")
+							.span(span);
 
-fn cmp_ty(ty1: &Type, ty2: &Type) -> bool {
-	match (ty1, ty2) {
-		(Type::Puint, _)  => matches!(ty2, Type::Puint  | Type::U(_) | Type::Usize | Type::I(_) | Type::F(_)),
-		(Type::Pint, _)   => matches!(ty2, Type::Pint   | Type::I(_) | Type::Isize | Type::F(_)),
-		(Type::Pbool, _)  => matches!(ty2, Type::Pbool  | Type::B(_)),
-		(Type::Pfloat, _) => matches!(ty2, Type::Pfloat | Type::F(_)),
-		(Type::Arr(_, _), Type::Ptr(ty2)) => cmp_ty(ty1, ty2),
-		(Type::Arr(ty1, _), Type::Arr(ty2, None)) => cmp_ty(ty1, ty2),
-		_ => ty1 == ty2,
+						return Err(match name.and_then(|n| self.decl_span(n).map(|s| (n, s))) {
+							Some((name, decl)) => err.related(decl, format!("'{name}' is declared here")),
+							None => err,
+						}.help(format!("Only pointers to 'mut' values can be written through, like '*mut {}'", pointee.base())).into());
+					},
+					(Type::Ptr(_), _) => (),
+					(_, None) => return ReportKind::TypeError
+						.title("Cannot assign to a field of a temporary struct")
+						.help("Store it in a variable first")
+						.span(span)
+						.as_err(),
+					(_, Some(name)) if !matches!(sty, Type::Mut(_)) => {
+						let err = ReportKind::TypeError
+							.title(format!("Cannot assign to a field of '{name}', it is not mutable"))
+							.span(span);
+
+						return Err(match self.decl_span(name) {
+							Some(decl) => err.related(decl, format!("'{name}' is declared here")),
+							None => err,
+						}.help(format!("Declare it as 'mut {sty}'")).into());
+					},
+					_ => (),
+				}
+
+				let ty = ty.base().clone();
+				let (vspan, vname) = (value.span, source(&value.elem));
+				let (t, vnodes, v) = self.analyze_expr(*value, Some(&ty))?;
+
+				if !cmp_ty(&t, &ty) {
+					if let Some(e) = self.mut_mismatch(vname, &t, &ty, vspan).or_else(|| unwrap_mismatch(&t, &ty, vspan)) { return Err(e); }
+					return ReportKind::TypeError
+						.title("Type mismatch in assignment")
+						.label(format!("expected '{ty}', found '{t}'"))
+						.span(vspan)
+						.as_err();
+				}
+
+				nodes.extend(vnodes);
+				nodes.push(Node::StoreField { to: (s, base), index, from: (v, ty), deref });
+				nodes
+			},
+			ast::Node::Store { to, value } => {
+				let ast::Node::Ident(name) = to.elem else { unreachable!() };
+				let name = Sp { span: to.span, elem: name };
+				let (depth, (id, _, ty)) = self.find_matching_descending(|(_, n, _)| n == name.elem)
+					.ok_or_else(|| ReportKind::UndefinedSym
+						.title(format!("'{}' is not defined", *name))
+						.span(name.span))?;
+
+				// the global scope only holds functions and constants
+				if depth == 0 {
+					return ReportKind::TypeError
+						.title(format!("Cannot assign to '{}'", *name))
+						.help("Only variables can be assigned to")
+						.span(name.span)
+						.as_err();
+				}
+
+				if !matches!(ty, Type::Mut(_)) {
+					let err = ReportKind::TypeError
+						.title(format!("Cannot assign to '{}', it is not mutable", *name))
+						.span(name.span);
+
+					return Err(match self.scope[depth].decls.get(&id) {
+						Some(decl) => err.related(*decl, format!("'{}' is declared here", *name)),
+						None => err,
+					}.help(match ty.is_literal() {
+						// a literal type cannot be written, so the one it would default to is given
+						true  => format!("Give it a 'mut' type, like 'let {}: mut {} = ...'", *name, concretize(&ty, None)),
+						false => format!("Declare it as 'mut {ty}'"),
+					}).into());
+				}
+
+				let vspan = value.span;
+				let vname = source(&value.elem);
+				let (t, mut nodes, v) = self.analyze_expr(*value, Some(&ty))?;
+
+				if !cmp_ty(&t, &ty) {
+					if let Some(e) = self.mut_mismatch(vname, &t, &ty, vspan).or_else(|| unwrap_mismatch(&t, &ty, vspan)) { return Err(e); }
+					return ReportKind::TypeError
+						.title("Type mismatch in assignment")
+						.label(format!("expected '{}', found '{t}'", ty.base()))
+						.span(vspan)
+						.as_err();
+				}
+
+				nodes.push(Node::Store { to: Var::Local(id), from: (v, ty), deref: false });
+				nodes
+			},
+			ast::Node::If { cond, body, els } => {
+				let (mut nodes, cond) = self.analyze_cond(*cond)?;
+
+				let (then, end) = (self.new_label(), self.new_label());
+				let else_label = match els {
+					Some(_) => self.new_label(),
+					None    => end,
+				};
+
+				nodes.push(Node::Branch { cond, then, els: else_label });
+				nodes.push(Node::Label(then));
+				nodes.extend(self.analyze_block(body, ret)?);
+				nodes.push(Node::Jump(end));
+
+				// control only stops here when both branches stop it
+				let then_diverged = self.diverged.take().is_some();
+				if let Some(els) = els {
+					nodes.push(Node::Label(else_label));
+					nodes.extend(self.analyze_block(els, ret)?);
+					nodes.push(Node::Jump(end));
+
+					let els_diverged = self.diverged.take().is_some();
+					if then_diverged && els_diverged { self.diverged = Some(node.span); }
+				}
+
+				nodes.push(Node::Label(end));
+				nodes
+			},
+			ast::Node::While { cond, body } => {
+				let (head, start, end) = (self.new_label(), self.new_label(), self.new_label());
+
+				let mut nodes = vec![Node::Jump(head), Node::Label(head)];
+				let (cnodes, cond) = self.analyze_cond(*cond)?;
+				nodes.extend(cnodes);
+				nodes.push(Node::Branch { cond, then: start, els: end });
+				nodes.push(Node::Label(start));
+
+				self.loops.push((head, end));
+				nodes.extend(self.analyze_block(body, ret)?);
+				self.loops.pop();
+				self.diverged = None;
+
+				nodes.push(Node::Jump(head));
+				nodes.push(Node::Label(end));
+				nodes
+			},
+			ast::Node::Loop(body) => {
+				let (start, end) = (self.new_label(), self.new_label());
+
+				let mut nodes = vec![Node::Jump(start), Node::Label(start)];
+
+				self.loops.push((start, end));
+				nodes.extend(self.analyze_block(body, ret)?);
+				self.loops.pop();
+
+				// only a 'break' leaves it
+				self.diverged = match nodes.iter().any(|n| matches!(n, Node::Jump(l) if *l == end)) {
+					true  => None,
+					false => Some(node.span),
+				};
+
+				nodes.push(Node::Jump(start));
+				nodes.push(Node::Label(end));
+				nodes
+			},
+			ast::Node::Match { expr, arms } => {
+				let span = expr.span;
+				let (ty, mut nodes, val) = self.analyze_expr(*expr, None)?;
+
+				let (Type::Enum(_, name), &Var::Local(scrut)) = (ty.base(), &val) else {
+					return ReportKind::TypeError
+						.title(format!("Cannot match on type '{ty}'"))
+						.label("only enums can be matched")
+						.span(span)
+						.as_err();
+				};
+
+				let Some(TypeDef::Enum { variants, .. }) = self.types.get(name)
+					else { unreachable!() };
+				let variants = variants.clone();
+				let ety = ty.base().clone();
+
+				let tag = self.new_tmp("__tag", &Type::U(32));
+				nodes.push(Node::Assign { id: tag, ty: Type::U(32), val: Box::new(Node::Tag { ty: ety.clone(), val: Var::Local(scrut) }) });
+
+				let end = self.new_label();
+				let mut matched = vec![false; variants.len()];
+				let mut wildcard = false;
+				let mut diverged = true;
+
+				// every arm tests the tag in turn, falling through to the next one on a mismatch
+				for (pat, body) in arms {
+					if wildcard {
+						return ReportKind::TypeError
+							.title("Unreachable match arm")
+							.label("'_' above already matches everything")
+							.span(pat.span)
+							.as_err();
+					}
+
+					let ast::Pattern::Variant(vname, bind) = pat.elem else {
+						wildcard = true;
+						nodes.extend(self.analyze_block(body, ret)?);
+						nodes.push(Node::Jump(end));
+						diverged &= self.diverged.take().is_some();
+						continue;
+					};
+
+					let Some(index) = variants.iter().position(|(n, _)| n == vname.elem) else {
+						return Err(no_variant(&ety, &variants, &vname).into());
+					};
+
+					if matched[index] {
+						return ReportKind::TypeError
+							.title(format!("Variant '{}' is already matched", *vname))
+							.span(vname.span)
+							.as_err();
+					}
+					matched[index] = true;
+
+					let payload = variants[index].1.clone();
+					if let (None, Some(bind)) = (&payload, &bind) {
+						return ReportKind::TypeError
+							.title(format!("Variant '{}' has no value to bind", *vname))
+							.help(format!("Remove the binding, leaving just '{}'", *vname))
+							.span(bind.span)
+							.as_err();
+					}
+
+					let cond = self.new_tmp("__tmp", &Type::B(1));
+					#[allow(clippy::cast_possible_truncation)]
+					nodes.push(Node::Assign {
+						id:  cond,
+						ty:  Type::B(1),
+						val: Box::new(Node::BinOp {
+							op:  BinOp::Eq,
+							ty:  Type::U(32),
+							lhs: Var::Local(tag),
+							rhs: Var::Imm((index as u64).into()),
+						}),
+					});
+
+					let (arm, next) = (self.new_label(), self.new_label());
+					nodes.push(Node::Branch { cond: (Var::Local(cond), Type::B(1)), then: arm, els: next });
+					nodes.push(Node::Label(arm));
+
+					self.push_block_scope();
+					if let (Some(bind), Some(pty)) = (bind, payload) {
+						let id = self.new_local(&bind, &pty);
+						nodes.push(Node::Assign {
+							id, ty: pty,
+							val: Box::new(Node::Payload { ty: ety.clone(), val: Var::Local(scrut), variant: index }),
+						});
+					}
+
+					nodes.extend(self.analyze_stmts(body, ret)?);
+					self.pop_block_scope();
+					diverged &= self.diverged.take().is_some();
+
+					nodes.push(Node::Jump(end));
+					nodes.push(Node::Label(next));
+				}
+
+				if !wildcard && matched.contains(&false) {
+					let missing: Vec<String> = variants.iter().zip(&matched)
+						.filter(|(_, m)| !**m)
+						.map(|((n, _), _)| format!("'{n}'"))
+						.collect();
+
+					return ReportKind::TypeError
+						.title(format!("Match on '{ety}' is not exhaustive"))
+						.label(format!("{} not covered", missing.join(", ")))
+						.help("Add arms for them, or a '_' arm")
+						.span(span)
+						.as_err();
+				}
+
+				if diverged { self.diverged = Some(node.span); }
+
+				nodes.push(Node::Label(end));
+				nodes
+			},
+			ast::Node::Break | ast::Node::Continue => {
+				let (keyword, is_break) = match node.elem {
+					ast::Node::Break => ("break", true),
+					_ => ("continue", false),
+				};
+
+				let Some(&(cont, brk)) = self.loops.last() else {
+					return ReportKind::SyntaxError
+						.title(format!("'{keyword}' outside of a loop"))
+						.span(node.span)
+						.as_err();
+				};
+
+				self.diverged = Some(node.span);
+				vec![Node::Jump(if is_break { brk } else { cont })]
+			},
+			ast::Node::Func { .. } | ast::Node::Struct { .. } | ast::Node::Alias { .. } | ast::Node::Impl { .. }
+				| ast::Node::Import(_) | ast::Node::Enum { .. }
+				=> unreachable!("declarations are not parsed in bodies"),
+			// only calls are evaluated for what they do
+			ast::Node::BinOp { .. } | ast::Node::UnOp { .. } | ast::Node::StructLit { .. } | ast::Node::Field { .. }
+				| ast::Node::Unwrap(_) | ast::Node::AddrOf(_) | ast::Node::Deref(_) | ast::Node::Index { .. }
+				| ast::Node::ArrLit(_) | ast::Node::Cast { .. } | ast::Node::None | ast::Node::Ident(_)
+				| ast::Node::StrLit(_) | ast::Node::UIntLit(_) | ast::Node::SIntLit(_) | ast::Node::FloatLit(_)
+				| ast::Node::CharLit(_) => return ReportKind::SyntaxError
+				.title("Expression result is unused")
+				.label("this is not a statement")
+				.help("Assign it to '_' if that is intended, like 'let _ = ...'")
+				.span(node.span)
+				.as_err(),
+		})
+	}
+
+	fn analyze_block(&mut self, body: Vec<Sp<ast::Node>>, ret: &Type) -> Result<Vec<Node>> {
+		self.push_block_scope();
+		let nodes = self.analyze_stmts(body, ret)?;
+		self.pop_block_scope();
+		Ok(nodes)
+	}
+
+	fn analyze_stmts(&mut self, body: Vec<Sp<ast::Node>>, ret: &Type) -> Result<Vec<Node>> {
+		let mut nodes = Vec::new();
+		let mut ended = None;
+		for node in body {
+			// what comes after the end of a path is still checked, but only warned about once
+			if let Some(at) = self.diverged.take() {
+				if ended.is_none() {
+					self.warnings.push(ReportKind::UnreachableCode
+						.title("Unreachable code")
+						.label("this is never run")
+						.span(node.span)
+						.related(at, "any code after this is unreachable"));
+				}
+				ended = Some(at);
+			}
+			nodes.extend(self.analyze_stmt(node, ret)?);
+		}
+
+		if ended.is_some() { self.diverged = ended; }
+		Ok(nodes)
+	}
+
+	/// Conditions of `if` and `while` have to be bools
+	fn analyze_cond(&mut self, node: Sp<ast::Node>) -> Result<(Vec<Node>, (Var, Type))> {
+		let span = node.span;
+		let (ty, nodes, v) = self.analyze_expr(node, None)?;
+
+		if !ty.is_bool() {
+			return ReportKind::TypeError
+				.title("Condition is not a bool")
+				.label(format!("expected a bool, found '{ty}'"))
+				.span(span)
+				.as_err();
+		}
+
+		Ok((nodes, (v, concretize(&ty, None))))
+	}
+
+	/// `expr` is the enum in `Enum.Variant`, rather than a value with fields.
+	/// The args of a generic enum come from the type the value is expected to have
+	fn enum_of(&self, expr: &Sp<ast::Node>, hint: Option<&Type>) -> Option<(Type, Variants)> {
+		let ast::Node::Ident(name) = expr.elem else { return None };
+		if self.find_matching_descending(|(_, n, _)| n == name).is_some() { return None; }
+
+		let name = match hint.map(Type::base) {
+			Some(Type::Enum(id, inst)) if self.generic_tys.contains_key(name)
+				&& self.inst_args.get(id).is_some_and(|(g, _)| *g == name) => inst.as_str(),
+			_ => name,
+		};
+
+		match self.types.get(name)? {
+			def @ TypeDef::Enum { variants, .. } => Some((def.ty(name), variants.clone())),
+			TypeDef::Struct { .. } => None,
+		}
+	}
+
+	/// `Enum.Variant(...)` rather than a call of the method `Enum.name`
+	fn is_variant(&self, expr: &Sp<ast::Node>, name: &Sp<&str>, hint: Option<&Type>) -> bool {
+		self.enum_of(expr, hint).is_some_and(|(_, variants)| variants.iter().any(|(n, _)| n == name.elem))
+	}
+
+	fn analyze_variant(&mut self, ty: Type, variants: &[(String, Option<Type>)], name: &Sp<&str>, arg: Option<Sp<ast::Node>>, span: Span)
+	-> Result<(Type, Vec<Node>, Var)> {
+		let Some(variant) = variants.iter().position(|(n, _)| n == name.elem) else {
+			return Err(no_variant(&ty, variants, name).into());
+		};
+
+		let mut nodes = Vec::new();
+		let val = match (&variants[variant].1, arg) {
+			(Some(pty), Some(arg)) => {
+				let aspan = arg.span;
+				let (t, n, v) = self.analyze_expr(arg, Some(pty))?;
+
+				if !cmp_ty(&t, pty) {
+					return ReportKind::TypeError
+						.title("Type mismatch in enum literal")
+						.label(format!("expected '{pty}', found '{t}'"))
+						.span(aspan)
+						.as_err();
+				}
+
+				nodes.extend(n);
+				Some((v, pty.clone()))
+			},
+			(Some(pty), None) => return ReportKind::TypeError
+				.title(format!("Variant '{ty}.{}' needs a '{pty}' value", **name))
+				.help(format!("Give it one, with '{ty}.{}(...)'", **name))
+				.span(span)
+				.as_err(),
+			(None, Some(arg)) => return ReportKind::TypeError
+				.title(format!("Variant '{ty}.{}' takes no value", **name))
+				.span(arg.span)
+				.as_err(),
+			(None, None) => None,
+		};
+
+		let id = self.new_tmp("__tmp", &ty);
+		nodes.push(Node::Assign {
+			id, ty: ty.clone(),
+			val: Box::new(Node::EnumLit { variant, val }),
+		});
+		Ok((ty, nodes, Var::Local(id)))
+	}
+
+	fn new_label(&mut self) -> LabelId {
+		self.labels.0 += 1;
+		self.labels
+	}
+
+	fn new_tmp(&mut self, name: &str, ty: &Type) -> ValId {
+		let id = self.peek_scope_mut().new_id();
+		self.peek_scope_mut().locals.push((id, name.to_string(), ty.clone()));
+		id
+	}
+
+	fn new_local(&mut self, name: &Sp<&str>, ty: &Type) -> ValId {
+		let id = self.new_tmp(name.elem, ty);
+		self.peek_scope_mut().decls.insert(id, name.span);
+		id
+	}
+
+	/// A mismatch only in `mut`, like passing a pointer which does not allow writes where one that does is expected.
+	/// `name` is the variable the value came from or points to, if it did
+	fn mut_mismatch(&self, name: Option<&str>, found: &Type, expected: &Type, span: Span) -> Option<Box<Report>> {
+		if !cmp_ty(found, &immutable(expected)) { return None; }
+
+		let err = ReportKind::TypeError
+			.title(format!("Cannot use a '{}' as a '{}'", found.base(), expected.base()))
+			.label("it does not allow writes")
+			.span(span);
+
+		Some(match name.and_then(|n| self.decl_span(n).map(|s| (n, s))) {
+			Some((name, decl)) => err.related(decl, format!("'{name}' is declared here")),
+			None => err,
+		}.help("Only pointers to 'mut' values can be written through").into())
+	}
+
+	#[inline]
+	fn mark_used(&mut self, depth: usize, id: ValId) {
+		match (depth, self.func) {
+			(0, Some(func)) => { self.calls.entry(func).or_default().insert(id); },
+			_ => { self.scope[depth].used.insert(id); },
+		}
+	}
+
+	/// The functions which are reached from those that are not `candidates` for being unused, through what each uses.
+	/// A function only used by itself, or by others which are only used by it, is not
+	fn reachable(&self, candidates: &HashSet<ValId>) -> HashSet<ValId> {
+		let global = self.get_global();
+		// instances are made for their callers, so they are reached through them
+		let mut todo: Vec<ValId> = global.locals.iter()
+			.filter(|(id, n, t)| matches!(t, Type::Fn(..)) && !candidates.contains(id) && !n.contains('<'))
+			.map(|(id, _, _)| *id)
+			.chain(global.used.iter().copied())
+			.collect();
+
+		let mut reached = HashSet::new();
+		while let Some(id) = todo.pop() {
+			if !reached.insert(id) { continue; }
+			todo.extend(self.calls.get(&id).into_iter().flatten());
+		}
+		reached
+	}
+
+	/// Warns about the named locals of the innermost scope that are never read, `args` are those of the function.
+	/// A leading '_' says that is intended
+	fn check_unused(&mut self, args: &[(ValId, Type)]) {
+		// instances are left out, each would repeat the warnings of the others in the file of its caller
+		if !self.ty_args.is_empty() { return; }
+
+		let scope = self.peek_scope();
+		let unused: Vec<Report> = scope.locals.iter()
+			.filter(|(id, n, _)| !scope.used.contains(id) && !n.starts_with('_'))
+			.filter_map(|(id, n, _)| scope.decls.get(id).map(|span| ReportKind::UnusedVariable
+				.title(match args.iter().any(|(a, _)| a == id) {
+					true  => format!("Unused argument '{n}'"),
+					false => format!("Unused variable '{n}'"),
+				})
+				.label("it is never read")
+				.help(format!("Remove it, or name it '_{n}' if that is intended"))
+				.span(*span)))
+			.collect();
+
+		self.warnings.extend(unused);
+	}
+
+	/// The function `node` just declared, unless it is reached from outside or named to be left unused.
+	/// Those of a module which are `pub` count as reached from outside, like exported ones.
+	/// Generics have no id until they are instantiated
+	fn unused_candidate(&self, file: &'static str, node: &Sp<ast::Node<'src>>) -> Option<(&'static str, Option<ValId>, Sp<&'src str>)> {
+		let ast::Node::Func { name, attrs, .. } = &node.elem else { unreachable!() };
+
+		let sym = symbol(name.elem);
+		if sym.starts_with('_') || (self.impl_ty.is_none() && sym == "main")
+			|| attrs.iter().any(|a| matches!(**a, ast::Attrs::Export | ast::Attrs::Extern | ast::Attrs::Pub)) {
+			return None;
+		}
+
+		Some((file, self.declared.get(&(file, name.span)).copied(), name.clone()))
+	}
+
+	/// The struct `ty` is or points to, whether it points to it, and the index and type of its field `field`
+	fn struct_field(&self, ty: &Type, span: Span, field: &Sp<&str>) -> Result<(Type, bool, usize, Type)> {
+		// fields are reached through a pointer too
+		let (sty, deref) = match ty.base() {
+			Type::Ptr(ty) => (ty.base(), true),
+			ty => (ty, false),
+		};
+
+		let Type::Struct(_, name) = sty else {
+			return ReportKind::TypeError
+				.title(format!("Type '{ty}' has no fields"))
+				.span(span)
+				.as_err();
+		};
+
+		let TypeDef::Struct { fields, .. } = &self.types[name]
+			else { unreachable!() };
+		let Some(index) = fields.iter().position(|(n, _)| n == field.elem) else {
+			return ReportKind::TypeError
+				.title(format!("'{sty}' has no field '{}'", **field))
+				.span(field.span)
+				.note(match fields.is_empty() {
+					true  => String::from("It has no fields"),
+					false => format!("It has {}", fields.iter()
+						.map(|(n, t)| format!("'{n}: {t}'"))
+						.collect::<Vec<_>>().join(", ")),
+				})
+				.as_err();
+		};
+
+		Ok((sty.clone(), deref, index, fields[index].1.clone()))
+	}
+
+	/// Where the local `name` refers to was declared
+	fn decl_span(&self, name: &str) -> Option<Span> {
+		self.scope.iter().rev().find_map(|scope| scope.locals.iter().rev()
+			.find(|(_, n, _)| n == name)
+			.map(|(id, _, _)| scope.decls.get(id).copied()))
+			.flatten()
+	}
+
+	/// Resolves and typechecks a call, yielding the return type, the nodes computing the args and the call itself
+	/// `hint` is the type the result is expected to have, which can help to infer the type args of a generic
+	fn analyze_call(&mut self, name: &Sp<&str>, ty_args: &[Sp<ast::Type>], args: Vec<Sp<ast::Node>>, hint: Option<&Type>, span: Span)
+	-> Result<(Type, Vec<Node>, Node)> {
+		let found = self.find_matching_descending(|(_, n, _)| n == name.elem);
+		if found.is_none() && self.generic_fns.contains_key(name.elem) {
+			return self.analyze_generic_call(name, ty_args, args, hint, span);
+		}
+
+		let (depth, (id, _, ty)) = found
+			.ok_or_else(|| ReportKind::UndefinedSym
+				.title(format!("Function '{}' is not defined", **name))
+				.span(name.span))?;
+
+		// overloads all live in the global scope, a local of the same name hides them
+		let (id, ty) = match depth {
+			0 if self.overloads.get(name.elem).is_some_and(|n| *n > 1) => self.overload(name, &args, span)?,
+			_ => (id, ty),
+		};
+		self.mark_used(depth, id);
+
+		if let Some(arg) = ty_args.first() {
+			return ReportKind::TypeError
+				.title(format!("'{}' is not generic", **name))
+				.span(arg.span)
+				.as_err();
+		}
+
+		let Type::Fn(fn_args, fn_ret) = ty else {
+			return ReportKind::TypeError
+				.title(format!("'{}' is not callable", **name))
+				.help("consider changing the type to 'fn(...) ...'")
+				.span(name.span)
+				.as_err();
+		};
+
+		let id = match depth {
+			0 => Var::Glob(id),
+			_ => Var::Local(id),
+		};
+
+		let mut nodes = Vec::new();
+		let args = self.analyze_args(&mut nodes, fn_args, args, span)?;
+		Ok((*fn_ret, nodes, Node::FuncCall { id, args }))
+	}
+
+	/// Either `value.method(args)`, with the value passed as the first arg, or `Type.method(args)`
+	fn analyze_method(&mut self, expr: Sp<ast::Node>, name: &Sp<&str>, args: Vec<Sp<ast::Node>>, span: Span)
+	-> Result<(Type, Vec<Node>, Node)> {
+		// a type name rather than a value
+		let path = match expr.elem {
+			ast::Node::Ident(ty) if self.find_matching_descending(|(_, n, _)| n == ty).is_none() => Some(ty),
+			_ => None,
+		};
+
+		if let Some(ty) = path {
+			let def = self.types.get(ty)
+				.ok_or_else(|| ReportKind::UndefinedSym
+					.title(format!("'{ty}' is not defined"))
+					.span(expr.span))?;
+
+			let Some((id, _, Type::Fn(fn_args, fn_ret))) = self.method(ty, name.elem) else {
+				return Err(match def {
+					TypeDef::Enum { variants, .. } => no_variant(&def.ty(ty), variants, name),
+					TypeDef::Struct { .. } => ReportKind::TypeError
+						.title(format!("Type '{ty}' has no method '{}'", **name))
+						.span(name.span),
+				}.into());
+			};
+
+			self.mark_used(0, id);
+			let mut nodes = Vec::new();
+			let args = self.analyze_args(&mut nodes, fn_args, args, span)?;
+			return Ok((*fn_ret, nodes, Node::FuncCall { id: Var::Glob(id), args }));
+		}
+
+		let (rspan, rname) = (expr.span, ident(&expr.elem));
+		let (mut rty, mut nodes, mut recv) = self.analyze_expr(expr, None)?;
+
+		// methods are reached through a pointer too
+		let ty = match rty.base() {
+			Type::Ptr(ty) => ty.base(),
+			ty => ty,
+		};
+
+		let method = match ty {
+			Type::Struct(_, n) | Type::Enum(_, n) => self.method(n, name.elem),
+			_ => None,
+		};
+
+		let Some((id, _, Type::Fn(mut fn_args, fn_ret))) = method else {
+			return ReportKind::TypeError
+				.title(format!("Type '{rty}' has no method '{}'", **name))
+				.span(name.span)
+				.as_err();
+		};
+
+		let qualified = format!("{ty}.{}", **name);
+		if fn_args.is_empty() {
+			return ReportKind::TypeError
+				.title(format!("Method '{qualified}' has no receiver"))
+				.help(format!("Call it as '${qualified}(...)'"))
+				.span(span)
+				.as_err();
+		}
+
+		self.mark_used(0, id);
+		let recv_ty = fn_args.remove(0);
+		let by_ptr = matches!(recv_ty.base(), Type::Ptr(_)) && !matches!(rty.base(), Type::Ptr(_));
+
+		// a variable is passed by its address to methods taking a pointer
+		if let (true, Some(name)) = (by_ptr, rname) {
+			let addr = Sp { span: rspan, elem: ast::Node::AddrOf(Box::new(Sp { span: rspan, elem: ast::Node::Ident(name) })) };
+			(rty, nodes, recv) = self.analyze_expr(addr, None)?;
+		}
+
+		if !cmp_ty(&rty, &recv_ty) {
+			if let Some(e) = self.mut_mismatch(rname, &rty, &recv_ty, rspan) { return Err(e); }
+			let err = ReportKind::TypeError
+				.title(format!("Method '{qualified}' takes a '{recv_ty}' receiver, found '{rty}'"))
+				.span(rspan);
+
+			return match by_ptr {
+				true  => err.help("Store it in a variable first, its address is passed"),
+				false => err,
+			}.as_err();
+		}
+
+		let args = self.analyze_args(&mut nodes, fn_args, args, span)?;
+		let args = std::iter::once((recv, recv_ty)).chain(args).collect();
+		Ok((*fn_ret, nodes, Node::FuncCall { id: Var::Glob(id), args }))
+	}
+
+	fn method(&self, ty: &str, name: &str) -> Option<(ValId, String, Type)> {
+		let name = format!("{ty}.{name}");
+		self.get_global().locals.iter().rev().find(|(_, n, _)| *n == name).cloned()
+	}
+
+	/// Typechecks the args of a call against the types of its params, the nodes computing them go into `nodes`
+	fn analyze_args(&mut self, nodes: &mut Vec<Node>, fn_args: Vec<Type>, args: Vec<Sp<ast::Node>>, span: Span) -> Result<Vec<(Var, Type)>> {
+		if fn_args.len() != args.len() {
+			return ReportKind::InvalidArgCount
+				.title(format!("Expected {} arguments, got {}", fn_args.len(), args.len()))
+				.span(span)
+				.as_err();
+		}
+
+		let mut nargs = Vec::new();
+		for (arg, ty) in args.into_iter().zip(fn_args) {
+			let (span, name) = (arg.span, source(&arg.elem));
+			let (t, n, v) = self.analyze_expr(arg, Some(&ty))?;
+
+			if !cmp_ty(&t, &ty) {
+				if let Some(e) = self.mut_mismatch(name, &t, &ty, span).or_else(|| unwrap_mismatch(&t, &ty, span)) { return Err(e); }
+				return ReportKind::TypeError
+					.title("Type mismatch in function call")
+					.label(format!("expected '{ty}', found '{t}'"))
+					.span(span)
+					.as_err();
+			}
+
+			nodes.extend(n);
+			nargs.push((v, ty));
+		}
+
+		Ok(nargs)
+	}
+
+	/// `hint` is the type the value is expected to have, used to pick the type of literals.
+	/// A value is wrapped when an optional of its type is expected, and an array decays when a pointer or slice is
+	/// `hint` is the type the value is given, a literal has to fit in it
+	fn analyze_expr(&mut self, node: Sp<ast::Node>, hint: Option<&Type>) -> Result<(Type, Vec<Node>, Var)> {
+		let span = node.span;
+		let (ty, nodes, val) = self.analyze_hinted(node, hint)?;
+
+		if let (Some(hint), Var::Imm(v)) = (hint, &val) {
+			if ty.is_literal() && cmp_ty(&ty, hint) && !fits(v, hint) {
+				let err = ReportKind::TypeError
+					.title(format!("Literal '{v}' does not fit in a '{}'", hint.base()))
+					.span(span);
+
+				return Err(match int_range(hint) {
+					Some((min, max)) => err.label(format!("it holds {min} to {max}")),
+					None => err,
+				}.into());
+			}
+		}
+
+		Ok((ty, nodes, val))
+	}
+
+	/// Like `analyze_expr`, but a literal may not fit in `hint`, as when it is cast
+	fn analyze_hinted(&mut self, node: Sp<ast::Node>, hint: Option<&Type>) -> Result<(Type, Vec<Node>, Var)> {
+		let Some(Type::Opt(inner)) = hint.map(Type::base) else {
+			let (ty, nodes, val) = self.analyze_value(node, hint)?;
+			return Ok(self.decay(ty, nodes, val, hint));
+		};
+		if matches!(node.elem, ast::Node::None) { return self.analyze_value(node, hint); }
+
+		let (ty, mut nodes, val) = self.analyze_expr(node, Some(inner))?;
+		if matches!(ty.base(), Type::Opt(_)) || !cmp_ty(&ty, inner) {
+			return Ok((ty, nodes, val));
+		}
+
+		let ty = Type::Opt(Box::new(inner.base().clone()));
+		let id = self.new_tmp("__opt", &ty);
+		nodes.push(Node::Assign {
+			id, ty: ty.clone(),
+			val: Box::new(Node::OptLit(Some((val, inner.base().clone())))),
+		});
+		Ok((ty, nodes, Var::Local(id)))
+	}
+
+	/// An array used as a pointer or slice is the address of its first element. A global one is copied out
+	/// when it is used as a value, a global is only ever its address
+	fn decay(&mut self, ty: Type, mut nodes: Vec<Node>, val: Var, hint: Option<&Type>) -> (Type, Vec<Node>, Var) {
+		let Type::Arr(_, len) = ty.base() else { return (ty, nodes, val) };
+		let len = *len;
+
+		let decay = |this: &mut Self, nodes: &mut Vec<Node>, ty: Type, val: Var| {
+			let pty = Type::Ptr(Box::new(elem_ty(&ty)));
+			let id = this.new_tmp("__ptr", &pty);
+			nodes.push(Node::Assign { id, ty: pty.clone(), val: Box::new(Node::Decay { ty: ty.base().clone(), val }) });
+			(pty, Var::Local(id))
+		};
+
+		match (hint.map(Type::base), len) {
+			(Some(h @ Type::Ptr(_)), _) if cmp_ty(&ty, h) => {
+				let (pty, ptr) = decay(self, &mut nodes, ty, val);
+				(pty, nodes, ptr)
+			},
+			(Some(h @ Type::Arr(_, None)), Some(n)) if cmp_ty(&ty, h) => {
+				let sty = Type::Arr(Box::new(elem_ty(&ty)), None);
+				let (_, ptr) = decay(self, &mut nodes, ty, val);
+
+				let id = self.new_tmp("__slice", &sty);
+				nodes.push(Node::Assign { id, ty: sty.clone(), val: Box::new(Node::SliceLit { ptr, len: Var::Imm(n.into()) }) });
+				(sty, nodes, Var::Local(id))
+			},
+			(_, Some(_)) if matches!(val, Var::Glob(_)) => {
+				let id = self.new_tmp("__arr", &ty);
+				nodes.push(Node::Assign { id, ty: ty.clone(), val: Box::new(Node::Var(val)) });
+				(ty, nodes, Var::Local(id))
+			},
+			_ => (ty, nodes, val),
+		}
+	}
+
+	/// The array or slice `expr` and an `index` into it which is checked against its length.
+	/// A constant index into a sized array is checked here instead
+	fn analyze_index(&mut self, expr: Sp<ast::Node>, index: Sp<ast::Node>) -> Result<(Type, Vec<Node>, Var, Var)> {
+		let span = expr.span;
+		let (ty, mut nodes, val) = self.analyze_value(expr, None)?;
+
+		let len = match ty.base() {
+			Type::Arr(_, len) => *len,
+			Type::Ptr(pointee) if matches!(pointee.base(), Type::Void | Type::Fn(..)) => return ReportKind::TypeError
+				.title(format!("Cannot index a '{}'", ty.base()))
+				.label("what it points to has no size")
+				.span(span)
+				.as_err(),
+			// a pointer has no length to check against
+			Type::Ptr(_) => None,
+			_ => return ReportKind::TypeError
+				.title(format!("Cannot index a '{ty}', it is not an array"))
+				.span(span)
+				.as_err(),
+		};
+
+		let ispan = index.span;
+		let (ity, inodes, index) = self.analyze_expr(index, Some(&Type::Usize))?;
+		nodes.extend(inodes);
+
+		if !cmp_ty(&ity, &Type::Usize) {
+			return ReportKind::TypeError
+				.title(format!("Cannot index with a '{ity}'"))
+				.label("expected 'usize'")
+				.span(ispan)
+				.as_err();
+		}
+
+		let len = match (len, &index) {
+			(Some(n), Var::Imm(i)) if i.to_i128().is_none_or(|i| i >= i128::from(n)) => return ReportKind::TypeError
+				.title(format!("Index {i} is out of bounds"))
+				.label(format!("'{}' has {n} elements", ty.base()))
+				.span(ispan)
+				.as_err(),
+			(Some(_), Var::Imm(_)) => return Ok((ty, nodes, val, index)),
+			_ if !self.bounds_checks || matches!(ty.base(), Type::Ptr(_)) => return Ok((ty, nodes, val, index)),
+			(Some(n), _) => Var::Imm(n.into()),
+			(None, _) => {
+				let id = self.new_tmp("__len", &Type::Usize);
+				nodes.push(Node::Assign { id, ty: Type::Usize, val: Box::new(Node::Len(val.clone())) });
+				Var::Local(id)
+			},
+		};
+
+		// an index past the end traps
+		let (ok, oob) = (self.new_label(), self.new_label());
+		let cond = self.new_tmp("__tmp", &Type::B(1));
+		nodes.push(Node::Assign {
+			id: cond, ty: Type::B(1),
+			val: Box::new(Node::BinOp { op: BinOp::Lt, ty: Type::Usize, lhs: index.clone(), rhs: len }),
+		});
+		nodes.push(Node::Branch { cond: (Var::Local(cond), Type::B(1)), then: ok, els: oob });
+		nodes.push(Node::Label(oob));
+		nodes.push(Node::Trap("Index out of bounds"));
+		nodes.push(Node::Label(ok));
+		Ok((ty, nodes, val, index))
+	}
+
+	/// A pointer to element `index` of the array, slice or pointer `val`, the index is checked already
+	fn elem_ptr(&mut self, aty: &Type, val: Var, index: Var, nodes: &mut Vec<Node>) -> (Type, Var) {
+		let pty = Type::Ptr(Box::new(elem_ty(aty)));
+		let ptr = match aty.base() {
+			Type::Ptr(_) => val,
+			_ => {
+				let id = self.new_tmp("__ptr", &pty);
+				nodes.push(Node::Assign { id, ty: pty.clone(), val: Box::new(Node::Decay { ty: aty.base().clone(), val }) });
+				Var::Local(id)
+			},
+		};
+
+		let id = self.new_tmp("__ptr", &pty);
+		nodes.push(Node::Assign {
+			id, ty: pty.clone(),
+			val: Box::new(Node::Offset { ty: pty.clone(), ptr, op: BinOp::Add, index: (index, Type::Usize) }),
+		});
+		(pty, Var::Local(id))
+	}
+
+	fn analyze_value(&mut self, node: Sp<ast::Node>, hint: Option<&Type>) -> Result<(Type, Vec<Node>, Var)> {
+		Ok(match node.elem {
+			ast::Node::StrLit(s) => {
+				let id = self.get_global_mut().new_id();
+
+				let ty = Type::Arr(Type::U(8).into(), Some(s.len() as u64));
+				
+				self.get_global_mut().locals.push((id, format!("__const{id:?}"), ty.clone()));
+				(ty.clone(), vec![Node::Global { 
+					ty, id, 
+					val: Node::StrLit(s).into(),
+				}], Var::Glob(id))
+			},
+			ast::Node::UIntLit(v) => (Type::Puint, Vec::new(), Var::Imm(v)),
+			ast::Node::SIntLit(v) => (Type::Pint,  Vec::new(), Var::Imm(v)),
+			ast::Node::FloatLit(v) => (Type::Pfloat, Vec::new(), Var::FImm(v)),
+			ast::Node::CharLit(c)  => (Type::Puint,  Vec::new(), Var::Imm(u64::from(c).into())),
+			ast::Node::Ident(name) => {
+				let (depth, (id, _, ty)) = self.find_matching_descending(|(_, n, _)| n == name)
+					.ok_or_else(|| ReportKind::UndefinedSym
+						.title(format!("'{name}' is not defined"))
+						.span(node.span))?;
+				self.mark_used(depth, id);
+
+				(ty, Vec::new(), match depth {
+					0 => Var::Glob(id),
+					_ => Var::Local(id),
+				})
+			},
+			ast::Node::None => match hint.map(Type::base) {
+				Some(ty @ Type::Opt(_)) => {
+					let id = self.new_tmp("__opt", ty);
+					(ty.clone(), vec![Node::Assign { id, ty: ty.clone(), val: Box::new(Node::OptLit(None)) }], Var::Local(id))
+				},
+				Some(ty) => return ReportKind::TypeError
+					.title(format!("'none' is not a '{ty}'"))
+					.help(format!("Only optionals can be none, like 'opt {ty}'"))
+					.span(node.span)
+					.as_err(),
+				None => return ReportKind::TypeError
+					.title("Cannot infer the type of 'none'")
+					.help("Annotate the type, like 'let x: opt i32 = none'")
+					.span(node.span)
+					.as_err(),
+			},
+			ast::Node::Unwrap(expr) => {
+				let span = expr.span;
+				let ohint = hint.map(|h| Type::Opt(Box::new(h.base().clone())));
+				let (ty, mut nodes, val) = self.analyze_value(*expr, ohint.as_ref())?;
+
+				let Type::Opt(inner) = ty.base() else {
+					return ReportKind::TypeError
+						.title(format!("Cannot unwrap a '{ty}', it is not optional"))
+						.span(span)
+						.as_err();
+				};
+				let inner = inner.base().clone();
+
+				// a none traps, there is nothing to go on with
+				let (some, none) = (self.new_label(), self.new_label());
+				let cond = self.new_tmp("__tmp", &Type::B(1));
+				nodes.push(Node::Assign { id: cond, ty: Type::B(1), val: Box::new(Node::IsSome { ty: ty.clone(), val: val.clone() }) });
+				nodes.push(Node::Branch { cond: (Var::Local(cond), Type::B(1)), then: some, els: none });
+				nodes.push(Node::Label(none));
+				nodes.push(Node::Trap("Unwrapped a none value"));
+				nodes.push(Node::Label(some));
+
+				let id = self.new_tmp("__tmp", &inner);
+				nodes.push(Node::Assign { id, ty: inner.clone(), val: Box::new(Node::Unwrap { ty, val }) });
+				(inner, nodes, Var::Local(id))
+			},
+			ast::Node::AddrOf(expr) if matches!(expr.elem, ast::Node::Index { .. }) => {
+				let ast::Node::Index { expr, index } = expr.elem else { unreachable!() };
+				let (span, named) = (expr.span, matches!(expr.elem, ast::Node::Ident(_)));
+				let (aty, mut nodes, val, index) = self.analyze_index(*expr, *index)?;
+
+				// a slice or pointer points into memory of its own, a sized array has to be in a variable
+				if matches!(aty.base(), Type::Arr(_, Some(_))) && !named {
+					return ReportKind::TypeError
+						.title("Cannot take the address of an element of a temporary array")
+						.help("Store it in a variable first")
+						.span(span)
+						.as_err();
+				}
+
+				let (ty, p) = self.elem_ptr(&aty, val, index, &mut nodes);
+				(ty, nodes, p)
+			},
+			ast::Node::AddrOf(expr) => {
+				let ast::Node::Ident(name) = expr.elem else {
+					return ReportKind::TypeError
+						.title("Cannot take the address of a temporary value")
+						.help("Store it in a variable first")
+						.span(expr.span)
+						.as_err();
+				};
+
+				let (depth, (id, _, ty)) = self.find_matching_descending(|(_, n, _)| n == name)
+					.ok_or_else(|| ReportKind::UndefinedSym
+						.title(format!("'{name}' is not defined"))
+						.span(expr.span))?;
+				self.mark_used(depth, id);
+
+				if matches!(ty, Type::Fn(..)) {
+					return ReportKind::TypeError
+						.title(format!("Cannot take the address of function '{name}'"))
+						.help("Functions are passed by their name")
+						.span(expr.span)
+						.as_err();
+				}
+
+				let var = match depth {
+					0 => Var::Glob(id),
+					_ => Var::Local(id),
+				};
+
+				// the pointer allows writes if the variable does
+				let ty = Type::Ptr(Box::new(ty));
+				let id = self.new_tmp("__tmp", &ty);
+				(ty.clone(), vec![Node::Assign { id, ty, val: Box::new(Node::AddrOf(var)) }], Var::Local(id))
+			},
+			ast::Node::ArrLit(elems) => {
+				// the elements are typed by the hint, or else by the first of them
+				let mut ety = match hint.map(Type::base) {
+					Some(Type::Arr(ty, _)) => Some(ty.base().clone()),
+					_ => None,
+				};
+
+				let len = elems.len() as u64;
+				let mut nodes = Vec::new();
+				let mut vals = Vec::new();
+				for elem in elems {
+					let span = elem.span;
+					let (t, enodes, v) = self.analyze_expr(elem, ety.as_ref())?;
+					let ty = match ety.clone() {
+						Some(ty) => ty,
+						None => ety.insert(self.infer_ty(&t, None, span)).clone(),
+					};
+
+					if !cmp_ty(&t, &ty) {
+						return ReportKind::TypeError
+							.title("Type mismatch in array literal")
+							.label(format!("expected '{ty}', found '{t}'"))
+							.span(span)
+							.as_err();
+					}
+
+					nodes.extend(enodes);
+					vals.push((v, ty));
+				}
+
+				let Some(ety) = ety else {
+					return ReportKind::TypeError
+						.title("Cannot infer the type of an empty array")
+						.span(node.span)
+						.as_err();
+				};
+
+				let ty = Type::Arr(Box::new(ety), Some(len));
+				let id = self.new_tmp("__arr", &ty);
+				nodes.push(Node::Assign { id, ty: ty.clone(), val: Box::new(Node::ArrLit(vals)) });
+				(ty, nodes, Var::Local(id))
+			},
+			ast::Node::Index { expr, index } => {
+				let (aty, mut nodes, val, index) = self.analyze_index(*expr, *index)?;
+
+				// a pointer is read through at the offset
+				let ty = elem_ty(&aty).base().clone();
+				let val = match aty.base() {
+					Type::Ptr(_) => Node::Deref(self.elem_ptr(&aty, val, index, &mut nodes).1),
+					_ => Node::Index { ty: aty.base().clone(), val, index },
+				};
+
+				let id = self.new_tmp("__tmp", &ty);
+				nodes.push(Node::Assign { id, ty: ty.clone(), val: Box::new(val) });
+				(ty, nodes, Var::Local(id))
+			},
+			ast::Node::Deref(expr) => {
+				let span = expr.span;
+				let (pty, mut nodes, p) = self.analyze_expr(*expr, None)?;
+
+				let ty = match pty.base() {
+					Type::Ptr(ty) if matches!(ty.base(), Type::Void | Type::Fn(..)) => return ReportKind::TypeError
+						.title(format!("Cannot dereference a '{}'", pty.base()))
+						.label("what it points to has no size")
+						.span(span)
+						.as_err(),
+					Type::Ptr(ty) => ty.base().clone(),
+					_ => return ReportKind::TypeError
+						.title(format!("Cannot dereference a '{pty}', it is not a pointer"))
+						.span(span)
+						.as_err(),
+				};
+
+				let id = self.new_tmp("__tmp", &ty);
+				nodes.push(Node::Assign { id, ty: ty.clone(), val: Box::new(Node::Deref(p)) });
+				(ty, nodes, Var::Local(id))
+			},
+			ast::Node::Cast { expr, ty } => {
+				let to = self.convert_ty(&ty)?.base().clone();
+				let (from, mut nodes, val) = self.analyze_hinted(*expr, Some(&to))?;
+
+				// a value that already fits, literals in range included, only changes its type.
+				// A type variable is left to its uses, it is checked as its default until then
+				if cmp_ty(&from, &to) && !matches!(from.base(), Type::Infer(..)) && !matches!(&val, Var::Imm(v) if !fits(v, &to)) {
+					return Ok((to, nodes, val));
+				}
+
+				let checked = concretize(&from, None);
+				if let Some(why) = cast(&checked, &to, node.span)? {
+					self.warnings.push(ReportKind::LossyCast
+						.title(format!("Casting a '{checked}' to a '{to}' may change its value"))
+						.label(why)
+						.span(node.span));
+				}
+
+				let from = match from.base() {
+					Type::Infer(..) => from.base().clone(),
+					_ => checked,
+				};
+
+				let id = self.new_tmp("__tmp", &to);
+				nodes.push(Node::Assign { id, ty: to.clone(), val: Box::new(Node::Cast { ty: from, val }) });
+				(to, nodes, Var::Local(id))
+			},
+			ast::Node::BinOp { op, lhs, rhs } if matches!(*op, BinOp::Eq | BinOp::Ne)
+				&& (matches!(lhs.elem, ast::Node::None) || matches!(rhs.elem, ast::Node::None)) => {
+				let expr = match lhs.elem {
+					ast::Node::None => *rhs,
+					_ => *lhs,
+				};
+
+				let span = expr.span;
+				let (ty, mut nodes, val) = self.analyze_value(expr, None)?;
+				if !matches!(ty.base(), Type::Opt(_)) {
+					return ReportKind::TypeError
+						.title(format!("Cannot compare a '{ty}' with 'none'"))
+						.label("it is not optional")
+						.help("Only optionals can be compared with 'none'")
+						.span(span)
+						.as_err();
+				}
+
+				let res = concretize(&Type::Pbool, hint);
+				let id = self.new_tmp("__tmp", &res);
+				nodes.push(Node::Assign { id, ty: res.clone(), val: Box::new(Node::IsSome { ty, val }) });
+
+				if *op == BinOp::Ne { return Ok((res, nodes, Var::Local(id))); }
+
+				let not = self.new_tmp("__tmp", &res);
+				nodes.push(Node::Assign {
+					id: not, ty: res.clone(),
+					val: Box::new(Node::UnOp { op: UnOp::Not, ty: res.clone(), val: Var::Local(id) }),
+				});
+				(res, nodes, Var::Local(not))
+			},
+			ast::Node::FuncCall { name, ty_args, args } => {
+				let (ret, mut nodes, call) = self.analyze_call(&name, &ty_args, args, hint, node.span)?;
+
+				if ret == Type::Void {
+					return ReportKind::TypeError
+						.title(format!("'{}' does not return a value", *name))
+						.span(node.span)
+						.as_err();
+				}
+
+				let id = self.new_tmp("__ret", &ret);
+				nodes.push(Node::Assign { id, ty: ret.clone(), val: Box::new(call) });
+				(ret, nodes, Var::Local(id))
+			},
+			ast::Node::BinOp { op, lhs, rhs } => {
+				// the operands of a comparison are unrelated to its result
+				let ohint = if op.is_cmp() { None } else { hint };
+
+				let (lt, mut nodes, lhs) = self.analyze_expr(*lhs, ohint)?;
+				let (rt, rnodes, rhs) = self.analyze_expr(*rhs, ohint.or(Some(&lt)))?;
+				nodes.extend(rnodes);
+
+				// adding goes either way, the pointer is offset by the int
+				let ((lt, lhs), (rt, rhs)) = match (lt.base(), rt.base()) {
+					(lty, Type::Ptr(_)) if *op == BinOp::Add && lty.is_int() => ((rt, rhs), (lt, lhs)),
+					_ => ((lt, lhs), (rt, rhs)),
+				};
+
+				if let (BinOp::Add | BinOp::Sub, Type::Ptr(pointee)) = (*op, lt.base()) {
+					// the distance between two pointers is in the values they point to
+					let diff = *op == BinOp::Sub && matches!(rt.base(), Type::Ptr(_));
+					if diff && immutable(&lt) != immutable(&rt) {
+						return ReportKind::TypeError
+							.title("Type mismatch in binary expression")
+							.label(format!("'{}' - '{}'", lt.base(), rt.base()))
+							.help("Only pointers to the same type can be subtracted")
+							.span(node.span)
+							.as_err();
+					}
+
+					if (diff || rt.is_int()) && matches!(pointee.base(), Type::Void | Type::Fn(..)) {
+						return ReportKind::TypeError
+							.title(format!("Cannot {} a '{}'", if diff { "subtract" } else { "offset" }, lt.base()))
+							.label("what it points to has no size")
+							.span(node.span)
+							.as_err();
+					}
+
+					if diff {
+						let id = self.new_tmp("__tmp", &Type::Isize);
+						nodes.push(Node::Assign {
+							id, ty: Type::Isize,
+							val: Box::new(Node::Diff { ty: lt.base().clone(), lhs, rhs }),
+						});
+						return Ok((Type::Isize, nodes, Var::Local(id)));
+					}
+
+					if rt.is_int() {
+						let ty = lt.base().clone();
+						let id = self.new_tmp("__tmp", &ty);
+						nodes.push(Node::Assign {
+							id, ty: ty.clone(),
+							val: Box::new(Node::Offset { ty: ty.clone(), ptr: lhs, op: *op, index: (rhs, match rt.base() {
+								Type::Infer(..) => rt.base().clone(),
+								_ => concretize(&rt, Some(&Type::I(64))),
+							}) }),
+						});
+						return Ok((ty, nodes, Var::Local(id)));
+					}
+				}
+
+				let ty = unify(&lt, &rt).ok_or_else(|| {
+					let err = ReportKind::TypeError
+						.title("Type mismatch in binary expression")
+						.label(format!("'{lt}' {} '{rt}'", *op))
+						.span(node.span);
+
+					match matches!(lt.base(), Type::Opt(_)) || matches!(rt.base(), Type::Opt(_)) {
+						true  => err.help("Unwrap optionals with '?' first"),
+						false => err,
+					}
+				})?;
+
+				let valid = match *op {
+					BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => ty.is_int() || ty.is_float(),
+					// pointers are ordered by their address
+					BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => ty.is_int() || ty.is_float() 
+						|| matches!(ty.base(), Type::Ptr(_)),
+					BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => ty.is_int() || ty.is_bool(),
+					BinOp::Shl | BinOp::Shr => ty.is_int(),
+					BinOp::Eq | BinOp::Ne => ty.is_int() || ty.is_float() || ty.is_bool() 
+						|| matches!(ty.base(), Type::Ptr(_)),
+					op if op.is_logical() => ty.is_bool(),
+					_ => unreachable!(),
+				};
+
+				if !valid {
+					let err = ReportKind::TypeError
+						.title(format!("Operator '{}' cannot be applied to type '{ty}'", *op))
+						.span(op.span);
+
+					return Err(match ty.base() {
+						Type::Opt(_) => err.help("Unwrap it with '?' first, or compare it with 'none'"),
+						_ => err,
+					}.into());
+				}
+
+				let (res, ty) = match op.is_cmp() {
+					true  => (concretize(&Type::Pbool, hint), self.infer_ty(&ty, None, node.span)),
+					false => { let ty = self.infer_ty(&ty, hint, node.span); (ty.clone(), ty) },
+				};
+
+				let id = self.new_tmp("__tmp", &res);
+				nodes.push(Node::Assign { 
+					id, ty: res.clone(), 
+					val: Box::new(Node::BinOp { op: *op, ty, lhs, rhs }),
+				});
+				(res, nodes, Var::Local(id))
+			},
+			ast::Node::UnOp { op, expr } => {
+				let (ty, mut nodes, val) = self.analyze_expr(*expr, hint)?;
+
+				let valid = match *op {
+					UnOp::Neg    => ty.is_signed() || ty.is_float(),
+					UnOp::Not    => ty.is_bool(),
+					UnOp::BitNot => ty.is_int(),
+				};
+
+				if !valid {
+					let mut err = ReportKind::TypeError
+						.title(format!("Operator '{}' cannot be applied to type '{ty}'", *op))
+						.span(op.span);
+
+					if *op == UnOp::Neg && ty.is_int() {
+						err = err.help("Unsigned values cannot be negated, cast to a signed type first");
+					}
+
+					return Err(err.into());
+				}
+
+				let ty = self.infer_ty(&ty, hint, node.span);
+				let id = self.new_tmp("__tmp", &ty);
+				nodes.push(Node::Assign { 
+					id, ty: ty.clone(), 
+					val: Box::new(Node::UnOp { op: *op, ty: ty.clone(), val }),
+				});
+				(ty, nodes, Var::Local(id))
+			},
+			ast::Node::StructLit { name, fields } => {
+				// the args of a generic struct come from the type the value is expected to have
+				let tname = match hint.map(Type::base) {
+					Some(Type::Struct(id, inst)) if self.inst_args.get(id).is_some_and(|(g, _)| *g == name.elem) => inst.as_str(),
+					_ if self.generic_tys.contains_key(name.elem) => return ReportKind::TypeError
+						.title(format!("Cannot infer the type arguments of '{}'", *name))
+						.help(format!("Annotate the type, like 'let x: {}<...> = ...'", *name))
+						.span(name.span)
+						.as_err(),
+					_ => name.elem,
+				};
+
+				let def = self.types.get(tname)
+					.ok_or_else(|| ReportKind::UndefinedSym
+						.title(format!("Type '{}' is not defined", *name))
+						.span(name.span))?;
+
+				let ty = def.ty(tname);
+				let TypeDef::Struct { fields: def_fields, .. } = def else {
+					return ReportKind::TypeError
+						.title(format!("'{ty}' is not a struct"))
+						.span(name.span)
+						.as_err();
+				};
+				let def_fields = def_fields.clone();
+
+				let mut nodes = Vec::new();
+				let mut vals: Vec<Option<(Var, Type)>> = std::iter::repeat_with(|| None).take(def_fields.len()).collect();
+				for (field, expr) in fields {
+					let Some(i) = def_fields.iter().position(|(n, _)| n == field.elem) else {
+						return ReportKind::TypeError
+							.title(format!("'{ty}' has no field '{}'", *field))
+							.span(field.span)
+							.as_err();
+					};
+
+					if vals[i].is_some() {
+						return ReportKind::TypeError
+							.title(format!("Field '{}' is given twice", *field))
+							.span(field.span)
+							.as_err();
+					}
+
+					let span = expr.span;
+					let fty = &def_fields[i].1;
+					let (t, n, v) = self.analyze_expr(expr, Some(fty))?;
+
+					if !cmp_ty(&t, fty) {
+						return ReportKind::TypeError
+							.title("Type mismatch in struct literal")
+							.label(format!("expected '{fty}', found '{t}'"))
+							.span(span)
+							.as_err();
+					}
+
+					nodes.extend(n);
+					vals[i] = Some((v, fty.clone()));
+				}
+
+				let missing: Vec<String> = vals.iter().zip(&def_fields)
+					.filter(|(v, _)| v.is_none())
+					.map(|(_, (n, _))| format!("'{n}'"))
+					.collect();
+
+				if !missing.is_empty() {
+					return ReportKind::TypeError
+						.title(format!("Missing fields in '{ty}' literal"))
+						.label(format!("{} not given", missing.join(", ")))
+						.span(node.span)
+						.as_err();
+				}
+
+				let id = self.new_tmp("__tmp", &ty);
+				nodes.push(Node::Assign {
+					id, ty: ty.clone(),
+					val: Box::new(Node::StructLit(vals.into_iter().map(Option::unwrap).collect())),
+				});
+				(ty, nodes, Var::Local(id))
+			},
+			ast::Node::Field { expr, field } if self.enum_of(&expr, hint).is_some() => {
+				let (ty, variants) = self.enum_of(&expr, hint).unwrap();
+				self.analyze_variant(ty, &variants, &field, None, node.span)?
+			},
+			ast::Node::MethodCall { expr, name, mut args } if self.is_variant(&expr, &name, hint) => {
+				let (ty, variants) = self.enum_of(&expr, hint).unwrap();
+
+				if args.len() > 1 {
+					return ReportKind::InvalidArgCount
+						.title(format!("Expected 1 argument, got {}", args.len()))
+						.span(node.span)
+						.as_err();
+				}
+
+				self.analyze_variant(ty, &variants, &name, args.pop(), node.span)?
+			},
+			ast::Node::Field { ref expr, .. } | ast::Node::MethodCall { ref expr, .. } if self.generic_path(expr).is_some() => {
+				let name = self.generic_path(expr).unwrap();
+				return ReportKind::TypeError
+					.title(format!("Cannot infer the type arguments of '{name}'"))
+					.help(format!("Annotate the type, like 'let x: {name}<...> = ...'"))
+					.span(node.span)
+					.as_err();
+			},
+			ast::Node::MethodCall { expr, name, args } => {
+				let (ret, mut nodes, call) = self.analyze_method(*expr, &name, args, node.span)?;
+
+				if ret == Type::Void {
+					return ReportKind::TypeError
+						.title(format!("'{}' does not return a value", *name))
+						.span(node.span)
+						.as_err();
+				}
+
+				let id = self.new_tmp("__ret", &ret);
+				nodes.push(Node::Assign { id, ty: ret.clone(), val: Box::new(call) });
+				(ret, nodes, Var::Local(id))
+			},
+			ast::Node::Field { expr, field } => {
+				let span = expr.span;
+				let (ty, mut nodes, val) = self.analyze_expr(*expr, None)?;
+
+				match (ty.base(), *field) {
+					(Type::Arr(_, Some(n)), "len") => return Ok((Type::Usize, nodes, Var::Imm((*n).into()))),
+					(Type::Arr(_, None), "len") => {
+						let id = self.new_tmp("__len", &Type::Usize);
+						nodes.push(Node::Assign { id, ty: Type::Usize, val: Box::new(Node::Len(val)) });
+						return Ok((Type::Usize, nodes, Var::Local(id)));
+					},
+					(Type::Arr(..), _) => return ReportKind::TypeError
+						.title(format!("'{}' has no field '{}'", ty.base(), *field))
+						.span(field.span)
+						.note("It only has 'len'")
+						.as_err(),
+					_ => (),
+				}
+
+				let (sty, deref, index, fty) = self.struct_field(&ty, span, &field)?;
+
+				let id = self.new_tmp("__tmp", &fty);
+				nodes.push(Node::Assign {
+					id, ty: fty.clone(),
+					val: Box::new(Node::Field { ty: sty, val, index, deref }),
+				});
+				(fty, nodes, Var::Local(id))
+			},
+			ast::Node::Func { .. } | ast::Node::Struct { .. } | ast::Node::Alias { .. } | ast::Node::Impl { .. }
+				| ast::Node::Import(_) | ast::Node::Enum { .. } | ast::Node::Assign { .. } | ast::Node::Store { .. }
+				| ast::Node::Ret(_) | ast::Node::If { .. } | ast::Node::While { .. } | ast::Node::Loop(_)
+				| ast::Node::Match { .. } | ast::Node::Break | ast::Node::Continue
+				=> unreachable!("statements are not parsed as expressions"),
+		})
+	}
+}
+
+/// The error for a variant `name` that `ty` does not have
+fn no_variant(ty: &Type, variants: &[(String, Option<Type>)], name: &Sp<&str>) -> Report {
+	ReportKind::TypeError
+		.title(format!("'{ty}' has no variant '{}'", **name))
+		.span(name.span)
+		.note(match variants.is_empty() {
+			true  => String::from("It has no variants"),
+			false => format!("It has {}", variants.iter()
+				.map(|(n, _)| format!("'{n}'"))
+				.collect::<Vec<_>>().join(", ")),
+		})
+}
+
+/// The name of the variable an expression is, if it is one
+fn ident<'src>(node: &ast::Node<'src>) -> Option<&'src str> {
+	match node {
+		ast::Node::Ident(name) => Some(name),
+		_ => None,
+	}
+}
+
+/// The variable a value is read from, or whose address it is
+fn source<'src>(node: &ast::Node<'src>) -> Option<&'src str> {
+	match node {
+		ast::Node::AddrOf(expr) => ident(&expr.elem),
+		node => ident(node),
+	}
+}
+
+/// Whether a value of `ty` has a `id` struct or enum inline, rather than behind a pointer
+fn contains(ty: &Type, id: TypeId) -> bool {
+	match ty {
+		Type::Struct(i, _) | Type::Enum(i, _) => *i == id,
+		Type::Arr(ty, Some(_)) | Type::Mut(ty) | Type::Opt(ty) => contains(ty, id),
+		_ => false,
+	}
+}
+
+/// The name the linker knows a function by, it does not know about modules
+fn symbol(name: &str) -> &str {
+	name.rsplit('.').next().unwrap()
+}
+
+/// The types `ty` holds by value, which have to be laid out before it
+fn held_types(ty: &Type, held: &mut Vec<TypeId>) {
+	match ty {
+		Type::Struct(id, _) | Type::Enum(id, _) => held.push(*id),
+		Type::Arr(ty, Some(_)) | Type::Mut(ty) | Type::Opt(ty) => held_types(ty, held),
+		_ => (),
+	}
+}
+
+/// The type of the elements of the array, slice or pointer `ty`, those of a sized array are as mutable as it is
+fn elem_ty(ty: &Type) -> Type {
+	match (ty, ty.base()) {
+		(Type::Mut(_), Type::Arr(elem, Some(_))) => Type::Mut(Box::new(elem.base().clone())),
+		(_, Type::Arr(elem, _) | Type::Ptr(elem)) => (**elem).clone(),
+		_ => unreachable!(),
+	}
+}
+
+/// An optional used where its value is expected, it has to be unwrapped first
+fn unwrap_mismatch(found: &Type, expected: &Type, span: Span) -> Option<Box<Report>> {
+	let Type::Opt(inner) = found.base() else { return None };
+	if !cmp_ty(inner, expected) { return None; }
+
+	Some(ReportKind::TypeError
+		.title(format!("Cannot use a '{}' as a '{}'", found.base(), expected.base()))
+		.label("it may be none")
+		.help("Unwrap it with '?' first")
+		.span(span)
+		.into())
+}
+
+/// The type both operands of a binary expression are brought to
+fn unify(ty1: &Type, ty2: &Type) -> Option<Type> {
+	let (ty1, ty2) = (ty1.base(), ty2.base());
+
+	match (ty1, ty2) {
+		// a type variable takes the literals it is used with, and is bound by its uses later
+		(Type::Infer(id, k1), Type::Infer(_, k2)) => Some(Type::Infer(*id, Box::new(unify(k1, k2)?))),
+		(Type::Infer(id, kind), ty) | (ty, Type::Infer(id, kind)) if ty.is_literal() => Some(Type::Infer(*id, Box::new(unify(kind, ty)?))),
+		(Type::Infer(_, kind), ty) | (ty, Type::Infer(_, kind)) => cmp_ty(kind, ty).then(|| ty.clone()),
+		(Type::Puint, Type::Pint) | (Type::Pint, Type::Puint) => Some(Type::Pint),
+		(Type::Puint | Type::Pint, Type::Pfloat) | (Type::Pfloat, Type::Puint | Type::Pint) => Some(Type::Pfloat),
+		_ if ty1 == ty2 => Some(ty1.clone()),
+		_ if ty1.is_literal() && cmp_ty(ty1, ty2) => Some(ty2.clone()),
+		_ if ty2.is_literal() && cmp_ty(ty2, ty1) => Some(ty1.clone()),
+		_ => None,
+	}
+}
+
+/// Gives a literal type a concrete one, from the hint if it fits
+fn concretize(ty: &Type, hint: Option<&Type>) -> Type {
+	if !ty.is_literal() { return infer::defaulted(ty.base()); }
+
+	match hint.map(Type::base) {
+		Some(hint) if !hint.is_literal() && cmp_ty(ty, hint) => hint.clone(),
+		_ => match ty.kind() {
+			Type::Puint  => Type::U(64),
+			Type::Pint   => Type::I(64),
+			Type::Pfloat => Type::F(64),
+			Type::Pbool  => Type::B(1),
+			_ => unreachable!(),
+		},
+	}
+}
+
+/// Whether a value of `ty1` can be used as a `ty2`, a value is copied so the `mut` of either does not matter.
+/// What a pointer points to is not, so a pointer can lose its `mut` but never gain one
+fn cmp_ty(ty1: &Type, ty2: &Type) -> bool {
+	match (ty1.base(), ty2.base()) {
+		// a type variable is checked as its literals until it is bound
+		(Type::Infer(_, k1), Type::Infer(_, k2)) => unify(k1, k2).is_some(),
+		(Type::Infer(_, kind), ty2) => cmp_ty(kind, ty2),
+		(ty1, Type::Infer(_, kind)) if ty1.is_literal() => unify(ty1, kind).is_some(),
+		(ty1, Type::Infer(_, kind)) => cmp_ty(kind, ty1),
+		(Type::Puint, ty2)  => matches!(ty2, Type::Puint  | Type::U(_) | Type::Usize | Type::I(_) | Type::F(_)),
+		(Type::Pint, ty2)   => matches!(ty2, Type::Pint   | Type::I(_) | Type::Isize | Type::F(_)),
+		(Type::Pbool, ty2)  => matches!(ty2, Type::Pbool  | Type::B(_)),
+		(Type::Pfloat, ty2) => matches!(ty2, Type::Pfloat | Type::F(_)),
+		// the elements of an array are as mutable as the array
+		(Type::Arr(el1, _), Type::Ptr(el2) | Type::Arr(el2, None)) 
+			=> (matches!(ty1, Type::Mut(_)) || matches!(**el1, Type::Mut(_)) || !matches!(**el2, Type::Mut(_))) && cmp_ty(el1, el2),
+		(Type::Ptr(p1), Type::Ptr(p2)) => pointee_fits(p1, p2),
+		(ty1, ty2) => ty1 == ty2,
+	}
+}
+
+fn pointee_fits(p1: &Type, p2: &Type) -> bool {
+	(matches!(p1, Type::Mut(_)) || !matches!(p2, Type::Mut(_))) && match (p1.base(), p2.base()) {
+		(Type::Ptr(p1), Type::Ptr(p2)) => pointee_fits(p1, p2),
+		(p1, p2) if matches!(p1, Type::Infer(..)) || matches!(p2, Type::Infer(..)) => cmp_ty(p1, p2),
+		(p1, p2) => p1 == p2,
+	}
+}
+
+/// Checks that a `from` can be cast to a `to`, yielding why the value may change if it can
+fn cast(from: &Type, to: &Type, span: Span) -> Result<Option<&'static str>> {
+	let bits = |ty: &Type| match ty {
+		Type::U(n) | Type::I(n) | Type::F(n) => *n,
+		_ => 64, // usize, isize and pointers
+	};
+	// how many bits of an int a float holds exactly
+	let mantissa = |ty: &Type| if bits(ty) == 32 { 24 } else { 53 };
+
+	Ok(match (from.base(), to.base()) {
+		(from, to) if from == to => None,
+		(from, to) if from.is_int() && to.is_int() => match (from.is_signed(), to.is_signed()) {
+			(true, false) => Some("negative values wrap around"),
+			(false, true) if bits(to) == bits(from) => Some("large values wrap around"),
+			_ if bits(to) < bits(from) => Some("values that do not fit are truncated"),
+			_ => None,
+		},
+		// bools are only ever 0 or 1
+		(from, to) if from.is_bool() && (to.is_int() || to.is_bool()) => None,
+		(from, to) if from.is_int() && to.is_float() => (bits(from) > mantissa(to)).then_some("large values are rounded"),
+		(from, to) if from.is_float() && to.is_int() => Some("the fraction is dropped"),
+		(from, to) if from.is_float() && to.is_float() => (bits(to) < bits(from)).then_some("precision is lost"),
+		(Type::Ptr(_), Type::Ptr(_)) => None,
+		(Type::Ptr(_), to) if to.is_int() => (bits(to) < 64).then_some("the address is truncated"),
+		(from, Type::Ptr(_)) if from.is_int() => None,
+		_ => {
+			let err = ReportKind::TypeError
+				.title(format!("Cannot cast a '{from}' to a '{to}'"))
+				.span(span);
+
+			return Err(match to.is_bool() && (from.is_int() || from.is_float()) {
+				true  => err.help("Compare it with 0 instead"),
+				false => err,
+			}.into());
+		},
+	})
+}
+
+/// Whether the literal `v` keeps its value as a `ty`
+fn fits(v: &IBig, ty: &Type) -> bool {
+	let Some(v) = v.to_i128() else { return false };
+
+	int_range(ty).is_none_or(|(min, max)| v >= min && u128::try_from(v).ok().is_none_or(|v| v <= max))
+}
+
+/// The smallest and largest value of an integer type, none for those too wide to bother
+fn int_range(ty: &Type) -> Option<(i128, u128)> {
+	match ty.base() {
+		Type::U(n) if *n < 128 => Some((0, (1 << n) - 1)),
+		Type::I(n) if *n < 128 => Some((-(1 << (n - 1)), (1 << (n - 1)) - 1)),
+		Type::Usize => Some((0, u64::MAX.into())),
+		Type::Isize => Some((i64::MIN.into(), i64::MAX.unsigned_abs().into())),
+		_ => None,
+	}
+}
+
+/// `ty` with every `mut` behind its pointers dropped
+fn immutable(ty: &Type) -> Type {
+	match ty.base() {
+		Type::Ptr(ty) => Type::Ptr(Box::new(immutable(ty))),
+		Type::Arr(ty, None) => Type::Arr(Box::new(immutable(ty)), None),
+		ty => ty.clone(),
 	}
 }
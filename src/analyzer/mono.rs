@@ -0,0 +1,417 @@
+//! Monomorphization, generics are instantiated once for every set of type args they are used with.
+//! Each instance is analyzed like a normal function or type, with the params standing for the args,
+//! so it comes out as a plain `Func`, `Struct` or `Enum` with a name of its own.
+use crate::report::{Result, ReportKind};
+use crate::span::{Sp, Span};
+use crate::parser::ast;
+
+use super::{Analyzer, Node, Var, Type, ValId, cmp_ty, concretize, unify, unwrap_mismatch};
+
+/// Generics using themselves with ever larger types would never stop instantiating
+const MAX_DEPTH: usize = 64;
+
+/// An instance of a generic function whose body is yet to be analyzed
+pub(super) struct Pending<'src> {
+	params: Vec<&'src str>,
+	args:   Vec<Type>,
+	node:   Sp<ast::Node<'src>>,
+	id:     ValId,
+	depth:  usize, // of the instance it is used by, 0 outside of one
+	pub(super) file: &'static str, // of the generic, what is reported about the body is in it
+}
+
+impl<'src> Analyzer<'src> {
+	pub(super) fn declare_generic(&mut self, node: Sp<ast::Node<'src>>) -> Result<()> {
+		let (name, generics) = match &node.elem {
+			ast::Node::Func { name, generics, attrs, .. } => {
+				for attr in attrs {
+					let what = match **attr {
+						ast::Attrs::Extern => "extern",
+						ast::Attrs::Export => "exported",
+						ast::Attrs::Pub    => continue,
+					};
+
+					return ReportKind::TypeError
+						.title(format!("Generic functions cannot be {what}"))
+						.help("Call it from a function which is not generic")
+						.span(name.span)
+						.as_err();
+				}
+
+				(name, generics)
+			},
+			ast::Node::Struct { name, generics, .. } | ast::Node::Enum { name, generics, .. } => (name, generics),
+			_ => unreachable!(),
+		};
+
+		for (i, param) in generics.iter().enumerate() {
+			if generics[..i].iter().any(|p| p.elem == param.elem) {
+				return ReportKind::TypeError
+					.title(format!("Type parameter '{}' is declared twice", **param))
+					.span(param.span)
+					.as_err();
+			}
+		}
+
+		let name = name.clone();
+		if matches!(node.elem, ast::Node::Func { .. }) {
+			if self.generic_fns.contains_key(name.elem) {
+				return ReportKind::TypeError
+					.title(format!("Generic function '{}' is already defined", *name))
+					.span(name.span)
+					.as_err();
+			}
+
+			self.generic_fns.insert(name.elem, (self.file, node));
+		} else {
+			if self.types.contains_key(name.elem) || self.aliases.contains_key(name.elem)
+				|| self.generic_tys.contains_key(name.elem) {
+				return ReportKind::TypeError
+					.title(format!("Type '{}' is already defined", *name))
+					.span(name.span)
+					.as_err();
+			}
+
+			self.generic_tys.insert(name.elem, (self.file, node));
+		}
+
+		Ok(())
+	}
+
+	/// `name<T, ...>` with the args of the generic being instantiated, just `name` outside of one
+	pub(super) fn inst_name(&self, name: &str) -> String {
+		if self.ty_args.is_empty() { return name.to_string(); }
+
+		let args: Vec<String> = self.ty_args.iter().map(|(_, t)| plain(t)).collect();
+		format!("{name}<{}>", args.join(", "))
+	}
+
+	/// The enum in `Enum.Variant` when it is generic, so its args cannot be known
+	pub(super) fn generic_path(&self, expr: &Sp<ast::Node>) -> Option<&'src str> {
+		let ast::Node::Ident(name) = expr.elem else { return None };
+		if self.find_matching_descending(|(_, n, _)| n == name).is_some() { return None; }
+
+		self.generic_tys.get_key_value(name).map(|(k, _)| *k)
+	}
+
+	pub(super) fn instantiate_type(&mut self, name: &str, args: Vec<Type>, span: Span) -> Result<Type> {
+		let Some((&generic, (file, node))) = self.generic_tys.get_key_value(name) else {
+			return match self.types.contains_key(name) || self.aliases.contains_key(name) {
+				true  => ReportKind::TypeError.title(format!("Type '{name}' takes no type arguments")),
+				false => ReportKind::UndefinedSym.title(format!("Type '{name}' is not defined")),
+			}.span(span).as_err();
+		};
+
+		let (file, node) = (*file, node.clone());
+		let (ast::Node::Struct { generics, .. } | ast::Node::Enum { generics, .. }) = &node.elem
+			else { unreachable!() };
+		let params: Vec<&'src str> = generics.iter().map(|g| g.elem).collect();
+
+		if generics.len() != args.len() {
+			return ReportKind::InvalidArgCount
+				.title(format!("Type '{name}' takes {} type arguments, got {}", generics.len(), args.len()))
+				.span(span)
+				.as_err();
+		}
+
+		let inst = inst_name(name, &args);
+		if let Some(def) = self.types.get(&inst) {
+			return Ok(def.ty(&inst));
+		}
+
+		let def = self.instantiate(params, args.clone(), file, &node.clone(), |a| a.analyze_root(node))?;
+		self.instances.push(def);
+
+		let ty = self.types[&inst].ty(&inst);
+		let (Type::Struct(id, _) | Type::Enum(id, _)) = ty else { unreachable!() };
+		self.inst_args.insert(id, (generic, args));
+		Ok(ty)
+	}
+
+	fn instantiate_fn(&mut self, name: &str, args: Vec<Type>) -> Result<(ValId, Type)> {
+		let inst = inst_name(name, &args);
+		let find = |a: &Self| a.get_global().locals.iter()
+			.find(|(_, n, _)| *n == inst)
+			.map(|(id, _, ty)| (*id, ty.clone()));
+
+		if let Some(func) = find(self) {
+			self.mark_used(0, func.0);
+			return Ok(func);
+		}
+
+		let (file, node) = self.generic_fns[name].clone();
+		let ast::Node::Func { generics, .. } = &node.elem else { unreachable!() };
+		let params: Vec<&'src str> = generics.iter().map(|g| g.elem).collect();
+
+		// only the signature is needed by the call, the body is analyzed once the current root is done
+		let depth = self.inst_depth;
+		self.instantiate(params.clone(), args.clone(), file, &node, |a| a.declare_func(&node))?;
+
+		// other instances of the same generic may be declared before it, they all share where its name is
+		let (id, ty) = find(self).unwrap();
+		let ast::Node::Func { name, .. } = &node.elem else { unreachable!() };
+		self.declared.remove(&(file, name.span));
+
+		self.pending.push(Pending { params, args, node, id, depth, file });
+		self.mark_used(0, id);
+		Ok((id, ty))
+	}
+
+	/// Analyzes the body of an instance whose signature was declared by `instantiate_fn`
+	pub(super) fn analyze_instance(&mut self, inst: Pending<'src>) -> Result<Node> {
+		let Pending { params, args, node, id, depth, file } = inst;
+		let ast::Node::Func { name, .. } = &node.elem else { unreachable!() };
+		self.declared.insert((file, name.span), id);
+
+		self.inst_depth = depth;
+		let res = self.instantiate(params, args, file, &node.clone(), |a| a.analyze_root(node));
+		self.inst_depth = 0;
+		res
+	}
+
+	/// Runs `f` on `node` as a root, with `params` standing for `args` and nothing of the current function visible.
+	/// What is reported about it is in `file`, the one it is declared in
+	fn instantiate<T>(&mut self, params: Vec<&'src str>, args: Vec<Type>, file: &'static str, node: &Sp<ast::Node<'src>>,
+		f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+		let (ast::Node::Func { name, .. } | ast::Node::Struct { name, .. } | ast::Node::Enum { name, .. }) = &node.elem
+			else { unreachable!() };
+		let inst = inst_name(name.elem, &args);
+
+		if self.inst_depth >= MAX_DEPTH {
+			return ReportKind::TypeError
+				.title(format!("'{}' is instantiated too deeply", **name))
+				.note(format!("Reached '{inst}', it likely uses itself with ever larger types"))
+				.span(name.span)
+				.as_err();
+		}
+
+		let ty_args = std::mem::replace(&mut self.ty_args, params.into_iter().zip(args).collect());
+		let scope   = self.scope.split_off(1);
+		let loops   = std::mem::take(&mut self.loops);
+		let diverged = self.diverged.take();
+		let impl_ty = self.impl_ty.take();
+		let func    = self.func.take();
+		let caller  = std::mem::replace(&mut self.file, file);
+		let warnings = self.warnings.len();
+		self.inst_depth += 1;
+
+		let res = f(self);
+
+		self.inst_depth -= 1;
+		self.file    = caller;
+		self.impl_ty = impl_ty;
+		self.func    = func;
+		self.loops   = loops;
+		self.diverged = diverged;
+		self.scope.truncate(1);
+		self.scope.extend(scope);
+		self.ty_args = ty_args;
+
+		for w in &mut self.warnings[warnings..] { *w = w.clone().file(file); }
+		res.map_err(|e| Box::new(e.note(format!("In '{inst}'")).file(file)))
+	}
+
+	/// A call of a generic function, its type args are either given or inferred from the args
+	pub(super) fn analyze_generic_call(&mut self, name: &Sp<&str>, ty_args: &[Sp<ast::Type>], args: Vec<Sp<ast::Node>>,
+		hint: Option<&Type>, span: Span) -> Result<(Type, Vec<Node>, Node)> {
+		let node = self.generic_fns[name.elem].1.clone();
+		let ast::Node::Func { generics, args: params, ret, .. } = &node.elem else { unreachable!() };
+		let gnames: Vec<&str> = generics.iter().map(|g| g.elem).collect();
+
+		if params.len() != args.len() {
+			return ReportKind::InvalidArgCount
+				.title(format!("Expected {} arguments, got {}", params.len(), args.len()))
+				.span(span)
+				.as_err();
+		}
+
+		if !ty_args.is_empty() {
+			if ty_args.len() != generics.len() {
+				return ReportKind::InvalidArgCount
+					.title(format!("'{}' takes {} type arguments, got {}", **name, generics.len(), ty_args.len()))
+					.span(span)
+					.as_err();
+			}
+
+			let targs = ty_args.iter().map(|t| self.convert_ty(t)).collect::<Result<_>>()?;
+			let (id, Type::Fn(fn_args, fn_ret)) = self.instantiate_fn(name.elem, targs)?
+				else { unreachable!() };
+
+			let mut nodes = Vec::new();
+			let args = self.analyze_args(&mut nodes, fn_args, args, span)?;
+			return Ok((*fn_ret, nodes, Node::FuncCall { id: Var::Glob(id), args }));
+		}
+
+		let mut bound = vec![None; generics.len()];
+		let mut nodes = Vec::new();
+		let mut vals  = Vec::new();
+		for (arg, (_, pty)) in args.into_iter().zip(params.iter()) {
+			// params which do not depend on the type args still give literals their type
+			let hint = match mentions(pty, &gnames) {
+				true  => None,
+				false => {
+					let ty_args = std::mem::take(&mut self.ty_args);
+					let hint = self.convert_ty(pty);
+					self.ty_args = ty_args;
+					Some(hint?)
+				},
+			};
+
+			let aspan = arg.span;
+			let (t, n, v) = self.analyze_expr(arg, hint.as_ref())?;
+			self.infer(pty, &t, &gnames, &mut bound);
+
+			nodes.extend(n);
+			vals.push((t, v, aspan));
+		}
+
+		// the expected result only fills in what the args left open
+		if let (Some(ret), Some(hint)) = (ret, hint) {
+			self.infer(ret, hint, &gnames, &mut bound);
+		}
+
+		let targs = bound.into_iter().zip(generics)
+			.map(|(t, g)| t.map(|t| concretize(&t, None)).ok_or_else(|| ReportKind::TypeError
+				.title(format!("Cannot infer type parameter '{}' of '{}'", g.elem, **name))
+				.help(format!("Give it explicitly, like '${}<...>(...)'", **name))
+				.span(span)
+				.into()))
+			.collect::<Result<_>>()?;
+
+		let (id, Type::Fn(fn_args, fn_ret)) = self.instantiate_fn(name.elem, targs)?
+			else { unreachable!() };
+
+		let mut args = Vec::new();
+		for ((t, v, aspan), ty) in vals.into_iter().zip(fn_args) {
+			if !cmp_ty(&t, &ty) {
+				if let Some(e) = self.mut_mismatch(None, &t, &ty, aspan).or_else(|| unwrap_mismatch(&t, &ty, aspan)) { return Err(e); }
+				return ReportKind::TypeError
+					.title("Type mismatch in function call")
+					.label(format!("expected '{ty}', found '{t}'"))
+					.span(aspan)
+					.as_err();
+			}
+
+			args.push((v, ty));
+		}
+
+		Ok((*fn_ret, nodes, Node::FuncCall { id: Var::Glob(id), args }))
+	}
+
+	/// Binds the params in `pty` by matching it against `ty`, the type of the arg given for it
+	fn infer(&self, pty: &Sp<ast::Type>, ty: &Type, params: &[&str], bound: &mut [Option<Type>]) {
+		match (&pty.elem, ty.base()) {
+			(ast::Type::Ident(n), ty) => {
+				let Some(i) = params.iter().position(|p| p == n) else { return };
+
+				match &bound[i] {
+					None => bound[i] = Some(ty.clone()),
+					// a literal gives way to the type of another arg
+					Some(b) if b.is_literal() => if let Some(t) = unify(b, ty) { bound[i] = Some(t); },
+					Some(_) => (), // a mismatch, found when the args are checked
+				}
+			},
+			(ast::Type::Mut(p), ty) => self.infer(p, ty, params, bound),
+			(ast::Type::Ptr(p), Type::Ptr(ty)) | (ast::Type::Opt(p), Type::Opt(ty))
+				| (ast::Type::Arr(p, _), Type::Arr(ty, _)) => self.infer(p, ty, params, bound),
+			(ast::Type::Generic(n, ps), Type::Struct(id, _) | Type::Enum(id, _)) => match self.inst_args.get(id) {
+				Some((g, args)) if g == n => ps.iter().zip(args).for_each(|(p, t)| self.infer(p, t, params, bound)),
+				_ => (),
+			},
+			_ => (),
+		}
+	}
+
+	/// Part of a symbol name, instances of generic types are mangled from what they were made of
+	pub(super) fn mangle(&self, ty: &Type) -> String {
+		match ty {
+			Type::U(n) => format!("u{n}"),
+			Type::I(n) => format!("i{n}"),
+			Type::B(n) => format!("b{n}"),
+			Type::F(n) => format!("f{n}"),
+			Type::Usize => String::from("usize"),
+			Type::Isize => String::from("isize"),
+			Type::Void  => String::from("v"),
+			Type::Never => String::from("n"),
+			Type::Ptr(ty) => format!("P{}", self.mangle(ty)),
+			Type::Mut(ty) => format!("M{}", self.mangle(ty)),
+			Type::Opt(ty) => format!("O{}", self.mangle(ty)),
+			Type::Arr(ty, Some(n)) => format!("A{n}_{}", self.mangle(ty)),
+			Type::Arr(ty, None)    => format!("S{}", self.mangle(ty)),
+			Type::Fn(args, ret) => format!("F{}{}E", self.mangle(ret), args.iter().map(|t| self.mangle(t)).collect::<String>()),
+			Type::Struct(id, name) | Type::Enum(id, name) => match self.inst_args.get(id) {
+				Some((g, args)) => format!("{}I{}E", nested(g), args.iter().map(|t| self.mangle(t)).collect::<String>()),
+				None => nested(name),
+			},
+			Type::Puint | Type::Pint | Type::Pbool | Type::Pfloat | Type::Infer(..) => unreachable!("literal types are concretized first"),
+		}
+	}
+}
+
+/// `a.b` from another module as `1a1b`, a plain name is just prefixed with its length
+pub(super) fn parts(name: &str) -> String {
+	name.split('.').fold(String::new(), |acc, p| format!("{acc}{}{p}", p.len()))
+}
+
+/// A name on its own, those from another module are nested in it
+pub(super) fn nested(name: &str) -> String {
+	match name.contains('.') {
+		true  => format!("N{}E", parts(name)),
+		false => parts(name),
+	}
+}
+
+/// Whether a generic's decl is needed to instantiate it, rather than analyzing it right away
+pub(super) fn is_generic(node: &ast::Node) -> bool {
+	match node {
+		ast::Node::Func { generics, .. } | ast::Node::Struct { generics, .. } | ast::Node::Enum { generics, .. }
+			=> !generics.is_empty(),
+		_ => false,
+	}
+}
+
+fn inst_name(name: &str, args: &[Type]) -> String {
+	let args: Vec<String> = args.iter().map(plain).collect();
+	format!("{name}<{}>", args.join(", "))
+}
+
+/// Whether any of `params` appear in `ty`
+fn mentions(ty: &Sp<ast::Type>, params: &[&str]) -> bool {
+	match &ty.elem {
+		ast::Type::Ident(n) => params.contains(n),
+		ast::Type::Ptr(ty) | ast::Type::Opt(ty) | ast::Type::Mut(ty) | ast::Type::Arr(ty, _) => mentions(ty, params),
+		ast::Type::Generic(_, args) => args.iter().any(|t| mentions(t, params)),
+		ast::Type::Fn(args, ret) => args.iter().any(|t| mentions(t, params))
+			|| ret.as_ref().is_some_and(|t| mentions(t, params)),
+		_ => false,
+	}
+}
+
+/// The name of a type as written, without the colors of its `Display`
+fn plain(ty: &Type) -> String {
+	match ty {
+		Type::U(n) => format!("u{n}"),
+		Type::I(n) => format!("i{n}"),
+		Type::B(n) => format!("b{n}"),
+		Type::F(n) => format!("f{n}"),
+		Type::Puint  => String::from("{uint}"),
+		Type::Pint   => String::from("{int}"),
+		Type::Pbool  => String::from("{bool}"),
+		Type::Pfloat => String::from("{float}"),
+		Type::Usize  => String::from("usize"),
+		Type::Isize  => String::from("isize"),
+		Type::Void   => String::from("void"),
+		Type::Never  => String::from("never"),
+		Type::Ptr(ty) => format!("*{}", plain(ty)),
+		Type::Arr(ty, None)    => format!("[{}]", plain(ty)),
+		Type::Arr(ty, Some(n)) => format!("[{}:{n}]", plain(ty)),
+		Type::Mut(ty) => format!("mut {}", plain(ty)),
+		Type::Opt(ty) => format!("opt {}", plain(ty)),
+		Type::Infer(_, kind) => plain(kind),
+		Type::Struct(_, name) | Type::Enum(_, name) => name.clone(),
+		Type::Fn(args, ret) => {
+			let args: Vec<String> = args.iter().map(plain).collect();
+			format!("fn({}) {}", args.join(", "), plain(ret))
+		},
+	}
+}
@@ -0,0 +1,121 @@
+//! Overload resolution, functions in the global scope can share a name as long as their args differ.
+//! A call goes to the candidate whose params fit its args the closest, the args are typed up front
+//! without a hint so they can be compared against every candidate.
+use crate::report::{Result, ReportKind, Report};
+use crate::span::{Sp, Span};
+use crate::parser::ast;
+
+use super::{Analyzer, Type, ValId, cmp_ty, concretize, unify};
+
+impl Analyzer<'_> {
+	/// Picks the function called `name` which the args fit best, a single candidate is always picked
+	/// so its own errors are given
+	pub(super) fn overload(&mut self, name: &Sp<&str>, args: &[Sp<ast::Node>], span: Span) -> Result<(ValId, Type)> {
+		let cands: Vec<(ValId, Type)> = self.get_global().locals.iter()
+			.filter(|(_, n, t)| n == name.elem && matches!(t, Type::Fn(..)))
+			.map(|(id, _, t)| (*id, t.clone()))
+			.collect();
+
+		let fit: Vec<&(ValId, Type)> = cands.iter()
+			.filter(|(_, t)| matches!(t, Type::Fn(params, _) if params.len() == args.len()))
+			.collect();
+
+		match fit.len() {
+			_ if cands.len() == 1 => return Ok(cands[0].clone()),
+			1 => return Ok(fit[0].clone()),
+			_ => (),
+		}
+
+		let tys: Vec<Option<Type>> = args.iter().map(|a| self.peek_ty(a)).collect();
+		let scored: Vec<(&(ValId, Type), Vec<u8>)> = fit.into_iter()
+			.filter_map(|c| {
+				let Type::Fn(params, _) = &c.1 else { unreachable!() };
+				tys.iter().zip(params)
+					.map(|(t, p)| score(t.as_ref(), p))
+					.collect::<Option<Vec<u8>>>()
+					.map(|s| (c, s))
+			})
+			.collect();
+
+		// the ones no other candidate fits better than
+		let best: Vec<&(ValId, Type)> = scored.iter()
+			.filter(|(_, s)| !scored.iter().any(|(_, o)| beats(o, s)))
+			.map(|(c, _)| *c)
+			.collect();
+
+		let found: Vec<String> = tys.iter()
+			.map(|t| t.as_ref().map_or_else(|| String::from("_"), ToString::to_string))
+			.collect();
+
+		match best.len() {
+			1 => Ok(best[0].clone()),
+			0 => Err(candidates(ReportKind::TypeError
+				.title(format!("No overload of '{}' takes these arguments", **name))
+				.label(format!("found '({})'", found.join(", ")))
+				.span(span), name, &cands).into()),
+			_ => Err(candidates(ReportKind::TypeError
+				.title(format!("Call to '{}' is ambiguous", **name))
+				.label(format!("found '({})'", found.join(", ")))
+				.help("Pass a value of the exact type, so only one of them fits")
+				.span(span), name, &best.into_iter().cloned().collect::<Vec<_>>()).into()),
+		}
+	}
+
+	/// The type an arg has on its own, analyzed and thrown away. `None` when it needs a hint to be typed
+	fn peek_ty(&mut self, node: &Sp<ast::Node>) -> Option<Type> {
+		if let Some(ty) = literal(&node.elem) { return Some(ty); }
+
+		let depth = self.scope.len();
+		let loops = self.loops.len();
+		let warnings = self.warnings.len();
+		let ty = self.analyze_expr(node.clone(), None).ok().map(|(t, _, _)| t);
+
+		self.scope.truncate(depth);
+		self.loops.truncate(loops);
+		self.warnings.truncate(warnings);
+		ty
+	}
+}
+
+/// How close an arg is to a param, higher is closer and `None` does not fit at all.
+/// A literal is closest to the type it defaults to, then to any of its own kind, so an int goes to an int over a float
+fn score(ty: Option<&Type>, param: &Type) -> Option<u8> {
+	let Some(ty) = ty else { return Some(1) };
+
+	match (ty.is_literal(), cmp_ty(ty, param)) {
+		(_, false) => None,
+		(true, _) if concretize(ty, None) == *param.base() => Some(3),
+		(true, _) if ty.is_int() == param.is_int() && ty.is_float() == param.is_float() => Some(2),
+		(false, _) if ty.base() == param.base() => Some(3),
+		_ => Some(1),
+	}
+}
+
+/// Whether the scores `a` are at least as close as `b` everywhere, and closer somewhere
+fn beats(a: &[u8], b: &[u8]) -> bool {
+	a.iter().zip(b).all(|(a, b)| a >= b) && a.iter().zip(b).any(|(a, b)| a > b)
+}
+
+/// The type of an expression made only of literals, those take the type of whatever they are passed to
+fn literal(node: &ast::Node) -> Option<Type> {
+	match node {
+		ast::Node::UIntLit(_) | ast::Node::CharLit(_) => Some(Type::Puint),
+		ast::Node::SIntLit(_)  => Some(Type::Pint),
+		ast::Node::FloatLit(_) => Some(Type::Pfloat),
+		ast::Node::UnOp { expr, .. } => literal(&expr.elem),
+		ast::Node::BinOp { op, lhs, rhs } if !op.is_cmp() => unify(&literal(&lhs.elem)?, &literal(&rhs.elem)?),
+		_ => None,
+	}
+}
+
+fn candidates(report: Report, name: &str, cands: &[(ValId, Type)]) -> Report {
+	cands.iter().fold(report, |r, (_, ty)| {
+		let Type::Fn(params, ret) = ty else { unreachable!() };
+		let params: Vec<String> = params.iter().map(ToString::to_string).collect();
+		let ret = match **ret {
+			Type::Void => String::new(),
+			ref ty     => format!(" {ty}"),
+		};
+		r.note(format!("Candidate 'fn {name}({}){ret}'", params.join(", ")))
+	})
+}
@@ -1,6 +1,8 @@
 use std::fmt::Debug;
 use std::process::exit;
 
+use crate::codegen::Target;
+
 macro_rules! error {
 	($($ident:tt)*) => {{
 		eprintln!("{}", crate::report::ReportKind::ArgumentParserError
@@ -18,7 +20,12 @@ pub struct Args {
 	
 	// io
 	pub file:         &'static str,
+	pub include:      Vec<&'static str>,
 	pub output:       &'static str,
+	pub target:       Target,
+
+	// codegen
+	pub bounds_checks: bool,
 
 	// script
 	pub verbs:        Vec<&'static str>,
@@ -31,7 +38,10 @@ impl Args {
 			level:        2, // warn
 
 			file:         "main.shd",
+			include:      Vec::new(),
 			output:       "",
+			target:       Target::Llvm,
+			bounds_checks: true,
 			verbs:        Vec::new(),
 		}
 	}
@@ -50,6 +60,11 @@ impl Args {
 			}
 		}
 
+		match out.verbs.as_slice() {
+			[] | ["build" | "run"] => (),
+			verbs => error!("Unknown verb `{}`", verbs.join(" ")),
+		}
+
 		out
 	}
 
@@ -91,6 +106,12 @@ impl Args {
 
 					self.file = Box::leak(file.into_boxed_str());
 				},
+				"I" | "include" => {
+					err_if_arg_end!();
+					let dir = args.next().unwrap_or_else(|| error!("{arg} expected DIR"));
+
+					self.include.push(Box::leak(dir.into_boxed_str()));
+				},
 				"o" | "output" => {
 					err_if_arg_end!();
 
@@ -98,6 +119,14 @@ impl Args {
 						.unwrap_or_else(|| error!("expected file"))
 						.into_boxed_str());
 				},
+				"t" | "target" => {
+					err_if_arg_end!();
+					let target = args.next().unwrap_or_else(|| error!("expected target"));
+
+					self.target = Target::from_name(&target)
+						.unwrap_or_else(|| error!("invalid target `{target}`"));
+				},
+				"no-bounds-checks" => self.bounds_checks = false,
 				"l" | "level" => {
 					err_if_arg_end!();
 					let level = args.next().unwrap_or_else(|| error!("expected level"));
@@ -117,11 +146,16 @@ impl Args {
 	}
 }
 
-const USAGE: &str = "Usage: sharc [-hVd] [-l LEVEL] [-f FILE] [-o FILE] [VERB...]";
+const USAGE: &str = "Usage: sharc [-hVd] [-l LEVEL] [-t TARGET] [-f FILE] [-I DIR]... [-o FILE] [--no-bounds-checks] [VERB...]";
 const HELP_MESSAGE: &str = "\x1b[1mDESCRIPTION\x1b[0m
     The compiler for the Shard Programming Language.
     Documentation can be found at https://shardlang.org/doc/
 
+\x1b[1mVERBS\x1b[0m
+    build                       Compile FILE to the target (default)
+    run                         Interpret FILE instead of compiling it
+        Externs are provided by the interpreter (puts, putchar, exit, abort).
+
 \x1b[1mOPTIONS\x1b[0m
     -h, --help                  `-h` only shows the usage 
     -v, --version               Show version
@@ -129,10 +163,16 @@ const HELP_MESSAGE: &str = "\x1b[1mDESCRIPTION\x1b[0m
         Shows a ton of information not intended for mere mortals.
     -l, --level LEVEL           [fatal|error|warn|note|silent] (or 0-4)
         (default: warn)
+    -t, --target TARGET         [llvm|qbe|c|x86_64|wasm]
+        (default: llvm)
     -f, --file FILE             File to compile
         (default: main.shd)
+    -I, --include DIR           Also look for imported modules in DIR
+        Can be given more than once, searched in order.
     -o, --output FILE           File to write to
-        (default: main.asm)";
+        (default: main.asm)
+    --no-bounds-checks          Do not check indexes at runtime
+        An index past the end is no longer caught, one known when compiling still is.";
 
 // FIXME: placeholder, someone make a good one pls
 const SHARK_ASCII: &str = r#"                                 ,-
@@ -1,15 +1,20 @@
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IBig(bool, Vec<u64>);
 
 impl From<u64> for IBig {
+	#[allow(clippy::cast_possible_truncation)]
 	fn from(i: u64) -> Self {
-		Self(false, vec![i])
+		const BASE: u64 = IBig::CHUNK_BASE as u64;
+		match i {
+			i if i < BASE => Self(false, vec![i]),
+			i => Self(false, vec![i % BASE, i / BASE]),
+		}
 	}
 }
 
 impl From<i64> for IBig {
 	fn from(i: i64) -> Self {
-		Self(i < 0, vec![i.unsigned_abs()])
+		Self(i < 0, Self::from(i.unsigned_abs()).1)
 	}
 }
 
@@ -19,28 +24,60 @@ impl std::ops::Neg for IBig {
 	{ Self(!self.0, self.1) }
 }
 
+#[derive(Debug)]
+pub struct ParseIBigError;
+
 impl std::str::FromStr for IBig {
-	type Err = std::num::ParseIntError;
+	type Err = ParseIBigError;
 
 	// NOTE: naive impl assuming input is unsigned, use '-' to negate
-	fn from_str(mut s: &str) -> Result<Self, Self::Err> {
-		const CHUNK_SIZE: usize = 19; // max u64 is 20 digits, but leave 1 as margin
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::from_str_radix(s, 10)
+	}
+}
+
+impl std::fmt::Display for IBig {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if self.0 { write!(f, "-")?; }
+
+		let mut chunks = self.1.iter().rev();
+		write!(f, "{}", chunks.next().unwrap_or(&0))?;
+		chunks.try_for_each(|i| write!(f, "{i:019}"))
+	}
+}
+
+impl IBig {
+	const CHUNK_BASE: u128 = 10u128.pow(19);
 
-		let mut res = Vec::new();
+	/// Parses the digits of an unsigned integer in base `radix`
+	pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseIBigError> {
+		if s.is_empty() { return Err(ParseIBigError); }
 
-		loop {
-			if s.len() == 0 { break; }
-			res.push(s[s.len().saturating_sub(CHUNK_SIZE)..].parse::<u64>()?);
-			s = &s[..s.len().saturating_sub(CHUNK_SIZE)];
+		let mut res = vec![0];
+		for c in s.chars() {
+			let mut carry = u128::from(c.to_digit(radix).ok_or(ParseIBigError)?);
+
+			#[allow(clippy::cast_possible_truncation)]
+			for chunk in &mut res {
+				let v = u128::from(*chunk) * u128::from(radix) + carry;
+				*chunk = (v % Self::CHUNK_BASE) as u64;
+				carry  = v / Self::CHUNK_BASE;
+			}
+
+			#[allow(clippy::cast_possible_truncation)]
+			if carry != 0 { res.push(carry as u64); }
 		}
 
 		Ok(Self(false, res))
 	}
-}
 
-impl std::fmt::Display for IBig {
-	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		if self.0 { write!(f, "-")?; }
-		self.1.iter().rev().try_for_each(|i| write!(f, "{i}"))
+	/// None if the value doesnt fit
+	pub fn to_i128(&self) -> Option<i128> {
+		let mut res: i128 = 0;
+		for chunk in self.1.iter().rev() {
+			res = res.checked_mul(10i128.pow(19))?.checked_add(i128::from(*chunk))?;
+		}
+
+		Some(if self.0 { -res } else { res })
 	}
 }
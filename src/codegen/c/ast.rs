@@ -0,0 +1,259 @@
+use std::fmt::{self, Formatter, Display};
+
+pub type Name = String;
+
+#[derive(Default)]
+pub struct Module {
+	pub name:   &'static str,
+
+	pub types:  Vec<TypeDef>,
+	pub decls:  Vec<FuncDecl>,
+	pub protos: Vec<FuncDecl>,
+	pub data:   Vec<DataDef>,
+	pub funcs:  Vec<Function>,
+}
+
+
+pub enum TypeDef {
+	Struct(Name, Vec<Type>),
+	Enum(Name, Vec<Option<Type>>), // a tag and a union of the payloads
+}
+
+
+pub struct DataDef {
+	pub name:   Name,
+	pub ty:     Type,
+	pub value:  Expr,
+}
+
+
+pub struct Function {
+	pub name:   Name,
+	pub export: bool,
+	pub args:   Vec<(Type, Name)>,
+	pub ret:    Type,
+	pub body:   Vec<Stmt>,
+}
+
+pub struct FuncDecl {
+	pub name:   Name,
+	pub export: bool,
+	pub args:   Vec<Type>,
+	pub ret:    Type,
+}
+
+
+pub enum Stmt {
+	Decl(Type, Name, Option<Expr>),
+	Assign(Expr, Expr),
+	Expr(Expr),
+	Ret(Option<Expr>),
+	Label(Name),
+	Goto(Name),
+	Branch(Expr, Name, Name),
+}
+
+pub enum Expr {
+	Ident(Name),
+	Const(String),
+	Str(String),
+	Call(Box<Self>, Vec<Self>),
+	Binary(&'static str, Box<Self>, Box<Self>),
+	Unary(&'static str, Box<Self>),
+	Compound(Type, Vec<Self>),
+	Cast(Type, Box<Self>),
+	Field(Box<Self>, &'static str, Name), // the operator is either `.` or `->`
+	Index(Box<Self>, Box<Self>),
+	Init(Vec<Self>), // `{ a, b }`, only in initializers
+	Designated(Name, Box<Self>), // `{ .name = v }`
+}
+
+#[derive(Clone)]
+pub enum Type {
+	Int(bool, u32), // signed, width
+	Float, Double,
+	Size, PtrDiff,
+	Void,
+	Ptr(Box<Self>),
+	Array(u64, Box<Self>),
+	Fn(Vec<Self>, Box<Self>),
+	Struct(Name),
+}
+
+impl Type {
+	/// Writes a declarator for `name`, C puts arrays and function pointers around the name
+	pub fn declare(&self, name: &str) -> String {
+		match self {
+			Self::Ptr(ty) => match **ty {
+				Self::Array(_, _) | Self::Fn(_, _) => ty.declare(&format!("(*{name})")),
+				_ => ty.declare(&format!("*{name}")),
+			},
+			Self::Array(n, ty) => ty.declare(&format!("{name}[{n}]")),
+			Self::Fn(args, ret) => {
+				let args = match args.is_empty() {
+					true  => String::from("void"),
+					false => args.iter().map(|a| a.declare("")).collect::<Vec<_>>().join(", "),
+				};
+				ret.declare(&format!("{name}({args})"))
+			},
+			ty if name.is_empty() => ty.to_string(),
+			ty => format!("{ty} {name}"),
+		}
+	}
+}
+
+
+impl Display for Module {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		writeln!(f, "/* {} - generated by sharc {} */", self.name, env!("CARGO_PKG_VERSION"))?;
+		writeln!(f, "#include <stdint.h>")?;
+		writeln!(f, "#include <stddef.h>\n")?;
+
+		self.types .iter().try_for_each(|t| writeln!(f, "{t}"))?;
+		self.decls .iter().try_for_each(|d| writeln!(f, "{d};"))?;
+		self.protos.iter().try_for_each(|d| writeln!(f, "{d};"))?;
+		writeln!(f)?;
+		self.data  .iter().try_for_each(|d| writeln!(f, "{d}"))?;
+		writeln!(f)?;
+		self.funcs .iter().try_for_each(|c| writeln!(f, "{c}"))
+	}
+}
+
+impl Display for TypeDef {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Struct(name, fields) => {
+				write!(f, "struct {name} {{")?;
+				fields.iter().enumerate().try_for_each(|(i, t)| write!(f, " {};", t.declare(&format!("f{i}"))))?;
+				// empty structs are a gnu extension
+				if fields.is_empty() { write!(f, " char f0;")?; }
+			},
+			Self::Enum(name, variants) => {
+				write!(f, "struct {name} {{ uint32_t tag;")?;
+				if variants.iter().any(Option::is_some) {
+					write!(f, " union {{")?;
+					variants.iter().enumerate()
+						.filter_map(|(i, t)| t.as_ref().map(|t| (i, t)))
+						.try_for_each(|(i, t)| write!(f, " {};", t.declare(&format!("f{i}"))))?;
+					write!(f, " }} u;")?;
+				}
+			},
+		}
+		write!(f, " }};")
+	}
+}
+
+impl Display for DataDef {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "static {} = {};", self.ty.declare(&self.name), self.value)
+	}
+}
+
+impl Display for Function {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if !self.export { write!(f, "static ")?; }
+
+		let args = match self.args.is_empty() {
+			true  => String::from("void"),
+			false => self.args.iter()
+				.map(|(t, n)| t.declare(n))
+				.collect::<Vec<_>>().join(", "),
+		};
+
+		writeln!(f, "{} {{", self.ret.declare(&format!("{}({args})", self.name)))?;
+		self.body.iter().try_for_each(|s| match s {
+			Stmt::Label(_) => writeln!(f, "{s}"),
+			_ => writeln!(f, "   {s}"),
+		})?;
+		writeln!(f, "}}")
+	}
+}
+
+impl Display for FuncDecl {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if !self.export { write!(f, "static ")?; }
+		write!(f, "{}", Type::Fn(self.args.clone(), Box::new(self.ret.clone())).declare(&self.name))
+	}
+}
+
+impl Display for Stmt {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Decl(t, n, Some(v)) => write!(f, "{} = {v};", t.declare(n)),
+			Self::Decl(t, n, None)    => write!(f, "{};", t.declare(n)),
+			Self::Assign(a, v)        => write!(f, "{a} = {v};"),
+			Self::Expr(e)             => write!(f, "{e};"),
+			Self::Ret(Some(v))        => write!(f, "return {v};"),
+			Self::Ret(None)           => write!(f, "return;"),
+			// the empty statement keeps declarations right after a label valid
+			Self::Label(l)            => write!(f, "{l}:;"),
+			Self::Goto(l)             => write!(f, "goto {l};"),
+			Self::Branch(c, a, b)     => write!(f, "if ({c}) goto {a}; else goto {b};"),
+		}
+	}
+}
+
+impl Display for Expr {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Ident(n) | Self::Const(n) => write!(f, "{n}"),
+			Self::Str(s) => {
+				write!(f, "\"")?;
+				s.bytes().try_for_each(|c| match c {
+					b' '..=b'~' if c != b'"' && c != b'\\' && c != b'?' => write!(f, "{}", c as char),
+					_ => write!(f, "\\{c:03o}"),
+				})?;
+				write!(f, "\"")
+			},
+			Self::Call(func, args) => {
+				write!(f, "{func}(")?;
+				for (i, arg) in args.iter().enumerate() {
+					write!(f, "{arg}")?;
+					if i != args.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, ")")
+			},
+			Self::Binary(op, a, b) => write!(f, "{a} {op} {b}"),
+			Self::Unary(op, v)     => write!(f, "{op}{v}"),
+			Self::Compound(t, vs) if vs.is_empty() => write!(f, "({t}){{0}}"),
+			Self::Compound(t, vs)  => {
+				write!(f, "({t}){{")?;
+				for (i, v) in vs.iter().enumerate() {
+					write!(f, "{v}")?;
+					if i != vs.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, "}}")
+			},
+			// the operands are values already, only what is cast can be an operation
+			Self::Cast(t, v) if matches!(**v, Self::Binary(..) | Self::Unary(..)) => write!(f, "({t})({v})"),
+			Self::Cast(t, v)       => write!(f, "({t}){v}"),
+			Self::Field(v, op, n)  => write!(f, "{v}{op}{n}"),
+			Self::Index(v, i)      => write!(f, "{v}[{i}]"),
+			Self::Init(vs) => {
+				write!(f, "{{")?;
+				for (i, v) in vs.iter().enumerate() {
+					write!(f, "{v}")?;
+					if i != vs.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, "}}")
+			},
+			Self::Designated(n, v) => write!(f, "{{ .{n} = {v} }}"),
+		}
+	}
+}
+
+impl Display for Type {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Int(true, w)  => write!(f, "int{w}_t"),
+			Self::Int(false, w) => write!(f, "uint{w}_t"),
+			Self::Float   => write!(f, "float"),
+			Self::Double  => write!(f, "double"),
+			Self::Size    => write!(f, "size_t"),
+			Self::PtrDiff => write!(f, "ptrdiff_t"),
+			Self::Void    => write!(f, "void"),
+			Self::Struct(n) => write!(f, "struct {n}"),
+			ty => write!(f, "{}", ty.declare("")),
+		}
+	}
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use crate::report::{Result, LogHandler, ReportKind};
+use crate::analyzer::mir::{self, ValId, Var, Node, BinOp, UnOp, Type as mType};
+
+mod ast;
+use ast::{Stmt, Expr, Module, DataDef, TypeDef, FuncDecl, Type};
+
+pub struct Gen {
+	module: Module,
+	sym:    HashMap<ValId, String>,
+}
+
+impl Gen {
+	pub fn codegen(
+		file: &'static str, sym: HashMap<ValId, String>,
+		mir: Vec<Node>, handler: &LogHandler)
+	-> Module {
+		let mut cgen = Self {
+			sym,
+			module: Module { name: file, ..Module::default() },
+		};
+
+		mir.into_iter().for_each(|node| {
+			let loc = node.loc();
+			if let Err(e) = cgen.gen_node(node) {
+				handler.log(super::locate(*e, loc).file(file));
+			}
+		});
+
+		cgen.module
+	}
+
+	fn get_id_name(&self, id: mir::ValId) -> &str {
+		self.sym.get(&id).expect("ValId not found in sym table")
+	}
+
+	fn gen_node(&mut self, node: Node) -> Result<()> {
+		match node {
+			Node::Func { id, export, args, ret, body, .. } => {
+				let name = super::symbol(&self.sym, id);
+
+				let args = args.into_iter()
+					.map(|(i, t)| Ok((self.gen_type(&t)?, format!("t{}", *i))))
+					.collect::<Result<Vec<_>>>()?;
+				let ret = self.gen_type(&ret)?;
+
+				// every function gets a prototype so call order in the source doesnt matter
+				self.module.protos.push(FuncDecl {
+					export,
+					name: name.clone(),
+					args: args.iter().map(|(t, _)| t.clone()).collect(),
+					ret:  ret.clone(),
+				});
+
+				let mut nbody = Vec::new();
+				for stmt in body {
+					nbody.extend(self.gen_stmt(stmt)?);
+				}
+
+				self.module.funcs.push(ast::Function {
+					name, export, args, ret,
+					body: nbody,
+				});
+			},
+			Node::FuncDecl { id, args, ret, .. } => {
+				let func = FuncDecl {
+					export: true,
+					name:   self.get_id_name(id).to_string(),
+					args:   args.iter().map(|t| self.gen_type(t)).collect::<Result<Vec<_>>>()?,
+					ret:    self.gen_type(&ret)?,
+				};
+
+				self.module.decls.push(func);
+			},
+			Node::Struct { id, fields, .. } => {
+				let fields = fields.iter().map(|t| self.gen_type(t)).collect::<Result<Vec<_>>>()?;
+				self.module.types.push(TypeDef::Struct(id.to_string(), fields));
+			},
+			Node::Enum { id, variants, .. } => {
+				let variants = variants.iter()
+					.map(|t| t.as_ref().map(|t| self.gen_type(t)).transpose())
+					.collect::<Result<Vec<_>>>()?;
+				self.module.types.push(TypeDef::Enum(id.to_string(), variants));
+			},
+			Node::Assign { .. } | Node::Global { .. } | Node::Store { .. } | Node::StoreIndex { .. }
+				| Node::StoreField { .. } | Node::Ret(..) | Node::Trap(_) | Node::Label(_) | Node::Jump(_)
+				| Node::Branch { .. } | Node::FuncCall { .. } | Node::BinOp { .. } | Node::UnOp { .. }
+				| Node::StructLit(_) | Node::Field { .. } | Node::EnumLit { .. } | Node::Tag { .. }
+				| Node::Payload { .. } | Node::OptLit(_) | Node::IsSome { .. } | Node::Unwrap { .. }
+				| Node::AddrOf(_) | Node::Deref(_) | Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_)
+				| Node::Index { .. } | Node::Len(_) | Node::Decay { .. } | Node::SliceLit { .. }
+				| Node::Cast { .. } | Node::StrLit(_) | Node::Var(_)
+				=> unreachable!("only declarations are at the top level"),
+		}
+		Ok(())
+	}
+
+	fn gen_stmt(&mut self, node: Node) -> Result<Option<Stmt>> {
+		Ok(Some(match node {
+			Node::Assign { id, ty, val } => {
+				let val = match *val {
+					// compound literals need the type spelled out
+					Node::StructLit(fields) => Expr::Compound(
+						self.gen_type(&ty)?,
+						fields.iter().map(|(v, _)| self.gen_val(v)).collect()),
+					Node::EnumLit { variant, val } => {
+						let mut init = vec![Expr::Const(variant.to_string())];
+						if let Some((v, _)) = val {
+							init.push(Expr::Designated(format!("f{variant}"), Box::new(self.gen_val(&v))));
+						}
+						Expr::Compound(self.gen_type(&ty)?, init)
+					},
+					// `opt *T` is the pointer itself, with null as none
+					Node::OptLit(Some((v, _))) if ty.has_niche() => self.gen_val(&v),
+					Node::OptLit(None) if ty.has_niche() => Expr::Const(String::from("0")),
+					Node::OptLit(Some((v, _))) => Expr::Compound(self.gen_type(&ty)?, vec![Expr::Const(String::from("1")), self.gen_val(&v)]),
+					Node::OptLit(None) => Expr::Compound(self.gen_type(&ty)?, vec![Expr::Const(String::from("0"))]),
+					Node::ArrLit(elems) => Expr::Compound(
+						self.gen_type(&ty)?,
+						vec![Expr::Init(elems.iter().map(|(v, _)| self.gen_val(v)).collect())]),
+					Node::SliceLit { ptr, len } => Expr::Compound(self.gen_type(&ty)?, vec![self.gen_val(&ptr), self.gen_val(&len)]),
+					Node::Cast { val, .. } => Expr::Cast(self.gen_type(&ty)?, Box::new(self.gen_val(&val))),
+					val => self.gen_expr(val)?,
+				};
+				Stmt::Decl(self.gen_type(&ty)?, format!("t{}", *id), Some(val))
+			},
+			Node::Global { .. } => {
+				self.gen_expr(node)?;
+				return Ok(None);
+			},
+			Node::Store { to, from: (v, _), deref: false } => Stmt::Assign(self.gen_val(&to), self.gen_val(&v)),
+			Node::Store { to, from: (v, _), deref: true } => Stmt::Assign(Expr::Unary("*", Box::new(self.gen_val(&to))), self.gen_val(&v)),
+			Node::StoreIndex { to: (to, _), index, from: (v, _) } => Stmt::Assign(self.elem(&to, &index), self.gen_val(&v)),
+			Node::StoreField { to: (to, _), index, from: (v, _), deref } => Stmt::Assign(
+				Expr::Field(Box::new(self.gen_val(&to)), if deref { "->" } else { "." }, format!("f{index}")),
+				self.gen_val(&v)),
+			Node::Ret(None, _)    => Stmt::Ret(None),
+			Node::Ret(Some(v), _) => Stmt::Ret(Some(self.gen_val(&v))),
+			Node::FuncCall { .. } => Stmt::Expr(self.gen_expr(node)?),
+			Node::Label(l) => Stmt::Label(l.to_string()),
+			Node::Jump(l)  => Stmt::Goto(l.to_string()),
+			Node::Branch { cond: (v, _), then, els } 
+				=> Stmt::Branch(self.gen_val(&v), then.to_string(), els.to_string()),
+			Node::Trap(_) => Stmt::Expr(Expr::Call(Box::new(Expr::Ident(String::from("__builtin_trap"))), Vec::new())),
+			Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. }
+				=> unreachable!("declarations are not nested in functions"),
+			Node::BinOp { .. } | Node::UnOp { .. } | Node::StructLit(_) | Node::Field { .. }
+				| Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } | Node::OptLit(_)
+				| Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_) | Node::Deref(_)
+				| Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. } | Node::Len(_)
+				| Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. } | Node::StrLit(_)
+				| Node::Var(_)
+				=> unreachable!("only the value of an Assign"),
+		}))
+	}
+
+	fn gen_expr(&mut self, node: Node) -> Result<Expr> {
+		Ok(match node {
+			Node::FuncCall { id, args } => Expr::Call(
+				Box::new(self.gen_val(&id)),
+				args.iter().map(|(v, _)| self.gen_val(v)).collect()),
+			Node::Global { id, ty, val } => {
+				let name = format!("g{}", *id);
+				let value = match *val {
+					// arrays are wrapped in a struct
+					Node::StrLit(s) => Expr::Init(vec![Expr::Str(s)]),
+					Node::Var(v)    => self.gen_val(&v),
+					_ => unreachable!(),
+				};
+
+				let ty = self.gen_type(&ty)?;
+				self.module.data.push(DataDef {
+					name: name.clone(),
+					ty, value,
+				});
+				Expr::Ident(name)
+			},
+			Node::Var(v) => self.gen_val(&v),
+			Node::BinOp { op: BinOp::Rem, ty, .. } if ty.is_float() => return ReportKind::TypeError
+				.title("Floating point remainder is not supported on the c target")
+				.as_err(),
+			// signed overflow is undefined in c, and narrower ints are promoted to a signed int
+			Node::BinOp { op: op @ (BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Shl), ty, lhs, rhs } 
+				if wrapping_type(&self.gen_type(&ty)?).is_some() => {
+				let ty = self.gen_type(&ty)?;
+				let uty = wrapping_type(&ty).unwrap();
+				let op = match op {
+					BinOp::Add => "+",
+					BinOp::Sub => "-",
+					BinOp::Mul => "*",
+					_ => "<<",
+				};
+
+				Expr::Cast(ty, Box::new(Expr::Binary(op,
+					Box::new(Expr::Cast(uty.clone(), Box::new(self.gen_val(&lhs)))),
+					Box::new(Expr::Cast(uty, Box::new(self.gen_val(&rhs)))))))
+			},
+			Node::BinOp { op, lhs, rhs, .. } => Expr::Binary(match op {
+				BinOp::Add => "+",
+				BinOp::Sub => "-",
+				BinOp::Mul => "*",
+				BinOp::Div => "/",
+				BinOp::Rem => "%",
+				BinOp::Shl => "<<",
+				BinOp::Shr => ">>",
+				BinOp::BitAnd => "&",
+				BinOp::BitOr  => "|",
+				BinOp::BitXor => "^",
+				BinOp::And => "&&",
+				BinOp::Or  => "||",
+				BinOp::Xor | BinOp::Ne => "!=",
+				BinOp::Eq => "==",
+				BinOp::Lt => "<",
+				BinOp::Le => "<=",
+				BinOp::Gt => ">",
+				BinOp::Ge => ">=",
+			}, Box::new(self.gen_val(&lhs)), Box::new(self.gen_val(&rhs))),
+			Node::UnOp { op: UnOp::Neg, ty, val } if wrapping_type(&self.gen_type(&ty)?).is_some() => {
+				let ty = self.gen_type(&ty)?;
+				let uty = wrapping_type(&ty).unwrap();
+				Expr::Cast(ty, Box::new(Expr::Unary("- ", Box::new(Expr::Cast(uty, Box::new(self.gen_val(&val)))))))
+			},
+			Node::UnOp { op, val, .. } => Expr::Unary(match op {
+				// spaced so negating a negative constant doesnt become a decrement
+				UnOp::Neg    => "- ",
+				UnOp::Not    => "!",
+				UnOp::BitNot => "~",
+			}, Box::new(self.gen_val(&val))),
+			Node::Field { val, index, deref, .. } => Expr::Field(
+				Box::new(self.gen_val(&val)),
+				if deref { "->" } else { "." },
+				format!("f{index}")),
+			Node::Tag { val, .. } => Expr::Field(Box::new(self.gen_val(&val)), ".", String::from("tag")),
+			Node::Payload { val, variant, .. } 
+				=> Expr::Field(Box::new(self.gen_val(&val)), ".", format!("u.f{variant}")),
+			Node::IsSome { ty, val } if ty.has_niche() 
+				=> Expr::Binary("!=", Box::new(self.gen_val(&val)), Box::new(Expr::Const(String::from("0")))),
+			// the array of a sized array, and the pointer of a slice, are in the first field too
+			Node::IsSome { val, .. } | Node::Decay { val, .. } => Expr::Field(Box::new(self.gen_val(&val)), ".", String::from("f0")),
+			Node::Unwrap { ty, val } if ty.has_niche() => self.gen_val(&val),
+			Node::Unwrap { val, .. } => Expr::Field(Box::new(self.gen_val(&val)), ".", String::from("f1")),
+			Node::AddrOf(v) => Expr::Unary("&", Box::new(self.gen_val(&v))),
+			Node::Deref(v)  => Expr::Unary("*", Box::new(self.gen_val(&v))),
+			Node::Offset { ptr, op, index: (i, _), .. } => Expr::Binary(match op {
+				BinOp::Sub => "-",
+				_ => "+",
+			}, Box::new(self.gen_val(&ptr)), Box::new(self.gen_val(&i))),
+			// pointers subtract to the number of values between them already
+			Node::Diff { lhs, rhs, .. } => Expr::Binary("-", Box::new(self.gen_val(&lhs)), Box::new(self.gen_val(&rhs))),
+			Node::Index { val, index, .. } => self.elem(&val, &index),
+			Node::Len(v) => Expr::Field(Box::new(self.gen_val(&v)), ".", String::from("f1")),
+			_ => unreachable!(),
+		})
+	}
+
+	/// Element `index` of the array or slice `val`, both hold the elements in their first field
+	fn elem(&self, val: &Var, index: &Var) -> Expr {
+		Expr::Index(
+			Box::new(Expr::Field(Box::new(self.gen_val(val)), ".", String::from("f0"))),
+			Box::new(self.gen_val(index)))
+	}
+
+	/// A struct made of `fields`, declared the first time it is used
+	fn anon_struct(&mut self, name: String, fields: Vec<Type>) -> Type {
+		if !self.module.types.iter().any(|t| matches!(t, TypeDef::Struct(n, _) if *n == name)) {
+			self.module.types.push(TypeDef::Struct(name.clone(), fields));
+		}
+		Type::Struct(name)
+	}
+
+	fn gen_val(&self, v: &mir::Var) -> Expr {
+		match v {
+			Var::Imm(v)    => Expr::Const(v.to_string()),
+			Var::FImm(v)   => Expr::Const(format!("{v:?}")),
+			Var::Local(id) => Expr::Ident(format!("t{id}")),
+			Var::Glob(id)  => Expr::Ident(super::symbol(&self.sym, *id)),
+		}
+	}
+
+	fn gen_type(&mut self, ty: &mType) -> Result<Type> {
+		Ok(match &ty {
+			mType::U(i) | mType::B(i) | mType::I(i) if *i > 64 => return ReportKind::TypeError
+				.title("Unsuported bit width for integer on the c target")
+				.as_err(),
+			mType::U(i) | mType::B(i) => Type::Int(false, i.next_power_of_two().max(8)),
+			mType::I(i)               => Type::Int(true,  i.next_power_of_two().max(8)),
+
+			mType::F(32) => Type::Float,
+			mType::F(64) => Type::Double,
+			mType::F(_) => return ReportKind::TypeError
+				.title("Unsuported bit width for float on the c target")
+				// TODO: span on type mir
+				.as_err(),
+
+			mType::Usize => Type::Size,
+			mType::Isize => Type::PtrDiff,
+			mType::Void | mType::Never => Type::Void,
+
+			mType::Opt(inner) if ty.has_niche() => return self.gen_type(inner),
+			// a tag followed by the value
+			mType::Opt(ty) => {
+				let ty = self.gen_type(ty)?;
+				self.anon_struct(format!("o_{}", mangle(&ty)), vec![Type::Int(false, 8), ty])
+			},
+			mType::Mut(ty) => return self.gen_type(ty),
+			mType::Ptr(ty) => Type::Ptr(Box::new(self.gen_type(ty)?)),
+			mType::Fn(args, ret) => Type::Ptr(Box::new(Type::Fn(
+				args.iter().map(|t| self.gen_type(t)).collect::<Result<Vec<_>>>()?,
+				Box::new(self.gen_type(ret)?)))),
+			// wrapped so they are copied like any other value
+			mType::Arr(t, Some(n)) => {
+				let ty = self.gen_type(t)?;
+				self.anon_struct(format!("a{n}_{}", mangle(&ty)), vec![Type::Array(*n, Box::new(ty))])
+			},
+			// a pointer to the first element and the length
+			mType::Arr(t, None) => {
+				let ty = self.gen_type(t)?;
+				self.anon_struct(format!("s_{}", mangle(&ty)), vec![Type::Ptr(Box::new(ty)), Type::Size])
+			},
+			mType::Struct(id, _) | mType::Enum(id, _) => Type::Struct(id.to_string()),
+			_ => unreachable!()
+		})
+	}
+}
+
+/// The unsigned type the arithmetic of `ty` wraps around in, none when `ty` already does so itself
+fn wrapping_type(ty: &Type) -> Option<Type> {
+	match ty {
+		Type::Int(signed, width) if *signed || *width < 32 => Some(Type::Int(false, (*width).max(32))),
+		Type::PtrDiff => Some(Type::Size),
+		_ => None,
+	}
+}
+
+/// `ty` as part of an identifier
+fn mangle(ty: &Type) -> String {
+	ty.declare("").replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
@@ -0,0 +1,417 @@
+use std::fmt::{self, Formatter, Display};
+
+pub type Name = String;
+
+#[derive(Default)]
+pub struct Module {
+	pub name:  &'static str,
+
+	pub types: Vec<TypeDef>,
+	pub data:  Vec<DataDef>,
+	pub decls: Vec<FuncDecl>,
+	pub funcs: Vec<Function>,
+}
+
+
+pub struct TypeDef {
+	pub name:   Name,
+	pub fields: Vec<Type>,
+}
+
+
+#[allow(dead_code)]
+pub enum DataAttr {
+	Internal,
+	Global,
+	Private,
+	UnnamedAddr,
+	Constant,
+}
+
+pub struct DataDef {
+	pub name:  Name,
+	pub attr:  Vec<DataAttr>,
+	pub value: TypedVal,
+	// TODO: align
+}
+
+
+#[allow(dead_code)]
+pub enum FuncAttr {
+	Nounwind,
+	NoReturn,
+	NoInline,
+	AlwaysInline,
+	Cold,
+}
+
+pub struct Function {
+	pub name: Name,
+	pub attr: Vec<FuncAttr>,
+	pub internal: bool,
+	pub args: Vec<(Type, Name)>,
+	pub ret:  Type,
+	pub body: Vec<Instr>,
+}
+
+pub struct FuncDecl {
+	pub name: Name,
+	pub attr: Vec<FuncAttr>,
+	pub args: Vec<Type>,
+	pub ret:  Type,
+}
+
+
+pub enum Instr {
+	Assign(Val, Box<Self>),
+	Val(Val), // NOTE: This is an invalid instr by itself
+	Ret(Option<Val>, Type),
+	Call {
+		func: TypedVal,
+		args: Vec<TypedVal>,
+	},
+	Alloca(Type),
+	Store(TypedVal, TypedVal),
+	Load(Type, TypedVal),
+	BinOp(BinOp, TypedVal, TypedVal),
+	ICmp(ICond, TypedVal, TypedVal),
+	FCmp(FCond, TypedVal, TypedVal),
+	FNeg(TypedVal),
+	Cast(Cast, TypedVal, Type),
+	InsertValue(TypedVal, TypedVal, usize),
+	Gep(Type, TypedVal, Vec<TypedVal>),
+	Label(Name),
+	Br(Name),
+	CondBr(TypedVal, Name, Name), // i1, then, else
+	Unreachable,
+}
+
+pub enum BinOp {
+	Add, Sub, Mul, SDiv, UDiv, SRem, URem,
+	Shl, LShr, AShr, And, Or, Xor,
+	FAdd, FSub, FMul, FDiv, FRem,
+}
+
+pub enum ICond { Eq, Ne, Sgt, Sge, Slt, Sle, Ugt, Uge, Ult, Ule }
+pub enum FCond { Oeq, Une, Ogt, Oge, Olt, Ole }
+
+pub enum Cast {
+	ZExt, SExt, Trunc,
+	FPExt, FPTrunc,
+	FPToSI, FPToUI, SIToFP, UIToFP,
+	PtrToInt, IntToPtr,
+}
+
+#[derive(Clone)]
+pub enum ValKind { Local, Global, Str, Const, }
+#[derive(Clone)]
+pub struct Val(pub ValKind, pub Name);
+pub struct TypedVal(pub Type, pub ValKind, pub Name);
+
+impl Instr {
+	pub fn is_terminator(&self) -> bool {
+		matches!(self, Self::Ret(..) | Self::Br(_) | Self::CondBr(..) | Self::Unreachable)
+	}
+}
+
+impl Val {
+	pub fn typed(self, ty: Type) -> TypedVal {
+		TypedVal(ty, self.0, self.1)
+	}
+}
+
+impl TypedVal {
+	pub fn val(self) -> (Type, Val) {
+		(self.0, Val(self.1, self.2))
+	}
+}
+
+#[derive(Clone)]
+pub enum Type {
+	Int(u32),
+	F16, F32, F64, F128,
+	Ptr, Void,
+	Array(usize, Box<Self>),
+	Struct(Name), // named, declared by a `TypeDef`
+	Anon(Vec<Self>), // a literal struct, like the tag and value of an optional
+	// TODO: Vector, Function
+}
+
+
+impl Display for DataAttr {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Internal    => write!(f, "internal "),
+			Self::Private     => write!(f, "private "),
+			Self::UnnamedAddr => write!(f, "unnamed_addr "),
+			Self::Constant    => write!(f, "constant "),
+			Self::Global      => write!(f, "global "),
+		}
+	}
+}
+
+impl Display for FuncAttr {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Nounwind => write!(f, "nounwind "),
+			Self::NoReturn => write!(f, "noreturn "),
+			Self::NoInline => write!(f, "noinline "),
+			Self::AlwaysInline => write!(f, "alwaysinline "),
+			Self::Cold     => write!(f, "cold "),
+		}
+	}
+}
+
+impl Display for Module {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		writeln!(f, "source_filename = \"{}\"", self.name)?;
+		self.types.iter().try_for_each(|t| writeln!(f, "{t}"))?;
+		self.data .iter().try_for_each(|d| writeln!(f, "{d}"))?;
+		self.decls.iter().try_for_each(|c| writeln!(f, "{c}"))?;
+		self.funcs.iter().try_for_each(|c| writeln!(f, "{c}"))?;
+		writeln!(f, "!llvm.ident = !{{!0}}")?;
+		writeln!(f, "!0 = !{{!\"sharc {}\"}}", env!("CARGO_PKG_VERSION"))
+	}
+}
+
+impl Display for TypeDef {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "%{} = type {{ ", self.name)?;
+		for (i, ty) in self.fields.iter().enumerate() {
+			write!(f, "{ty}")?;
+			if i != self.fields.len() - 1 { write!(f, ", ")?; }
+		}
+		write!(f, " }}")
+	}
+}
+
+impl Display for DataDef {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "@{} = ", self.name)?;
+		self.attr.iter().try_for_each(|a| write!(f, "{a}"))?;
+		write!(f, "{}", self.value)
+	}
+}
+
+impl Display for Function {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "define {}{} @{}(", if self.internal { "internal " } else { "" }, self.ret, self.name)?;
+
+		for (i, arg) in self.args.iter().enumerate() {
+			write!(f, "{} %{}", arg.0, arg.1)?;
+			if i != self.args.len() - 1 { write!(f, ", ")?; }
+		}
+
+		write!(f, ")")?;
+		self.attr.iter().try_for_each(|a| write!(f, " {a}"))?;
+		writeln!(f, "{{")?;
+
+		self.body.iter().try_for_each(|i| match i {
+			Instr::Label(_) => writeln!(f, "{i}"),
+			_ => writeln!(f, "   {i}"),
+		})?;
+		writeln!(f, "}}")
+	}
+}
+
+impl Display for FuncDecl {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "declare {} @{}(", self.ret, self.name)?;
+
+		for (i, arg) in self.args.iter().enumerate() {
+			write!(f, "{arg}")?;
+			if i != self.args.len() - 1 { write!(f, ", ")?; }
+		}
+
+		write!(f, ")")?;
+		self.attr.iter().try_for_each(|a| write!(f, " {a}"))?;
+		write!(f, "\n")
+	}
+}
+
+impl Display for Instr {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Assign(v, i) => write!(f, "{v} = {i}"),
+			Self::Val(i) => write!(f, "{i}"),
+			Self::Ret(None, t) => write!(f, "ret {t}"),
+			Self::Ret(Some(Val(ValKind::Const, v)), t) => write!(f, "ret {t} {}", fmt_const(t, v)),
+			Self::Ret(Some(v), t) => write!(f, "ret {t} {v}"),
+			Self::Call { func, args } => {
+				write!(f, "call {func}(")?;
+				for (i, arg) in args.iter().enumerate() {
+					write!(f, "{arg}")?;
+					if i != args.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, ")")
+			},
+			Self::Alloca(t)   => write!(f, "alloca {t}"),
+			Self::Store(a, b) => write!(f, "store {a}, {b}"),
+			Self::Load(t, v)  => write!(f, "load {t}, {v}"),
+			Self::BinOp(op, a, b) => write!(f, "{op} {a}, {}", b.value()),
+			Self::ICmp(c, a, b)   => write!(f, "icmp {c} {a}, {}", b.value()),
+			Self::FCmp(c, a, b)   => write!(f, "fcmp {c} {a}, {}", b.value()),
+			Self::FNeg(v)         => write!(f, "fneg {v}"),
+			Self::Cast(c, v, t)   => write!(f, "{c} {v} to {t}"),
+			Self::InsertValue(a, v, i) => write!(f, "insertvalue {a}, {v}, {i}"),
+			Self::Gep(t, p, idx)  => {
+				write!(f, "getelementptr {t}, {p}")?;
+				idx.iter().try_for_each(|i| write!(f, ", {i}"))
+			},
+			Self::Label(l)        => write!(f, "{l}:"),
+			Self::Br(l)           => write!(f, "br label %{l}"),
+			Self::CondBr(c, a, b) => write!(f, "br {c}, label %{a}, label %{b}"),
+			Self::Unreachable     => write!(f, "unreachable"),
+		}
+	}
+}
+
+impl Display for Val {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}{}", self.0, self.1)
+	}
+}
+
+impl TypedVal {
+	/// The value without its type, for operands sharing the type of the first one
+	pub fn value(&self) -> String {
+		match self.1 {
+			ValKind::Const => fmt_const(&self.0, &self.2),
+			_ => format!("{}{}", self.1, self.2),
+		}
+	}
+}
+
+impl Display for TypedVal {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self.1 {
+			ValKind::Const | ValKind::Local | ValKind::Global => write!(f, "{} {}", self.0, self.value()),
+			ValKind::Str    => {
+				write!(f, "{} c\"", self.0)?;
+				self.2.chars().try_for_each(|c| match c {
+					'\x00'..='\x1f' => write!(f, "\\{:02x}", c as u8),
+					_ => write!(f, "{c}"),
+				})?;
+				write!(f, "\"")
+			},
+		}
+	}
+}
+
+/// Float constants are written as the hex of their double bits, decimal ones must be exact
+fn fmt_const(ty: &Type, v: &str) -> String {
+	#[allow(clippy::cast_possible_truncation)]
+	match ty {
+		Type::F32 => format!("0x{:016X}", f64::from(v.parse::<f64>().expect("invalid float constant") as f32).to_bits()),
+		Type::F64 => format!("0x{:016X}", v.parse::<f64>().expect("invalid float constant").to_bits()),
+		_ => v.to_string(),
+	}
+}
+
+impl Display for BinOp {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Add  => "add",
+			Self::Sub  => "sub",
+			Self::Mul  => "mul",
+			Self::SDiv => "sdiv",
+			Self::UDiv => "udiv",
+			Self::SRem => "srem",
+			Self::URem => "urem",
+			Self::Shl  => "shl",
+			Self::LShr => "lshr",
+			Self::AShr => "ashr",
+			Self::And  => "and",
+			Self::Or   => "or",
+			Self::Xor  => "xor",
+			Self::FAdd => "fadd",
+			Self::FSub => "fsub",
+			Self::FMul => "fmul",
+			Self::FDiv => "fdiv",
+			Self::FRem => "frem",
+		})
+	}
+}
+
+impl Display for ICond {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Eq  => "eq",
+			Self::Ne  => "ne",
+			Self::Sgt => "sgt",
+			Self::Sge => "sge",
+			Self::Slt => "slt",
+			Self::Sle => "sle",
+			Self::Ugt => "ugt",
+			Self::Uge => "uge",
+			Self::Ult => "ult",
+			Self::Ule => "ule",
+		})
+	}
+}
+
+impl Display for FCond {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Oeq => "oeq",
+			Self::Une => "une",
+			Self::Ogt => "ogt",
+			Self::Oge => "oge",
+			Self::Olt => "olt",
+			Self::Ole => "ole",
+		})
+	}
+}
+
+impl Display for Cast {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::ZExt     => "zext",
+			Self::SExt     => "sext",
+			Self::Trunc    => "trunc",
+			Self::FPExt    => "fpext",
+			Self::FPTrunc  => "fptrunc",
+			Self::FPToSI   => "fptosi",
+			Self::FPToUI   => "fptoui",
+			Self::SIToFP   => "sitofp",
+			Self::UIToFP   => "uitofp",
+			Self::PtrToInt => "ptrtoint",
+			Self::IntToPtr => "inttoptr",
+		})
+	}
+}
+
+impl Display for ValKind {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Local   => write!(f, "%"),
+			Self::Global  => write!(f, "@"),
+			Self::Str     => unreachable!(),
+			Self::Const   => Ok(()),
+		}
+	}
+}
+
+impl Display for Type {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Int(w)      => write!(f, "i{w}"),
+			Self::F16         => write!(f, "half"),
+			Self::F32         => write!(f, "float"),
+			Self::F64         => write!(f, "double"),
+			Self::F128        => write!(f, "fp128"),
+			Self::Ptr         => write!(f, "ptr"),
+			Self::Void        => write!(f, "void"),
+			Self::Array(n, t) => write!(f, "[{n} x {t}]"),
+			Self::Struct(n)   => write!(f, "%{n}"),
+			Self::Anon(fields) => {
+				write!(f, "{{ ")?;
+				for (i, ty) in fields.iter().enumerate() {
+					write!(f, "{ty}")?;
+					if i != fields.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, " }}")
+			},
+		}
+	}
+}
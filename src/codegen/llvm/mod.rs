@@ -0,0 +1,826 @@
+use std::collections::HashMap;
+use std::cmp::Ordering;
+
+use crate::report::{Result, LogHandler, ReportKind};
+use crate::analyzer::mir::{self, ValId, TypeId, Var, Node, BinOp, UnOp, Type as mType};
+
+mod ir;
+use ir::{Instr, Val, TypedVal, ValKind, Module, DataAttr, FuncAttr, Type, ICond, FCond, Cast};
+
+pub struct Gen {
+	module: Module,
+	sym:    HashMap<ValId, String>,
+	ucnt:   u64,
+	layouts: HashMap<TypeId, (u64, u64)>, // size and alignment of structs and enums
+}
+
+impl Gen {
+	pub fn codegen(
+		file: &'static str, sym: HashMap<ValId, String>, 
+		mir: Vec<Node>, handler: &LogHandler) 
+	-> Module {
+		let mut cgen = Self {
+			sym, ucnt: 0,
+			layouts: HashMap::new(),
+			module: Module { name: file, ..Module::default() },
+		};
+
+		mir.into_iter().for_each(|node| {
+			let loc = node.loc();
+			if let Err(e) = cgen.gen_node(node) {
+				handler.log(super::locate(*e, loc).file(file));
+			}
+		});
+
+		cgen.module
+	}
+
+	fn inc_ucnt(&mut self) -> u64 {
+		let ucnt = self.ucnt;
+		self.ucnt += 1;
+		ucnt
+	}
+
+	fn get_id_name(&self, id: mir::ValId) -> &str {
+		self.sym.get(&id).expect("ValId not found in sym table")
+	}
+
+	fn gen_node(&mut self, node: Node) -> Result<()> {
+		match node {
+			Node::Func { id, export, args, ret, body, .. } => {
+				// args are spilled into allocas so they can be treated like any other local
+				let mut nargs = Vec::new();
+				let mut nbody = Vec::new();
+				for (i, t) in args {
+					let ty = gen_type(&t)?;
+					nargs.push((ty.clone(), format!("a{}", *i)));
+					nbody.push(Instr::Assign(Val(ValKind::Local, format!("t{}", *i)), Instr::Alloca(ty.clone()).into()));
+					nbody.push(Instr::Store(TypedVal(ty, ValKind::Local, format!("a{}", *i)),
+						TypedVal(Type::Ptr, ValKind::Local, format!("t{}", *i))));
+				}
+
+				for stmt in body {
+					nbody.extend(self.gen_stmt(stmt)?);
+				}
+
+				// only void functions can fall off their end, anything else never gets there
+				let attr = noreturn(&ret);
+				let ret = gen_type(&ret)?;
+				if !nbody.last().is_some_and(Instr::is_terminator) {
+					nbody.push(match ret {
+						Type::Void if attr.is_empty() => Instr::Ret(None, Type::Void),
+						_ => Instr::Unreachable,
+					});
+				}
+
+				// allocas go into the entry block, so loops dont grow the stack
+				let (mut allocas, nbody): (Vec<_>, Vec<_>) = nbody.into_iter()
+					.partition(|i| matches!(i, Instr::Assign(_, i) if matches!(**i, Instr::Alloca(_))));
+				allocas.extend(self.seal_blocks(nbody));
+				let nbody = allocas;
+
+				let func = ir::Function {
+					attr,
+					internal: !export,
+					name: super::symbol(&self.sym, id),
+					args: nargs,
+					body: nbody,
+					ret,
+				};
+
+				self.module.funcs.push(func);
+			},
+			Node::FuncDecl { id, args, ret, .. } => {
+				let func = ir::FuncDecl {
+					attr: noreturn(&ret),
+					name: self.get_id_name(id).to_string(),
+					args: args.into_iter().map(|t| gen_type(&t)).collect::<Result<Vec<_>>>()?,
+					ret:  gen_type(&ret)?,
+				};
+
+				self.module.decls.push(func);
+			},
+			Node::Struct { id, fields, .. } => {
+				let (mut size, mut align) = (0u64, 1);
+				for (fsize, falign) in fields.iter().map(|t| self.layout(t)) {
+					size = size.next_multiple_of(falign) + fsize;
+					align = align.max(falign);
+				}
+				self.layouts.insert(id, (size.next_multiple_of(align), align));
+
+				let ty = ir::TypeDef {
+					name:   id.to_string(),
+					fields: fields.iter().map(gen_type).collect::<Result<Vec<_>>>()?,
+				};
+
+				self.module.types.push(ty);
+			},
+			// a u32 tag followed by room for the largest payload, aligned for the strictest one
+			Node::Enum { id, variants, .. } => {
+				let (size, align) = variants.iter().flatten()
+					.map(|t| self.layout(t))
+					.fold((0, 1), |(s, a), (ts, ta)| (s.max(ts), a.max(ta)));
+
+				let mut fields = vec![Type::Int(32)];
+				#[allow(clippy::cast_possible_truncation)]
+				if size > 0 {
+					fields.push(Type::Array(size.div_ceil(align) as usize, Box::new(Type::Int(align as u32 * 8))));
+				}
+
+				let align = align.max(4);
+				self.layouts.insert(id, ((4u64.next_multiple_of(align) + size).next_multiple_of(align), align));
+				self.module.types.push(ir::TypeDef { name: id.to_string(), fields });
+			},
+			Node::Assign { .. } | Node::Global { .. } | Node::Store { .. } | Node::StoreIndex { .. }
+				| Node::StoreField { .. } | Node::Ret(..) | Node::Trap(_) | Node::Label(_) | Node::Jump(_)
+				| Node::Branch { .. } | Node::FuncCall { .. } | Node::BinOp { .. } | Node::UnOp { .. }
+				| Node::StructLit(_) | Node::Field { .. } | Node::EnumLit { .. } | Node::Tag { .. }
+				| Node::Payload { .. } | Node::OptLit(_) | Node::IsSome { .. } | Node::Unwrap { .. }
+				| Node::AddrOf(_) | Node::Deref(_) | Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_)
+				| Node::Index { .. } | Node::Len(_) | Node::Decay { .. } | Node::SliceLit { .. }
+				| Node::Cast { .. } | Node::StrLit(_) | Node::Var(_)
+				=> unreachable!("only declarations are at the top level"),
+		}
+		Ok(())
+	}
+
+	fn gen_stmt(&mut self, node: Node) -> Result<Vec<Instr>> {
+		Ok(vec![match node {
+			Node::Assign { id, ty, val } => {
+				let lty = gen_type(&ty)?;
+				let (mut instrs, val) = self.gen_rval(*val, &ty)?;
+
+				instrs.push(Instr::Assign(Val(ValKind::Local, format!("t{}", *id)), Instr::Alloca(lty.clone()).into()));
+				instrs.push(Instr::Store(val.typed(lty), 
+					TypedVal(Type::Ptr, ValKind::Local, format!("t{}", *id))));
+				return Ok(instrs);
+			},
+			Node::Global { id, ty, val } => {
+				let Instr::Val(val) = self.gen_stmt(*val)?.remove(0)
+					else { unreachable!() };
+
+				let data = ir::DataDef {
+					value: val.typed(gen_type(&ty)?),
+					name:  format!("g{}", *id),
+					attr:  vec![DataAttr::Internal, DataAttr::Global], // TODO: global var attrs
+				};
+
+				self.module.data.push(data);
+				return Ok(Vec::new());
+			},
+			Node::Store { to, from: (v, ty), deref } => {
+				let (instr, val) = self.use_val(self.gen_val(&v).typed(gen_type(&ty)?));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				// locals and globals already are the address of their value
+				let ptr = match deref {
+					true => {
+						let (instr, ptr) = self.use_val(self.gen_val(&to).typed(Type::Ptr));
+						instrs.extend(instr);
+						ptr
+					},
+					false => self.gen_val(&to).typed(Type::Ptr),
+				};
+
+				instrs.push(Instr::Store(val, ptr));
+				return Ok(instrs);
+			},
+			Node::StoreIndex { to: (arr, aty), index, from: (v, ty) } => {
+				let (instr, val) = self.use_val(self.gen_val(&v).typed(gen_type(&ty)?));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let ptr = self.elem_ptr(&mut instrs, &aty, &arr, &index)?;
+				instrs.push(Instr::Store(val, ptr));
+				return Ok(instrs);
+			},
+			Node::StoreField { to: (to, sty), index, from: (v, ty), deref } => {
+				let (instr, val) = self.use_val(self.gen_val(&v).typed(gen_type(&ty)?));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let ptr = self.field_ptr(&mut instrs, &sty, &to, index, deref)?;
+				instrs.push(Instr::Store(val, ptr));
+				return Ok(instrs);
+			},
+			Node::Ret(None, ty)    => Instr::Ret(None, gen_type(&ty)?), // realistically this is only ever void
+			Node::Ret(Some(v), ty) => {
+				let (instr, tyval) = self.use_val(self.gen_val(&v).typed(gen_type(&ty)?));
+				let mut instrs = instr.map_or(Vec::new(), |i| vec![i]);
+
+				let (ty, val) = tyval.val();
+				instrs.push(Instr::Ret(Some(val), ty));
+
+				return Ok(instrs);
+			},
+			Node::FuncCall { id, args } => return self.gen_fncall(&id, args, Type::Void),
+			Node::Label(l) => Instr::Label(l.to_string()),
+			Node::Jump(l)  => Instr::Br(l.to_string()),
+			Node::Branch { cond: (v, ty), then, els } => {
+				let (instr, cond) = self.use_val(self.gen_val(&v).typed(gen_type(&ty)?));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				// wider bools have to be narrowed to an i1 first
+				let cond = match cond.0 {
+					Type::Int(1) => cond,
+					ref ty => {
+						let ty = ty.clone();
+						let val = self.new_tmp();
+						instrs.push(Instr::Assign(val.clone(), 
+							Instr::ICmp(ICond::Ne, cond, TypedVal(ty, ValKind::Const, String::from("0"))).into()));
+						val.typed(Type::Int(1))
+					},
+				};
+
+				instrs.push(Instr::CondBr(cond, then.to_string(), els.to_string()));
+				return Ok(instrs);
+			},
+			Node::Trap(_) => {
+				if !self.module.decls.iter().any(|d| d.name == "llvm.trap") {
+					self.module.decls.push(ir::FuncDecl {
+						attr: vec![FuncAttr::NoReturn, FuncAttr::Cold],
+						name: String::from("llvm.trap"),
+						args: Vec::new(),
+						ret:  Type::Void,
+					});
+				}
+
+				return Ok(vec![Instr::Call {
+					func: TypedVal(Type::Void, ValKind::Global, String::from("llvm.trap")),
+					args: Vec::new(),
+				}, Instr::Unreachable]);
+			},
+			Node::Var(v)    => Instr::Val(self.gen_val(&v)),
+			Node::StrLit(l) => Instr::Val(Val(ValKind::Str, l)),
+			Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. }
+				=> unreachable!("declarations are not nested in functions"),
+			Node::BinOp { .. } | Node::UnOp { .. } | Node::StructLit(_) | Node::Field { .. }
+				| Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } | Node::OptLit(_)
+				| Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_) | Node::Deref(_)
+				| Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. } | Node::Len(_)
+				| Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. }
+				=> unreachable!("only the value of an Assign"),
+		}])
+	}
+
+	/// Lowers the value side of an assignment, returning the instrs needed to compute it
+	fn gen_rval(&mut self, node: Node, ty: &mType) -> Result<(Vec<Instr>, Val)> {
+		Ok(match node {
+			Node::FuncCall { id, args } => {
+				let mut instrs = self.gen_fncall(&id, args, gen_type(ty)?)?;
+				let call = instrs.pop().unwrap();
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), call.into()));
+				(instrs, val)
+			},
+			// globals are only their address, so an array is copied out of one
+			Node::Var(v @ Var::Glob(_)) if matches!(ty.base(), mType::Arr(_, Some(_))) => {
+				let val = self.new_tmp();
+				(vec![Instr::Assign(val.clone(), Instr::Load(gen_type(ty)?, self.gen_val(&v).typed(Type::Ptr)).into())], val)
+			},
+			Node::Var(v) => {
+				let (instr, tyval) = self.use_val(self.gen_val(&v).typed(gen_type(ty)?));
+				(instr.into_iter().collect(), tyval.val().1)
+			},
+			Node::Global { id, ty: gty, val } => {
+				self.gen_stmt(Node::Global { id, ty: gty, val })?;
+				(Vec::new(), Val(ValKind::Global, format!("g{}", *id)))
+			},
+			Node::BinOp { op, ty: oty, lhs, rhs } => {
+				let lty = gen_type(&oty)?;
+				let (linstr, lhs) = self.use_val(self.gen_val(&lhs).typed(lty.clone()));
+				let (rinstr, rhs) = self.use_val(self.gen_val(&rhs).typed(lty));
+				let mut instrs: Vec<Instr> = linstr.into_iter().chain(rinstr).collect();
+
+				let (signed, float) = (oty.is_signed(), oty.is_float());
+				let instr = match op {
+					_ if op.is_cmp() && float => Instr::FCmp(match op {
+						BinOp::Eq => FCond::Oeq,
+						BinOp::Ne => FCond::Une,
+						BinOp::Lt => FCond::Olt,
+						BinOp::Le => FCond::Ole,
+						BinOp::Gt => FCond::Ogt,
+						BinOp::Ge => FCond::Oge,
+						_ => unreachable!(),
+					}, lhs, rhs),
+					_ if op.is_cmp() => Instr::ICmp(match (op, signed) {
+						(BinOp::Eq, _)     => ICond::Eq,
+						(BinOp::Ne, _)     => ICond::Ne,
+						(BinOp::Lt, true)  => ICond::Slt,
+						(BinOp::Le, true)  => ICond::Sle,
+						(BinOp::Gt, true)  => ICond::Sgt,
+						(BinOp::Ge, true)  => ICond::Sge,
+						(BinOp::Lt, false) => ICond::Ult,
+						(BinOp::Le, false) => ICond::Ule,
+						(BinOp::Gt, false) => ICond::Ugt,
+						(BinOp::Ge, false) => ICond::Uge,
+						_ => unreachable!(),
+					}, lhs, rhs),
+					_ => Instr::BinOp(match (op, signed, float) {
+						(BinOp::Add, _, true)     => ir::BinOp::FAdd,
+						(BinOp::Sub, _, true)     => ir::BinOp::FSub,
+						(BinOp::Mul, _, true)     => ir::BinOp::FMul,
+						(BinOp::Div, _, true)     => ir::BinOp::FDiv,
+						(BinOp::Rem, _, true)     => ir::BinOp::FRem,
+						(BinOp::Add, _, _)        => ir::BinOp::Add,
+						(BinOp::Sub, _, _)        => ir::BinOp::Sub,
+						(BinOp::Mul, _, _)        => ir::BinOp::Mul,
+						(BinOp::Div, true, _)     => ir::BinOp::SDiv,
+						(BinOp::Div, false, _)    => ir::BinOp::UDiv,
+						(BinOp::Rem, true, _)     => ir::BinOp::SRem,
+						(BinOp::Rem, false, _)    => ir::BinOp::URem,
+						(BinOp::Shl, _, _)        => ir::BinOp::Shl,
+						(BinOp::Shr, true, _)     => ir::BinOp::AShr,
+						(BinOp::Shr, false, _)    => ir::BinOp::LShr,
+						(BinOp::BitAnd | BinOp::And, _, _) => ir::BinOp::And,
+						(BinOp::BitOr  | BinOp::Or,  _, _) => ir::BinOp::Or,
+						(BinOp::BitXor | BinOp::Xor, _, _) => ir::BinOp::Xor,
+						_ => unreachable!(),
+					}, lhs, rhs),
+				};
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), instr.into()));
+
+				match op.is_cmp() {
+					true  => { let val = self.widen_bool(&mut instrs, val, ty)?; (instrs, val) },
+					false => (instrs, val),
+				}
+			},
+			Node::StructLit(fields) | Node::ArrLit(fields) => {
+				let sty = gen_type(ty)?;
+				let mut instrs = Vec::new();
+
+				let mut agg = TypedVal(sty.clone(), ValKind::Const, String::from("undef"));
+				for (i, (v, fty)) in fields.into_iter().enumerate() {
+					let (instr, val) = self.use_val(self.gen_val(&v).typed(gen_type(&fty)?));
+					instrs.extend(instr);
+
+					let tmp = self.new_tmp();
+					instrs.push(Instr::Assign(tmp.clone(), Instr::InsertValue(agg, val, i).into()));
+					agg = tmp.typed(sty.clone());
+				}
+
+				(instrs, agg.val().1)
+			},
+			Node::Field { ty: sty, val, index, deref } => {
+				let mut instrs = Vec::new();
+				let field = self.field_ptr(&mut instrs, &sty, &val, index, deref)?;
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), Instr::Load(gen_type(ty)?, field).into()));
+				(instrs, val)
+			},
+			// built in memory, since the payload is written through a pointer of its own type
+			Node::EnumLit { variant, val } => {
+				let ety = gen_type(ty)?;
+				let mut instrs = Vec::new();
+
+				let tmp = self.new_tmp();
+				instrs.push(Instr::Assign(tmp.clone(), Instr::Alloca(ety.clone()).into()));
+				instrs.push(Instr::Store(TypedVal(Type::Int(32), ValKind::Const, variant.to_string()), tmp.clone().typed(Type::Ptr)));
+
+				if let Some((v, pty)) = val {
+					let (instr, val) = self.use_val(self.gen_val(&v).typed(gen_type(&pty)?));
+					instrs.extend(instr);
+
+					let payload = self.enum_field(&mut instrs, ety.clone(), tmp.clone().typed(Type::Ptr), 1);
+					instrs.push(Instr::Store(val, payload));
+				}
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), Instr::Load(ety, tmp.typed(Type::Ptr)).into()));
+				(instrs, val)
+			},
+			Node::Tag { ty: ety, val } => self.gen_enum_load(&ety, &val, 0, ty)?,
+			// `opt *T` is the pointer itself, with null as none
+			Node::OptLit(Some((v, _))) if ty.has_niche() => {
+				let (instr, val) = self.use_val(self.gen_val(&v).typed(Type::Ptr));
+				(instr.into_iter().collect(), val.val().1)
+			},
+			Node::OptLit(None) if ty.has_niche() => (Vec::new(), Val(ValKind::Const, String::from("null"))),
+			Node::OptLit(None) => (Vec::new(), Val(ValKind::Const, String::from("zeroinitializer"))),
+			Node::OptLit(Some((v, pty))) => {
+				let oty = gen_type(ty)?;
+				let (instr, val) = self.use_val(self.gen_val(&v).typed(gen_type(&pty)?));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let tag = self.new_tmp();
+				instrs.push(Instr::Assign(tag.clone(), Instr::InsertValue(
+					TypedVal(oty.clone(), ValKind::Const, String::from("undef")),
+					TypedVal(Type::Int(8), ValKind::Const, String::from("1")), 0).into()));
+
+				let opt = self.new_tmp();
+				instrs.push(Instr::Assign(opt.clone(), Instr::InsertValue(tag.typed(oty), val, 1).into()));
+				(instrs, opt)
+			},
+			Node::IsSome { ty: oty, val } => {
+				let (mut instrs, cmp) = match oty.has_niche() {
+					true => {
+						let (instr, val) = self.use_val(self.gen_val(&val).typed(Type::Ptr));
+						(instr.into_iter().collect(), val)
+					},
+					false => {
+						let (instrs, tag) = self.gen_enum_load(&oty, &val, 0, &mType::U(8))?;
+						(instrs, tag.typed(Type::Int(8)))
+					},
+				};
+
+				let res = self.new_tmp();
+				let zero = TypedVal(cmp.0.clone(), ValKind::Const, String::from(match cmp.0 {
+					Type::Ptr => "null",
+					_ => "0",
+				}));
+				instrs.push(Instr::Assign(res.clone(), Instr::ICmp(ICond::Ne, cmp, zero).into()));
+
+				let res = self.widen_bool(&mut instrs, res, ty)?;
+				(instrs, res)
+			},
+			Node::Unwrap { ty: oty, val } if oty.has_niche() => {
+				let (instr, val) = self.use_val(self.gen_val(&val).typed(Type::Ptr));
+				(instr.into_iter().collect(), val.val().1)
+			},
+			Node::Unwrap { ty: oty, val } => self.gen_enum_load(&oty, &val, 1, ty)?,
+			// locals and globals already are the address of their value
+			Node::AddrOf(v) => (Vec::new(), self.gen_val(&v)),
+			Node::Index { ty: aty, val, index } => {
+				let mut instrs = Vec::new();
+				let ptr = self.elem_ptr(&mut instrs, &aty, &val, &index)?;
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), Instr::Load(gen_type(ty)?, ptr).into()));
+				(instrs, val)
+			},
+			Node::Len(v) => {
+				let mut instrs = Vec::new();
+				let len = self.slice_field(&mut instrs, &v, 1, Type::Int(64));
+				(instrs, len.val().1)
+			},
+			Node::Decay { ty: mType::Arr(_, Some(_)), val } => (Vec::new(), self.gen_val(&val)),
+			Node::Decay { val, .. } => {
+				let mut instrs = Vec::new();
+				let ptr = self.slice_field(&mut instrs, &val, 0, Type::Ptr);
+				(instrs, ptr.val().1)
+			},
+			Node::SliceLit { ptr, len } => {
+				let (pinstr, ptr) = self.use_val(self.gen_val(&ptr).typed(Type::Ptr));
+				let (linstr, len) = self.use_val(self.gen_val(&len).typed(Type::Int(64)));
+				let mut instrs: Vec<Instr> = pinstr.into_iter().chain(linstr).collect();
+
+				let with_ptr = self.new_tmp();
+				instrs.push(Instr::Assign(with_ptr.clone(), Instr::InsertValue(
+					TypedVal(slice_type(), ValKind::Const, String::from("undef")), ptr, 0).into()));
+
+				let slice = self.new_tmp();
+				instrs.push(Instr::Assign(slice.clone(), Instr::InsertValue(with_ptr.typed(slice_type()), len, 1).into()));
+				(instrs, slice)
+			},
+			Node::Deref(v) => {
+				let (instr, ptr) = self.use_val(self.gen_val(&v).typed(Type::Ptr));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), Instr::Load(gen_type(ty)?, ptr).into()));
+				(instrs, val)
+			},
+			Node::Offset { ty: pty, ptr, op, index: (i, ity) } => {
+				let mType::Ptr(pointee) = pty.base() else { unreachable!() };
+				let (pinstr, ptr) = self.use_val(self.gen_val(&ptr).typed(Type::Ptr));
+				let (iinstr, index) = self.use_val(self.gen_val(&i).typed(gen_type(&ity)?));
+				let mut instrs: Vec<Instr> = pinstr.into_iter().chain(iinstr).collect();
+
+				// gep takes its indices as signed, so narrower unsigned ones are extended first
+				let index = match index.0 {
+					Type::Int(w) if w < 64 && !ity.is_signed() => {
+						let ext = self.new_tmp();
+						instrs.push(Instr::Assign(ext.clone(), Instr::Cast(Cast::ZExt, index, Type::Int(64)).into()));
+						ext.typed(Type::Int(64))
+					},
+					_ => index,
+				};
+
+				let index = match op {
+					BinOp::Sub => {
+						let ity = index.0.clone();
+						let neg = self.new_tmp();
+						instrs.push(Instr::Assign(neg.clone(), Instr::BinOp(ir::BinOp::Sub,
+							TypedVal(ity.clone(), ValKind::Const, String::from("0")), index).into()));
+						neg.typed(ity)
+					},
+					_ => index,
+				};
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), Instr::Gep(gen_type(pointee)?, ptr, vec![index]).into()));
+				(instrs, val)
+			},
+			// the size of what they point to is how far the next one is from null
+			Node::Diff { ty: pty, lhs, rhs } => {
+				let mType::Ptr(pointee) = pty.base() else { unreachable!() };
+				let (linstr, lhs) = self.use_val(self.gen_val(&lhs).typed(Type::Ptr));
+				let (rinstr, rhs) = self.use_val(self.gen_val(&rhs).typed(Type::Ptr));
+				let mut instrs: Vec<Instr> = linstr.into_iter().chain(rinstr).collect();
+
+				let next = self.new_tmp();
+				instrs.push(Instr::Assign(next.clone(), Instr::Gep(gen_type(pointee)?,
+					TypedVal(Type::Ptr, ValKind::Const, String::from("null")),
+					vec![TypedVal(Type::Int(64), ValKind::Const, String::from("1"))]).into()));
+
+				let [l, r, size, bytes] = [(); 4].map(|()| self.new_tmp());
+				instrs.push(Instr::Assign(l.clone(), Instr::Cast(Cast::PtrToInt, lhs, Type::Int(64)).into()));
+				instrs.push(Instr::Assign(r.clone(), Instr::Cast(Cast::PtrToInt, rhs, Type::Int(64)).into()));
+				instrs.push(Instr::Assign(size.clone(), Instr::Cast(Cast::PtrToInt, next.typed(Type::Ptr), Type::Int(64)).into()));
+				instrs.push(Instr::Assign(bytes.clone(), Instr::BinOp(ir::BinOp::Sub, l.typed(Type::Int(64)), r.typed(Type::Int(64))).into()));
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), Instr::BinOp(ir::BinOp::SDiv, bytes.typed(Type::Int(64)), size.typed(Type::Int(64))).into()));
+				(instrs, val)
+			},
+			Node::Payload { ty: ety, val, .. } => self.gen_enum_load(&ety, &val, 1, ty)?,
+			// the widths pick between extending and truncating, the source's sign between sign and zero extension
+			Node::Cast { ty: from, val } => {
+				let (fty, tty) = (gen_type(&from)?, gen_type(ty)?);
+				let (instr, val) = self.use_val(self.gen_val(&val).typed(fty.clone()));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let width = bits(&fty).cmp(&bits(&tty));
+				let cast = match (from.base(), ty.base()) {
+					(mType::Ptr(_), mType::Ptr(_)) => None,
+					(mType::Ptr(_), _) => Some(Cast::PtrToInt),
+					(_, mType::Ptr(_)) => Some(Cast::IntToPtr),
+					(f, t) if f.is_float() && t.is_float() => match width {
+						Ordering::Less    => Some(Cast::FPExt),
+						Ordering::Greater => Some(Cast::FPTrunc),
+						Ordering::Equal   => None,
+					},
+					(f, t) if f.is_float() => Some(if t.is_signed() { Cast::FPToSI } else { Cast::FPToUI }),
+					(f, t) if t.is_float() => Some(if f.is_signed() { Cast::SIToFP } else { Cast::UIToFP }),
+					(f, _) => match width {
+						Ordering::Less if f.is_signed() => Some(Cast::SExt),
+						Ordering::Less    => Some(Cast::ZExt),
+						Ordering::Greater => Some(Cast::Trunc),
+						Ordering::Equal   => None,
+					},
+				};
+
+				let Some(cast) = cast else { return Ok((instrs, val.val().1)) };
+				let res = self.new_tmp();
+				instrs.push(Instr::Assign(res.clone(), Instr::Cast(cast, val, tty).into()));
+				(instrs, res)
+			},
+			Node::UnOp { op, ty: oty, val } => {
+				let lty = gen_type(&oty)?;
+				let (instr, val) = self.use_val(self.gen_val(&val).typed(lty.clone()));
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let instr = match op {
+					UnOp::Neg if oty.is_float() => Instr::FNeg(val),
+					UnOp::Neg => Instr::BinOp(ir::BinOp::Sub, TypedVal(lty, ValKind::Const, String::from("0")), val),
+					// bools are only ever 0 or 1
+					UnOp::Not    => Instr::BinOp(ir::BinOp::Xor, val, TypedVal(lty, ValKind::Const, String::from("1"))),
+					UnOp::BitNot => Instr::BinOp(ir::BinOp::Xor, val, TypedVal(lty, ValKind::Const, String::from("-1"))),
+				};
+
+				let val = self.new_tmp();
+				instrs.push(Instr::Assign(val.clone(), instr.into()));
+				(instrs, val)
+			},
+			_ => unreachable!(),
+		})
+	}
+
+	/// Every block has to end in exactly one terminator, so fallthrough gets an explicit branch
+	/// and code following a terminator is put into a block of its own
+	fn seal_blocks(&mut self, body: Vec<Instr>) -> Vec<Instr> {
+		let mut sealed = false;
+		let mut nbody = Vec::with_capacity(body.len());
+
+		for instr in body {
+			match &instr {
+				Instr::Label(l) if !sealed => nbody.push(Instr::Br(l.clone())),
+				Instr::Label(_) => (),
+				_ if sealed => nbody.push(Instr::Label(format!("dead{}", self.inc_ucnt()))),
+				_ => (),
+			}
+
+			sealed = instr.is_terminator();
+			nbody.push(instr);
+		}
+
+		nbody
+	}
+
+	/// Comparisons yield an i1, wider bools are zero extended
+	fn widen_bool(&mut self, instrs: &mut Vec<Instr>, val: Val, ty: &mType) -> Result<Val> {
+		Ok(match gen_type(ty)? {
+			Type::Int(w) if w != 1 => {
+				let ext = self.new_tmp();
+				instrs.push(Instr::Assign(ext.clone(), 
+					Instr::Cast(Cast::ZExt, val.typed(Type::Int(1)), Type::Int(w)).into()));
+				ext
+			},
+			_ => val,
+		})
+	}
+
+	/// Loads the tag (0) or the payload (1) of the enum or optional in the local `val`
+	fn gen_enum_load(&mut self, ety: &mType, val: &Var, index: usize, ty: &mType) -> Result<(Vec<Instr>, Val)> {
+		let mut instrs = Vec::new();
+		let ptr = self.enum_field(&mut instrs, gen_type(ety)?, self.gen_val(val).typed(Type::Ptr), index);
+
+		let val = self.new_tmp();
+		instrs.push(Instr::Assign(val.clone(), Instr::Load(gen_type(ty)?, ptr).into()));
+		Ok((instrs, val))
+	}
+
+	/// Address of the tag (0) or the payload (1) of the enum at `ptr`
+	fn enum_field(&mut self, instrs: &mut Vec<Instr>, ety: Type, ptr: TypedVal, index: usize) -> TypedVal {
+		let field = self.new_tmp();
+		instrs.push(Instr::Assign(field.clone(), Instr::Gep(ety, ptr, vec![
+			TypedVal(Type::Int(32), ValKind::Const, String::from("0")),
+			TypedVal(Type::Int(32), ValKind::Const, index.to_string()),
+		]).into()));
+		field.typed(Type::Ptr)
+	}
+
+	/// Address of element `index` of the array or slice `val`
+	fn elem_ptr(&mut self, instrs: &mut Vec<Instr>, aty: &mType, val: &Var, index: &Var) -> Result<TypedVal> {
+		let (instr, index) = self.use_val(self.gen_val(index).typed(Type::Int(64)));
+		instrs.extend(instr);
+
+		let gep = match aty {
+			// locals and globals already are the address of their value
+			mType::Arr(_, Some(_)) => Instr::Gep(gen_type(aty)?, self.gen_val(val).typed(Type::Ptr), vec![
+				TypedVal(Type::Int(64), ValKind::Const, String::from("0")),
+				index,
+			]),
+			mType::Arr(ety, None) => {
+				let ptr = self.slice_field(instrs, val, 0, Type::Ptr);
+				Instr::Gep(gen_type(ety)?, ptr, vec![index])
+			},
+			_ => unreachable!(),
+		};
+
+		let ptr = self.new_tmp();
+		instrs.push(Instr::Assign(ptr.clone(), gep.into()));
+		Ok(ptr.typed(Type::Ptr))
+	}
+
+	/// Address of field `index` of the struct `val`, or of the one it points to when `deref`
+	fn field_ptr(&mut self, instrs: &mut Vec<Instr>, sty: &mType, val: &Var, index: usize, deref: bool) -> Result<TypedVal> {
+		// locals already are the address of their value
+		let ptr = match deref {
+			true => {
+				let (instr, ptr) = self.use_val(self.gen_val(val).typed(Type::Ptr));
+				instrs.extend(instr);
+				ptr
+			},
+			false => self.gen_val(val).typed(Type::Ptr),
+		};
+
+		let field = self.new_tmp();
+		instrs.push(Instr::Assign(field.clone(), Instr::Gep(gen_type(sty)?, ptr, vec![
+			TypedVal(Type::Int(32), ValKind::Const, String::from("0")),
+			TypedVal(Type::Int(32), ValKind::Const, index.to_string()),
+		]).into()));
+		Ok(field.typed(Type::Ptr))
+	}
+
+	/// Loads the pointer (0) or the length (1) of the slice `val`
+	fn slice_field(&mut self, instrs: &mut Vec<Instr>, val: &Var, index: usize, ty: Type) -> TypedVal {
+		let field = self.new_tmp();
+		instrs.push(Instr::Assign(field.clone(), Instr::Gep(slice_type(), self.gen_val(val).typed(Type::Ptr), vec![
+			TypedVal(Type::Int(32), ValKind::Const, String::from("0")),
+			TypedVal(Type::Int(32), ValKind::Const, index.to_string()),
+		]).into()));
+
+		let v = self.new_tmp();
+		instrs.push(Instr::Assign(v.clone(), Instr::Load(ty.clone(), field.typed(Type::Ptr)).into()));
+		v.typed(ty)
+	}
+
+	/// Size and alignment in bytes, as llvm lays them out for x86-64
+	fn layout(&self, ty: &mType) -> (u64, u64) {
+		match ty {
+			mType::U(n) | mType::I(n) | mType::B(n) | mType::F(n) => {
+				let bytes = u64::from(n.next_power_of_two().max(8) / 8);
+				(bytes, bytes.min(16))
+			},
+			mType::Opt(_) if ty.has_niche() => (8, 8),
+			// an i8 tag in front of the value
+			mType::Opt(ty) => {
+				let (size, align) = self.layout(ty);
+				((align + size).next_multiple_of(align), align)
+			},
+			mType::Mut(ty) => self.layout(ty),
+			mType::Arr(ty, Some(n)) => {
+				let (size, align) = self.layout(ty);
+				(size * n, align)
+			},
+			mType::Arr(_, None) => (16, 8),
+			mType::Struct(id, _) | mType::Enum(id, _) => self.layouts[id],
+			_ => (8, 8),
+		}
+	}
+
+	fn new_tmp(&mut self) -> Val {
+		Val(ValKind::Local, format!("v{}", self.inc_ucnt()))
+	}
+
+	fn gen_fncall(&mut self, var: &Var, args: Vec<(Var, mType)>, ret: Type) -> Result<Vec<Instr>> {
+		let mut instrs = Vec::new();
+
+		let instr = Instr::Call {
+			// a local holds the address of a function, it is loaded as a pointer but called with the return type
+			func: {
+				let (instr, TypedVal(_, kind, name)) = self.use_val(self.gen_val(var).typed(Type::Ptr));
+				instr.map(|i| instrs.push(i));
+				TypedVal(ret, kind, name)
+			},
+			args: {
+				let mut nargs = Vec::new();
+				for (var, ty) in args {
+					let (instr, tyval) = self.use_val(self.gen_val(&var).typed(gen_type(&ty)?));
+					instr.map(|i| instrs.push(i));
+					nargs.push(tyval);
+				}
+				nargs
+			},
+		};
+		instrs.push(instr);
+		Ok(instrs)
+	}
+
+	// TODO: maybe not always a ptr
+	fn use_val(&mut self, val: TypedVal) -> (Option<Instr>, TypedVal) {
+		match val {
+			TypedVal(ty, ValKind::Local, name) => {
+				let nname = format!("{}_{}", name, self.inc_ucnt());
+
+				(Some(Instr::Assign(Val(ValKind::Local, nname.clone()), 
+					Instr::Load(ty.clone(), TypedVal(Type::Ptr, ValKind::Local, name)).into())),
+					TypedVal(ty, ValKind::Local, nname))
+			},
+			_ => (None, val),
+		}
+	}
+
+	fn gen_val(&self, v: &mir::Var) -> Val {
+		match v {
+			Var::Imm(v)    => Val(ValKind::Const, v.to_string()),
+			Var::FImm(v)   => Val(ValKind::Const, format!("{v:?}")),
+			Var::Local(id) => Val(ValKind::Local, format!("t{id}")),
+			Var::Glob(id)  => Val(ValKind::Global, super::symbol(&self.sym, *id)),
+		}
+	}
+}
+
+/// Functions returning 'never' are marked so llvm knows calls to them end their block
+fn noreturn(ret: &mType) -> Vec<FuncAttr> {
+	match ret {
+		mType::Never => vec![FuncAttr::NoReturn],
+		_ => Vec::new(),
+	}
+}
+
+fn gen_type(ty: &mType) -> Result<Type> {
+	Ok(match &ty {
+		mType::U(i) | mType::B(i) | mType::I(i) => Type::Int(*i),
+		mType::Usize | mType::Isize => Type::Int(64),
+
+		mType::F(16)  => Type::F16,
+		mType::F(32)  => Type::F32,
+		mType::F(64)  => Type::F64,
+		mType::F(128) => Type::F128,
+		mType::F(_) => return ReportKind::TypeError
+			.title("Unsuported bit width for float")
+			// TODO: span on type mir
+			.as_err(),
+
+		mType::Void | mType::Never => Type::Void,
+
+		mType::Opt(_) if ty.has_niche() => Type::Ptr,
+		mType::Opt(ty) => Type::Anon(vec![Type::Int(8), gen_type(ty)?]),
+		mType::Mut(ty) => return gen_type(ty),
+		mType::Ptr(_) | mType::Fn(..) => Type::Ptr,
+		#[allow(clippy::cast_possible_truncation)]
+		mType::Arr(t, Some(n)) => Type::Array(*n as usize, Box::new(gen_type(t)?)),
+		mType::Arr(_, None) => slice_type(),
+		mType::Struct(id, _) | mType::Enum(id, _) => Type::Struct(id.to_string()),
+		_ => unreachable!()
+	})
+}
+
+/// Width of a number, pointers are 64 bits
+fn bits(ty: &Type) -> u32 {
+	match ty {
+		Type::Int(w) => *w,
+		Type::F16  => 16,
+		Type::F32  => 32,
+		Type::F128 => 128,
+		_ => 64,
+	}
+}
+
+/// A slice is a pointer to its first element and its length
+fn slice_type() -> Type {
+	Type::Anon(vec![Type::Ptr, Type::Int(64)])
+}
@@ -0,0 +1,285 @@
+use std::fmt::{self, Formatter, Display};
+
+pub type Name = String;
+
+#[derive(Default)]
+pub struct Module {
+	pub data:  Vec<DataDef>,
+	pub funcs: Vec<Function>,
+}
+
+
+pub struct DataDef {
+	pub name:   Name,
+	pub export: bool,
+	pub items:  Vec<DataItem>,
+}
+
+pub enum DataItem {
+	Str(String),
+	Val(Type, Val),
+}
+
+
+pub struct Function {
+	pub name:   Name,
+	pub export: bool,
+	pub args:   Vec<(Type, Name)>,
+	pub ret:    Option<Type>,
+	pub body:   Vec<Instr>,
+}
+
+
+pub enum Instr {
+	Assign(Val, Type, Box<Self>),
+	Ret(Option<Val>),
+	Call {
+		func: Val,
+		args: Vec<(Type, Val)>,
+	},
+	Alloc(u64, u64), // align, size
+	Store(Type, Val, Val),
+	Load(Type, Val),
+	BinOp(BinOp, Val, Val),
+	Cmp(Cmp, Type, Val, Val),
+	Neg(Val),
+	Ext(bool, Val), // signed, word to long
+	Conv(&'static str, Val), // any other conversion, by the name of its instruction
+	Label(Name),
+	Jmp(Name),
+	Jnz(Val, Name, Name), // word, nonzero, zero
+	Hlt,
+}
+
+impl Instr {
+	pub fn is_jump(&self) -> bool {
+		matches!(self, Self::Ret(_) | Self::Jmp(_) | Self::Jnz(..) | Self::Hlt)
+	}
+}
+
+pub enum BinOp {
+	Add, Sub, Mul, Div, UDiv, Rem, URem,
+	Shl, Sar, Shr, And, Or, Xor,
+}
+
+/// The unprefixed conditions are the float ones
+pub enum Cmp {
+	Eq, Ne,
+	Sle, Slt, Sge, Sgt,
+	Ule, Ult, Uge, Ugt,
+	Le, Lt, Ge, Gt,
+}
+
+pub enum ValKind { Temp, Global, Const, }
+pub struct Val(pub ValKind, pub Name);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Type {
+	W, L, S, D,
+	Sb, Ub, Sh, Uh, // sub-word, only valid in memory ops, args and returns
+}
+
+impl Type {
+	/// The type a temporary holding this value has
+	pub fn base(self) -> Self {
+		match self {
+			Self::Sb | Self::Ub | Self::Sh | Self::Uh => Self::W,
+			t => t,
+		}
+	}
+
+	/// Suffix used by `store` instructions
+	fn store(self) -> &'static str {
+		match self {
+			Self::Sb | Self::Ub => "b",
+			Self::Sh | Self::Uh => "h",
+			Self::W => "w",
+			Self::L => "l",
+			Self::S => "s",
+			Self::D => "d",
+		}
+	}
+}
+
+
+impl Display for Module {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		self.data .iter().try_for_each(|d| writeln!(f, "{d}"))?;
+		self.funcs.iter().try_for_each(|c| writeln!(f, "{c}"))?;
+		writeln!(f, "# sharc {}", env!("CARGO_PKG_VERSION"))
+	}
+}
+
+impl Display for DataDef {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if self.export { write!(f, "export ")?; }
+		write!(f, "data ${} = {{ ", self.name)?;
+
+		for (i, item) in self.items.iter().enumerate() {
+			write!(f, "{item}")?;
+			if i != self.items.len() - 1 { write!(f, ", ")?; }
+		}
+
+		write!(f, " }}")
+	}
+}
+
+impl Display for DataItem {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Val(t, v) => write!(f, "{} {v}", t.store()),
+			// QBE passes strings to the assembler verbatim, so anything that
+			// would need escaping is emitted as a separate byte instead.
+			Self::Str(s) => {
+				let mut in_str = false;
+				for (i, c) in s.bytes().enumerate() {
+					match c {
+						b' '..=b'~' if c != b'"' && c != b'\\' => {
+							if !in_str {
+								if i != 0 { write!(f, ", ")?; }
+								write!(f, "b \"")?;
+								in_str = true;
+							}
+							write!(f, "{}", c as char)?;
+						},
+						_ => {
+							if in_str { write!(f, "\"")?; in_str = false; }
+							if i != 0 { write!(f, ", ")?; }
+							write!(f, "b {c}")?;
+						},
+					}
+				}
+				if in_str { write!(f, "\"")?; }
+				Ok(())
+			},
+		}
+	}
+}
+
+impl Display for Function {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if self.export { write!(f, "export ")?; }
+		write!(f, "function ")?;
+		if let Some(ret) = self.ret { write!(f, "{ret} ")?; }
+		write!(f, "${}(", self.name)?;
+
+		for (i, arg) in self.args.iter().enumerate() {
+			write!(f, "{} %{}", arg.0, arg.1)?;
+			if i != self.args.len() - 1 { write!(f, ", ")?; }
+		}
+
+		writeln!(f, ") {{")?;
+		writeln!(f, "@start")?;
+
+		self.body.iter().try_for_each(|i| match i {
+			Instr::Label(_) => writeln!(f, "{i}"),
+			_ => writeln!(f, "   {i}"),
+		})?;
+		writeln!(f, "}}")
+	}
+}
+
+impl Display for Instr {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Assign(v, t, i) => write!(f, "{v} ={} {i}", t.base()),
+			Self::Ret(v) => match v {
+				Some(v) => write!(f, "ret {v}"),
+				None    => write!(f, "ret"),
+			},
+			Self::Call { func, args } => {
+				write!(f, "call {func}(")?;
+				for (i, (t, arg)) in args.iter().enumerate() {
+					write!(f, "{t} {arg}")?;
+					if i != args.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, ")")
+			},
+			Self::Alloc(align, size) => write!(f, "alloc{align} {size}"),
+			Self::Store(t, v, p) => write!(f, "store{} {v}, {p}", t.store()),
+			Self::Load(t, p)     => write!(f, "load{t} {p}"),
+			Self::BinOp(op, a, b)   => write!(f, "{op} {a}, {b}"),
+			Self::Cmp(cond, t, a, b) => write!(f, "c{cond}{} {a}, {b}", t.base()),
+			Self::Neg(v)            => write!(f, "neg {v}"),
+			Self::Ext(true, v)      => write!(f, "extsw {v}"),
+			Self::Ext(false, v)     => write!(f, "extuw {v}"),
+			Self::Conv(op, v)       => write!(f, "{op} {v}"),
+			Self::Label(l)          => write!(f, "@{l}"),
+			Self::Jmp(l)            => write!(f, "jmp @{l}"),
+			Self::Jnz(v, a, b)      => write!(f, "jnz {v}, @{a}, @{b}"),
+			Self::Hlt               => write!(f, "hlt"),
+		}
+	}
+}
+
+impl Display for BinOp {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Add  => "add",
+			Self::Sub  => "sub",
+			Self::Mul  => "mul",
+			Self::Div  => "div",
+			Self::UDiv => "udiv",
+			Self::Rem  => "rem",
+			Self::URem => "urem",
+			Self::Shl  => "shl",
+			Self::Sar  => "sar",
+			Self::Shr  => "shr",
+			Self::And  => "and",
+			Self::Or   => "or",
+			Self::Xor  => "xor",
+		})
+	}
+}
+
+impl Display for Cmp {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Eq  => "eq",
+			Self::Ne  => "ne",
+			Self::Sle => "sle",
+			Self::Slt => "slt",
+			Self::Sge => "sge",
+			Self::Sgt => "sgt",
+			Self::Ule => "ule",
+			Self::Ult => "ult",
+			Self::Uge => "uge",
+			Self::Ugt => "ugt",
+			Self::Le  => "le",
+			Self::Lt  => "lt",
+			Self::Ge  => "ge",
+			Self::Gt  => "gt",
+		})
+	}
+}
+
+impl Display for Val {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}{}", self.0, self.1)
+	}
+}
+
+impl Display for ValKind {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Temp   => write!(f, "%"),
+			Self::Global => write!(f, "$"),
+			Self::Const  => Ok(()),
+		}
+	}
+}
+
+impl Display for Type {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::W  => "w",
+			Self::L  => "l",
+			Self::S  => "s",
+			Self::D  => "d",
+			Self::Sb => "sb",
+			Self::Ub => "ub",
+			Self::Sh => "sh",
+			Self::Uh => "uh",
+		})
+	}
+}
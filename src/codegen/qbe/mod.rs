@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+
+use crate::report::{Result, LogHandler, ReportKind};
+use crate::analyzer::mir::{self, ValId, Var, Node, BinOp, UnOp, Type as mType};
+
+mod il;
+use il::{Instr, Val, ValKind, Module, DataDef, DataItem, Type, Cmp};
+
+pub struct Gen {
+	module: Module,
+	sym:    HashMap<ValId, String>,
+	ucnt:   u64,
+}
+
+impl Gen {
+	pub fn codegen(
+		file: &'static str, sym: HashMap<ValId, String>,
+		mir: Vec<Node>, handler: &LogHandler)
+	-> Module {
+		let mut cgen = Self {
+			sym, ucnt: 0,
+			module: Module::default(),
+		};
+
+		mir.into_iter().for_each(|node| {
+			let loc = node.loc();
+			if let Err(e) = cgen.gen_node(node) {
+				handler.log(super::locate(*e, loc).file(file));
+			}
+		});
+
+		cgen.module
+	}
+
+	fn inc_ucnt(&mut self) -> u64 {
+		let ucnt = self.ucnt;
+		self.ucnt += 1;
+		ucnt
+	}
+
+	fn gen_node(&mut self, node: Node) -> Result<()> {
+		match node {
+			Node::Func { id, export, args, ret, body, .. } => {
+				// args are spilled into stack slots so they can be treated like any other local
+				let mut nargs = Vec::new();
+				let mut nbody = Vec::new();
+				for (i, t) in args {
+					let ty = gen_type(&t)?;
+					nargs.push((ty, format!("a{}", *i)));
+					nbody.extend(alloc_slot(i, ty));
+					nbody.push(Instr::Store(ty,
+						Val(ValKind::Temp, format!("a{}", *i)),
+						Val(ValKind::Temp, format!("t{}", *i))));
+				}
+
+				let ret = match ret {
+					mType::Void | mType::Never => None,
+					t => Some(gen_type(&t)?),
+				};
+
+				for stmt in body {
+					nbody.extend(self.gen_stmt(stmt)?);
+				}
+
+				// QBE requires every block to end in a jump, only void functions can fall off their end
+				if !nbody.last().is_some_and(Instr::is_jump) {
+					nbody.push(match ret {
+						None    => Instr::Ret(None),
+						Some(_) => Instr::Hlt,
+					});
+				}
+
+				// slots go into the start block, so loops dont grow the stack
+				let (mut allocs, nbody): (Vec<_>, Vec<_>) = nbody.into_iter()
+					.partition(|i| matches!(i, Instr::Assign(_, _, i) if matches!(**i, Instr::Alloc(..))));
+				allocs.extend(self.seal_blocks(nbody));
+				let nbody = allocs;
+
+				let func = il::Function {
+					export, ret,
+					name: super::symbol(&self.sym, id),
+					args: nargs,
+					body: nbody,
+				};
+
+				self.module.funcs.push(func);
+			},
+			Node::FuncDecl { .. } => (), // QBE does not need external declarations
+			Node::Struct { .. } => return ReportKind::TypeError
+				.title("Structs are not yet supported on the qbe target")
+				.as_err(),
+			Node::Enum { .. } => return ReportKind::TypeError
+				.title("Enums are not yet supported on the qbe target")
+				.as_err(),
+			Node::Assign { .. } | Node::Global { .. } | Node::Store { .. } | Node::StoreIndex { .. }
+				| Node::StoreField { .. } | Node::Ret(..) | Node::Trap(_) | Node::Label(_) | Node::Jump(_)
+				| Node::Branch { .. } | Node::FuncCall { .. } | Node::BinOp { .. } | Node::UnOp { .. }
+				| Node::StructLit(_) | Node::Field { .. } | Node::EnumLit { .. } | Node::Tag { .. }
+				| Node::Payload { .. } | Node::OptLit(_) | Node::IsSome { .. } | Node::Unwrap { .. }
+				| Node::AddrOf(_) | Node::Deref(_) | Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_)
+				| Node::Index { .. } | Node::Len(_) | Node::Decay { .. } | Node::SliceLit { .. }
+				| Node::Cast { .. } | Node::StrLit(_) | Node::Var(_)
+				=> unreachable!("only declarations are at the top level"),
+		}
+		Ok(())
+	}
+
+	fn gen_stmt(&mut self, node: Node) -> Result<Vec<Instr>> {
+		Ok(match node {
+			Node::Assign { id, ty, val } => {
+				let qty = gen_type(&ty)?;
+				let mut instrs = alloc_slot(id, qty);
+
+				let (instr, val) = match *val {
+					Node::Cast { ty: from, val } => self.gen_cast(&from, &ty, &val)?,
+					val => self.gen_rval(val, qty)?,
+				};
+				instrs.extend(instr);
+				instrs.push(Instr::Store(qty, val, Val(ValKind::Temp, format!("t{}", *id))));
+				instrs
+			},
+			Node::Global { id, ty, val } => {
+				let item = match *val {
+					Node::StrLit(s) => DataItem::Str(s),
+					Node::Var(v)    => {
+						let ty = gen_type(&ty)?;
+						DataItem::Val(ty, float_const(self.gen_val(&v), ty))
+					},
+					_ => unreachable!(),
+				};
+
+				self.module.data.push(DataDef {
+					name:   format!("g{}", *id),
+					export: false,
+					items:  vec![item],
+				});
+				Vec::new()
+			},
+			Node::Store { to, from: (v, ty), deref } => {
+				let qty = gen_type(&ty)?;
+				let (instr, val) = self.use_val(self.gen_val(&v), qty);
+
+				// slots already are addresses, a pointer in one has to be loaded first
+				let (pinstr, ptr) = match deref {
+					true  => self.use_val(self.gen_val(&to), Type::L),
+					false => (None, self.gen_val(&to)),
+				};
+
+				instr.into_iter()
+					.chain(pinstr)
+					.chain(std::iter::once(Instr::Store(qty, val, ptr)))
+					.collect()
+			},
+			Node::Ret(None, _) => vec![Instr::Ret(None)],
+			Node::Ret(Some(v), ty) => {
+				let (instr, val) = self.use_val(self.gen_val(&v), gen_type(&ty)?);
+				let mut instrs = instr.map_or(Vec::new(), |i| vec![i]);
+				instrs.push(Instr::Ret(Some(val)));
+				instrs
+			},
+			Node::FuncCall { id, args } => {
+				let (mut instrs, call) = self.gen_fncall(&id, args)?;
+				instrs.push(call);
+				instrs
+			},
+			Node::Label(l) => vec![Instr::Label(l.to_string())],
+			Node::Jump(l)  => vec![Instr::Jmp(l.to_string())],
+			Node::Branch { cond: (v, ty), then, els } => {
+				let qty = gen_type(&ty)?;
+				let (instr, cond) = self.use_val(self.gen_val(&v), qty);
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				// jnz only looks at words
+				let cond = match qty {
+					Type::L => {
+						let name = format!("o{}", self.inc_ucnt());
+						instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), Type::W,
+							Instr::Cmp(Cmp::Ne, Type::L, cond, Val(ValKind::Const, String::from("0"))).into()));
+						Val(ValKind::Temp, name)
+					},
+					_ => cond,
+				};
+
+				instrs.push(Instr::Jnz(cond, then.to_string(), els.to_string()));
+				instrs
+			},
+			Node::Trap(_) => vec![Instr::Hlt],
+			Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. }
+				=> unreachable!("declarations are not nested in functions"),
+			Node::StoreIndex { .. } => return ReportKind::TypeError
+				.title("Arrays are not yet supported on the qbe target")
+				.as_err(),
+			Node::StoreField { .. } => return ReportKind::TypeError
+				.title("Structs are not yet supported on the qbe target")
+				.as_err(),
+			Node::BinOp { .. } | Node::UnOp { .. } | Node::StructLit(_) | Node::Field { .. }
+				| Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } | Node::OptLit(_)
+				| Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_) | Node::Deref(_)
+				| Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. } | Node::Len(_)
+				| Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. } | Node::StrLit(_)
+				| Node::Var(_)
+				=> unreachable!("only the value of an Assign"),
+		})
+	}
+
+	/// Code following a jump has to start a new block
+	fn seal_blocks(&mut self, body: Vec<Instr>) -> Vec<Instr> {
+		let mut sealed = false;
+		let mut nbody = Vec::with_capacity(body.len());
+
+		for instr in body {
+			if sealed && !matches!(instr, Instr::Label(_)) {
+				nbody.push(Instr::Label(format!("dead{}", self.inc_ucnt())));
+			}
+
+			sealed = instr.is_jump();
+			nbody.push(instr);
+		}
+
+		nbody
+	}
+
+	/// Lowers a cast of `val`. Narrower ints are cut down once they are stored, so only the class of the
+	/// value changes here
+	fn gen_cast(&mut self, from: &mType, to: &mType, val: &Var) -> Result<(Vec<Instr>, Val)> {
+		let (fty, tty) = (gen_type(from)?.base(), gen_type(to)?.base());
+		let (instr, val) = self.use_val(self.gen_val(val), fty);
+		let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+		let conv = match (fty, tty) {
+			(Type::W, Type::L) => Instr::Ext(from.is_signed(), val),
+			(f, t) if f == t => Instr::Conv("copy", val),
+			(Type::S, Type::D) => Instr::Conv("exts", val),
+			(Type::D, Type::S) => Instr::Conv("truncd", val),
+			(Type::S, _) => Instr::Conv(if to.is_signed() { "stosi" } else { "stoui" }, val),
+			(Type::D, _) => Instr::Conv(if to.is_signed() { "dtosi" } else { "dtoui" }, val),
+			(Type::W, _) if to.is_float() => Instr::Conv(if from.is_signed() { "swtof" } else { "uwtof" }, val),
+			(_, _) if to.is_float() => Instr::Conv(if from.is_signed() { "sltof" } else { "ultof" }, val),
+			// a word is the lower half of a long
+			_ => Instr::Conv("copy", val),
+		};
+
+		let name = format!("o{}", self.inc_ucnt());
+		instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), tty, conv.into()));
+		Ok((instrs, Val(ValKind::Temp, name)))
+	}
+
+	/// Lowers the value side of an assignment, returning the instrs needed to compute it
+	fn gen_rval(&mut self, node: Node, ty: Type) -> Result<(Vec<Instr>, Val)> {
+		Ok(match node {
+			Node::FuncCall { id, args } => {
+				let (mut instrs, call) = self.gen_fncall(&id, args)?;
+				let name = format!("c{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), ty, call.into()));
+				(instrs, Val(ValKind::Temp, name))
+			},
+			Node::Var(v) => {
+				let (instr, val) = self.use_val(self.gen_val(&v), ty);
+				(instr.map_or(Vec::new(), |i| vec![i]), val)
+			},
+			Node::Global { id, ty: gty, val } => {
+				self.gen_stmt(Node::Global { id, ty: gty, val })?;
+				(Vec::new(), Val(ValKind::Global, format!("g{}", *id)))
+			},
+			Node::BinOp { op, ty: oty, lhs, rhs } => {
+				let qty = gen_type(&oty)?;
+				let (linstr, lhs) = self.use_val(self.gen_val(&lhs), qty);
+				let (rinstr, rhs) = self.use_val(self.gen_val(&rhs), qty);
+				let mut instrs: Vec<Instr> = linstr.into_iter().chain(rinstr).collect();
+
+				let (signed, float) = (oty.is_signed(), oty.is_float());
+				let (instr, ty) = match op {
+					_ if op.is_cmp() => (Instr::Cmp(match (op, signed, float) {
+						(BinOp::Eq, _, _)         => Cmp::Eq,
+						(BinOp::Ne, _, _)         => Cmp::Ne,
+						(BinOp::Lt, _, true)      => Cmp::Lt,
+						(BinOp::Le, _, true)      => Cmp::Le,
+						(BinOp::Gt, _, true)      => Cmp::Gt,
+						(BinOp::Ge, _, true)      => Cmp::Ge,
+						(BinOp::Lt, true, _)      => Cmp::Slt,
+						(BinOp::Le, true, _)      => Cmp::Sle,
+						(BinOp::Gt, true, _)      => Cmp::Sgt,
+						(BinOp::Ge, true, _)      => Cmp::Sge,
+						(BinOp::Lt, false, _)     => Cmp::Ult,
+						(BinOp::Le, false, _)     => Cmp::Ule,
+						(BinOp::Gt, false, _)     => Cmp::Ugt,
+						(BinOp::Ge, false, _)     => Cmp::Uge,
+						_ => unreachable!(),
+					}, qty, lhs, rhs), ty),
+					_ => (Instr::BinOp(match (op, signed) {
+						(BinOp::Add, _)     => il::BinOp::Add,
+						(BinOp::Sub, _)     => il::BinOp::Sub,
+						(BinOp::Mul, _)     => il::BinOp::Mul,
+						(BinOp::Div, true)  => il::BinOp::Div,
+						(BinOp::Div, false) => il::BinOp::UDiv,
+						(BinOp::Rem, true)  => il::BinOp::Rem,
+						(BinOp::Rem, false) => il::BinOp::URem,
+						(BinOp::Shl, _)     => il::BinOp::Shl,
+						(BinOp::Shr, true)  => il::BinOp::Sar,
+						(BinOp::Shr, false) => il::BinOp::Shr,
+						(BinOp::BitAnd | BinOp::And, _) => il::BinOp::And,
+						(BinOp::BitOr  | BinOp::Or,  _) => il::BinOp::Or,
+						(BinOp::BitXor | BinOp::Xor, _) => il::BinOp::Xor,
+						_ => unreachable!(),
+					}, lhs, rhs), qty),
+				};
+
+				if float && matches!(op, BinOp::Rem) {
+					return ReportKind::TypeError
+						.title("Floating point remainder is not supported on the qbe target")
+						.as_err();
+				}
+
+				let name = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), ty, instr.into()));
+				(instrs, Val(ValKind::Temp, name))
+			},
+			Node::UnOp { op, ty: oty, val } => {
+				let qty = gen_type(&oty)?;
+				let (instr, val) = self.use_val(self.gen_val(&val), qty);
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let instr = match op {
+					UnOp::Neg    => Instr::Neg(val),
+					// bools are only ever 0 or 1
+					UnOp::Not    => Instr::BinOp(il::BinOp::Xor, val, Val(ValKind::Const, String::from("1"))),
+					UnOp::BitNot => Instr::BinOp(il::BinOp::Xor, val, Val(ValKind::Const, String::from("-1"))),
+				};
+
+				let name = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), qty, instr.into()));
+				(instrs, Val(ValKind::Temp, name))
+			},
+			Node::StructLit(_) | Node::Field { .. } => return ReportKind::TypeError
+				.title("Structs are not yet supported on the qbe target")
+				.as_err(),
+			Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } => return ReportKind::TypeError
+				.title("Enums are not yet supported on the qbe target")
+				.as_err(),
+			// only `opt *T` gets here, it is the pointer itself with null as none
+			Node::OptLit(Some((v, _))) | Node::Unwrap { val: v, .. } => {
+				let (instr, val) = self.use_val(self.gen_val(&v), ty);
+				(instr.map_or(Vec::new(), |i| vec![i]), val)
+			},
+			Node::OptLit(None) => (Vec::new(), Val(ValKind::Const, String::from("0"))),
+			Node::AddrOf(v) | Node::Decay { val: v, .. } => (Vec::new(), self.gen_val(&v)),
+			Node::Deref(v) => {
+				let (instr, ptr) = self.use_val(self.gen_val(&v), Type::L);
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let name = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), ty, Instr::Load(ty, ptr).into()));
+				(instrs, Val(ValKind::Temp, name))
+			},
+			Node::Offset { ty: pty, ptr, op, index: (i, ity) } => {
+				let mType::Ptr(pointee) = pty.base() else { unreachable!() };
+				let ity_q = gen_type(&ity)?;
+
+				let (pinstr, ptr) = self.use_val(self.gen_val(&ptr), Type::L);
+				let (iinstr, mut idx) = self.use_val(self.gen_val(&i), ity_q);
+				let mut instrs: Vec<Instr> = pinstr.into_iter().chain(iinstr).collect();
+
+				if ity_q != Type::L && !matches!(idx.0, ValKind::Const) {
+					let name = format!("o{}", self.inc_ucnt());
+					instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), Type::L,
+						Instr::Ext(ity.is_signed(), idx).into()));
+					idx = Val(ValKind::Temp, name);
+				}
+
+				let size = size_of(gen_type(pointee)?);
+				let scaled = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, scaled.clone()), Type::L,
+					Instr::BinOp(il::BinOp::Mul, idx, Val(ValKind::Const, size.to_string())).into()));
+
+				let name = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), Type::L,
+					Instr::BinOp(match op {
+						BinOp::Sub => il::BinOp::Sub,
+						_ => il::BinOp::Add,
+					}, ptr, Val(ValKind::Temp, scaled)).into()));
+				(instrs, Val(ValKind::Temp, name))
+			},
+			Node::Diff { ty: pty, lhs, rhs } => {
+				let mType::Ptr(pointee) = pty.base() else { unreachable!() };
+				let (linstr, lhs) = self.use_val(self.gen_val(&lhs), Type::L);
+				let (rinstr, rhs) = self.use_val(self.gen_val(&rhs), Type::L);
+				let mut instrs: Vec<Instr> = linstr.into_iter().chain(rinstr).collect();
+
+				let bytes = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, bytes.clone()), Type::L, Instr::BinOp(il::BinOp::Sub, lhs, rhs).into()));
+
+				let size = size_of(gen_type(pointee)?);
+				let name = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), Type::L,
+					Instr::BinOp(il::BinOp::Div, Val(ValKind::Temp, bytes), Val(ValKind::Const, size.to_string())).into()));
+				(instrs, Val(ValKind::Temp, name))
+			},
+			Node::IsSome { val, .. } => {
+				let (instr, val) = self.use_val(self.gen_val(&val), Type::L);
+				let mut instrs: Vec<Instr> = instr.into_iter().collect();
+
+				let name = format!("o{}", self.inc_ucnt());
+				instrs.push(Instr::Assign(Val(ValKind::Temp, name.clone()), ty,
+					Instr::Cmp(Cmp::Ne, Type::L, val, Val(ValKind::Const, String::from("0"))).into()));
+				(instrs, Val(ValKind::Temp, name))
+			},
+			_ => unreachable!(),
+		})
+	}
+
+	/// Returns the argument setup and the call itself, so it can be assigned if needed
+	fn gen_fncall(&mut self, var: &Var, args: Vec<(Var, mType)>) -> Result<(Vec<Instr>, Instr)> {
+		let mut instrs = Vec::new();
+
+		let func = {
+			let (instr, val) = self.use_val(self.gen_val(var), Type::L);
+			instr.map(|i| instrs.push(i));
+			val
+		};
+
+		let mut nargs = Vec::new();
+		for (var, ty) in args {
+			let ty = gen_type(&ty)?;
+			let (instr, val) = self.use_val(self.gen_val(&var), ty);
+			instr.map(|i| instrs.push(i));
+			nargs.push((ty, val));
+		}
+
+		Ok((instrs, Instr::Call { func, args: nargs }))
+	}
+
+	fn use_val(&mut self, val: Val, ty: Type) -> (Option<Instr>, Val) {
+		match val {
+			Val(ValKind::Temp, name) => {
+				let nname = format!("{}_{}", name, self.inc_ucnt());
+
+				(Some(Instr::Assign(Val(ValKind::Temp, nname.clone()), ty,
+					Instr::Load(ty, Val(ValKind::Temp, name)).into())),
+					Val(ValKind::Temp, nname))
+			},
+			val => (None, float_const(val, ty)),
+		}
+	}
+
+	fn gen_val(&self, v: &mir::Var) -> Val {
+		match v {
+			Var::Imm(v)    => Val(ValKind::Const, v.to_string()),
+			Var::FImm(v)   => Val(ValKind::Const, format!("{v:?}")),
+			Var::Local(id) => Val(ValKind::Temp, format!("t{id}")),
+			Var::Glob(id)  => Val(ValKind::Global, super::symbol(&self.sym, *id)),
+		}
+	}
+}
+
+/// Constants used as floats need their prefix, otherwise qbe reads them as raw bits
+fn float_const(val: Val, ty: Type) -> Val {
+	match (val, ty) {
+		(Val(ValKind::Const, v), Type::S) => Val(ValKind::Const, format!("s_{v}")),
+		(Val(ValKind::Const, v), Type::D) => Val(ValKind::Const, format!("d_{v}")),
+		(val, _) => val,
+	}
+}
+
+fn size_of(ty: Type) -> u64 {
+	match ty {
+		Type::Sb | Type::Ub => 1,
+		Type::Sh | Type::Uh => 2,
+		Type::W  | Type::S  => 4,
+		Type::L  | Type::D  => 8,
+	}
+}
+
+fn alloc_slot(id: ValId, ty: Type) -> Vec<Instr> {
+	let size = size_of(ty);
+
+	vec![Instr::Assign(Val(ValKind::Temp, format!("t{}", *id)), Type::L,
+		Instr::Alloc(size.max(4), size).into())]
+}
+
+fn gen_type(ty: &mType) -> Result<Type> {
+	Ok(match &ty {
+		mType::U(i) | mType::B(i) if *i <= 8  => Type::Ub,
+		mType::I(i)               if *i <= 8  => Type::Sb,
+		mType::U(i) | mType::B(i) if *i <= 16 => Type::Uh,
+		mType::I(i)               if *i <= 16 => Type::Sh,
+		mType::U(i) | mType::B(i) | mType::I(i) if *i <= 32 => Type::W,
+		mType::U(i) | mType::B(i) | mType::I(i) if *i <= 64 => Type::L,
+		mType::U(_) | mType::B(_) | mType::I(_) => return ReportKind::TypeError
+			.title("Unsuported bit width for integer on the qbe target")
+			.as_err(),
+
+		mType::F(32) => Type::S,
+		mType::F(64) => Type::D,
+		mType::F(_) => return ReportKind::TypeError
+			.title("Unsuported bit width for float on the qbe target")
+			// TODO: span on type mir
+			.as_err(),
+
+		mType::Void | mType::Never => unreachable!("void values have no qbe type"),
+
+		mType::Opt(_) if ty.has_niche() => Type::L,
+		mType::Opt(_) => return ReportKind::TypeError
+			.title("Optionals are not yet supported on the qbe target")
+			.as_err(),
+		mType::Mut(ty) => return gen_type(ty),
+		mType::Ptr(_) | mType::Usize | mType::Isize | mType::Fn(_, _) => Type::L,
+		mType::Arr(_, _) => return ReportKind::TypeError
+			.title("Stack arrays are not yet supported on the qbe target")
+			.as_err(),
+		mType::Struct(..) => return ReportKind::TypeError
+			.title("Structs are not yet supported on the qbe target")
+			.as_err(),
+		mType::Enum(..) => return ReportKind::TypeError
+			.title("Enums are not yet supported on the qbe target")
+			.as_err(),
+		_ => unreachable!()
+	})
+}
@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+
+use crate::report::{Result, LogHandler, ReportKind};
+use crate::analyzer::mir::{self, ValId, LabelId, Var, Node, BinOp, UnOp, Type as mType};
+
+mod wat;
+use wat::{Instr, Module, Import, DataDef, Type};
+
+const PAGE_SIZE: u32 = 65536;
+
+pub struct Gen {
+	module:  Module,
+	sym:     HashMap<ValId, String>,
+	globals: HashMap<ValId, u32>, // offset into linear memory
+	heap:    u32,
+	locals:  Vec<(String, Type)>, // reset for every function
+}
+
+impl Gen {
+	pub fn codegen(
+		file: &'static str, sym: HashMap<ValId, String>,
+		mir: Vec<Node>, handler: &LogHandler)
+	-> Module {
+		let mut cgen = Self {
+			sym,
+			module:  Module { name: file, ..Module::default() },
+			globals: HashMap::new(),
+			heap:    16, // keep null unused
+			locals:  Vec::new(),
+		};
+
+		mir.into_iter().for_each(|node| {
+			let loc = node.loc();
+			if let Err(e) = cgen.gen_node(node) {
+				handler.log(super::locate(*e, loc).file(file));
+			}
+		});
+
+		cgen.module.pages = cgen.heap.div_ceil(PAGE_SIZE).max(1);
+		cgen.module
+	}
+
+	fn get_id_name(&self, id: mir::ValId) -> &str {
+		self.sym.get(&id).expect("ValId not found in sym table")
+	}
+
+	fn gen_node(&mut self, node: Node) -> Result<()> {
+		match node {
+			Node::Func { id, export, args, ret, body, .. } => {
+				self.locals.clear();
+
+				let params = args.iter()
+					.map(|(i, t)| Ok((format!("t{}", **i), gen_type(t)?)))
+					.collect::<Result<Vec<_>>>()?;
+
+				let result = gen_ret(&ret)?;
+
+				let mut instrs = self.gen_body(body)?;
+
+				// falling off the end of a function with a result would not validate
+				if result.is_some() && !matches!(instrs.last(), Some(Instr::Return)) {
+					instrs.push(Instr::Unreachable);
+				}
+
+				let name = super::symbol(&self.sym, id);
+				let export = export.then(|| name.clone());
+
+				self.module.funcs.push(wat::Function {
+					name, export, params, result,
+					locals: std::mem::take(&mut self.locals),
+					body:   instrs,
+				});
+			},
+			Node::FuncDecl { id, args, ret, .. } => {
+				let name = self.get_id_name(id).to_string();
+				self.module.imports.push(Import {
+					module: "env",
+					func:   name.clone(),
+					name,
+					params: args.iter().map(gen_type).collect::<Result<Vec<_>>>()?,
+					result: gen_ret(&ret)?,
+				});
+			},
+			Node::Struct { .. } => return ReportKind::TypeError
+				.title("Structs are not yet supported on the wasm target")
+				.as_err(),
+			Node::Enum { .. } => return ReportKind::TypeError
+				.title("Enums are not yet supported on the wasm target")
+				.as_err(),
+			Node::Assign { .. } | Node::Global { .. } | Node::Store { .. } | Node::StoreIndex { .. }
+				| Node::StoreField { .. } | Node::Ret(..) | Node::Trap(_) | Node::Label(_) | Node::Jump(_)
+				| Node::Branch { .. } | Node::FuncCall { .. } | Node::BinOp { .. } | Node::UnOp { .. }
+				| Node::StructLit(_) | Node::Field { .. } | Node::EnumLit { .. } | Node::Tag { .. }
+				| Node::Payload { .. } | Node::OptLit(_) | Node::IsSome { .. } | Node::Unwrap { .. }
+				| Node::AddrOf(_) | Node::Deref(_) | Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_)
+				| Node::Index { .. } | Node::Len(_) | Node::Decay { .. } | Node::SliceLit { .. }
+				| Node::Cast { .. } | Node::StrLit(_) | Node::Var(_)
+				=> unreachable!("only declarations are at the top level"),
+		}
+		Ok(())
+	}
+
+	/// Wasm only has structured control flow, so a body with labels becomes a loop
+	/// dispatching on the index of the block to run next
+	fn gen_body(&mut self, body: Vec<Node>) -> Result<Vec<Instr>> {
+		let index: HashMap<LabelId, u32> = body.iter()
+			.filter_map(|n| match n {
+				Node::Label(l) => Some(*l),
+				_ => None,
+			})
+			.zip(1..)
+			.collect();
+
+		let goto = |instrs: &mut Vec<Instr>| {
+			instrs.push(Instr::LocalSet(String::from("block")));
+			instrs.push(Instr::Br(String::from("dispatch")));
+		};
+
+		let mut blocks = vec![Vec::new()];
+		for stmt in body {
+			let instrs = blocks.last_mut().unwrap();
+			match stmt {
+				Node::Label(_) => blocks.push(Vec::new()),
+				Node::Jump(l) => {
+					instrs.push(Instr::Const(Type::I32, index[&l].to_string()));
+					goto(instrs);
+				},
+				Node::Branch { cond: (v, ty), then, els } => {
+					instrs.push(Instr::Const(Type::I32, index[&then].to_string()));
+					instrs.push(Instr::Const(Type::I32, index[&els].to_string()));
+					instrs.push(self.gen_val(&v, &ty)?);
+					if gen_type(&ty)? == Type::I64 {
+						instrs.push(Instr::Const(Type::I64, String::from("0")));
+						instrs.push(Instr::Op(Type::I64, "ne"));
+					}
+					instrs.push(Instr::Select);
+					goto(instrs);
+				},
+				stmt => instrs.extend(self.gen_stmt(stmt)?),
+			}
+		}
+
+		if blocks.len() == 1 { return Ok(blocks.pop().unwrap()); }
+
+		self.locals.push((String::from("block"), Type::I32));
+
+		let names: Vec<String> = (0..blocks.len()).map(|i| format!("b{i}")).collect();
+		let mut instrs = vec![Instr::Loop(String::from("dispatch"))];
+		instrs.extend(names.iter().rev().map(|n| Instr::Block(n.clone())));
+		instrs.push(Instr::LocalGet(String::from("block")));
+		instrs.push(Instr::BrTable(names));
+
+		// the code of a block follows the end of its wasm block, the entry is block 0
+		for block in blocks {
+			instrs.push(Instr::End);
+			instrs.extend(block);
+		}
+
+		instrs.push(Instr::End);
+		Ok(instrs)
+	}
+
+	fn gen_stmt(&mut self, node: Node) -> Result<Vec<Instr>> {
+		Ok(match node {
+			Node::Assign { id, ty, val } => {
+				let name = format!("t{}", *id);
+				self.locals.push((name.clone(), gen_type(&ty)?));
+
+				let mut instrs = match *val {
+					Node::FuncCall { id, args } => self.gen_fncall(&id, &args)?,
+					Node::Var(v) => vec![self.gen_val(&v, &ty)?],
+					node @ Node::Global { .. } => {
+						let Node::Global { id, .. } = node
+							else { unreachable!() };
+
+						self.gen_stmt(node)?;
+						vec![Instr::Const(Type::I32, self.globals[&id].to_string())]
+					},
+					Node::BinOp { op, ty: oty, lhs, rhs } => {
+						let wty = gen_type(&oty)?;
+						let (signed, float) = (oty.is_signed(), oty.is_float());
+
+						let mut instrs = vec![self.gen_val(&lhs, &oty)?, self.gen_val(&rhs, &oty)?];
+						instrs.push(Instr::Op(wty, match op {
+							BinOp::Add => "add",
+							BinOp::Sub => "sub",
+							BinOp::Mul => "mul",
+							BinOp::Div if float  => "div",
+							BinOp::Div if signed => "div_s",
+							BinOp::Div => "div_u",
+							BinOp::Rem if float => return ReportKind::TypeError
+								.title("Floating point remainder is not supported on the wasm target")
+								.as_err(),
+							BinOp::Rem if signed => "rem_s",
+							BinOp::Rem => "rem_u",
+							BinOp::Shl => "shl",
+							BinOp::Shr if signed => "shr_s",
+							BinOp::Shr => "shr_u",
+							BinOp::BitAnd | BinOp::And => "and",
+							BinOp::BitOr  | BinOp::Or  => "or",
+							BinOp::BitXor | BinOp::Xor => "xor",
+							BinOp::Eq => "eq",
+							BinOp::Ne => "ne",
+							BinOp::Lt if float  => "lt",
+							BinOp::Le if float  => "le",
+							BinOp::Gt if float  => "gt",
+							BinOp::Ge if float  => "ge",
+							BinOp::Lt if signed => "lt_s",
+							BinOp::Le if signed => "le_s",
+							BinOp::Gt if signed => "gt_s",
+							BinOp::Ge if signed => "ge_s",
+							BinOp::Lt => "lt_u",
+							BinOp::Le => "le_u",
+							BinOp::Gt => "gt_u",
+							BinOp::Ge => "ge_u",
+						}));
+
+						// comparisons always yield an i32
+						match op.is_cmp() {
+							true if gen_type(&ty)? == Type::I64 => instrs.push(Instr::Op(Type::I64, "extend_i32_u")),
+							true  => (),
+							false => instrs.extend(truncate(&oty)?),
+						}
+						instrs
+					},
+					Node::UnOp { op, ty: oty, val } => {
+						let wty = gen_type(&oty)?;
+						let val = self.gen_val(&val, &oty)?;
+
+						let mut instrs = match op {
+							UnOp::Neg if oty.is_float() => vec![val, Instr::Op(wty, "neg")],
+							UnOp::Neg    => vec![Instr::Const(wty, String::from("0")), val, Instr::Op(wty, "sub")],
+							// bools are only ever 0 or 1
+							UnOp::Not    => vec![val, Instr::Const(wty, String::from("1")), Instr::Op(wty, "xor")],
+							UnOp::BitNot => vec![val, Instr::Const(wty, String::from("-1")), Instr::Op(wty, "xor")],
+						};
+						instrs.extend(truncate(&oty)?);
+						instrs
+					},
+					Node::StructLit(_) | Node::Field { .. } => return ReportKind::TypeError
+						.title("Structs are not yet supported on the wasm target")
+						.as_err(),
+					Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } => return ReportKind::TypeError
+						.title("Enums are not yet supported on the wasm target")
+						.as_err(),
+					// only `opt *T` gets here, it is the pointer itself with null as none
+					Node::OptLit(Some((v, pty))) => vec![self.gen_val(&v, &pty)?],
+					Node::OptLit(None) => vec![Instr::Const(Type::I32, String::from("0"))],
+					Node::Unwrap { ty: oty, val } => vec![self.gen_val(&val, &oty)?],
+					Node::AddrOf(Var::Glob(id)) | Node::Decay { val: Var::Glob(id), .. }
+						=> vec![self.gen_val(&Var::Glob(id), &mType::Usize)?],
+					Node::Decay { .. } => return ReportKind::TypeError
+						.title("Stack arrays are not yet supported on the wasm target")
+						.as_err(),
+					Node::AddrOf(_) => return ReportKind::TypeError
+						.title("Taking the address of a local is not yet supported on the wasm target")
+						.as_err(),
+					// ints are brought to the new wasm type first, then kept extended the way the new type is
+					Node::Cast { ty: from, val } => {
+						let (fty, tty) = (gen_type(&from)?, gen_type(&ty)?);
+						let mut instrs = vec![self.gen_val(&val, &from)?];
+
+						let op = match (fty, tty) {
+							(Type::F32, Type::F64) => Some("promote_f32"),
+							(Type::F64, Type::F32) => Some("demote_f64"),
+							(Type::F32 | Type::F64, _) if ty.is_float() => None,
+							(Type::F32, _) if ty.is_signed() => Some("trunc_sat_f32_s"),
+							(Type::F32, _) => Some("trunc_sat_f32_u"),
+							(Type::F64, _) if ty.is_signed() => Some("trunc_sat_f64_s"),
+							(Type::F64, _) => Some("trunc_sat_f64_u"),
+							(Type::I32, _) if ty.is_float() => Some(if from.is_signed() { "convert_i32_s" } else { "convert_i32_u" }),
+							(Type::I64, _) if ty.is_float() => Some(if from.is_signed() { "convert_i64_s" } else { "convert_i64_u" }),
+							(Type::I32, Type::I64) => Some(if from.is_signed() { "extend_i32_s" } else { "extend_i32_u" }),
+							(Type::I64, Type::I32) => Some("wrap_i64"),
+							_ => None,
+						};
+
+						if let Some(op) = op { instrs.push(Instr::Op(tty, op)); }
+						instrs.extend(truncate(&ty)?);
+						instrs
+					},
+					Node::Deref(v) => vec![self.gen_val(&v, &mType::Usize)?, Instr::Op(gen_type(&ty)?, mem_op(&ty, true)?)],
+					// wasm32, so the index is brought down to 32 bits first
+					Node::Offset { ty: pty, ptr, op, index: (i, ity) } => {
+						let mType::Ptr(pointee) = pty.base() else { unreachable!() };
+
+						let mut instrs = vec![self.gen_val(&ptr, &pty)?, self.gen_val(&i, &ity)?];
+						if gen_type(&ity)? == Type::I64 { instrs.push(Instr::Op(Type::I32, "wrap_i64")); }
+						instrs.push(Instr::Const(Type::I32, size_of(pointee)?.to_string()));
+						instrs.push(Instr::Op(Type::I32, "mul"));
+						instrs.push(Instr::Op(Type::I32, match op {
+							BinOp::Sub => "sub",
+							_ => "add",
+						}));
+						instrs
+					},
+					Node::Diff { ty: pty, lhs, rhs } => {
+						let mType::Ptr(pointee) = pty.base() else { unreachable!() };
+						vec![
+							self.gen_val(&lhs, &pty)?,
+							self.gen_val(&rhs, &pty)?,
+							Instr::Op(Type::I32, "sub"),
+							Instr::Const(Type::I32, size_of(pointee)?.to_string()),
+							Instr::Op(Type::I32, "div_s"),
+						]
+					},
+					Node::IsSome { ty: oty, val } => {
+						let mut instrs = vec![self.gen_val(&val, &oty)?, Instr::Const(Type::I32, String::from("0")), Instr::Op(Type::I32, "ne")];
+						if gen_type(&ty)? == Type::I64 { instrs.push(Instr::Op(Type::I64, "extend_i32_u")); }
+						instrs
+					},
+					_ => unreachable!(),
+				};
+
+				instrs.push(Instr::LocalSet(name));
+				instrs
+			},
+			Node::Global { id, ty, val } => {
+				let bytes = match *val {
+					Node::StrLit(s) => s.into_bytes(),
+					Node::Var(Var::Imm(v)) => {
+						let v = v.to_string().parse::<i128>()
+							.map_err(|_| ReportKind::TypeError
+								.title("Integer constant too large for the wasm target"))?;
+						#[allow(clippy::cast_possible_truncation)]
+						v.to_le_bytes()[..size_of(&ty)? as usize].to_vec()
+					},
+					Node::Var(Var::FImm(v)) => match gen_type(&ty)? {
+						#[allow(clippy::cast_possible_truncation)]
+						Type::F32 => (v as f32).to_le_bytes().to_vec(),
+						_ => v.to_le_bytes().to_vec(),
+					},
+					Node::Var(Var::Glob(id)) => match self.globals.get(&id) {
+						Some(addr) => addr.to_le_bytes().to_vec(),
+						None => return ReportKind::TypeError
+							.title("Function values are not yet supported on the wasm target")
+							.as_err(),
+					},
+					_ => unreachable!(),
+				};
+
+				self.heap = self.heap.next_multiple_of(8);
+				self.globals.insert(id, self.heap);
+
+				#[allow(clippy::cast_possible_truncation)]
+				let len = bytes.len() as u32;
+				self.module.data.push(DataDef { offset: self.heap, bytes });
+				self.heap += len;
+
+				Vec::new()
+			},
+			Node::Store { to: Var::Local(id), from: (v, ty), deref: false } => vec![self.gen_val(&v, &ty)?, Instr::LocalSet(format!("t{id}"))],
+			Node::Store { deref: false, .. } => unreachable!("only locals are stored to without a pointer"),
+			Node::Store { to, from: (v, ty), deref: true } => vec![
+				self.gen_val(&to, &mType::Usize)?,
+				self.gen_val(&v, &ty)?,
+				Instr::Op(gen_type(&ty)?, mem_op(&ty, false)?),
+			],
+			Node::Ret(None, _) => vec![Instr::Return],
+			Node::Ret(Some(v), ty) => vec![self.gen_val(&v, &ty)?, Instr::Return],
+			Node::FuncCall { id, args } => self.gen_fncall(&id, &args)?,
+			Node::Trap(_) => vec![Instr::Unreachable],
+			Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. }
+				=> unreachable!("declarations are not nested in functions"),
+			Node::StoreIndex { .. } => return ReportKind::TypeError
+				.title("Arrays are not yet supported on the wasm target")
+				.as_err(),
+			Node::StoreField { .. } => return ReportKind::TypeError
+				.title("Structs are not yet supported on the wasm target")
+				.as_err(),
+			Node::Label(_) | Node::Jump(_) | Node::Branch { .. }
+				=> unreachable!("control flow is lowered by gen_body"),
+			Node::BinOp { .. } | Node::UnOp { .. } | Node::StructLit(_) | Node::Field { .. }
+				| Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } | Node::OptLit(_)
+				| Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_) | Node::Deref(_)
+				| Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. } | Node::Len(_)
+				| Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. } | Node::StrLit(_)
+				| Node::Var(_)
+				=> unreachable!("only the value of an Assign"),
+		})
+	}
+
+	fn gen_fncall(&self, func: &Var, args: &[(Var, mType)]) -> Result<Vec<Instr>> {
+		let mut instrs = Vec::new();
+		for (v, ty) in args {
+			instrs.push(self.gen_val(v, ty)?);
+		}
+
+		instrs.push(match func {
+			Var::Glob(id) if !self.globals.contains_key(id) => Instr::Call(super::symbol(&self.sym, *id)),
+			_ => return ReportKind::TypeError
+				.title("Indirect calls are not yet supported on the wasm target")
+				.as_err(),
+		});
+
+		Ok(instrs)
+	}
+
+	fn gen_val(&self, v: &mir::Var, ty: &mType) -> Result<Instr> {
+		Ok(match v {
+			Var::Imm(v)    => Instr::Const(gen_type(ty)?, v.to_string()),
+			Var::FImm(v)   => Instr::Const(gen_type(ty)?, format!("{v:?}")),
+			Var::Local(id) => Instr::LocalGet(format!("t{id}")),
+			Var::Glob(id)  => match self.globals.get(id) {
+				Some(addr) => Instr::Const(Type::I32, addr.to_string()),
+				None => return ReportKind::TypeError
+					.title("Function values are not yet supported on the wasm target")
+					.as_err(),
+			},
+		})
+	}
+}
+
+/// Integers narrower than their wasm type are kept zero or sign extended, the way they are loaded
+fn truncate(ty: &mType) -> Result<Vec<Instr>> {
+	let (bits, signed) = match ty {
+		mType::Opt(ty) | mType::Mut(ty) => return truncate(ty),
+		mType::U(i) | mType::B(i) => (*i, false),
+		mType::I(i) => (*i, true),
+		_ => return Ok(Vec::new()),
+	};
+
+	let wty = gen_type(ty)?;
+	let width = match wty {
+		Type::I32 => 32,
+		_ => 64,
+	};
+
+	if bits >= width { return Ok(Vec::new()); }
+
+	Ok(match signed {
+		true => vec![
+			Instr::Const(wty, (width - bits).to_string()), Instr::Op(wty, "shl"),
+			Instr::Const(wty, (width - bits).to_string()), Instr::Op(wty, "shr_s"),
+		],
+		false => vec![Instr::Const(wty, ((1u64 << bits) - 1).to_string()), Instr::Op(wty, "and")],
+	})
+}
+
+/// The load or store of a value of type `ty`, narrower integers are extended the way they are kept
+fn mem_op(ty: &mType, load: bool) -> Result<&'static str> {
+	Ok(match (size_of(ty)?, gen_type(ty)?, ty.is_signed(), load) {
+		(1, _, true, true)  => "load8_s",
+		(1, _, false, true) => "load8_u",
+		(2, _, true, true)  => "load16_s",
+		(2, _, false, true) => "load16_u",
+		(4, Type::I64, true, true)  => "load32_s",
+		(4, Type::I64, false, true) => "load32_u",
+		(_, _, _, true) => "load",
+		(1, _, _, false) => "store8",
+		(2, _, _, false) => "store16",
+		(4, Type::I64, _, false) => "store32",
+		(_, _, _, false) => "store",
+	})
+}
+
+fn gen_ret(ty: &mType) -> Result<Option<Type>> {
+	Ok(match ty {
+		mType::Void | mType::Never => None,
+		t => Some(gen_type(t)?),
+	})
+}
+
+/// Size of a value stored in linear memory
+fn size_of(ty: &mType) -> Result<u32> {
+	Ok(match ty {
+		mType::U(i) | mType::B(i) | mType::I(i) => i.next_power_of_two().max(8) / 8,
+		mType::Mut(ty) => return size_of(ty),
+		ty => match gen_type(ty)? {
+			Type::I32 | Type::F32 => 4,
+			Type::I64 | Type::F64 => 8,
+		},
+	})
+}
+
+fn gen_type(ty: &mType) -> Result<Type> {
+	Ok(match &ty {
+		mType::U(i) | mType::B(i) | mType::I(i) if *i <= 32 => Type::I32,
+		mType::U(i) | mType::B(i) | mType::I(i) if *i <= 64 => Type::I64,
+		mType::U(_) | mType::B(_) | mType::I(_) => return ReportKind::TypeError
+			.title("Unsuported bit width for integer on the wasm target")
+			.as_err(),
+
+		mType::F(32) => Type::F32,
+		mType::F(64) => Type::F64,
+		mType::F(_) => return ReportKind::TypeError
+			.title("Unsuported bit width for float on the wasm target")
+			// TODO: span on type mir
+			.as_err(),
+
+		mType::Void | mType::Never => unreachable!("void values have no wasm type"),
+
+		mType::Opt(_) if ty.has_niche() => Type::I32,
+		mType::Opt(_) => return ReportKind::TypeError
+			.title("Optionals are not yet supported on the wasm target")
+			.as_err(),
+		mType::Mut(ty) => return gen_type(ty),
+		// wasm32, pointers are offsets into linear memory
+		mType::Ptr(_) | mType::Usize | mType::Isize => Type::I32,
+		mType::Fn(_, _) => return ReportKind::TypeError
+			.title("Function values are not yet supported on the wasm target")
+			.as_err(),
+		mType::Arr(_, _) => return ReportKind::TypeError
+			.title("Stack arrays are not yet supported on the wasm target")
+			.as_err(),
+		mType::Struct(..) => return ReportKind::TypeError
+			.title("Structs are not yet supported on the wasm target")
+			.as_err(),
+		mType::Enum(..) => return ReportKind::TypeError
+			.title("Enums are not yet supported on the wasm target")
+			.as_err(),
+		_ => unreachable!()
+	})
+}
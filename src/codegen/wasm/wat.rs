@@ -0,0 +1,142 @@
+use std::fmt::{self, Formatter, Display};
+
+pub type Name = String;
+
+#[derive(Default)]
+pub struct Module {
+	pub name:    &'static str,
+
+	pub imports: Vec<Import>,
+	pub data:    Vec<DataDef>,
+	pub funcs:   Vec<Function>,
+	pub pages:   u32,
+}
+
+
+pub struct Import {
+	pub module: &'static str,
+	pub name:   Name,
+	pub func:   Name,
+	pub params: Vec<Type>,
+	pub result: Option<Type>,
+}
+
+pub struct DataDef {
+	pub offset: u32,
+	pub bytes:  Vec<u8>,
+}
+
+
+pub struct Function {
+	pub name:   Name,
+	pub export: Option<Name>,
+	pub params: Vec<(Name, Type)>,
+	pub result: Option<Type>,
+	pub locals: Vec<(Name, Type)>,
+	pub body:   Vec<Instr>,
+}
+
+
+pub enum Instr {
+	LocalGet(Name),
+	LocalSet(Name),
+	Const(Type, String),
+	Call(Name),
+	Op(Type, &'static str), // numeric instructions, like `i32.add`
+	Block(Name),
+	Loop(Name),
+	End,
+	Br(Name),
+	BrTable(Vec<Name>), // the last label is the default
+	Select,
+	Return,
+	Unreachable,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Type { I32, I64, F32, F64 }
+
+
+impl Display for Module {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		writeln!(f, ";; {} - generated by sharc {}", self.name, env!("CARGO_PKG_VERSION"))?;
+		writeln!(f, "(module")?;
+		self.imports.iter().try_for_each(|i| writeln!(f, "  {i}"))?;
+		writeln!(f, "  (memory (export \"memory\") {})", self.pages)?;
+		self.data.iter().try_for_each(|d| writeln!(f, "  {d}"))?;
+		self.funcs.iter().try_for_each(|c| writeln!(f, "  {c}"))?;
+		writeln!(f, ")")
+	}
+}
+
+impl Display for Import {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "(import \"{}\" \"{}\" (func ${}", self.module, self.name, self.func)?;
+		if !self.params.is_empty() {
+			write!(f, " (param")?;
+			self.params.iter().try_for_each(|p| write!(f, " {p}"))?;
+			write!(f, ")")?;
+		}
+		if let Some(r) = self.result { write!(f, " (result {r})")?; }
+		write!(f, "))")
+	}
+}
+
+impl Display for DataDef {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "(data (i32.const {}) \"", self.offset)?;
+		self.bytes.iter().try_for_each(|c| match c {
+			b' '..=b'~' if *c != b'"' && *c != b'\\' => write!(f, "{}", *c as char),
+			_ => write!(f, "\\{c:02x}"),
+		})?;
+		write!(f, "\")")
+	}
+}
+
+impl Display for Function {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "(func ${}", self.name)?;
+		if let Some(e) = &self.export { write!(f, " (export \"{e}\")")?; }
+		self.params.iter().try_for_each(|(n, t)| write!(f, " (param ${n} {t})"))?;
+		if let Some(r) = self.result { write!(f, " (result {r})")?; }
+		writeln!(f)?;
+
+		self.locals.iter().try_for_each(|(n, t)| writeln!(f, "    (local ${n} {t})"))?;
+		self.body.iter().try_for_each(|i| writeln!(f, "    {i}"))?;
+		write!(f, "  )")
+	}
+}
+
+impl Display for Instr {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::LocalGet(n) => write!(f, "local.get ${n}"),
+			Self::LocalSet(n) => write!(f, "local.set ${n}"),
+			Self::Const(t, v) => write!(f, "{t}.const {v}"),
+			Self::Call(n)     => write!(f, "call ${n}"),
+			Self::Op(t, op)   => write!(f, "{t}.{op}"),
+			Self::Block(n)    => write!(f, "block ${n}"),
+			Self::Loop(n)     => write!(f, "loop ${n}"),
+			Self::End         => write!(f, "end"),
+			Self::Br(n)       => write!(f, "br ${n}"),
+			Self::BrTable(ns) => {
+				write!(f, "br_table")?;
+				ns.iter().try_for_each(|n| write!(f, " ${n}"))
+			},
+			Self::Select      => write!(f, "select"),
+			Self::Return      => write!(f, "return"),
+			Self::Unreachable => write!(f, "unreachable"),
+		}
+	}
+}
+
+impl Display for Type {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::I32 => "i32",
+			Self::I64 => "i64",
+			Self::F32 => "f32",
+			Self::F64 => "f64",
+		})
+	}
+}
@@ -0,0 +1,308 @@
+use std::fmt::{self, Formatter, Display};
+
+pub type Name = String;
+
+#[derive(Default)]
+pub struct Module {
+	pub name:  &'static str,
+
+	pub data:  Vec<DataDef>,
+	pub funcs: Vec<Function>,
+}
+
+
+pub struct DataDef {
+	pub name:  Name,
+	pub value: Data,
+}
+
+pub enum Data {
+	Str(String),
+	Int(Size, String),
+	Addr(Name),
+}
+
+
+pub struct Function {
+	pub name:   Name,
+	pub global: bool,
+	pub body:   Vec<Instr>,
+}
+
+
+#[derive(Clone)]
+pub enum Instr {
+	Mov(Operand, Operand),
+	Lea(Operand, Operand),
+	Movzx(Operand, Operand),
+	Movsx(Operand, Operand),
+	Push(Operand),
+	Pop(Operand),
+	Sub(Operand, Operand),
+	Add(Operand, Operand),
+	Xor(Operand, Operand),
+	And(Operand, Operand),
+	Or(Operand, Operand),
+	Imul(Operand, Operand),
+	Cmp(Operand, Operand),
+	Set(Cond, Operand),
+	Shl(Operand, Operand),
+	Shr(Operand, Operand),
+	Sar(Operand, Operand),
+	Neg(Operand),
+	Not(Operand),
+	Cqo(Size), // sign extends rax into rdx
+	Div(Operand),
+	Idiv(Operand),
+	Call(Operand),
+	Ret,
+	Label(Name),
+	Jmp(Name),
+	Jcc(Cond, Name),
+	Ud2,
+}
+
+#[derive(Clone, Copy)]
+pub enum Cond {
+	E, Ne,
+	L, Le, G, Ge, // signed
+	B, Be, A, Ae, // unsigned
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Operand {
+	Reg(Reg, Size),
+	Virt(u32, Size), // only valid before register allocation
+	Imm(String),
+	Mem(Size, Reg, i64),
+	Sym(Name), // rip relative
+	Got(Name), // rip relative through the GOT, for external symbols
+	Label(Name),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reg {
+	Rax, Rbx, Rcx, Rdx, Rsi, Rdi, Rbp, Rsp,
+	R8, R9, R10, R11, R12, R13, R14, R15,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Size { Byte, Word, Dword, Qword }
+
+impl Size {
+	pub fn from_bits(bits: u32) -> Option<Self> {
+		Some(match bits {
+			0..=8   => Self::Byte,
+			9..=16  => Self::Word,
+			17..=32 => Self::Dword,
+			33..=64 => Self::Qword,
+			_ => return None,
+		})
+	}
+}
+
+impl Operand {
+	pub fn is_mem(&self) -> bool {
+		matches!(self, Self::Mem(..) | Self::Sym(_) | Self::Got(_))
+	}
+
+	pub fn size(&self) -> Size {
+		match self {
+			Self::Reg(_, s) | Self::Virt(_, s) | Self::Mem(s, _, _) => *s,
+			_ => Size::Qword,
+		}
+	}
+
+	pub fn resized(&self, size: Size) -> Self {
+		match self {
+			Self::Reg(r, _)    => Self::Reg(*r, size),
+			Self::Virt(v, _)   => Self::Virt(*v, size),
+			Self::Mem(_, r, o) => Self::Mem(size, *r, *o),
+			o => o.clone(),
+		}
+	}
+
+	/// Whether the operand can be encoded as a sign extended 32 bit immediate
+	pub fn is_wide_imm(&self) -> bool {
+		match self {
+			Self::Imm(i) => i.parse::<i32>().is_err(),
+			_ => false,
+		}
+	}
+}
+
+impl Instr {
+	pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+		match self {
+			Self::Mov(a, b) | Self::Lea(a, b) | Self::Movzx(a, b) | Self::Movsx(a, b)
+				| Self::Sub(a, b) | Self::Add(a, b) | Self::Xor(a, b) | Self::And(a, b) | Self::Or(a, b)
+				| Self::Imul(a, b) | Self::Cmp(a, b) | Self::Shl(a, b) | Self::Shr(a, b) | Self::Sar(a, b) 
+				=> vec![a, b],
+			Self::Push(a) | Self::Pop(a) | Self::Call(a) | Self::Set(_, a) | Self::Neg(a) | Self::Not(a)
+				| Self::Div(a) | Self::Idiv(a) => vec![a],
+			Self::Ret | Self::Cqo(_) | Self::Label(_) | Self::Jmp(_) | Self::Jcc(..) | Self::Ud2 => Vec::new(),
+		}
+	}
+}
+
+
+impl Display for Module {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		writeln!(f, "\t.file \"{}\"", self.name)?;
+		writeln!(f, "\t.intel_syntax noprefix")?;
+
+		if !self.data.is_empty() {
+			writeln!(f, "\n\t.data")?;
+			self.data.iter().try_for_each(|d| writeln!(f, "{d}"))?;
+		}
+
+		writeln!(f, "\n\t.text")?;
+		self.funcs.iter().try_for_each(|c| writeln!(f, "{c}"))?;
+		writeln!(f, "\t.ident \"sharc {}\"", env!("CARGO_PKG_VERSION"))?;
+		writeln!(f, "\t.section .note.GNU-stack,\"\",@progbits")
+	}
+}
+
+impl Display for DataDef {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}:\n\t", self.name)?;
+		match &self.value {
+			Data::Str(s) => {
+				write!(f, ".ascii \"")?;
+				s.bytes().try_for_each(|c| match c {
+					b' '..=b'~' if c != b'"' && c != b'\\' => write!(f, "{}", c as char),
+					_ => write!(f, "\\{c:03o}"),
+				})?;
+				write!(f, "\"")
+			},
+			Data::Int(s, v) => write!(f, ".{} {v}", match s {
+				Size::Byte  => "byte",
+				Size::Word  => "word",
+				Size::Dword => "long",
+				Size::Qword => "quad",
+			}),
+			Data::Addr(n) => write!(f, ".quad {n}"),
+		}
+	}
+}
+
+impl Display for Function {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if self.global { writeln!(f, "\t.globl {}", self.name)?; }
+		writeln!(f, "\t.type {}, @function", self.name)?;
+		writeln!(f, "{}:", self.name)?;
+		self.body.iter().try_for_each(|i| match i {
+			Instr::Label(_) => writeln!(f, "{i}"),
+			_ => writeln!(f, "\t{i}"),
+		})?;
+		writeln!(f, "\t.size {0}, .-{0}", self.name)
+	}
+}
+
+impl Display for Instr {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Mov(a, b) => write!(f, "mov {a}, {b}"),
+			Self::Lea(a, b) => write!(f, "lea {a}, {b}"),
+			Self::Movzx(a, b) => write!(f, "movzx {a}, {b}"),
+			// a dword source needs its own mnemonic
+			Self::Movsx(a, b) if b.size() == Size::Dword => write!(f, "movsxd {a}, {b}"),
+			Self::Movsx(a, b) => write!(f, "movsx {a}, {b}"),
+			Self::Sub(a, b) => write!(f, "sub {a}, {b}"),
+			Self::Add(a, b) => write!(f, "add {a}, {b}"),
+			Self::Xor(a, b) => write!(f, "xor {a}, {b}"),
+			Self::And(a, b) => write!(f, "and {a}, {b}"),
+			Self::Or(a, b)  => write!(f, "or {a}, {b}"),
+			Self::Imul(a, b) => write!(f, "imul {a}, {b}"),
+			Self::Cmp(a, b) => write!(f, "cmp {a}, {b}"),
+			Self::Set(c, a) => write!(f, "set{c} {a}"),
+			Self::Shl(a, b) => write!(f, "shl {a}, {b}"),
+			Self::Shr(a, b) => write!(f, "shr {a}, {b}"),
+			Self::Sar(a, b) => write!(f, "sar {a}, {b}"),
+			Self::Neg(a)    => write!(f, "neg {a}"),
+			Self::Not(a)    => write!(f, "not {a}"),
+			Self::Cqo(Size::Qword) => write!(f, "cqo"),
+			Self::Cqo(_)    => write!(f, "cdq"),
+			Self::Div(a)    => write!(f, "div {a}"),
+			Self::Idiv(a)   => write!(f, "idiv {a}"),
+			Self::Push(a)   => write!(f, "push {}", a.resized(Size::Qword)),
+			Self::Pop(a)    => write!(f, "pop {}", a.resized(Size::Qword)),
+			Self::Call(a) if a.is_mem() => write!(f, "call {}", a.resized(Size::Qword)),
+			Self::Call(a)   => write!(f, "call {a}"),
+			Self::Ret       => write!(f, "ret"),
+			Self::Label(l)  => write!(f, "{l}:"),
+			Self::Jmp(l)    => write!(f, "jmp {l}"),
+			Self::Jcc(c, l) => write!(f, "j{c} {l}"),
+			Self::Ud2       => write!(f, "ud2"),
+		}
+	}
+}
+
+impl Display for Operand {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Reg(r, s)   => write!(f, "{}", r.name(*s)),
+			Self::Virt(v, _)  => write!(f, "%v{v}"),
+			Self::Imm(i)      => write!(f, "{i}"),
+			Self::Mem(s, r, 0) => write!(f, "{s} ptr [{}]", r.name(Size::Qword)),
+			Self::Mem(s, r, o) if *o < 0 => write!(f, "{s} ptr [{} - {}]", r.name(Size::Qword), -o),
+			Self::Mem(s, r, o) => write!(f, "{s} ptr [{} + {o}]", r.name(Size::Qword)),
+			Self::Sym(n)      => write!(f, "[rip + {n}]"),
+			Self::Got(n)      => write!(f, "qword ptr [rip + {n}@GOTPCREL]"),
+			Self::Label(n)    => write!(f, "{n}"),
+		}
+	}
+}
+
+impl Display for Cond {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::E  => "e",
+			Self::Ne => "ne",
+			Self::L  => "l",
+			Self::Le => "le",
+			Self::G  => "g",
+			Self::Ge => "ge",
+			Self::B  => "b",
+			Self::Be => "be",
+			Self::A  => "a",
+			Self::Ae => "ae",
+		})
+	}
+}
+
+impl Display for Size {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Byte  => "byte",
+			Self::Word  => "word",
+			Self::Dword => "dword",
+			Self::Qword => "qword",
+		})
+	}
+}
+
+impl Reg {
+	pub fn name(self, size: Size) -> &'static str {
+		const NAMES: [[&str; 4]; 16] = [
+			["al",   "ax",   "eax",  "rax"],
+			["bl",   "bx",   "ebx",  "rbx"],
+			["cl",   "cx",   "ecx",  "rcx"],
+			["dl",   "dx",   "edx",  "rdx"],
+			["sil",  "si",   "esi",  "rsi"],
+			["dil",  "di",   "edi",  "rdi"],
+			["bpl",  "bp",   "ebp",  "rbp"],
+			["spl",  "sp",   "esp",  "rsp"],
+			["r8b",  "r8w",  "r8d",  "r8"],
+			["r9b",  "r9w",  "r9d",  "r9"],
+			["r10b", "r10w", "r10d", "r10"],
+			["r11b", "r11w", "r11d", "r11"],
+			["r12b", "r12w", "r12d", "r12"],
+			["r13b", "r13w", "r13d", "r13"],
+			["r14b", "r14w", "r14d", "r14"],
+			["r15b", "r15w", "r15d", "r15"],
+		];
+
+		NAMES[self as usize][size as usize]
+	}
+}
@@ -0,0 +1,605 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::report::{Result, LogHandler, ReportKind};
+use crate::analyzer::mir::{ValId, Var, Node, BinOp, UnOp, Type as mType};
+
+mod asm;
+mod regalloc;
+use asm::{Instr, Operand, Reg, Size, Cond, Module, DataDef, Data};
+
+/// System V integer argument registers, in order
+const ARG_REGS: [Reg; 6] = [Reg::Rdi, Reg::Rsi, Reg::Rdx, Reg::Rcx, Reg::R8, Reg::R9];
+
+pub struct Gen {
+	module:  Module,
+	sym:     HashMap<ValId, String>,
+	externs: HashSet<ValId>,
+	vregs:   HashMap<ValId, Operand>, // reset for every function
+	vcnt:    u32,
+}
+
+impl Gen {
+	pub fn codegen(
+		file: &'static str, sym: HashMap<ValId, String>,
+		mir: Vec<Node>, handler: &LogHandler)
+	-> Module {
+		let mut cgen = Self {
+			sym,
+			module:  Module { name: file, ..Module::default() },
+			externs: HashSet::new(),
+			vregs:   HashMap::new(),
+			vcnt:    0,
+		};
+
+		mir.into_iter().for_each(|node| {
+			let loc = node.loc();
+			if let Err(e) = cgen.gen_node(node) {
+				handler.log(super::locate(*e, loc).file(file));
+			}
+		});
+
+		cgen.module
+	}
+
+	fn new_vreg(&mut self, size: Size) -> Operand {
+		self.vcnt += 1;
+		Operand::Virt(self.vcnt, size)
+	}
+
+	fn local(&mut self, id: ValId, ty: &mType) -> Result<Operand> {
+		if let Some(op) = self.vregs.get(&id) { return Ok(op.clone()); }
+
+		let op = self.new_vreg(gen_size(ty)?);
+		self.vregs.insert(id, op.clone());
+		Ok(op)
+	}
+
+	fn gen_node(&mut self, node: Node) -> Result<()> {
+		match node {
+			Node::Func { id, export, args, body, .. } => {
+				self.vregs.clear();
+				self.vcnt = 0;
+
+				let mut instrs = Vec::new();
+
+				// incoming args are pushed and popped into their vregs,
+				// so no arg register gets overwritten before it is read
+				let regs = &ARG_REGS[..args.len().min(ARG_REGS.len())];
+				regs.iter().for_each(|r| instrs.push(Instr::Push(Operand::Reg(*r, Size::Qword))));
+
+				let mut ops = Vec::new();
+				for (id, ty) in &args {
+					ops.push(self.local(*id, ty)?);
+				}
+
+				for op in ops.iter().take(regs.len()).rev() {
+					instrs.push(Instr::Pop(op.clone()));
+				}
+
+				#[allow(clippy::cast_possible_wrap)]
+				for (i, op) in ops.iter().enumerate().skip(regs.len()) {
+					let offset = 16 + 8 * (i - regs.len()) as i64;
+					instrs.push(Instr::Mov(op.clone(), Operand::Mem(op.size(), Reg::Rbp, offset)));
+				}
+
+				for stmt in body {
+					instrs.extend(self.gen_stmt(stmt)?);
+				}
+
+				if !matches!(instrs.last(), Some(Instr::Ret)) {
+					instrs.push(Instr::Ret);
+				}
+
+				let alloc = regalloc::allocate(&mut instrs);
+
+				self.module.funcs.push(asm::Function {
+					name:   super::symbol(&self.sym, id),
+					global: export,
+					body:   frame(instrs, &alloc),
+				});
+			},
+			Node::FuncDecl { id, .. } => { self.externs.insert(id); },
+			Node::Struct { .. } => return ReportKind::TypeError
+				.title("Structs are not yet supported on the x86_64 target")
+				.as_err(),
+			Node::Enum { .. } => return ReportKind::TypeError
+				.title("Enums are not yet supported on the x86_64 target")
+				.as_err(),
+			Node::Assign { .. } | Node::Global { .. } | Node::Store { .. } | Node::StoreIndex { .. }
+				| Node::StoreField { .. } | Node::Ret(..) | Node::Trap(_) | Node::Label(_) | Node::Jump(_)
+				| Node::Branch { .. } | Node::FuncCall { .. } | Node::BinOp { .. } | Node::UnOp { .. }
+				| Node::StructLit(_) | Node::Field { .. } | Node::EnumLit { .. } | Node::Tag { .. }
+				| Node::Payload { .. } | Node::OptLit(_) | Node::IsSome { .. } | Node::Unwrap { .. }
+				| Node::AddrOf(_) | Node::Deref(_) | Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_)
+				| Node::Index { .. } | Node::Len(_) | Node::Decay { .. } | Node::SliceLit { .. }
+				| Node::Cast { .. } | Node::StrLit(_) | Node::Var(_)
+				=> unreachable!("only declarations are at the top level"),
+		}
+		Ok(())
+	}
+
+	fn gen_stmt(&mut self, node: Node) -> Result<Vec<Instr>> {
+		Ok(match node {
+			Node::Assign { id, ty, val } => {
+				let dst = self.local(id, &ty)?;
+
+				match *val {
+					Node::FuncCall { id, args } => {
+						let mut instrs = self.gen_fncall(&id, &args)?;
+						instrs.push(Instr::Mov(dst.clone(), Operand::Reg(Reg::Rax, dst.size())));
+						instrs
+					},
+					// an `opt *T` is the pointer itself
+					Node::Var(v) | Node::OptLit(Some((v, _))) | Node::Unwrap { val: v, .. } => {
+						let (mut instrs, op) = self.use_val(&v, dst.size());
+						instrs.push(Instr::Mov(dst, op));
+						instrs
+					},
+					node @ Node::Global { .. } => {
+						let Node::Global { id, .. } = node
+							else { unreachable!() };
+
+						self.gen_stmt(node)?;
+						vec![Instr::Lea(dst, Operand::Sym(format!("g{}", *id)))]
+					},
+					Node::BinOp { op, ty, lhs, rhs } => self.gen_binop(op, &ty, &lhs, &rhs, &dst)?,
+					Node::UnOp { op, ty, val } => {
+						let (mut instrs, op_) = self.operand(&val, &ty)?;
+						let dst = dst.resized(gen_size(&ty)?.max(Size::Dword));
+						instrs.push(Instr::Mov(dst.clone(), op_));
+						instrs.push(match op {
+							UnOp::Neg    => Instr::Neg(dst),
+							UnOp::BitNot => Instr::Not(dst),
+							// bools are only ever 0 or 1
+							UnOp::Not    => Instr::Xor(dst, Operand::Imm(String::from("1"))),
+						});
+						instrs
+					},
+					Node::StructLit(_) | Node::Field { .. } => return ReportKind::TypeError
+						.title("Structs are not yet supported on the x86_64 target")
+						.as_err(),
+					Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } => return ReportKind::TypeError
+						.title("Enums are not yet supported on the x86_64 target")
+						.as_err(),
+					Node::OptLit(None) => vec![Instr::Mov(dst, Operand::Imm(String::from("0")))],
+					Node::AddrOf(Var::Glob(id)) | Node::Decay { val: Var::Glob(id), .. }
+						=> vec![Instr::Lea(dst, Operand::Sym(super::symbol(&self.sym, id)))],
+					Node::Decay { .. } => return ReportKind::TypeError
+						.title("Stack arrays are not yet supported on the x86_64 target")
+						.as_err(),
+					Node::AddrOf(_) => return ReportKind::TypeError
+						.title("Taking the address of a local is not yet supported on the x86_64 target")
+						.as_err(),
+					// narrowing keeps the low part, widening extends by the sign of the source
+					Node::Cast { ty: from, val } => {
+						let (mut instrs, src) = self.use_val(&val, gen_size(&from)?);
+						let src = match src {
+							Operand::Imm(_) => {
+								let tmp = self.new_vreg(Size::Qword);
+								instrs.push(Instr::Mov(tmp.clone(), src));
+								tmp
+							},
+							src => src,
+						};
+
+						instrs.push(match (dst.size(), src.size()) {
+							(d, s) if d <= s => Instr::Mov(dst, src.resized(d)),
+							// writing a dword clears the upper half
+							(Size::Qword, Size::Dword) if !is_signed(&from) => Instr::Mov(dst.resized(Size::Dword), src),
+							_ if is_signed(&from) => Instr::Movsx(dst, src),
+							_ => Instr::Movzx(dst, src),
+						});
+						instrs
+					},
+					Node::Deref(v) => {
+						let (mut instrs, ptr) = self.use_val(&v, Size::Qword);
+						instrs.push(Instr::Mov(Operand::Reg(Reg::R10, Size::Qword), ptr));
+						instrs.push(Instr::Mov(dst.clone(), Operand::Mem(dst.size(), Reg::R10, 0)));
+						instrs
+					},
+					Node::Offset { ty, ptr, op, index: (i, ity) } => {
+						let mType::Ptr(pointee) = ty.base() else { unreachable!() };
+						let size = match gen_size(pointee)? {
+							Size::Byte  => 1,
+							Size::Word  => 2,
+							Size::Dword => 4,
+							Size::Qword => 8,
+						};
+
+						let (mut instrs, idx) = self.operand(&i, &ity)?;
+						let wide = self.new_vreg(Size::Qword);
+						instrs.push(match (idx.size(), is_signed(&ity)) {
+							(Size::Dword, true) if !matches!(idx, Operand::Imm(_)) => Instr::Movsx(wide.clone(), idx),
+							// writing a dword clears the upper half
+							(Size::Dword, _) => Instr::Mov(wide.resized(Size::Dword), idx),
+							_ => Instr::Mov(wide.clone(), idx),
+						});
+						instrs.push(Instr::Imul(wide.clone(), Operand::Imm(size.to_string())));
+
+						let (i, ptr) = self.use_val(&ptr, Size::Qword);
+						instrs.extend(i);
+						instrs.push(Instr::Mov(dst.clone(), ptr));
+						instrs.push(match op {
+							BinOp::Sub => Instr::Sub(dst, wide),
+							_ => Instr::Add(dst, wide),
+						});
+						instrs
+					},
+					// the sizes are powers of two, so the bytes between them are divided by shifting
+					Node::Diff { ty, lhs, rhs } => {
+						let mType::Ptr(pointee) = ty.base() else { unreachable!() };
+						let shift = match gen_size(pointee)? {
+							Size::Byte  => 0,
+							Size::Word  => 1,
+							Size::Dword => 2,
+							Size::Qword => 3,
+						};
+
+						let (mut instrs, lhs) = self.use_val(&lhs, Size::Qword);
+						instrs.push(Instr::Mov(dst.clone(), lhs));
+						let (i, rhs) = self.use_val(&rhs, Size::Qword);
+						instrs.extend(i);
+						instrs.push(Instr::Sub(dst.clone(), rhs));
+						if shift != 0 { instrs.push(Instr::Sar(dst, Operand::Imm(shift.to_string()))); }
+						instrs
+					},
+					Node::IsSome { ty, val } => {
+						let (mut instrs, op) = self.operand(&val, &ty)?;
+						instrs.push(Instr::Cmp(op, Operand::Imm(String::from("0"))));
+						instrs.push(Instr::Set(Cond::Ne, dst.resized(Size::Byte)));
+
+						if dst.size() != Size::Byte {
+							instrs.push(Instr::Movzx(dst.clone(), dst.resized(Size::Byte)));
+						}
+						instrs
+					},
+					_ => unreachable!(),
+				}
+			},
+			Node::Global { id, ty, val } => {
+				let value = match *val {
+					Node::StrLit(s) => Data::Str(s),
+					Node::Var(Var::Imm(v)) => Data::Int(gen_size(&ty)?, v.to_string()),
+					Node::Var(Var::Glob(id)) => Data::Addr(super::symbol(&self.sym, id)),
+					Node::Var(Var::FImm(_)) => return ReportKind::TypeError
+						.title("Floating point is not yet supported on the x86_64 target")
+						.as_err(),
+					_ => unreachable!(),
+				};
+
+				self.module.data.push(DataDef { name: format!("g{}", *id), value });
+				Vec::new()
+			},
+			Node::Store { to: Var::Local(id), from: (v, ty), deref: false } => {
+				let dst = self.local(id, &ty)?;
+				let (mut instrs, op) = self.use_val(&v, dst.size());
+				instrs.push(Instr::Mov(dst, op));
+				instrs
+			},
+			Node::Store { deref: false, .. } => unreachable!("only locals are stored to without a pointer"),
+			Node::Store { to, from: (v, ty), deref: true } => {
+				let size = gen_size(&ty)?;
+				let (mut instrs, ptr) = self.use_val(&to, Size::Qword);
+				let (i, op) = self.use_val(&v, size);
+				instrs.extend(i);
+
+				// r10 is never allocated, so it can hold the address across the store
+				instrs.push(Instr::Mov(Operand::Reg(Reg::R10, Size::Qword), ptr));
+				instrs.push(Instr::Mov(Operand::Mem(size, Reg::R10, 0), op));
+				instrs
+			},
+			Node::Ret(None, _) => vec![Instr::Ret],
+			Node::Ret(Some(v), ty) => {
+				let (mut instrs, op) = self.use_val(&v, gen_size(&ty)?);
+				let op = self.extend(op, &ty, &mut instrs);
+				instrs.push(Instr::Mov(Operand::Reg(Reg::Rax, op.size()), op));
+				instrs.push(Instr::Ret);
+				instrs
+			},
+			Node::FuncCall { id, args } => self.gen_fncall(&id, &args)?,
+			Node::Label(l) => vec![Instr::Label(format!(".{l}"))],
+			Node::Jump(l)  => vec![Instr::Jmp(format!(".{l}"))],
+			Node::Branch { cond: (v, ty), then, els } => {
+				let size = gen_size(&ty)?;
+				let (mut instrs, op) = self.use_val(&v, size);
+
+				// cmp cannot take an immediate on the left
+				let op = match op {
+					Operand::Imm(_) => {
+						let tmp = self.new_vreg(size);
+						instrs.push(Instr::Mov(tmp.clone(), op));
+						tmp
+					},
+					op => op,
+				};
+
+				instrs.push(Instr::Cmp(op, Operand::Imm(String::from("0"))));
+				instrs.push(Instr::Jcc(Cond::Ne, format!(".{then}")));
+				instrs.push(Instr::Jmp(format!(".{els}")));
+				instrs
+			},
+			Node::Trap(_) => vec![Instr::Ud2],
+			Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. }
+				=> unreachable!("declarations are not nested in functions"),
+			Node::StoreIndex { .. } => return ReportKind::TypeError
+				.title("Arrays are not yet supported on the x86_64 target")
+				.as_err(),
+			Node::StoreField { .. } => return ReportKind::TypeError
+				.title("Structs are not yet supported on the x86_64 target")
+				.as_err(),
+			Node::BinOp { .. } | Node::UnOp { .. } | Node::StructLit(_) | Node::Field { .. }
+				| Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } | Node::OptLit(_)
+				| Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_) | Node::Deref(_)
+				| Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. } | Node::Len(_)
+				| Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. } | Node::StrLit(_)
+				| Node::Var(_)
+				=> unreachable!("only the value of an Assign"),
+		})
+	}
+
+	/// Operations are done in at least 32 bits, the result is truncated when it is read back
+	fn gen_binop(&mut self, op: BinOp, ty: &mType, lhs: &Var, rhs: &Var, dst: &Operand) -> Result<Vec<Instr>> {
+		let (mut instrs, lhs) = self.operand(lhs, ty)?;
+		let (i, rhs) = self.operand(rhs, ty)?;
+		instrs.extend(i);
+
+		let size = gen_size(ty)?.max(Size::Dword);
+		let signed = is_signed(ty);
+
+		let rax = Operand::Reg(Reg::Rax, size);
+		let rdx = Operand::Reg(Reg::Rdx, size);
+		let r11 = Operand::Reg(Reg::R11, size);
+
+		if op.is_cmp() {
+			// cmp cannot take an immediate on the left
+			let lhs = match lhs {
+				Operand::Imm(_) => {
+					let tmp = self.new_vreg(size);
+					instrs.push(Instr::Mov(tmp.clone(), lhs));
+					tmp
+				},
+				lhs => lhs,
+			};
+
+			instrs.push(Instr::Cmp(lhs, rhs));
+			instrs.push(Instr::Set(match (op, signed) {
+				(BinOp::Eq, _)     => Cond::E,
+				(BinOp::Ne, _)     => Cond::Ne,
+				(BinOp::Lt, true)  => Cond::L,
+				(BinOp::Le, true)  => Cond::Le,
+				(BinOp::Gt, true)  => Cond::G,
+				(BinOp::Ge, true)  => Cond::Ge,
+				(BinOp::Lt, false) => Cond::B,
+				(BinOp::Le, false) => Cond::Be,
+				(BinOp::Gt, false) => Cond::A,
+				(BinOp::Ge, false) => Cond::Ae,
+				_ => unreachable!(),
+			}, dst.resized(Size::Byte)));
+
+			if dst.size() != Size::Byte {
+				instrs.push(Instr::Movzx(dst.clone(), dst.resized(Size::Byte)));
+			}
+
+			return Ok(instrs);
+		}
+
+		let dst = dst.resized(size);
+		match op {
+			BinOp::Div | BinOp::Rem => {
+				instrs.push(Instr::Mov(r11.clone(), rhs));
+				instrs.push(Instr::Mov(rax.clone(), lhs));
+				instrs.push(match signed {
+					true  => Instr::Cqo(size),
+					false => Instr::Xor(rdx.resized(Size::Dword), rdx.resized(Size::Dword)),
+				});
+				instrs.push(match signed {
+					true  => Instr::Idiv(r11),
+					false => Instr::Div(r11),
+				});
+				instrs.push(Instr::Mov(dst, match op {
+					BinOp::Div => rax,
+					_ => rdx,
+				}));
+			},
+			BinOp::Shl | BinOp::Shr => {
+				instrs.push(Instr::Mov(dst.clone(), lhs));
+
+				let count = match rhs {
+					Operand::Imm(_) => rhs,
+					rhs => {
+						instrs.push(Instr::Mov(Operand::Reg(Reg::Rcx, size), rhs));
+						Operand::Reg(Reg::Rcx, Size::Byte)
+					},
+				};
+
+				instrs.push(match (op, signed) {
+					(BinOp::Shl, _)    => Instr::Shl(dst, count),
+					(_, true)  => Instr::Sar(dst, count),
+					(_, false) => Instr::Shr(dst, count),
+				});
+			},
+			_ => {
+				instrs.push(Instr::Mov(dst.clone(), lhs));
+				instrs.push(match op {
+					BinOp::Add => Instr::Add(dst, rhs),
+					BinOp::Sub => Instr::Sub(dst, rhs),
+					BinOp::Mul => Instr::Imul(dst, rhs),
+					BinOp::BitAnd | BinOp::And => Instr::And(dst, rhs),
+					BinOp::BitOr  | BinOp::Or  => Instr::Or(dst, rhs),
+					BinOp::BitXor | BinOp::Xor => Instr::Xor(dst, rhs),
+					_ => unreachable!(),
+				});
+			},
+		}
+
+		Ok(instrs)
+	}
+
+	/// An operand of an arithmetic instruction, widened to 32 bits and with large immediates in a register
+	fn operand(&mut self, v: &Var, ty: &mType) -> Result<(Vec<Instr>, Operand)> {
+		let (mut instrs, op) = self.use_val(v, gen_size(ty)?);
+		let op = self.extend(op, ty, &mut instrs);
+
+		if !op.is_wide_imm() { return Ok((instrs, op)); }
+
+		let tmp = self.new_vreg(Size::Qword);
+		instrs.push(Instr::Mov(tmp.clone(), op));
+		Ok((instrs, tmp))
+	}
+
+	fn gen_fncall(&mut self, func: &Var, args: &[(Var, mType)]) -> Result<Vec<Instr>> {
+		let mut instrs = Vec::new();
+
+		let mut ops = Vec::new();
+		for (v, ty) in args {
+			let (i, op) = self.use_val(v, gen_size(ty)?);
+			instrs.extend(i);
+			ops.push(self.extend(op, ty, &mut instrs));
+		}
+
+		let callee = match func {
+			Var::Glob(id) if self.externs.contains(id) => Operand::Label(format!("{}@PLT", super::symbol(&self.sym, *id))),
+			Var::Glob(id) => Operand::Label(super::symbol(&self.sym, *id)),
+			v => {
+				let (i, op) = self.use_val(v, Size::Qword);
+				instrs.extend(i);
+				// r10 is never allocated and not touched by the arg setup below
+				instrs.push(Instr::Mov(Operand::Reg(Reg::R10, Size::Qword), op));
+				Operand::Reg(Reg::R10, Size::Qword)
+			},
+		};
+
+		let nregs  = ops.len().min(ARG_REGS.len());
+		let nstack = ops.len() - nregs;
+
+		// the stack has to be 16 byte aligned at the call
+		let pad = nstack % 2;
+		if pad != 0 {
+			instrs.push(Instr::Sub(Operand::Reg(Reg::Rsp, Size::Qword), Operand::Imm(String::from("8"))));
+		}
+
+		ops[nregs..].iter().rev().for_each(|op| instrs.push(Instr::Push(op.clone())));
+		ops[..nregs].iter().for_each(|op| instrs.push(Instr::Push(op.clone())));
+		ARG_REGS[..nregs].iter().rev().for_each(|r| instrs.push(Instr::Pop(Operand::Reg(*r, Size::Qword))));
+
+		// al holds the number of vector registers used by variadic functions
+		instrs.push(Instr::Xor(Operand::Reg(Reg::Rax, Size::Dword), Operand::Reg(Reg::Rax, Size::Dword)));
+		instrs.push(Instr::Call(callee));
+
+		if nstack + pad != 0 {
+			instrs.push(Instr::Add(Operand::Reg(Reg::Rsp, Size::Qword),
+				Operand::Imm((8 * (nstack + pad)).to_string())));
+		}
+
+		Ok(instrs)
+	}
+
+	/// Widens values smaller than 32 bits, as System V callers and callees expect
+	fn extend(&mut self, op: Operand, ty: &mType, instrs: &mut Vec<Instr>) -> Operand {
+		if matches!(op, Operand::Imm(_)) || matches!(op.size(), Size::Dword | Size::Qword) {
+			return op;
+		}
+
+		let wide = self.new_vreg(Size::Dword);
+		instrs.push(match is_signed(ty) {
+			true  => Instr::Movsx(wide.clone(), op),
+			false => Instr::Movzx(wide.clone(), op),
+		});
+		wide
+	}
+
+	/// Turns a MIR value into an operand, addresses of globals are loaded into a fresh vreg
+	fn use_val(&mut self, v: &Var, size: Size) -> (Vec<Instr>, Operand) {
+		match v {
+			Var::Imm(i)    => (Vec::new(), Operand::Imm(i.to_string())),
+			Var::FImm(_)   => unreachable!("floats are rejected by gen_size"),
+			Var::Local(id) => (Vec::new(), self.vregs.get(id).expect("use of undefined local").resized(size)),
+			Var::Glob(id) => {
+				let op = self.new_vreg(Size::Qword);
+				(vec![match self.externs.contains(id) {
+					true  => Instr::Mov(op.clone(), Operand::Got(super::symbol(&self.sym, *id))),
+					false => Instr::Lea(op.clone(), Operand::Sym(super::symbol(&self.sym, *id))),
+				}], op)
+			},
+		}
+	}
+}
+
+/// Wraps an allocated body in the prologue and expands every `ret` into an epilogue
+fn frame(body: Vec<Instr>, alloc: &regalloc::Allocation) -> Vec<Instr> {
+	let rsp = Operand::Reg(Reg::Rsp, Size::Qword);
+	let rbp = Operand::Reg(Reg::Rbp, Size::Qword);
+
+	let mut instrs = vec![
+		Instr::Push(rbp.clone()),
+		Instr::Mov(rbp.clone(), rsp.clone()),
+	];
+	alloc.saved.iter().for_each(|r| instrs.push(Instr::Push(Operand::Reg(*r, Size::Qword))));
+
+	// keep rsp 16 byte aligned, rbp itself already is
+	let mut frame = 8 * u64::from(alloc.slots);
+	if !(frame + 8 * alloc.saved.len() as u64).is_multiple_of(16) { frame += 8; }
+	if frame != 0 {
+		instrs.push(Instr::Sub(rsp.clone(), Operand::Imm(frame.to_string())));
+	}
+
+	for instr in body {
+		match instr {
+			Instr::Ret => {
+				#[allow(clippy::cast_possible_wrap)]
+				instrs.push(match alloc.saved.is_empty() {
+					true  => Instr::Mov(rsp.clone(), rbp.clone()),
+					false => Instr::Lea(rsp.clone(),
+						Operand::Mem(Size::Qword, Reg::Rbp, -8 * alloc.saved.len() as i64)),
+				});
+				alloc.saved.iter().rev().for_each(|r| instrs.push(Instr::Pop(Operand::Reg(*r, Size::Qword))));
+				instrs.push(Instr::Pop(rbp.clone()));
+				instrs.push(Instr::Ret);
+			},
+			i => instrs.push(i),
+		}
+	}
+
+	instrs
+}
+
+fn is_signed(ty: &mType) -> bool {
+	match ty {
+		mType::Opt(ty) | mType::Mut(ty) => is_signed(ty),
+		mType::I(_) | mType::Isize => true,
+		_ => false,
+	}
+}
+
+fn gen_size(ty: &mType) -> Result<Size> {
+	Ok(match &ty {
+		mType::U(i) | mType::B(i) | mType::I(i) => match Size::from_bits(*i) {
+			Some(s) => s,
+			None => return ReportKind::TypeError
+				.title("Unsuported bit width for integer on the x86_64 target")
+				.as_err(),
+		},
+
+		mType::F(_) => return ReportKind::TypeError
+			.title("Floating point is not yet supported on the x86_64 target")
+			// TODO: span on type mir
+			.as_err(),
+
+		mType::Opt(_) if ty.has_niche() => Size::Qword,
+		mType::Opt(_) => return ReportKind::TypeError
+			.title("Optionals are not yet supported on the x86_64 target")
+			.as_err(),
+		mType::Mut(ty) => return gen_size(ty),
+		mType::Ptr(_) | mType::Usize | mType::Isize | mType::Fn(_, _) => Size::Qword,
+		mType::Arr(_, _) => return ReportKind::TypeError
+			.title("Stack arrays are not yet supported on the x86_64 target")
+			.as_err(),
+		mType::Struct(..) => return ReportKind::TypeError
+			.title("Structs are not yet supported on the x86_64 target")
+			.as_err(),
+		mType::Enum(..) => return ReportKind::TypeError
+			.title("Enums are not yet supported on the x86_64 target")
+			.as_err(),
+		_ => unreachable!()
+	})
+}
@@ -0,0 +1,236 @@
+//! Linear scan register allocation (Poletto & Sarkar) over the virtual registers
+//! produced by instruction selection.
+use std::collections::HashMap;
+
+use super::asm::{Instr, Operand, Reg, Size};
+
+/// Preserved across calls, anything that lives past a call or other clobber has to end up in here
+pub const CALLEE_SAVED: [Reg; 5] = [Reg::Rbx, Reg::R12, Reg::R13, Reg::R14, Reg::R15];
+/// Clobbered by calls. rax, r10 and r11 are never allocated, they are kept free as scratch
+const CALLER_SAVED: [Reg; 6] = [Reg::Rcx, Reg::Rdx, Reg::Rsi, Reg::Rdi, Reg::R8, Reg::R9];
+/// Caller saved registers come first, so short lived values dont cost a save and restore
+const ALLOCATABLE: [Reg; 11] = [
+	CALLER_SAVED[0], CALLER_SAVED[1], CALLER_SAVED[2], CALLER_SAVED[3], CALLER_SAVED[4], CALLER_SAVED[5],
+	CALLEE_SAVED[0], CALLEE_SAVED[1], CALLEE_SAVED[2], CALLEE_SAVED[3], CALLEE_SAVED[4],
+];
+
+/// Scratch register used to fix up instructions after rewriting
+const SCRATCH: Reg = Reg::R11;
+
+#[derive(Clone, Copy, Debug)]
+enum Loc {
+	Reg(Reg),
+	Slot(u32),
+}
+
+#[derive(Debug)]
+struct Interval {
+	vreg:  u32,
+	start: usize,
+	end:   usize,
+	crosses_clobber: bool,
+}
+
+pub struct Allocation {
+	/// Callee saved registers that were handed out and need to be preserved
+	pub saved: Vec<Reg>,
+	/// Number of 8 byte stack slots used for spilled values
+	pub slots: u32,
+}
+
+impl Allocation {
+	/// Offset from rbp of a spill slot, the saved registers are pushed right after rbp
+	#[allow(clippy::cast_possible_wrap)]
+	pub fn slot_offset(&self, slot: u32) -> i64 {
+		-8 * (self.saved.len() as i64 + 1 + i64::from(slot))
+	}
+}
+
+pub fn allocate(body: &mut Vec<Instr>) -> Allocation {
+	let intervals = build_intervals(body);
+
+	let mut locs: HashMap<u32, Loc> = HashMap::new();
+	let mut active: Vec<(usize, u32, Reg)> = Vec::new(); // (end, vreg, reg), sorted by end
+	let mut slots = 0;
+
+	for int in &intervals {
+		// expire intervals which ended before this one starts
+		active.retain(|(end, _, _)| *end >= int.start);
+
+		let candidates: &[Reg] = match int.crosses_clobber {
+			true  => &CALLEE_SAVED,
+			false => &ALLOCATABLE,
+		};
+
+		let free = candidates.iter()
+			.find(|r| !active.iter().any(|(_, _, a)| a == *r))
+			.copied();
+
+		let reg = match free {
+			Some(reg) => Some(reg),
+			None => {
+				// spill whichever compatible interval lives the longest
+				let victim = active.iter().enumerate()
+					.filter(|(_, (_, _, r))| candidates.contains(r))
+					.max_by_key(|(_, (end, _, _))| *end)
+					.map(|(i, (end, vreg, reg))| (i, *end, *vreg, *reg));
+
+				match victim {
+					Some((i, end, vreg, reg)) if end > int.end => {
+						active.remove(i);
+						locs.insert(vreg, Loc::Slot(slots));
+						slots += 1;
+						Some(reg)
+					},
+					_ => None,
+				}
+			},
+		};
+
+		match reg {
+			Some(reg) => {
+				locs.insert(int.vreg, Loc::Reg(reg));
+				let pos = active.partition_point(|(end, _, _)| *end < int.end);
+				active.insert(pos, (int.end, int.vreg, reg));
+			},
+			None => {
+				locs.insert(int.vreg, Loc::Slot(slots));
+				slots += 1;
+			},
+		}
+	}
+
+	let saved = CALLEE_SAVED.iter()
+		.filter(|r| locs.values().any(|l| matches!(l, Loc::Reg(a) if a == *r)))
+		.copied()
+		.collect();
+
+	let alloc = Allocation { saved, slots };
+	rewrite(body, &locs, &alloc);
+	alloc
+}
+
+fn build_intervals(body: &mut [Instr]) -> Vec<Interval> {
+	// (head, back edge) of every loop, the body is laid out in order so a backwards jump closes one
+	let loops: Vec<(usize, usize)> = {
+		let labels: HashMap<&str, usize> = body.iter().enumerate()
+			.filter_map(|(pos, i)| match i {
+				Instr::Label(l) => Some((l.as_str(), pos)),
+				_ => None,
+			})
+			.collect();
+
+		body.iter().enumerate()
+			.filter_map(|(pos, i)| match i {
+				Instr::Jmp(l) | Instr::Jcc(_, l) => Some((labels[l.as_str()], pos)),
+				_ => None,
+			})
+			.filter(|(head, pos)| head < pos)
+			.collect()
+	};
+
+	let mut ranges: HashMap<u32, (usize, usize)> = HashMap::new();
+	let mut clobbers = Vec::new();
+
+	for (pos, instr) in body.iter_mut().enumerate() {
+		// calls, and anything writing a fixed caller saved register like division and shifts
+		let clobbers_regs = match &*instr {
+			Instr::Call(_) | Instr::Cqo(_) | Instr::Div(_) | Instr::Idiv(_) => true,
+			Instr::Push(_) => false,
+			_ => matches!(instr.operands_mut().first(), Some(Operand::Reg(r, _)) if CALLER_SAVED.contains(r)),
+		};
+		if clobbers_regs { clobbers.push(pos); }
+
+		for op in instr.operands_mut() {
+			if let Operand::Virt(v, _) = op {
+				ranges.entry(*v)
+					.and_modify(|(_, end)| *end = pos)
+					.or_insert((pos, pos));
+			}
+		}
+	}
+
+	// anything live at the head of a loop has to survive until the jump back to it
+	let mut changed = true;
+	while changed {
+		changed = false;
+		for (start, end) in ranges.values_mut() {
+			for &(head, tail) in &loops {
+				if *start < head && head <= *end && *end < tail {
+					*end = tail;
+					changed = true;
+				}
+			}
+		}
+	}
+
+	let mut intervals: Vec<Interval> = ranges.into_iter()
+		.map(|(vreg, (start, end))| Interval {
+			vreg, start, end,
+			crosses_clobber: clobbers.iter().any(|c| start < *c && *c < end),
+		})
+		.collect();
+
+	intervals.sort_by_key(|i| (i.start, i.vreg));
+	intervals
+}
+
+fn rewrite(body: &mut Vec<Instr>, locs: &HashMap<u32, Loc>, alloc: &Allocation) {
+	for instr in body.iter_mut() {
+		for op in instr.operands_mut() {
+			if let Operand::Virt(v, size) = op {
+				*op = match locs[v] {
+					Loc::Reg(r)  => Operand::Reg(r, *size),
+					Loc::Slot(s) => Operand::Mem(*size, Reg::Rbp, alloc.slot_offset(s)),
+				};
+			}
+		}
+	}
+
+	// spilling may have produced instructions x86 cannot encode
+	let old = std::mem::take(body);
+	old.into_iter().for_each(|i| body.extend(legalize(i)));
+}
+
+fn legalize(instr: Instr) -> Vec<Instr> {
+	fn via_scratch(f: fn(Operand, Operand) -> Instr, a: Operand, b: &Operand) -> Vec<Instr> {
+		let scratch = Operand::Reg(SCRATCH, a.size());
+		vec![Instr::Mov(scratch.clone(), b.resized(a.size())), f(a, scratch)]
+	}
+
+	match instr {
+		Instr::Mov(a, b) if a.is_mem() && (b.is_mem() || b.is_wide_imm())
+			=> via_scratch(Instr::Mov, a, &b),
+		Instr::Add(a, b) if b.is_wide_imm() || (a.is_mem() && b.is_mem())
+			=> via_scratch(Instr::Add, a, &b),
+		Instr::Sub(a, b) if b.is_wide_imm() || (a.is_mem() && b.is_mem())
+			=> via_scratch(Instr::Sub, a, &b),
+		Instr::Xor(a, b) if b.is_wide_imm() || (a.is_mem() && b.is_mem())
+			=> via_scratch(Instr::Xor, a, &b),
+		Instr::And(a, b) if a.is_mem() && b.is_mem() => via_scratch(Instr::And, a, &b),
+		Instr::Or(a, b)  if a.is_mem() && b.is_mem() => via_scratch(Instr::Or, a, &b),
+		Instr::Cmp(a, b) if a.is_mem() && b.is_mem() => via_scratch(Instr::Cmp, a, &b),
+		// imul can only write a register
+		Instr::Imul(a, b) if a.is_mem() => {
+			let scratch = Operand::Reg(SCRATCH, a.size());
+			vec![Instr::Mov(scratch.clone(), a.clone()), Instr::Imul(scratch.clone(), b), Instr::Mov(a, scratch)]
+		},
+		Instr::Movzx(a, b) if a.is_mem() => {
+			let scratch = Operand::Reg(SCRATCH, a.size());
+			vec![Instr::Movzx(scratch.clone(), b), Instr::Mov(a, scratch)]
+		},
+		Instr::Movsx(a, b) if a.is_mem() => {
+			let scratch = Operand::Reg(SCRATCH, a.size());
+			vec![Instr::Movsx(scratch.clone(), b), Instr::Mov(a, scratch)]
+		},
+		Instr::Lea(a, b) if a.is_mem() => vec![
+			Instr::Lea(Operand::Reg(SCRATCH, Size::Qword), b),
+			Instr::Mov(a, Operand::Reg(SCRATCH, Size::Qword)),
+		],
+		Instr::Push(a) if a.is_wide_imm() => vec![
+			Instr::Mov(Operand::Reg(SCRATCH, Size::Qword), a),
+			Instr::Push(Operand::Reg(SCRATCH, Size::Qword)),
+		],
+		i => vec![i],
+	}
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::report::ReportKind;
+use super::{Memory, Value, Stop};
+
+/// A function provided by the interpreter in place of an extern
+pub type HostFn = fn(&mut Memory, &[Value]) -> Result<Value, Stop>;
+
+/// Table of the externs a program may call while being interpreted
+pub struct Host(HashMap<&'static str, HostFn>);
+
+impl Host {
+	pub fn empty() -> Self {
+		Self(HashMap::new())
+	}
+
+	pub fn with(mut self, name: &'static str, func: HostFn) -> Self {
+		self.0.insert(name, func);
+		self
+	}
+
+	pub fn get(&self, name: &str) -> Option<HostFn> {
+		self.0.get(name).copied()
+	}
+
+	pub fn names(&self) -> Vec<&'static str> {
+		let mut names: Vec<_> = self.0.keys().copied().collect();
+		names.sort_unstable();
+		names
+	}
+}
+
+/// The bits of libc programs commonly reach for
+impl Default for Host {
+	fn default() -> Self {
+		Self::empty()
+			.with("puts",    puts)
+			.with("putchar", putchar)
+			.with("exit",    exit)
+			.with("abort",   abort)
+	}
+}
+
+fn write_out(bytes: &[u8]) -> Result<(), Stop> {
+	let mut out = std::io::stdout().lock();
+	out.write_all(bytes).and_then(|()| out.flush())
+		.map_err(|e| ReportKind::IOError
+			.title("Failed to write to stdout")
+			.footer(e).into())
+}
+
+fn puts(mem: &mut Memory, args: &[Value]) -> Result<Value, Stop> {
+	let mut s = mem.read_cstr(args[0].as_ptr()?)?.to_vec();
+	s.push(b'\n');
+	write_out(&s)?;
+	Ok(Value::Int(0))
+}
+
+fn putchar(_: &mut Memory, args: &[Value]) -> Result<Value, Stop> {
+	let c = args[0].as_int()?;
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	write_out(&[c as u8])?;
+	Ok(Value::Int(c))
+}
+
+fn exit(_: &mut Memory, args: &[Value]) -> Result<Value, Stop> {
+	#[allow(clippy::cast_possible_truncation)]
+	Err(Stop::Exit(args[0].as_int()? as i32))
+}
+
+fn abort(_: &mut Memory, _: &[Value]) -> Result<Value, Stop> {
+	Err(ReportKind::RuntimeError
+		.title("Program aborted")
+		.into())
+}
@@ -0,0 +1,850 @@
+//! Executes MIR directly, used by `sharc run` so programs can be tried without
+//! an assembler or linker.
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::report::{Report, Result, ReportKind};
+use crate::analyzer::mir::{ValId, TypeId, LabelId, Var, Node, Type, BinOp, UnOp};
+
+mod host;
+pub use host::Host;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+	Int(i128),
+	Float(f64),
+	Ptr(usize), // offset into `Memory`
+	Func(ValId),
+	Struct(Rc<[Self]>),
+	Enum(usize, Rc<Self>), // the variant and its payload, `Void` if it has none
+	Void,
+}
+
+impl Value {
+	pub fn as_int(&self) -> std::result::Result<i128, Stop> {
+		match *self {
+			Self::Int(i) => Ok(i),
+			#[allow(clippy::cast_possible_wrap)]
+			Self::Ptr(p) => Ok(p as i128),
+			ref v => Err(ReportKind::RuntimeError
+				.title(format!("Expected an integer, found {v:?}"))
+				.into()),
+		}
+	}
+
+	pub fn as_ptr(&self) -> std::result::Result<usize, Stop> {
+		match *self {
+			Self::Ptr(p) => Ok(p),
+			Self::Int(i) => usize::try_from(i).map_err(|_| ReportKind::RuntimeError
+				.title(format!("Invalid pointer {i}"))
+				.into()),
+			ref v => Err(ReportKind::RuntimeError
+				.title(format!("Expected a pointer, found {v:?}"))
+				.into()),
+		}
+	}
+}
+
+/// Why execution unwound
+pub enum Stop {
+	Exit(i32),
+	Trap(Box<Report>),
+}
+
+impl From<Box<Report>> for Stop {
+	fn from(report: Box<Report>) -> Self {
+		Self::Trap(report)
+	}
+}
+
+impl From<Report> for Stop {
+	fn from(report: Report) -> Self {
+		Self::Trap(Box::new(report))
+	}
+}
+
+/// Flat byte addressed memory, globals are laid out in here with the locals whose address is taken above them
+pub struct Memory(Vec<u8>);
+
+impl Default for Memory {
+	fn default() -> Self {
+		Self(vec![0; 16]) // keep null unused
+	}
+}
+
+impl Memory {
+	pub fn alloc(&mut self, data: &[u8]) -> usize {
+		self.0.resize(self.0.len().next_multiple_of(8), 0);
+		let addr = self.0.len();
+		self.0.extend_from_slice(data);
+		addr
+	}
+
+	/// The end of what is allocated, for freeing anything allocated after it with `release`
+	pub fn top(&self) -> usize {
+		self.0.len()
+	}
+
+	pub fn release(&mut self, top: usize) {
+		self.0.truncate(top);
+	}
+
+	pub fn read(&self, addr: usize, len: usize) -> std::result::Result<&[u8], Stop> {
+		self.0.get(addr..addr + len).filter(|_| addr != 0)
+			.ok_or_else(|| ReportKind::RuntimeError
+				.title(format!("Invalid read at address {addr:#x}"))
+				.into())
+	}
+
+	pub fn write(&mut self, addr: usize, data: &[u8]) -> std::result::Result<(), Stop> {
+		self.0.get_mut(addr..addr + data.len()).filter(|_| addr != 0)
+			.ok_or_else(|| ReportKind::RuntimeError
+				.title(format!("Invalid write at address {addr:#x}")))?
+			.copy_from_slice(data);
+		Ok(())
+	}
+
+	pub fn read_cstr(&self, addr: usize) -> std::result::Result<&[u8], Stop> {
+		let bytes = self.0.get(addr..).filter(|_| addr != 0)
+			.ok_or_else(|| ReportKind::RuntimeError
+				.title(format!("Invalid read at address {addr:#x}")))?;
+
+		match bytes.iter().position(|b| *b == 0) {
+			Some(len) => Ok(&bytes[..len]),
+			None => Err(ReportKind::RuntimeError
+				.title("Read past the end of memory")
+				.help("Strings passed to C functions need a trailing '\\0'")
+				.into()),
+		}
+	}
+}
+
+struct Func {
+	args:   Vec<ValId>,
+	body:   Vec<Node>,
+	labels: HashMap<LabelId, usize>, // index of the statement after the label
+}
+
+/// Where the fields of a struct, or the payloads of an enum's variants, are laid out
+struct Layout {
+	size:  usize,
+	align: usize,
+	parts: Vec<(usize, Option<Type>)>, // offset and type
+}
+
+/// The locals of a call, those whose address was taken live in memory instead
+struct Frame {
+	vals:  HashMap<ValId, Value>,
+	slots: HashMap<ValId, (usize, Type)>,
+}
+
+/// Where execution continues after a statement
+enum Flow {
+	Next,
+	Jump(LabelId),
+	Ret(Value),
+}
+
+pub struct Interp<'h> {
+	sym:     HashMap<ValId, String>,
+	host:    &'h Host,
+	funcs:   HashMap<ValId, Rc<Func>>,
+	externs: HashMap<ValId, String>,
+	globals: HashMap<ValId, Value>,
+	layouts: HashMap<TypeId, Layout>,
+	mem:     Memory,
+}
+
+impl<'h> Interp<'h> {
+	pub fn new(sym: HashMap<ValId, String>, mir: Vec<Node>, host: &'h Host) -> Result<Self> {
+		let mut interp = Self {
+			sym, host,
+			funcs:   HashMap::new(),
+			externs: HashMap::new(),
+			globals: HashMap::new(),
+			layouts: HashMap::new(),
+			mem:     Memory::default(),
+		};
+
+		// globals in function bodies are laid out up front, so memory past them can be freed after each call
+		let mut order = Vec::new();
+		for node in mir {
+			match node {
+				Node::Func { id, args, body, .. } => {
+					let labels = body.iter().enumerate()
+						.filter_map(|(i, n)| match n {
+							Node::Label(l) => Some((*l, i + 1)),
+							_ => None,
+						})
+						.collect();
+
+					order.push(id);
+					interp.funcs.insert(id, Rc::new(Func {
+						args: args.into_iter().map(|(id, _)| id).collect(),
+						body, labels,
+					}));
+					interp.globals.insert(id, Value::Func(id));
+				},
+				Node::FuncDecl { id, .. } => {
+					let name = interp.sym.get(&id).expect("ValId not found in sym table").clone();
+					interp.externs.insert(id, name);
+					interp.globals.insert(id, Value::Func(id));
+				},
+				node @ Node::Global { .. } => { interp.global(&node)?; },
+				Node::Struct { id, fields, .. } => {
+					let (mut size, mut align, mut parts) = (0usize, 1, Vec::new());
+					for ty in fields {
+						let (fsize, falign) = interp.layout(&ty)?;
+						size = size.next_multiple_of(falign);
+						parts.push((size, Some(ty)));
+						size += fsize;
+						align = align.max(falign);
+					}
+					interp.layouts.insert(id, Layout { size: size.next_multiple_of(align), align, parts });
+				},
+				// a u32 tag followed by the payload, as the compiled program has it
+				Node::Enum { id, variants, .. } => {
+					let (mut size, mut align) = (0, 4);
+					for ty in variants.iter().flatten() {
+						let (psize, palign) = interp.layout(ty)?;
+						size = size.max(psize);
+						align = align.max(palign);
+					}
+					let offset = 4usize.next_multiple_of(align);
+					interp.layouts.insert(id, Layout {
+						size: (offset + size).next_multiple_of(align), align,
+						parts: variants.into_iter().map(|ty| (offset, ty)).collect(),
+					});
+				},
+				Node::Assign { .. } | Node::Store { .. } | Node::StoreIndex { .. } | Node::StoreField { .. }
+					| Node::Ret(..) | Node::Trap(_) | Node::Label(_) | Node::Jump(_) | Node::Branch { .. }
+					| Node::FuncCall { .. } | Node::BinOp { .. } | Node::UnOp { .. } | Node::StructLit(_)
+					| Node::Field { .. } | Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. }
+					| Node::OptLit(_) | Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_)
+					| Node::Deref(_) | Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. }
+					| Node::Len(_) | Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. }
+					| Node::StrLit(_) | Node::Var(_)
+					=> unreachable!("only declarations are at the top level"),
+			}
+		}
+
+		for id in order {
+			let func = Rc::clone(&interp.funcs[&id]);
+			for node in &func.body {
+				match node {
+					Node::Assign { val, .. } if matches!(**val, Node::Global { .. }) => interp.global(val)?,
+					node @ Node::Global { .. } => interp.global(node)?,
+					_ => continue,
+				};
+			}
+		}
+
+		Ok(interp)
+	}
+
+	/// Runs the exported `main`, returning the exit code
+	pub fn run(&mut self) -> Result<i32> {
+		let main = self.sym.iter()
+			.find(|(id, name)| *name == "main" && self.funcs.contains_key(id))
+			.map(|(id, _)| *id)
+			.ok_or_else(|| ReportKind::UndefinedSym
+				.title("No entry point found")
+				.help("Add an exported 'main' function"))?;
+
+		#[allow(clippy::cast_possible_truncation)]
+		match self.call(&Value::Func(main), Vec::new()) {
+			Ok(Value::Int(code)) => Ok(code as i32),
+			Ok(_) => Ok(0),
+			Err(Stop::Exit(code)) => Ok(code),
+			Err(Stop::Trap(e)) => Err(e),
+		}
+	}
+
+	pub fn call(&mut self, func: &Value, args: Vec<Value>) -> std::result::Result<Value, Stop> {
+		let Value::Func(id) = *func else {
+			return Err(ReportKind::RuntimeError
+				.title(format!("Called a non function value {func:?}"))
+				.into());
+		};
+
+		if let Some(name) = self.externs.get(&id) {
+			let host = self.host.get(name).ok_or_else(|| ReportKind::UndefinedSym
+				.title(format!("Extern function '{name}' is not available in the interpreter"))
+				.note(format!("Provided functions are: {}", self.host.names().join(", "))))?;
+
+			return host(&mut self.mem, &args);
+		}
+
+		let func = Rc::clone(&self.funcs[&id]);
+		let mut locals = Frame {
+			vals:  func.args.iter().copied().zip(args).collect(),
+			slots: HashMap::new(),
+		};
+
+		let stack = self.mem.top();
+		let ret = self.exec_body(&func, &mut locals);
+		self.mem.release(stack);
+		ret
+	}
+
+	fn exec_body(&mut self, func: &Func, locals: &mut Frame) -> std::result::Result<Value, Stop> {
+		let mut pc = 0;
+		while let Some(stmt) = func.body.get(pc) {
+			pc += 1;
+			match self.exec(stmt, locals)? {
+				Flow::Next    => (),
+				Flow::Jump(l) => pc = func.labels[&l],
+				Flow::Ret(v)  => return Ok(v),
+			}
+		}
+
+		Ok(Value::Void)
+	}
+
+	/// Executes a single statement, yielding where to continue
+	fn exec(&mut self, node: &Node, locals: &mut Frame)
+	-> std::result::Result<Flow, Stop> {
+		match node {
+			Node::Assign { id, ty, val } => {
+				let v = match &**val {
+					Node::FuncCall { id, args } => self.exec_call(id, args, locals)?,
+					// a sized array in memory is copied out
+					Node::Var(Var::Glob(id)) if matches!(ty.base(), Type::Arr(_, Some(_))) => {
+						let Type::Arr(elem, Some(n)) = ty.base() else { unreachable!() };
+						let addr = self.globals.get(id).expect("use of undefined global").as_ptr()?;
+						let (size, _) = self.layout(elem)?;
+						#[allow(clippy::cast_possible_truncation)]
+						Value::Struct((0..*n as usize)
+							.map(|i| self.load(addr + i * size, elem))
+							.collect::<std::result::Result<_, Stop>>()?)
+					},
+					Node::Var(v) => self.eval(v, ty, locals)?,
+					node @ Node::Global { .. } => self.global(node)?,
+					Node::BinOp { op, ty, lhs, rhs } => self.binop(*op, ty, lhs, rhs, locals)?,
+					Node::StructLit(fields) => Value::Struct(fields.iter()
+						.map(|(v, ty)| self.eval(v, ty, locals).map(|v| wrap(v, ty)))
+						.collect::<std::result::Result<_, Stop>>()?),
+					Node::ArrLit(elems) => Value::Struct(elems.iter()
+						.map(|(v, ty)| self.eval(v, ty, locals).map(|v| wrap(v, ty)))
+						.collect::<std::result::Result<_, Stop>>()?),
+					Node::SliceLit { ptr, len } => Value::Struct(Rc::new([
+						self.eval(ptr, &Type::Usize, locals)?,
+						self.eval(len, &Type::Usize, locals)?,
+					])),
+					Node::Index { ty: aty, val, index } => {
+						let i = self.eval(index, &Type::Usize, locals)?.as_int()?;
+						match self.eval(val, aty, locals)? {
+							#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+							Value::Struct(elems) if matches!(aty, Type::Arr(_, Some(_))) => elems[i as usize].clone(),
+							arr => {
+								let addr = self.elem_addr(&arr, aty, i)?;
+								self.load(addr, ty)?
+							},
+						}
+					},
+					Node::Len(v) => match self.eval(v, &Type::Void, locals)? {
+						Value::Struct(fields) => fields[1].clone(),
+						v => return Err(ReportKind::RuntimeError
+							.title(format!("Expected a slice, found {v:?}"))
+							.into()),
+					},
+					Node::Decay { ty: aty, val: Var::Local(id) } if matches!(aty.base(), Type::Arr(_, Some(_)))
+						=> Value::Ptr(self.addr_of(*id, aty, locals)?),
+					Node::Decay { val, .. } => match self.eval(val, &Type::Void, locals)? {
+						Value::Struct(fields) => fields[0].clone(),
+						v => v,
+					},
+					Node::Field { val, index, deref: false, .. } => match self.eval(val, &Type::Void, locals)? {
+						Value::Struct(fields) => fields[*index].clone(),
+						v => return Err(ReportKind::RuntimeError
+							.title(format!("Expected a struct, found {v:?}"))
+							.into()),
+					},
+					Node::EnumLit { variant, val } => Value::Enum(*variant, Rc::new(match val {
+						Some((v, ty)) => wrap(self.eval(v, ty, locals)?, ty),
+						None => Value::Void,
+					})),
+					Node::Tag { val, .. } => match self.eval(val, &Type::Void, locals)? {
+						#[allow(clippy::cast_possible_wrap)]
+						Value::Enum(variant, _) => Value::Int(variant as i128),
+						v => return Err(ReportKind::RuntimeError
+							.title(format!("Expected an enum, found {v:?}"))
+							.into()),
+					},
+					Node::Payload { val, variant, .. } => match self.eval(val, &Type::Void, locals)? {
+						Value::Enum(v, payload) if v == *variant => (*payload).clone(),
+						v => return Err(ReportKind::RuntimeError
+							.title(format!("Expected variant {variant}, found {v:?}"))
+							.into()),
+					},
+					// `opt *T` is the pointer itself, with null as none
+					Node::OptLit(Some((v, pty))) if ty.has_niche() => self.eval(v, pty, locals)?,
+					Node::OptLit(None) if ty.has_niche() => Value::Ptr(0),
+					Node::OptLit(val) => match val {
+						Some((v, pty)) => Value::Enum(1, Rc::new(wrap(self.eval(v, pty, locals)?, pty))),
+						None => Value::Enum(0, Rc::new(Value::Void)),
+					},
+					Node::IsSome { ty: oty, val } => match self.eval(val, oty, locals)? {
+						#[allow(clippy::cast_possible_wrap)]
+						Value::Enum(tag, _) => Value::Int(tag as i128),
+						v => Value::Int((v.as_int()? != 0).into()),
+					},
+					Node::Unwrap { ty: oty, val } => match self.eval(val, oty, locals)? {
+						Value::Enum(_, payload) => (*payload).clone(),
+						v => v,
+					},
+					Node::Field { ty: sty, val, index, deref: true } => {
+						let addr = self.eval(val, &Type::Usize, locals)?.as_ptr()?;
+						self.load(addr + self.field_offset(sty, *index), ty)?
+					},
+					Node::AddrOf(Var::Glob(id)) => self.globals.get(id).expect("use of undefined global").clone(),
+					Node::AddrOf(Var::Local(id)) => {
+						let Type::Ptr(pointee) = ty.base() else { unreachable!() };
+						Value::Ptr(self.addr_of(*id, pointee, locals)?)
+					},
+					Node::Deref(v) => {
+						let addr = self.eval(v, &Type::Usize, locals)?.as_ptr()?;
+						self.load(addr, ty)?
+					},
+					Node::Offset { ty: pty, ptr, op, index: (i, ity) } => {
+						let Type::Ptr(pointee) = pty.base() else { unreachable!() };
+						let ptr = self.eval(ptr, pty, locals)?.as_ptr()?;
+						let offset = wrap(self.eval(i, ity, locals)?, ity).as_int()? * self.layout(pointee)?.0 as i128;
+
+						#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+						Value::Ptr(match op {
+							BinOp::Sub => (ptr as i128 - offset) as usize,
+							_ => (ptr as i128 + offset) as usize,
+						})
+					},
+					Node::Diff { ty: pty, lhs, rhs } => {
+						let Type::Ptr(pointee) = pty.base() else { unreachable!() };
+						let (lhs, rhs) = (self.eval(lhs, pty, locals)?.as_ptr()?, self.eval(rhs, pty, locals)?.as_ptr()?);
+						Value::Int((lhs as i128 - rhs as i128) / self.layout(pointee)?.0 as i128)
+					},
+					// ints are truncated to the new width once the value is stored
+					#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+					Node::Cast { ty: from, val } => match (wrap(self.eval(val, from, locals)?, from), ty.base()) {
+						(Value::Float(f), to) if is_float(to) => Value::Float(f),
+						(Value::Float(f), _) => Value::Int(f as i128),
+						(v, to) if is_float(to) => Value::Float(v.as_int()? as f64),
+						(v, Type::Ptr(_)) => Value::Ptr(v.as_ptr()?),
+						(v, _) => Value::Int(v.as_int()?),
+					},
+					Node::UnOp { op, ty, val } => match (op, wrap(self.eval(val, ty, locals)?, ty)) {
+						(UnOp::Neg, Value::Float(f)) => Value::Float(-f),
+						(UnOp::Neg, v)    => Value::Int(v.as_int()?.wrapping_neg()),
+						(UnOp::Not, v)    => Value::Int(v.as_int()? ^ 1),
+						(UnOp::BitNot, v) => Value::Int(!v.as_int()?),
+					},
+					_ => unreachable!(),
+				};
+
+				self.set(*id, wrap(v, ty), locals)?;
+			},
+			node @ Node::Global { .. } => { self.global(node)?; },
+			Node::Store { to: Var::Local(id), from: (v, ty), deref: false } => {
+				let v = wrap(self.eval(v, ty, locals)?, ty);
+				self.set(*id, v, locals)?;
+			},
+			Node::StoreIndex { to: (to, aty), index, from: (v, ty) } => {
+				let i = self.eval(index, &Type::Usize, locals)?.as_int()?;
+				let v = wrap(self.eval(v, ty, locals)?, ty);
+
+				match (to, self.eval(to, aty, locals)?) {
+					#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+					(Var::Local(id), Value::Struct(elems)) if matches!(aty.base(), Type::Arr(_, Some(_))) => {
+						let mut elems = elems.to_vec();
+						elems[i as usize] = v;
+						self.set(*id, Value::Struct(elems.into()), locals)?;
+					},
+					(_, arr) => {
+						let addr = self.elem_addr(&arr, aty, i)?;
+						self.store(addr, v, ty)?;
+					},
+				}
+			},
+			Node::StoreField { to: (Var::Local(id), sty), index, from: (v, ty), deref: false } => {
+				let v = wrap(self.eval(v, ty, locals)?, ty);
+
+				match self.eval(&Var::Local(*id), sty, locals)? {
+					Value::Struct(fields) => {
+						let mut fields = fields.to_vec();
+						fields[*index] = v;
+						self.set(*id, Value::Struct(fields.into()), locals)?;
+					},
+					v => return Err(ReportKind::RuntimeError
+						.title(format!("Expected a struct, found {v:?}"))
+						.into()),
+				}
+			},
+			Node::StoreField { to: (to, sty), index, from: (v, ty), deref: true } => {
+				let addr = self.eval(to, &Type::Usize, locals)?.as_ptr()?;
+				let v = self.eval(v, ty, locals)?;
+				self.store(addr + self.field_offset(sty, *index), v, ty)?;
+			},
+			Node::Store { deref: false, .. } | Node::StoreField { deref: false, .. }
+				=> unreachable!("only locals are stored to without a pointer"),
+			Node::Store { to, from: (v, ty), deref: true } => {
+				let addr = self.eval(to, &Type::Usize, locals)?.as_ptr()?;
+				let v = self.eval(v, ty, locals)?;
+				self.store(addr, v, ty)?;
+			},
+			Node::Ret(None, _) => return Ok(Flow::Ret(Value::Void)),
+			Node::Ret(Some(v), ty) => return Ok(Flow::Ret(wrap(self.eval(v, ty, locals)?, ty))),
+			Node::FuncCall { id, args } => { self.exec_call(id, args, locals)?; },
+			Node::Label(_) => (),
+			Node::Jump(l)  => return Ok(Flow::Jump(*l)),
+			Node::Trap(why) => return Err(ReportKind::RuntimeError.title(why).into()),
+			Node::Branch { cond: (v, ty), then, els } => 
+				return Ok(Flow::Jump(match wrap(self.eval(v, ty, locals)?, ty).as_int()? {
+					0 => *els,
+					_ => *then,
+				})),
+			Node::Func { .. } | Node::FuncDecl { .. } | Node::Struct { .. } | Node::Enum { .. }
+				=> unreachable!("declarations are not nested in functions"),
+			Node::BinOp { .. } | Node::UnOp { .. } | Node::StructLit(_) | Node::Field { .. }
+				| Node::EnumLit { .. } | Node::Tag { .. } | Node::Payload { .. } | Node::OptLit(_)
+				| Node::IsSome { .. } | Node::Unwrap { .. } | Node::AddrOf(_) | Node::Deref(_)
+				| Node::Offset { .. } | Node::Diff { .. } | Node::ArrLit(_) | Node::Index { .. } | Node::Len(_)
+				| Node::Decay { .. } | Node::SliceLit { .. } | Node::Cast { .. } | Node::StrLit(_)
+				| Node::Var(_)
+				=> unreachable!("only the value of an Assign"),
+		}
+
+		Ok(Flow::Next)
+	}
+
+	fn exec_call(&mut self, func: &Var, args: &[(Var, Type)], locals: &Frame)
+	-> std::result::Result<Value, Stop> {
+		let func = self.eval(func, &Type::Void, locals)?;
+		let args = args.iter()
+			.map(|(v, ty)| self.eval(v, ty, locals).map(|v| wrap(v, ty)))
+			.collect::<std::result::Result<Vec<_>, Stop>>()?;
+
+		self.call(&func, args)
+	}
+
+	/// Operands are wrapped to their type first, so signedness is already encoded in the value
+	fn binop(&self, op: BinOp, ty: &Type, lhs: &Var, rhs: &Var, locals: &Frame)
+	-> std::result::Result<Value, Stop> {
+		let l = wrap(self.eval(lhs, ty, locals)?, ty);
+		let r = wrap(self.eval(rhs, ty, locals)?, ty);
+
+		if let (&Value::Float(l), &Value::Float(r)) = (&l, &r) {
+			#[allow(clippy::float_cmp)]
+			return Ok(match op {
+				BinOp::Add => Value::Float(l + r),
+				BinOp::Sub => Value::Float(l - r),
+				BinOp::Mul => Value::Float(l * r),
+				BinOp::Div => Value::Float(l / r),
+				BinOp::Rem => Value::Float(l % r),
+				BinOp::Eq  => Value::Int((l == r).into()),
+				BinOp::Ne  => Value::Int((l != r).into()),
+				BinOp::Lt  => Value::Int((l < r).into()),
+				BinOp::Le  => Value::Int((l <= r).into()),
+				BinOp::Gt  => Value::Int((l > r).into()),
+				BinOp::Ge  => Value::Int((l >= r).into()),
+				_ => unreachable!(),
+			});
+		}
+
+		let (l, r) = (l.as_int()?, r.as_int()?);
+		let bits = size_of(ty).map_or(64, |s| s * 8);
+
+		Ok(Value::Int(match op {
+			BinOp::Div | BinOp::Rem if r == 0 => return Err(ReportKind::RuntimeError
+				.title("Division by zero")
+				.into()),
+			BinOp::Shl | BinOp::Shr if !(0..bits as i128).contains(&r) => return Err(ReportKind::RuntimeError
+				.title(format!("Shift by {r} is out of range for type '{ty}'"))
+				.into()),
+			BinOp::Add => l.wrapping_add(r),
+			BinOp::Sub => l.wrapping_sub(r),
+			BinOp::Mul => l.wrapping_mul(r),
+			BinOp::Div => l.wrapping_div(r),
+			BinOp::Rem => l.wrapping_rem(r),
+			BinOp::Shl => l << r,
+			BinOp::Shr => l >> r,
+			BinOp::BitAnd | BinOp::And => l & r,
+			BinOp::BitOr  | BinOp::Or  => l | r,
+			BinOp::BitXor | BinOp::Xor => l ^ r,
+			BinOp::Eq => (l == r).into(),
+			BinOp::Ne => (l != r).into(),
+			BinOp::Lt => (l < r).into(),
+			BinOp::Le => (l <= r).into(),
+			BinOp::Gt => (l > r).into(),
+			BinOp::Ge => (l >= r).into(),
+		}))
+	}
+
+	/// Lays a global out in memory the first time it is reached, yielding its address
+	fn global(&mut self, node: &Node) -> Result<Value> {
+		let Node::Global { id, ty, val } = node
+			else { unreachable!() };
+
+		if let Some(v) = self.globals.get(id) { return Ok(v.clone()); }
+
+		let bytes = match &**val {
+			Node::StrLit(s) => s.as_bytes().to_vec(),
+			Node::Var(v) => self.encode(self.constant(v, ty)?, ty)?,
+			_ => unreachable!(),
+		};
+
+		let v = Value::Ptr(self.mem.alloc(&bytes));
+		self.globals.insert(*id, v.clone());
+		Ok(v)
+	}
+
+	fn eval(&self, v: &Var, ty: &Type, locals: &Frame) -> std::result::Result<Value, Stop> {
+		match v {
+			Var::Local(id) => match locals.slots.get(id) {
+				Some((addr, ty)) => self.load(*addr, ty),
+				None => Ok(locals.vals.get(id).expect("use of undefined local").clone()),
+			},
+			v => Ok(self.constant(v, ty)?),
+		}
+	}
+
+	fn constant(&self, v: &Var, ty: &Type) -> Result<Value> {
+		Ok(match v {
+			Var::Imm(i) => {
+				let v = i.to_i128().ok_or_else(|| ReportKind::RuntimeError
+					.title(format!("Integer literal {i} is too large for type '{ty}'")))?;
+
+				#[allow(clippy::cast_precision_loss)]
+				match is_float(ty) {
+					true  => Value::Float(v as f64),
+					false => Value::Int(v),
+				}
+			},
+			Var::FImm(v)   => Value::Float(*v),
+			Var::Local(_)  => unreachable!("locals are only known while running"),
+			Var::Glob(id)  => self.globals.get(id).expect("use of undefined global").clone(),
+		})
+	}
+
+	fn set(&mut self, id: ValId, v: Value, locals: &mut Frame) -> std::result::Result<(), Stop> {
+		match locals.slots.get(&id) {
+			Some((addr, ty)) => self.store(*addr, v, ty),
+			None => { locals.vals.insert(id, v); Ok(()) },
+		}
+	}
+
+	/// Moves a local into memory the first time its address is taken, it is read and written there from then on
+	fn addr_of(&mut self, id: ValId, ty: &Type, locals: &mut Frame) -> std::result::Result<usize, Stop> {
+		if let Some((addr, _)) = locals.slots.get(&id) { return Ok(*addr); }
+
+		let bytes = match locals.vals.remove(&id) {
+			Some(v) => self.encode(v, ty)?,
+			None => vec![0; self.layout(ty)?.0],
+		};
+
+		let addr = self.mem.alloc(&bytes);
+		locals.slots.insert(id, (addr, ty.clone()));
+		Ok(addr)
+	}
+
+	fn load(&self, addr: usize, ty: &Type) -> std::result::Result<Value, Stop> {
+		let (size, _) = self.layout(ty)?;
+		Ok(self.decode(self.mem.read(addr, size)?, ty))
+	}
+
+	fn store(&mut self, addr: usize, v: Value, ty: &Type) -> std::result::Result<(), Stop> {
+		let bytes = self.encode(v, ty)?;
+		self.mem.write(addr, &bytes)
+	}
+
+	/// Address of element `i` of an array in memory, or of what a slice points to
+	fn elem_addr(&self, arr: &Value, ty: &Type, i: i128) -> std::result::Result<usize, Stop> {
+		let Type::Arr(elem, _) = ty.base() else { unreachable!() };
+		let base = match arr {
+			Value::Struct(fields) => fields[0].as_ptr()?,
+			v => v.as_ptr()?,
+		};
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		Ok(base + i as usize * self.layout(elem)?.0)
+	}
+
+	fn field_offset(&self, ty: &Type, index: usize) -> usize {
+		let Type::Struct(id, _) = ty.base() else { unreachable!() };
+		self.layouts[id].parts[index].0
+	}
+
+	/// Size and alignment in bytes of a value of type `ty` in memory, laid out the way llvm does
+	fn layout(&self, ty: &Type) -> Result<(usize, usize)> {
+		Ok(match ty.base() {
+			// an u8 tag in front of the value
+			Type::Opt(inner) if !ty.has_niche() => {
+				let (size, align) = self.layout(inner)?;
+				((align + size).next_multiple_of(align), align)
+			},
+			#[allow(clippy::cast_possible_truncation)]
+			Type::Arr(elem, Some(n)) => {
+				let (size, align) = self.layout(elem)?;
+				(size * *n as usize, align)
+			},
+			Type::Arr(_, None) => (16, 8),
+			Type::Struct(id, _) | Type::Enum(id, _) => (self.layouts[id].size, self.layouts[id].align),
+			ty => match size_of(ty) {
+				Some(size) => (size, size.min(16)),
+				None => return ReportKind::RuntimeError
+					.title(format!("Cannot store a value of type '{ty}' in memory"))
+					.as_err(),
+			},
+		})
+	}
+
+	/// The bytes of `v` as a value of type `ty` in memory
+	fn encode(&self, v: Value, ty: &Type) -> Result<Vec<u8>> {
+		let mut bytes = vec![0; self.layout(ty)?.0];
+		self.encode_into(v, ty, &mut bytes)?;
+		Ok(bytes)
+	}
+
+	fn encode_into(&self, v: Value, ty: &Type, out: &mut [u8]) -> Result<()> {
+		match (wrap(v, ty), ty.base()) {
+			(Value::Struct(fields), Type::Struct(id, _)) => for (v, (offset, ty)) in fields.iter().zip(&self.layouts[id].parts) {
+				self.encode_into(v.clone(), ty.as_ref().unwrap(), &mut out[*offset..])?;
+			},
+			(Value::Struct(elems), Type::Arr(elem, Some(_))) => {
+				let (size, _) = self.layout(elem)?;
+				for (i, v) in elems.iter().enumerate() {
+					self.encode_into(v.clone(), elem, &mut out[i * size..])?;
+				}
+			},
+			// a slice is its pointer and length
+			(Value::Struct(fields), Type::Arr(_, None)) => {
+				self.encode_into(fields[0].clone(), &Type::Usize, out)?;
+				self.encode_into(fields[1].clone(), &Type::Usize, &mut out[8..])?;
+			},
+			#[allow(clippy::cast_possible_truncation)]
+			(Value::Enum(tag, payload), Type::Enum(id, _)) => {
+				out[..4].copy_from_slice(&(tag as u32).to_le_bytes());
+				if let (offset, Some(ty)) = &self.layouts[id].parts[tag] {
+					self.encode_into((*payload).clone(), ty, &mut out[*offset..])?;
+				}
+			},
+			#[allow(clippy::cast_possible_truncation)]
+			(Value::Enum(tag, payload), Type::Opt(inner)) => {
+				out[0] = tag as u8;
+				if tag == 1 {
+					let (_, align) = self.layout(inner)?;
+					self.encode_into((*payload).clone(), inner, &mut out[align..])?;
+				}
+			},
+			(v, ty) => {
+				let size = self.layout(ty)?.0;
+				let bytes = match v {
+					#[allow(clippy::cast_possible_truncation)]
+					Value::Float(f) if size == 4 => (f as f32).to_le_bytes().to_vec(),
+					Value::Float(f) => f.to_le_bytes().to_vec(),
+					v => v.as_int().map_err(|_| ReportKind::RuntimeError
+						.title("Function values cannot be stored in memory yet"))?
+						.to_le_bytes()[..size].to_vec(),
+				};
+				out[..size].copy_from_slice(&bytes);
+			},
+		}
+
+		Ok(())
+	}
+
+	/// Reads back a value of type `ty` written by `encode`
+	fn decode(&self, bytes: &[u8], ty: &Type) -> Value {
+		match ty.base() {
+			Type::Struct(id, _) => Value::Struct(self.layouts[id].parts.iter()
+				.map(|(offset, ty)| self.decode(&bytes[*offset..], ty.as_ref().unwrap()))
+				.collect()),
+			Type::Arr(elem, Some(n)) => {
+				let size = self.layout(elem).map_or(0, |(size, _)| size);
+				#[allow(clippy::cast_possible_truncation)]
+				Value::Struct((0..*n as usize).map(|i| self.decode(&bytes[i * size..], elem)).collect())
+			},
+			Type::Arr(_, None) => Value::Struct(Rc::new([
+				self.decode(bytes, &Type::Ptr(Box::new(Type::Void))),
+				self.decode(&bytes[8..], &Type::Usize),
+			])),
+			Type::Enum(id, _) => {
+				let tag = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+				Value::Enum(tag, Rc::new(match &self.layouts[id].parts[tag] {
+					(offset, Some(ty)) => self.decode(&bytes[*offset..], ty),
+					(_, None) => Value::Void,
+				}))
+			},
+			Type::Opt(inner) if !ty.has_niche() => {
+				let align = self.layout(inner).map_or(1, |(_, align)| align);
+				Value::Enum(bytes[0].into(), Rc::new(match bytes[0] {
+					0 => Value::Void,
+					_ => self.decode(&bytes[align..], inner),
+				}))
+			},
+			_ => decode(&bytes[..size_of(ty).unwrap_or(bytes.len())], ty),
+		}
+	}
+}
+
+fn is_float(ty: &Type) -> bool {
+	match ty {
+		Type::Mut(ty) | Type::Opt(ty) => is_float(ty),
+		Type::F(_) | Type::Pfloat => true,
+		_ => false,
+	}
+}
+
+/// Size in bytes of a value of type `ty` stored in memory
+fn size_of(ty: &Type) -> Option<usize> {
+	Some(match ty {
+		Type::F(32) => 4,
+		Type::U(i) | Type::I(i) | Type::B(i) if *i <= 128
+			=> i.next_power_of_two().max(8) as usize / 8,
+		Type::F(64) | Type::Usize | Type::Isize | Type::Ptr(_) | Type::Fn(_, _) => 8,
+		Type::Opt(_) if ty.has_niche() => 8,
+		Type::Mut(ty) => return size_of(ty),
+		_ => return None,
+	})
+}
+
+/// Reads back a scalar written by `encode`
+fn decode(bytes: &[u8], ty: &Type) -> Value {
+	if is_float(ty) {
+		return match bytes.len() {
+			4 => Value::Float(f64::from(f32::from_le_bytes(bytes.try_into().unwrap()))),
+			_ => Value::Float(f64::from_le_bytes(bytes.try_into().unwrap())),
+		};
+	}
+
+	let mut buf = [0; 16];
+	buf[..bytes.len()].copy_from_slice(bytes);
+	let v = Value::Int(i128::from_le_bytes(buf));
+
+	match ty.base() {
+		#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+		Type::Ptr(_) | Type::Opt(_) => Value::Ptr(i128::from_le_bytes(buf) as usize),
+		_ => wrap(v, ty),
+	}
+}
+
+/// Truncates integers to the width of their type, the way the compiled program would
+fn wrap(v: Value, ty: &Type) -> Value {
+	let (bits, signed) = match (&v, ty) {
+		(_, Type::Mut(ty) | Type::Opt(ty)) => return wrap(v, ty),
+		#[allow(clippy::cast_possible_truncation)]
+		(Value::Float(f), Type::F(32)) => return Value::Float(f64::from(*f as f32)),
+		(Value::Int(_), Type::U(n) | Type::B(n)) => (*n, false),
+		(Value::Int(_), Type::I(n)) => (*n, true),
+		(Value::Int(_), Type::Usize) => (64, false),
+		(Value::Int(_), Type::Isize) => (64, true),
+		_ => return v,
+	};
+
+	let Value::Int(i) = v else { unreachable!() };
+	if bits == 0 || bits >= 128 { return v; }
+
+	let shift = 128 - bits;
+	Value::Int(match signed {
+		true  => (i << shift) >> shift,
+		false => i & ((1 << bits) - 1),
+	})
+}
@@ -94,7 +94,9 @@ impl<'src> Lexer<'src> {
 					_ => lex.push_token_simple(TokenKind::Slash, 1),
 				},
 
-				c if c.chars().any(|c| c.is_ascii_alphabetic()) => {
+				// a lone '_' is a token of its own, '_x' is a name
+				c if c.chars().any(|c| c.is_ascii_alphabetic())
+					|| (c == "_" && lex.peek().is_some_and(|c| c.chars().any(|c| c.is_ascii_alphanumeric() || c == '_'))) => {
 					while let Some(c) = lex.peek() {
 						if c.chars().any(|c| c.is_ascii_alphanumeric() || c == '_') {
 							lex.next();
@@ -113,8 +115,19 @@ impl<'src> Lexer<'src> {
 						"struct" => TokenKind::KWStruct,
 						"enum"   => TokenKind::KWEnum,
 						"impl"   => TokenKind::KWImpl,
+						"import" => TokenKind::KWImport,
+						"pub"    => TokenKind::KWPub,
 						"type"   => TokenKind::KWType,
 						"extern" => TokenKind::KWExtern,
+						"if"     => TokenKind::KWIf,
+						"else"   => TokenKind::KWElse,
+						"while"  => TokenKind::KWWhile,
+						"loop"   => TokenKind::KWLoop,
+						"match"  => TokenKind::KWMatch,
+						"none"   => TokenKind::KWNone,
+						"as"     => TokenKind::KWAs,
+						"break"  => TokenKind::KWBreak,
+						"continue" => TokenKind::KWContinue,
 						_ => TokenKind::Identifier,
 					};
 
@@ -134,6 +147,7 @@ impl<'src> Lexer<'src> {
 					loop { 
 						match lex.next() {
 							Some("\"") => break,
+							Some("\\") => { lex.next(); end = lex.index; },
 							None => {
 								lex.log(
 									ReportKind::UnterminatedLiteral
@@ -165,7 +179,7 @@ impl<'src> Lexer<'src> {
 						continue;
 					}
 
-					let mut end = start;
+					let end;
 					match lex.next().unwrap() {
 						"\\" => {
 							if matches!(lex.next(), Some("'")) && !matches!(lex.peek(), Some("'")) {
@@ -178,7 +192,13 @@ impl<'src> Lexer<'src> {
 								continue;
 							}
 
-							lex.next();
+							end = lex.index;
+							if !matches!(lex.next(), Some("'")) {
+								lex.log(
+									ReportKind::UnterminatedLiteral
+										.untitled().span(lex.span_from(index)));
+								continue;
+							}
 						},
 
 						"\n" => {
@@ -205,7 +225,7 @@ impl<'src> Lexer<'src> {
 					lex.push_token(TokenKind::CharLiteral, start, end);
 				},
 
-				"0" if lex.peek().filter(|c| "box".contains(c)).is_some() => {
+				"0" if lex.peek().is_some_and(|c| "box".contains(c)) => {
 					let (kind, base) = match lex.peek() {
 						Some("b") => (TokenKind::BinaryIntLiteral, 2),
 						Some("o") => (TokenKind::OctalIntLiteral, 8),
@@ -214,11 +234,9 @@ impl<'src> Lexer<'src> {
 					};
 
 					lex.next();
-					let start = lex.index;
-
 					if !lex.lex_integer(base) { continue; }
 
-					lex.push_token(kind, start, lex.index);
+					lex.push_token(kind, index, lex.index);
 				},
 
 				c if c.chars().any(|c| c.is_ascii_digit()) => {
@@ -333,9 +351,9 @@ impl<'src> Lexer<'src> {
 			['0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f'];
 
 		let f = match base {
-			2  => |c| CHARS[..1].contains(&c),
-			8  => |c| CHARS[..7].contains(&c),
-			10 => |c| CHARS[..9].contains(&c),
+			2  => |c| CHARS[..2].contains(&c),
+			8  => |c| CHARS[..8].contains(&c),
+			10 => |c| CHARS[..10].contains(&c),
 			16 => |c| CHARS.contains(&c),
 			_  => unreachable!(),
 		};
@@ -8,15 +8,26 @@ use crate::span::Span;
 pub enum TokenKind {
 	Identifier,
 
+	KWAs,
+	KWBreak,
+	KWContinue,
+	KWElse,
 	KWEnum,
 	KWExport,
 	KWExtern,
 	KWFn,
+	KWIf,
 	KWImpl,
+	KWImport,
 	KWLet,
+	KWLoop,
+	KWMatch,
+	KWNone,
+	KWPub,
 	KWRet,
 	KWStruct,
 	KWType,
+	KWWhile,
 
 	FloatLiteral,
 
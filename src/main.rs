@@ -15,9 +15,11 @@ mod lexer;
 mod parser;
 mod analyzer;
 mod codegen;
+mod interp;
 mod report;
 mod bigint;
 mod fs;
+mod module;
 mod span;
 
 fn main() {
@@ -27,41 +29,50 @@ fn main() {
 	let handler = report::LogHandler::new();
 
 
-	if args.debug { eprintln!("\n{}", "LEXER".bold()); }
-	let tokens = lexer::Lexer::tokenize(args.file, fs::CACHE.get(args.file), handler.clone());
-	if args.debug { tokens.iter().for_each(|token| eprintln!("{token:#}")); }
-
-	if report::ERR_COUNT.load(Ordering::Relaxed) > 0 {
-		std::process::exit(1);
-	}
-
-
 	if args.debug { eprintln!("\n{}", "PARSER".bold()); }
-	let ast = parser::Parser::parse(tokens, args.file, handler.clone());
-	if args.debug { ast.iter().for_each(|n| eprintln!("{n:#}")); }
+	let modules = module::load(args.file, &args.include, args.debug, &handler);
+	if args.debug { modules.iter().flat_map(|m| &m.ast).for_each(|n| eprintln!("{n:#}")); }
 
 	if report::ERR_COUNT.load(Ordering::Relaxed) > 0 {
+		handler.terminate();
 		std::process::exit(1);
 	}
 
 
 	if args.debug { eprintln!("\n{}", "ANALYSIS".bold()); }
-	let (mir, sym) = analyzer::Analyzer::analyze(ast, args.file, &handler);
+	let (mir, sym) = analyzer::Analyzer::analyze(modules, args.bounds_checks, &handler);
 	if args.debug {
 		sym.iter().map(|(k,v)| (k.0, v)).for_each(|(k,v)| eprintln!("{k}: \"{v}\""));
 		mir.iter().for_each(|n| eprintln!("{n:#}")); 
 	}
 
 	if report::ERR_COUNT.load(Ordering::Relaxed) > 0 {
+		handler.terminate();
 		std::process::exit(1);
 	}
 
 
+	if args.verbs == ["run"] {
+		if args.debug { eprintln!("\n{}", "RUN".bold()); }
+		let host = interp::Host::default();
+		let code = interp::Interp::new(sym, mir, &host)
+			.and_then(|mut i| i.run())
+			.unwrap_or_else(|e| {
+				handler.log(e.file(args.file));
+				1
+			});
+
+		handler.terminate();
+		std::process::exit(code);
+	}
+
+
 	if args.debug { eprintln!("\n{}", "CODEGEN".bold()); }
-	let code = codegen::Gen::codegen(args.file, sym, mir, &handler);
+	let code = codegen::codegen(args.target, args.file, sym, mir, &handler);
 	if args.debug { eprintln!("{code}"); }
 
 	if report::ERR_COUNT.load(Ordering::Relaxed) > 0 {
+		handler.terminate();
 		std::process::exit(1);
 	}
 
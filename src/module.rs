@@ -0,0 +1,369 @@
+//! Modules, one per file. `import a.b;` loads `a/b.shd`, looked up next to the importing file and then in the
+//! include paths, and binds it as `b`. Every global of a module is renamed to `path.name` before analysis, so the
+//! analyzer sees one namespace where `b.f` is the `f` of `a/b.shd`. Only what is `pub` can be named from outside
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+use crate::report::{self, Report, ReportKind, LogHandler};
+use crate::span::{Sp, Span, Spannable};
+use crate::parser::ast;
+
+pub struct Module<'src> {
+	pub name: &'static str, // what its globals are qualified with, empty for the root
+	pub file: &'static str,
+	pub ast:  Vec<Sp<ast::Node<'src>>>,
+}
+
+/// Loads `root` and everything it imports, each module once however often it is imported
+pub fn load(root: &'static str, include: &[&'static str], debug: bool, handler: &LogHandler) -> Vec<Module<'static>> {
+	let mut modules = vec![Module { name: "", file: root, ast: parse(root, debug, handler) }];
+	let mut loaded = HashMap::from([(canonical(root), 0)]);
+	let mut imports = Vec::new();
+
+	let mut i = 0;
+	while i < modules.len() {
+		let paths: Vec<Vec<Sp<&str>>> = modules[i].ast.iter()
+			.filter_map(|n| match &n.elem {
+				ast::Node::Import(path) => Some(path.clone()),
+				_ => None,
+			})
+			.collect();
+
+		let mut bound: HashMap<&'static str, (usize, Span)> = HashMap::new();
+		for path in paths {
+			let last = path.last().unwrap();
+			let span = path[0].span.extend(&last.span);
+
+			if bound.contains_key(last.elem) {
+				handler.log(ReportKind::TypeError
+					.title(format!("'{}' is already imported", **last))
+					.help("Only one module can be bound to a name")
+					.span(span)
+					.file(modules[i].file));
+				continue;
+			}
+
+			let Some((file, name)) = resolve(&modules[i], &path, include) else {
+				let rel = path.iter().map(|p| p.elem).collect::<Vec<_>>().join("/") + ".shd";
+				handler.log(ReportKind::UndefinedSym
+					.title(format!("Module '{}' not found", path.iter().map(|p| p.elem).collect::<Vec<_>>().join(".")))
+					.note(format!("Looked for '{rel}' next to this file{}", match include.is_empty() {
+						true  => String::new(),
+						false => format!(" and in '{}'", include.join("', '")),
+					}))
+					.span(span)
+					.file(modules[i].file));
+				continue;
+			};
+
+			let index = *loaded.entry(canonical(file)).or_insert_with(|| {
+				let mut name = name;
+				while modules.iter().any(|m| m.name == name) {
+					name = leak(format!("{name}_"));
+				}
+
+				modules.push(Module { name, file, ast: parse(file, debug, handler) });
+				modules.len() - 1
+			});
+			bound.insert(last.elem, (index, span));
+		}
+
+		imports.push(bound);
+		i += 1;
+	}
+
+	if report::ERR_COUNT.load(Ordering::Relaxed) > 0 { return modules; }
+
+	let globals: Vec<HashMap<&str, bool>> = modules.iter().map(|m| declared(&m.ast)).collect();
+	let names: Vec<&'static str> = modules.iter().map(|m| m.name).collect();
+
+	for (i, module) in modules.iter_mut().enumerate() {
+		let mut q = Qualify {
+			module:   i,
+			globals:  &globals,
+			names:    &names,
+			imports:  &imports[i],
+			locals:   Vec::new(),
+			params:   Vec::new(),
+			cache:    HashMap::new(),
+			used:     HashSet::new(),
+			reports:  Vec::new(),
+		};
+
+		module.ast.retain(|n| !matches!(n.elem, ast::Node::Import(_)));
+		module.ast.iter_mut().for_each(|n| q.root(n));
+
+		let mut unused: Vec<_> = imports[i].iter().filter(|(name, _)| !q.used.contains(*name)).collect();
+		unused.sort_by_key(|(_, (_, span))| span.start);
+		for (name, (_, span)) in unused {
+			q.reports.push(ReportKind::UnusedImport
+				.title(format!("Module '{name}' is imported but never used"))
+				.label("nothing from it is named")
+				.help("Remove the import")
+				.span(*span));
+		}
+		q.reports.into_iter().for_each(|e| handler.log(e.file(module.file)));
+	}
+
+	modules
+}
+
+fn parse(file: &'static str, debug: bool, handler: &LogHandler) -> Vec<Sp<ast::Node<'static>>> {
+	let errors = report::ERR_COUNT.load(Ordering::Relaxed);
+	let tokens = crate::lexer::Lexer::tokenize(file, crate::fs::CACHE.get(file), handler.clone());
+	if debug { tokens.iter().for_each(|token| eprintln!("{token:#}")); }
+
+	// parsing what failed to lex only repeats the errors
+	if report::ERR_COUNT.load(Ordering::Relaxed) > errors { return Vec::new(); }
+	crate::parser::Parser::parse(tokens, file, handler.clone())
+}
+
+/// The file `path` names when imported from `from`, and the name of the module in it
+fn resolve(from: &Module<'static>, path: &[Sp<&str>], include: &[&'static str]) -> Option<(&'static str, &'static str)> {
+	let rel: PathBuf = path.iter().map(|p| p.elem).collect::<PathBuf>().with_extension("shd");
+	let name = path.iter().map(|p| p.elem).collect::<Vec<_>>().join(".");
+
+	// next to the importing file it is nested in its module, in an include path it stands on its own
+	let local = Path::new(from.file).parent().unwrap_or_else(|| Path::new("")).join(&rel);
+	if local.is_file() {
+		let parent = from.name.rsplit_once('.').map_or("", |(p, _)| p);
+		let name = match parent.is_empty() {
+			true  => name,
+			false => format!("{parent}.{name}"),
+		};
+		return Some((leak(local.to_string_lossy().into_owned()), leak(name)));
+	}
+
+	include.iter()
+		.map(|dir| Path::new(dir).join(&rel))
+		.find(|p| p.is_file())
+		.map(|p| (leak(p.to_string_lossy().into_owned()), leak(name)))
+}
+
+fn canonical(file: &str) -> PathBuf {
+	std::fs::canonicalize(file).unwrap_or_else(|_| PathBuf::from(file))
+}
+
+fn leak(s: String) -> &'static str {
+	Box::leak(s.into_boxed_str())
+}
+
+/// The globals `ast` declares, and whether they are `pub`
+fn declared<'src>(ast: &[Sp<ast::Node<'src>>]) -> HashMap<&'src str, bool> {
+	let mut globals = HashMap::new();
+	for node in ast {
+		let (ast::Node::Func { name, attrs, .. } | ast::Node::Struct { name, attrs, .. }
+			| ast::Node::Enum { name, attrs, .. } | ast::Node::Alias { name, attrs, .. }) = &node.elem else { continue };
+
+		let public = attrs.iter().any(|a| matches!(**a, ast::Attrs::Pub));
+		*globals.entry(name.elem).or_default() |= public;
+	}
+	globals
+}
+
+/// Renames the globals of one module and what refers to them, including those of the modules it imports
+struct Qualify<'a> {
+	module:  usize,
+	globals: &'a [HashMap<&'static str, bool>],
+	names:   &'a [&'static str],
+	imports: &'a HashMap<&'static str, (usize, Span)>,
+	locals:  Vec<&'static str>, // they hide globals and imports of the same name
+	params:  Vec<&'static str>, // the type params of the generic being walked
+	cache:   HashMap<(usize, &'static str), &'static str>,
+	used:    HashSet<&'static str>, // the imports named so far
+	reports: Vec<Report>, // errors and warnings, logged once the module is walked
+}
+
+impl Qualify<'_> {
+	fn qualified(&mut self, module: usize, name: &'static str) -> &'static str {
+		if self.names[module].is_empty() { return name; }
+
+		let prefix = self.names[module];
+		self.cache.entry((module, name)).or_insert_with(|| leak(format!("{prefix}.{name}")))
+	}
+
+	/// A global named where only a global can be, either one of this module or `m.name` of an import
+	fn global(&mut self, name: &Sp<&'static str>) -> &'static str {
+		if let Some((module, member)) = name.elem.split_once('.') {
+			return match self.imports.get_key_value(module) {
+				Some((&module, &(m, _))) => self.member(m, module, member, name),
+				None => name.elem,
+			};
+		}
+
+		match self.globals[self.module].contains_key(name.elem) {
+			true  => self.qualified(self.module, name.elem),
+			false => name.elem,
+		}
+	}
+
+	/// `member` of the module `m`, bound as `module`
+	fn member(&mut self, m: usize, module: &'static str, member: &'static str, name: &Sp<&'static str>) -> &'static str {
+		self.used.insert(module);
+		match self.globals[m].get(member) {
+			Some(&public) if public || m == self.module => self.qualified(m, member),
+			Some(_) => {
+				self.reports.push(ReportKind::PrivateSym
+					.title(format!("'{member}' is private to module '{module}'"))
+					.help("Mark it 'pub' in the module that declares it")
+					.span(name.span));
+				name.elem
+			},
+			None => {
+				self.reports.push(ReportKind::UndefinedSym
+					.title(format!("Module '{module}' has no '{member}'"))
+					.span(name.span));
+				name.elem
+			},
+		}
+	}
+
+	fn root(&mut self, node: &mut Sp<ast::Node<'static>>) {
+		match &mut node.elem {
+			ast::Node::Func { name, .. } => {
+				name.elem = self.qualified(self.module, name.elem);
+				self.func(node);
+			},
+			ast::Node::Impl { ty, funcs } => {
+				ty.elem = self.global(ty);
+				funcs.iter_mut().for_each(|f| self.func(f));
+			},
+			ast::Node::Struct { name, generics, fields, .. } => {
+				name.elem = self.qualified(self.module, name.elem);
+				self.params = generics.iter().map(|g| g.elem).collect();
+				fields.iter_mut().for_each(|(_, t)| self.ty(t));
+			},
+			ast::Node::Enum { name, generics, variants, .. } => {
+				name.elem = self.qualified(self.module, name.elem);
+				self.params = generics.iter().map(|g| g.elem).collect();
+				variants.iter_mut().filter_map(|(_, t)| t.as_mut()).for_each(|t| self.ty(t));
+			},
+			ast::Node::Alias { name, ty, .. } => {
+				name.elem = self.qualified(self.module, name.elem);
+				self.params.clear();
+				self.ty(ty);
+			},
+			_ => (),
+		}
+	}
+
+	fn func(&mut self, node: &mut Sp<ast::Node<'static>>) {
+		let ast::Node::Func { generics, args, ret, body, .. } = &mut node.elem else { unreachable!() };
+
+		self.params = generics.iter().map(|g| g.elem).collect();
+		self.locals.clear();
+		for (name, ty) in args {
+			self.ty(ty);
+			self.locals.push(name.elem);
+		}
+
+		if let Some(ret) = ret { self.ty(ret); }
+		self.block(body);
+	}
+
+	fn ty(&mut self, ty: &mut Sp<ast::Type<'static>>) {
+		let span = ty.span;
+		match &mut ty.elem {
+			ast::Type::Ident(name) if !self.params.contains(name) => *name = self.global(&(*name).span(span)),
+			ast::Type::Generic(name, args) => {
+				*name = self.global(&(*name).span(span));
+				args.iter_mut().for_each(|t| self.ty(t));
+			},
+			ast::Type::Opt(t) | ast::Type::Ptr(t) | ast::Type::Arr(t, _) | ast::Type::Mut(t) => self.ty(t),
+			ast::Type::Fn(args, ret) => {
+				args.iter_mut().for_each(|t| self.ty(t));
+				if let Some(ret) = ret { self.ty(ret); }
+			},
+			_ => (),
+		}
+	}
+
+	fn block(&mut self, body: &mut [Sp<ast::Node<'static>>]) {
+		let len = self.locals.len();
+		body.iter_mut().for_each(|n| self.stmt(n));
+		self.locals.truncate(len);
+	}
+
+	fn stmt(&mut self, node: &mut Sp<ast::Node<'static>>) {
+		match &mut node.elem {
+			ast::Node::Assign { name, ty, value } => {
+				if let Some(ty) = ty { self.ty(ty); }
+				self.expr(value);
+				self.locals.push(name.elem);
+			},
+			ast::Node::Store { to, value } => {
+				self.expr(to);
+				self.expr(value);
+			},
+			ast::Node::Ret(Some(expr)) => self.expr(expr),
+			ast::Node::If { cond, body, els } => {
+				self.expr(cond);
+				self.block(body);
+				if let Some(els) = els { self.block(els); }
+			},
+			ast::Node::While { cond, body } => {
+				self.expr(cond);
+				self.block(body);
+			},
+			ast::Node::Loop(body) => self.block(body),
+			ast::Node::Match { expr, arms } => {
+				self.expr(expr);
+				for (pat, body) in arms {
+					let len = self.locals.len();
+					if let ast::Pattern::Variant(_, Some(bind)) = &pat.elem { self.locals.push(bind.elem); }
+					self.block(body);
+					self.locals.truncate(len);
+				}
+			},
+			_ => self.expr(node),
+		}
+	}
+
+	fn expr(&mut self, node: &mut Sp<ast::Node<'static>>) {
+		match &mut node.elem {
+			// `m.T` is a path into the module bound as `m`, unless a local hides it
+			ast::Node::Field { expr, field } => {
+				let bound = match expr.elem {
+					ast::Node::Ident(m) if !self.locals.contains(&m) => self.imports.get(m).map(|(i, _)| (*i, m)),
+					_ => None,
+				};
+
+				match bound {
+					Some((m, module)) => {
+						let field = field.clone();
+						node.elem = ast::Node::Ident(self.member(m, module, field.elem, &field));
+					},
+					None => self.expr(expr),
+				}
+			},
+			ast::Node::Ident(name) if !self.locals.contains(name) && self.globals[self.module].contains_key(*name) =>
+				*name = self.qualified(self.module, name),
+			ast::Node::FuncCall { name, ty_args, args } => {
+				name.elem = self.global(name);
+				ty_args.iter_mut().for_each(|t| self.ty(t));
+				args.iter_mut().for_each(|a| self.expr(a));
+			},
+			ast::Node::MethodCall { expr, args, .. } => {
+				self.expr(expr);
+				args.iter_mut().for_each(|a| self.expr(a));
+			},
+			ast::Node::StructLit { name, fields } => {
+				name.elem = self.global(name);
+				fields.iter_mut().for_each(|(_, v)| self.expr(v));
+			},
+			ast::Node::BinOp { lhs, rhs, .. } | ast::Node::Index { expr: lhs, index: rhs } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ast::Node::Cast { expr, ty } => {
+				self.expr(expr);
+				self.ty(ty);
+			},
+			ast::Node::UnOp { expr, .. } | ast::Node::Unwrap(expr) | ast::Node::AddrOf(expr)
+				| ast::Node::Deref(expr) => self.expr(expr),
+			ast::Node::ArrLit(elems) => elems.iter_mut().for_each(|e| self.expr(e)),
+			_ => (),
+		}
+	}
+}
@@ -4,34 +4,145 @@ use crate::bigint::IBig;
 
 use colored::Colorize;
 
+#[derive(Clone)]
 pub enum Node<'src> {
 	Func {
-		name:   Sp<&'src str>,
-		attrs:  Vec<Sp<Attrs>>,
-		args:   Vec<(Sp<&'src str>, Sp<Type<'src>>)>,
-		ret:    Option<Sp<Type<'src>>>,
-		body:   Vec<Sp<Node<'src>>>
+		name:     Sp<&'src str>,
+		generics: Vec<Sp<&'src str>>,
+		attrs:    Vec<Sp<Attrs>>,
+		args:     Vec<(Sp<&'src str>, Sp<Type<'src>>)>,
+		ret:      Option<Sp<Type<'src>>>,
+		body:     Vec<Sp<Self>>
+	},
+	Struct {
+		name:     Sp<&'src str>,
+		generics: Vec<Sp<&'src str>>,
+		attrs:    Vec<Sp<Attrs>>,
+		fields:   Vec<(Sp<&'src str>, Sp<Type<'src>>)>,
+	},
+	Alias {
+		name:  Sp<&'src str>,
+		attrs: Vec<Sp<Attrs>>,
+		ty:    Sp<Type<'src>>,
+	},
+	Impl {
+		ty:    Sp<&'src str>,
+		funcs: Vec<Sp<Self>>, // Func
+	},
+	Import(Vec<Sp<&'src str>>), // `import a.b;`, bound as `b`
+	Enum {
+		name:     Sp<&'src str>,
+		generics: Vec<Sp<&'src str>>,
+		attrs:    Vec<Sp<Attrs>>,
+		variants: Vec<(Sp<&'src str>, Option<Sp<Type<'src>>>)>,
 	},
 	Assign {
 		name: Sp<&'src str>,
-		ty: Sp<Type<'src>>,
-		value: Box<Sp<Node<'src>>>
+		ty: Option<Sp<Type<'src>>>, // inferred from the value when left out
+		value: Box<Sp<Self>>
 	},
 	Store {
-		name: Sp<&'src str>,
-		value: Box<Sp<Node<'src>>>
+		to:    Box<Sp<Self>>, // Ident | Deref | Index | Field
+		value: Box<Sp<Self>>
+	},
+	Ret(Option<Box<Sp<Self>>>),
+	If {
+		cond: Box<Sp<Self>>,
+		body: Vec<Sp<Self>>,
+		els:  Option<Vec<Sp<Self>>>, // `else if` is an `If` as the only statement
+	},
+	While {
+		cond: Box<Sp<Self>>,
+		body: Vec<Sp<Self>>,
 	},
-	Ret(Option<Box<Sp<Node<'src>>>>),
+	Loop(Vec<Sp<Self>>),
+	Match {
+		expr: Box<Sp<Self>>,
+		arms: Vec<(Sp<Pattern<'src>>, Vec<Sp<Self>>)>,
+	},
+	Break,
+	Continue,
 	FuncCall {
+		name:    Sp<&'src str>,
+		ty_args: Vec<Sp<Type<'src>>>, // given explicitly, with `$f<T>(...)`
+		args:    Vec<Sp<Self>>,
+	},
+	BinOp {
+		op:  Sp<BinOp>,
+		lhs: Box<Sp<Self>>,
+		rhs: Box<Sp<Self>>,
+	},
+	UnOp {
+		op:   Sp<UnOp>,
+		expr: Box<Sp<Self>>,
+	},
+	StructLit {
+		name:   Sp<&'src str>,
+		fields: Vec<(Sp<&'src str>, Sp<Self>)>,
+	},
+	Field {
+		expr:  Box<Sp<Self>>,
+		field: Sp<&'src str>,
+	},
+	MethodCall {
+		expr: Box<Sp<Self>>,
 		name: Sp<&'src str>,
-		args: Vec<Sp<Node<'src>>>,
+		args: Vec<Sp<Self>>,
+	},
+	Unwrap(Box<Sp<Self>>), // `x?`
+	AddrOf(Box<Sp<Self>>), // `&x`
+	Deref(Box<Sp<Self>>),  // `@p`
+	Index {
+		expr:  Box<Sp<Self>>,
+		index: Box<Sp<Self>>,
 	},
+	ArrLit(Vec<Sp<Self>>),
+	Cast { // `x as T`
+		expr: Box<Sp<Self>>,
+		ty:   Sp<Type<'src>>,
+	},
+	None,
 	Ident(&'src str),
 	StrLit(String),
 	UIntLit(IBig),
 	SIntLit(IBig),
+	FloatLit(f64),
+	CharLit(char),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinOp {
+	Add, Sub, Mul, Div, Rem,
+	Shl, Shr,
+	BitAnd, BitOr, BitXor,
+	And, Or, Xor,
+	Eq, Ne, Lt, Le, Gt, Ge,
+}
+
+impl BinOp {
+	pub fn is_cmp(self) -> bool {
+		matches!(self, Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge)
+	}
+
+	pub fn is_logical(self) -> bool {
+		matches!(self, Self::And | Self::Or | Self::Xor)
+	}
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnOp {
+	Neg,    // -
+	Not,    // !
+	BitNot, // ~
+}
+
+#[derive(Clone)]
+pub enum Pattern<'src> {
+	Variant(Sp<&'src str>, Option<Sp<&'src str>>), // `Name` or `Name(binding)`, `Name(_)` binds nothing
+	Wildcard,
+}
+
+#[derive(Clone)]
 pub enum Attrs {
 	Export,
 	Extern,
@@ -43,20 +154,24 @@ pub enum Type<'src> {
 	U(u32), I(u32), B(u32), F(u32),
 	Usize, Isize,
 	Void, Never,
-	Opt(Box<Sp<Type<'src>>>),
-	Ptr(Box<Sp<Type<'src>>>),
-	Arr(Box<Sp<Type<'src>>>, Option<u64>),
-	Mut(Box<Sp<Type<'src>>>),
-	Fn(Vec<Sp<Type<'src>>>, Option<Box<Sp<Type<'src>>>>),
+	Opt(Box<Sp<Self>>),
+	Ptr(Box<Sp<Self>>),
+	Arr(Box<Sp<Self>>, Option<u64>),
+	Mut(Box<Sp<Self>>),
+	#[allow(dead_code)]
+	Fn(Vec<Sp<Self>>, Option<Box<Sp<Self>>>),
 	Ident(&'src str),
+	Generic(&'src str, Vec<Sp<Self>>), // `Name<T, ...>`
 }
 
 impl Display for Node<'_> {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
-			Self::Func { name, attrs, args, ret, body } => {
+			Self::Func { name, generics, attrs, args, ret, body } => {
 				attrs.iter().try_for_each(|a| write!(f, "{a} "))?;
-				write!(f, "{} {}(", "fn".yellow().dimmed(), name.red())?;
+				write!(f, "{} {}", "fn".yellow().dimmed(), name.red())?;
+				write_generics(f, generics)?;
+				write!(f, "(")?;
 				for (i, (name, typ)) in args.iter().enumerate() {
 					write!(f, "{name}: {typ}")?;
 					if i != args.len() - 1 { write!(f, ", ")?; }
@@ -74,18 +189,102 @@ impl Display for Node<'_> {
 				body.iter().try_for_each(|s| writeln!(f, "   {s};"))?;
 				write!(f, "}}")
 			},
-			Self::Assign { name, value, ty } => 
+			Self::Struct { name, generics, attrs, fields } => {
+				attrs.iter().try_for_each(|a| write!(f, "{a} "))?;
+				write!(f, "{} {}", "struct".yellow().dimmed(), name.blue())?;
+				write_generics(f, generics)?;
+				writeln!(f, " {{")?;
+				fields.iter().try_for_each(|(n, t)| writeln!(f, "   {n}: {t},"))?;
+				write!(f, "}}")
+			},
+			Self::Alias { name, attrs, ty } => {
+				attrs.iter().try_for_each(|a| write!(f, "{a} "))?;
+				write!(f, "{} {} = {ty}", "type".yellow().dimmed(), name.blue())
+			},
+			Self::Impl { ty, funcs } => {
+				writeln!(f, "{} {} {{", "impl".yellow().dimmed(), ty.blue())?;
+				funcs.iter().try_for_each(|func| writeln!(f, "{func}"))?;
+				write!(f, "}}")
+			},
+			Self::Import(path) => {
+				let path: Vec<&str> = path.iter().map(|p| p.elem).collect();
+				write!(f, "{} {}", "import".yellow().dimmed(), path.join("."))
+			},
+			Self::Enum { name, generics, attrs, variants } => {
+				attrs.iter().try_for_each(|a| write!(f, "{a} "))?;
+				write!(f, "{} {}", "enum".yellow().dimmed(), name.blue())?;
+				write_generics(f, generics)?;
+				writeln!(f, " {{")?;
+				variants.iter().try_for_each(|(n, t)| match t {
+					Some(t) => writeln!(f, "   {n}({t}),"),
+					None    => writeln!(f, "   {n},"),
+				})?;
+				write!(f, "}}")
+			},
+			Self::StructLit { name, fields } => {
+				write!(f, "{} {{ ", name.blue())?;
+				fields.iter().try_for_each(|(n, v)| write!(f, "{n}: {v}, "))?;
+				write!(f, "}}")
+			},
+			Self::Field { expr, field } => write!(f, "{expr}.{field}"),
+			Self::MethodCall { expr, name, args } => {
+				write!(f, "{expr}.{}(", name.red())?;
+				for (i, arg) in args.iter().enumerate() {
+					write!(f, "{arg}")?;
+					if i != args.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, ")")
+			},
+			Self::Assign { name, value, ty: Some(ty) } => 
 				write!(f, "{} {name}: {} = {value}",
 					"let".yellow().dimmed(),
 					ty.to_string().blue()),
-			Self::Store { name, value } =>
-				write!(f, "{name} = {value}"),
+			Self::Assign { name, value, ty: None } => 
+				write!(f, "{} {name} = {value}", "let".yellow().dimmed()),
+			Self::Store { to, value } =>
+				write!(f, "{to} = {value}"),
 			Self::Ret(expr) => match expr {
 				Some(expr) => write!(f, "{} {expr}", "ret".yellow().dimmed()),
 				None => write!(f, "{}", "ret".yellow().dimmed()),
 			},
-			Self::FuncCall { name, args } => {
-				write!(f, "{}(", format!("${name}").red())?;
+			Self::If { cond, body, els } => {
+				writeln!(f, "{} {cond} {{", "if".yellow().dimmed())?;
+				body.iter().try_for_each(|s| writeln!(f, "   {s};"))?;
+				write!(f, "}}")?;
+				match els {
+					Some(els) => {
+						writeln!(f, " {} {{", "else".yellow().dimmed())?;
+						els.iter().try_for_each(|s| writeln!(f, "   {s};"))?;
+						write!(f, "}}")
+					},
+					None => Ok(()),
+				}
+			},
+			Self::While { cond, body } => {
+				writeln!(f, "{} {cond} {{", "while".yellow().dimmed())?;
+				body.iter().try_for_each(|s| writeln!(f, "   {s};"))?;
+				write!(f, "}}")
+			},
+			Self::Loop(body) => {
+				writeln!(f, "{} {{", "loop".yellow().dimmed())?;
+				body.iter().try_for_each(|s| writeln!(f, "   {s};"))?;
+				write!(f, "}}")
+			},
+			Self::Match { expr, arms } => {
+				writeln!(f, "{} {expr} {{", "match".yellow().dimmed())?;
+				for (pat, body) in arms {
+					writeln!(f, "   {pat} => {{")?;
+					body.iter().try_for_each(|s| writeln!(f, "      {s};"))?;
+					writeln!(f, "   }},")?;
+				}
+				write!(f, "}}")
+			},
+			Self::Break    => write!(f, "{}", "break".yellow().dimmed()),
+			Self::Continue => write!(f, "{}", "continue".yellow().dimmed()),
+			Self::FuncCall { name, ty_args, args } => {
+				write!(f, "{}", format!("${name}").red())?;
+				write_generics(f, ty_args)?;
+				write!(f, "(")?;
 				for (i, arg) in args.iter().enumerate() {
 					write!(f, "{arg}")?;
 					if i != args.len() - 1 { write!(f, ", ")?; }
@@ -94,11 +293,75 @@ impl Display for Node<'_> {
 			},
 			Self::StrLit(s)  => write!(f, "{}", format!("{s:?}").green()),
 			Self::UIntLit(i) | Self::SIntLit(i) => write!(f, "{}", i.to_string().cyan()),
+			Self::FloatLit(v) => write!(f, "{}", format!("{v:?}").cyan()),
+			Self::CharLit(c)  => write!(f, "{}", format!("{c:?}").green()),
+			Self::BinOp { op, lhs, rhs } => write!(f, "({lhs} {} {rhs})", op.elem),
+			Self::UnOp { op, expr } => write!(f, "{}{expr}", op.elem),
+			Self::Unwrap(expr) => write!(f, "{expr}?"),
+			Self::AddrOf(expr) => write!(f, "&{expr}"),
+			Self::Deref(expr)  => write!(f, "@{expr}"),
+			Self::Index { expr, index } => write!(f, "{expr}[{index}]"),
+			Self::Cast { expr, ty } => write!(f, "({expr} as {ty})"),
+			Self::ArrLit(elems) => {
+				write!(f, "[")?;
+				for (i, elem) in elems.iter().enumerate() {
+					write!(f, "{elem}")?;
+					if i != elems.len() - 1 { write!(f, ", ")?; }
+				}
+				write!(f, "]")
+			},
+			Self::None => write!(f, "{}", "none".yellow().dimmed()),
 			Self::Ident(name) => write!(f, "{name}"),
 		}
 	}
 }
 
+impl Display for Pattern<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Variant(name, Some(bind)) => write!(f, "{name}({bind})"),
+			Self::Variant(name, None) => write!(f, "{name}"),
+			Self::Wildcard => write!(f, "_"),
+		}
+	}
+}
+
+impl Display for BinOp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Add    => "+",
+			Self::Sub    => "-",
+			Self::Mul    => "*",
+			Self::Div    => "/",
+			Self::Rem    => "%",
+			Self::Shl    => "<<",
+			Self::Shr    => ">>",
+			Self::BitAnd => "&",
+			Self::BitOr  => "|",
+			Self::BitXor => "^",
+			Self::And    => "&&",
+			Self::Or     => "||",
+			Self::Xor    => "^^",
+			Self::Eq     => "=",
+			Self::Ne     => "~=",
+			Self::Lt     => "<",
+			Self::Le     => "<=",
+			Self::Gt     => ">",
+			Self::Ge     => ">=",
+		})
+	}
+}
+
+impl Display for UnOp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", match self {
+			Self::Neg    => "-",
+			Self::Not    => "!",
+			Self::BitNot => "~",
+		})
+	}
+}
+
 impl Display for Type<'_> {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(f, "{}", match self {
@@ -128,10 +391,26 @@ impl Display for Type<'_> {
 				return Ok(());
 			},
 			Self::Ident(name) => String::from(*name),
+			Self::Generic(name, args) => {
+				write!(f, "{}", name.purple())?;
+				return write_generics(f, args);
+			},
 		}.purple())
 	}
 }
 
+/// The `<...>` after a generic name, nothing when there are no params
+fn write_generics<T: Display>(f: &mut fmt::Formatter<'_>, list: &[T]) -> fmt::Result {
+	if list.is_empty() { return Ok(()); }
+
+	write!(f, "<")?;
+	for (i, t) in list.iter().enumerate() {
+		write!(f, "{t}")?;
+		if i != list.len() - 1 { write!(f, ", ")?; }
+	}
+	write!(f, ">")
+}
+
 impl Display for Attrs {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(f, "{}", match self {
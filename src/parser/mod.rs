@@ -4,13 +4,14 @@ use crate::span::{Spannable, Sp};
 use crate::bigint::IBig;
 
 pub mod ast;
-use ast::{Node, Type, Attrs};
+use ast::{Node, Type, Pattern, Attrs, BinOp, UnOp};
 
 pub struct Parser<'src> {
 	tokens:  Vec<Token<'src>>,
 	index:   usize,
 	handler: LogHandler,
 	filename: &'static str,
+	no_struct: bool, // set in `if` and `while` conditions, where `{` starts the body
 }
 
 impl<'src> Parser<'src> {
@@ -25,11 +26,11 @@ impl<'src> Parser<'src> {
 	}
 
 	#[inline]
-	#[allow(clippy::cast_possible_wrap)]
+	#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
 	fn peek(&self, index: isize) -> Option<&Token<'src>> {
 		let index = self.index as isize + index;
 		assert!(index >= 0, "peek() out of bounds");
-		self.tokens.get(unsafe { std::mem::transmute::<isize, usize>(index) })
+		self.tokens.get(index as usize)
 	}
 
 	#[inline]
@@ -51,6 +52,7 @@ impl<'src> Parser<'src> {
 		let mut parser = Self {
 			tokens, handler, filename,
 			index: 0,
+			no_struct: false,
 		};
 
 		while !matches!(parser.current().kind, TokenKind::EOF) {
@@ -76,7 +78,12 @@ impl<'src> Parser<'src> {
 
 		match token.kind {
 			TokenKind::KWFn => self.parse_func(),
-			TokenKind::KWExtern | TokenKind::KWExport => { // FIXME: come up with a better way to do this
+			TokenKind::KWStruct => self.parse_struct(),
+			TokenKind::KWEnum => self.parse_enum(),
+			TokenKind::KWImpl => self.parse_impl(),
+			TokenKind::KWType => self.parse_alias(),
+			TokenKind::KWImport => self.parse_import(),
+			TokenKind::KWExtern | TokenKind::KWExport | TokenKind::KWPub => { // FIXME: come up with a better way to do this
 				self.advance();
 
 				if matches!(self.current().kind, TokenKind::EOF) {
@@ -84,23 +91,29 @@ impl<'src> Parser<'src> {
 						.untitled().span(token.span).as_err();
 				}
 
+				let (attr, what) = match token.kind {
+					TokenKind::KWExtern => (Attrs::Extern, "extern"),
+					TokenKind::KWExport => (Attrs::Export, "export"),
+					_ => (Attrs::Pub, "pub"),
+				};
+
 				let mut r = self.parse_global()?;
-				match *r {
-					Node::Func { .. } => Ok({
-						let Node::Func { ref mut attrs, .. } = *r
-							else { unreachable!() };
-
-						attrs.push(match token.kind {
-							TokenKind::KWExtern => Attrs::Extern,
-							TokenKind::KWExport => Attrs::Export,
-							_ => unreachable!(),
-						}.span(token.span));
-
-						r.span = token.span.extend(&r.span);
-						r
-					}),
-					_ => unreachable!(),
-				}
+				let attrs = match &mut r.elem {
+					Node::Func { attrs, .. } => attrs,
+					Node::Struct { attrs, .. } | Node::Enum { attrs, .. } | Node::Alias { attrs, .. }
+						if matches!(attr, Attrs::Pub) => attrs,
+					_ => return ReportKind::SyntaxError
+						.title(format!("'{what}' cannot be used here"))
+						.help(match attr {
+							Attrs::Pub => "Only functions and types can be 'pub'",
+							_ => "Only functions can be extern or exported",
+						})
+						.span(token.span).as_err(),
+				};
+
+				attrs.push(attr.span(token.span));
+				r.span = token.span.extend(&r.span);
+				Ok(r)
 			},
 			s => {
 				self.advance();
@@ -121,8 +134,7 @@ impl<'src> Parser<'src> {
 				.span(token.span))?;
 
 		let name = token.text.span(token.span);
-
-		// TODO: generic parsing
+		let generics = self.parse_generics()?;
 
 		if self.current().kind != TokenKind::LParen {
 			return ReportKind::UnexpectedToken
@@ -159,31 +171,334 @@ impl<'src> Parser<'src> {
 		}
 
 		self.advance();
-		let (body, ret) = match self.current().kind {
-			TokenKind::Colon     => (vec![self.parse_stmt()?],  None),
-			TokenKind::LBrace    => (self.parse_block()?, None),
-			TokenKind::Semicolon => (Vec::new(), None),
-			_ => {
-				let ty = self.parse_type()?;
+		let ret = match self.current().kind {
+			TokenKind::Colon | TokenKind::LBrace | TokenKind::Semicolon => None,
+			_ => Some(self.parse_type()?),
+		};
 
-				let token = self.current();
-				self.advance();
+		let token = self.current();
+		self.advance();
 
-				(match token.kind {
-					TokenKind::Colon     => vec![self.parse_stmt()?],
-					TokenKind::LBrace    => self.parse_block()?,
-					TokenKind::Semicolon => Vec::new(),
-					_ => return ReportKind::UnexpectedToken
-						.title("Expected '{', ';', or ':'")
-						.span(token.span).as_err()?,
-				}, Some(ty))
-			},
+		let body = match token.kind {
+			TokenKind::Colon     => vec![self.parse_stmt()?],
+			TokenKind::LBrace    => self.parse_block()?,
+			TokenKind::Semicolon => Vec::new(),
+			_ => return ReportKind::UnexpectedToken
+				.title("Expected '{', ';', or ':'")
+				.span(token.span).as_err()?,
 		};
 
-		Ok(Node::Func { name, args, ret, body, attrs: Vec::new() }
+		Ok(Node::Func { name, generics, args, ret, body, attrs: Vec::new() }
 			.span(token.span.extend(&self.current().span)))
 	}
 
+	fn parse_struct(&mut self) -> Result<Sp<Node<'src>>> {
+		let start = self.current().span;
+		self.advance();
+
+		let token = self.current();
+		self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title("Expected identifier")
+				.span(token.span))?;
+
+		let name = token.text.span(token.span);
+		let generics = self.parse_generics()?;
+
+		self.advance_if(|t| matches!(t, TokenKind::LBrace)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected '{{', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		let mut fields = Vec::new();
+		loop {
+			let token = self.current();
+			match token.kind {
+				TokenKind::RBrace => {
+					self.advance();
+					break;
+				},
+				TokenKind::Identifier => {
+					self.advance();
+
+					self.advance_if(|t| matches!(t, TokenKind::Colon)).then_some(())
+						.ok_or_else(|| ReportKind::UnexpectedToken
+							.title(format!("Expected ':', got '{:?}'", self.current().kind))
+							.span(self.current().span))?;
+
+					fields.push((token.text.span(token.span), self.parse_type()?));
+					self.parse_list_sep()?;
+				},
+				TokenKind::EOF => return ReportKind::UnexpectedEOF
+					.title("Expected '}'")
+					.span(self.peek(-1).unwrap().span).as_err(),
+				_ => return ReportKind::UnexpectedToken
+					.title("Expected identifier")
+					.span(token.span).as_err(),
+			}
+		}
+
+		let span = start.extend(&name.span);
+		Ok(Node::Struct { name, generics, fields, attrs: Vec::new() }.span(span))
+	}
+
+	fn parse_enum(&mut self) -> Result<Sp<Node<'src>>> {
+		let start = self.current().span;
+		self.advance();
+
+		let token = self.current();
+		self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title("Expected identifier")
+				.span(token.span))?;
+
+		let name = token.text.span(token.span);
+		let generics = self.parse_generics()?;
+
+		self.advance_if(|t| matches!(t, TokenKind::LBrace)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected '{{', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		let mut variants = Vec::new();
+		loop {
+			let token = self.current();
+			match token.kind {
+				TokenKind::RBrace => {
+					self.advance();
+					break;
+				},
+				TokenKind::Identifier => {
+					self.advance();
+
+					let payload = match self.advance_if(|t| matches!(t, TokenKind::LParen)) {
+						true => {
+							let ty = self.parse_type()?;
+							self.advance_if(|t| matches!(t, TokenKind::RParen)).then_some(())
+								.ok_or_else(|| ReportKind::UnexpectedToken
+									.title("Expected ')'")
+									.span(self.current().span))?;
+							Some(ty)
+						},
+						false => None,
+					};
+
+					variants.push((token.text.span(token.span), payload));
+					self.parse_list_sep()?;
+				},
+				TokenKind::EOF => return ReportKind::UnexpectedEOF
+					.title("Expected '}'")
+					.span(self.peek(-1).unwrap().span).as_err(),
+				_ => return ReportKind::UnexpectedToken
+					.title("Expected identifier")
+					.span(token.span).as_err(),
+			}
+		}
+
+		let span = start.extend(&name.span);
+		Ok(Node::Enum { name, generics, variants, attrs: Vec::new() }.span(span))
+	}
+
+	/// The `<T, ...>` params after the name of a generic function or type
+	fn parse_generics(&mut self) -> Result<Vec<Sp<&'src str>>> {
+		let mut generics = Vec::new();
+		if !self.advance_if(|t| matches!(t, TokenKind::LessThan)) { return Ok(generics); }
+
+		loop {
+			let token = self.current();
+			self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+				.ok_or_else(|| ReportKind::UnexpectedToken
+					.title("Expected type parameter")
+					.span(token.span))?;
+
+			generics.push(token.text.span(token.span));
+
+			match self.current().kind {
+				TokenKind::Comma => self.advance(),
+				TokenKind::GreaterThan => {
+					self.advance();
+					return Ok(generics);
+				},
+				_ => return ReportKind::UnexpectedToken
+					.title(format!("Expected ',' or '>', got '{:?}'", self.current().kind))
+					.span(self.current().span)
+					.as_err(),
+			}
+		}
+	}
+
+	/// The `<T, ...>` args given to a generic, nothing if there is no '<'
+	fn parse_ty_args(&mut self) -> Result<Vec<Sp<Type<'src>>>> {
+		let mut args = Vec::new();
+		if !self.advance_if(|t| matches!(t, TokenKind::LessThan)) { return Ok(args); }
+
+		loop {
+			args.push(self.parse_type()?);
+
+			match self.current().kind {
+				TokenKind::Comma => self.advance(),
+				TokenKind::GreaterThan => {
+					self.advance();
+					return Ok(args);
+				},
+				// the '>>' closing `A<B<T>>`, this takes the first half and leaves the other
+				TokenKind::ShiftRight => {
+					let token = &mut self.tokens[self.index];
+					token.kind = TokenKind::GreaterThan;
+					token.text = &token.text[1..];
+					token.span.start += 1;
+					return Ok(args);
+				},
+				_ => return ReportKind::UnexpectedToken
+					.title(format!("Expected ',' or '>', got '{:?}'", self.current().kind))
+					.span(self.current().span)
+					.as_err(),
+			}
+		}
+	}
+
+	fn parse_alias(&mut self) -> Result<Sp<Node<'src>>> {
+		let start = self.current().span;
+		self.advance();
+
+		let token = self.current();
+		self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title("Expected identifier")
+				.span(token.span))?;
+
+		let name = token.text.span(token.span);
+		if is_builtin(name.elem) {
+			return ReportKind::TypeError
+				.title(format!("Type '{}' is already defined", *name))
+				.label("it is a builtin type")
+				.span(name.span).as_err();
+		}
+
+		self.advance_if(|t| matches!(t, TokenKind::Equals)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected '=', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		let ty = self.parse_type()?;
+
+		self.advance_if(|t| matches!(t, TokenKind::Semicolon)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected ';', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		let span = start.extend(&name.span);
+		Ok(Node::Alias { name, ty, attrs: Vec::new() }.span(span))
+	}
+
+	fn parse_import(&mut self) -> Result<Sp<Node<'src>>> {
+		let start = self.current().span;
+		self.advance();
+
+		let mut path = Vec::new();
+		loop {
+			let token = self.current();
+			self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+				.ok_or_else(|| ReportKind::UnexpectedToken
+					.title("Expected module name")
+					.span(token.span))?;
+			path.push(token.text.span(token.span));
+
+			if !self.advance_if(|t| matches!(t, TokenKind::Dot)) { break; }
+		}
+
+		self.advance_if(|t| matches!(t, TokenKind::Semicolon)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected ';', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		let span = start.extend(&path.last().unwrap().span);
+		Ok(Node::Import(path).span(span))
+	}
+
+	/// `a.b.c` as one name, for what another module declares. `token` is the first part, already advanced past
+	fn parse_path(&mut self, token: Token<'src>) -> Sp<&'src str> {
+		let mut span = token.span;
+		let mut parts = vec![token.text];
+		while self.current().kind == TokenKind::Dot
+			&& self.peek(1).is_some_and(|t| t.kind == TokenKind::Identifier) {
+			self.advance();
+			parts.push(self.current().text);
+			span = span.extend(&self.current().span);
+			self.advance();
+		}
+
+		match parts.len() {
+			1 => token.text.span(span),
+			_ => (&*Box::leak(parts.join(".").into_boxed_str())).span(span),
+		}
+	}
+
+	/// Whether `a.b.c {` starts at the current token, a struct literal of a type from another module
+	fn at_struct_lit(&self) -> bool {
+		let mut i = 1;
+		while self.peek(i).is_some_and(|t| t.kind == TokenKind::Dot)
+			&& self.peek(i + 1).is_some_and(|t| t.kind == TokenKind::Identifier) {
+			i += 2;
+		}
+		self.peek(i).is_some_and(|t| t.kind == TokenKind::LBrace)
+	}
+
+	fn parse_impl(&mut self) -> Result<Sp<Node<'src>>> {
+		let start = self.current().span;
+		self.advance();
+
+		let token = self.current();
+		self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title("Expected type name")
+				.span(token.span))?;
+
+		let ty = token.text.span(token.span);
+
+		self.advance_if(|t| matches!(t, TokenKind::LBrace)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected '{{', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		let mut funcs = Vec::new();
+		loop {
+			match self.current().kind {
+				TokenKind::RBrace => {
+					self.advance();
+					break;
+				},
+				TokenKind::EOF => return ReportKind::UnexpectedEOF
+					.title("Expected '}'")
+					.span(self.peek(-1).unwrap().span).as_err(),
+				_ => {
+					let func = self.parse_global()?;
+					if !matches!(*func, Node::Func { .. }) {
+						return ReportKind::SyntaxError
+							.title("Only functions can be in an impl block")
+							.span(func.span)
+							.as_err();
+					}
+					funcs.push(func);
+				},
+			}
+		}
+
+		let span = start.extend(&ty.span);
+		Ok(Node::Impl { ty, funcs }.span(span))
+	}
+
+	/// Items in braces are separated by ',', the last one may have a trailing one
+	fn parse_list_sep(&mut self) -> Result<()> {
+		match self.current().kind {
+			TokenKind::Comma  => { self.advance(); Ok(()) },
+			TokenKind::RBrace => Ok(()),
+			_ => ReportKind::UnexpectedToken
+				.title(format!("Expected ',' or '}}', got '{:?}'", self.current().kind))
+				.span(self.current().span).as_err(),
+		}
+	}
+
 	fn parse_block(&mut self) -> Result<Vec<Sp<Node<'src>>>> {
 		let mut body = Vec::new();
 
@@ -221,26 +536,53 @@ impl<'src> Parser<'src> {
 	}
 
 	fn parse_stmt(&mut self) -> Result<Sp<Node<'src>>> {
-		let ast = match self.current().kind {
+		if matches!(self.current().kind, TokenKind::KWIf | TokenKind::KWWhile | TokenKind::KWLoop | TokenKind::KWMatch) {
+			return self.parse_control();
+		}
+
+		let ast = self.parse_bare_stmt()?;
+
+		self.advance_if(|t| matches!(t, TokenKind::Semicolon)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected ';', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		Ok(ast)
+	}
+
+	/// A statement without its ';', as taken by match arms
+	fn parse_bare_stmt(&mut self) -> Result<Sp<Node<'src>>> {
+		Ok(match self.current().kind {
+			TokenKind::KWBreak | TokenKind::KWContinue => {
+				let tok = self.current();
+				self.advance();
+
+				match tok.kind {
+					TokenKind::KWBreak => Node::Break,
+					_ => Node::Continue,
+				}.span(tok.span)
+			},
 			TokenKind::KWLet => {
 				self.advance();
 				let tok = self.current();
 
-				self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+				// 'let _ = ...' discards a value
+				self.advance_if(|t| matches!(t, TokenKind::Identifier | TokenKind::Underscore)).then_some(())
 					.ok_or_else(|| ReportKind::UnexpectedToken
 						.title(format!("Expected identifier, got '{:?}'", self.current().kind))
 						.span(self.current().span))?;
 
-				self.advance_if(|t| matches!(t, TokenKind::Colon)).then_some(())
-					.ok_or_else(|| ReportKind::UnexpectedToken
-						.title(format!("Expected ':', got '{:?}'", self.current().kind))
-						.span(self.current().span))?;
-
-				let ty = self.parse_type()?;
+				let ty = match self.advance_if(|t| matches!(t, TokenKind::Colon)) {
+					true  => Some(self.parse_type()?),
+					false => None,
+				};
 
 				self.advance_if(|t| matches!(t, TokenKind::Equals)).then_some(())
 					.ok_or_else(|| ReportKind::UnexpectedToken
-						.title(format!("Expected '=', got '{:?}'", self.current().kind))
+						.title(match ty {
+							Some(_) => format!("Expected '=', got '{:?}'", self.current().kind),
+							None    => format!("Expected ':' or '=', got '{:?}'", self.current().kind),
+						})
 						.span(self.current().span))?;
 
 				Node::Assign {
@@ -250,44 +592,344 @@ impl<'src> Parser<'src> {
 				}.span(tok.span.extend(&self.current().span))
 			},
 			TokenKind::KWRet => {
+				let tok = self.current();
 				self.advance();
 
 				match self.current().kind {
-					TokenKind::Semicolon => Node::Ret(None),
-					_ => Node::Ret(Some(Box::new(self.parse_expr()?))),
-				}.span(self.current().span)
+					// a match arm can end right after it too
+					TokenKind::Semicolon | TokenKind::Comma | TokenKind::RBrace => Node::Ret(None).span(tok.span),
+					_ => {
+						let value = self.parse_expr()?;
+						let span = tok.span.extend(&value.span);
+						Node::Ret(Some(Box::new(value))).span(span)
+					},
+				}
 			},
-			TokenKind::Identifier => {
-				let tok = self.current();
-				self.advance();
+			TokenKind::Identifier | TokenKind::At | TokenKind::Dollar => {
+				let start = self.index;
+				let to = self.parse_unary()?;
 
-				match self.current().kind {
-					TokenKind::Equals => {
+				match (&to.elem, self.current().kind) {
+					(Node::Ident(_) | Node::Deref(_) | Node::Index { .. } | Node::Field { .. }, TokenKind::Equals) => {
 						self.advance();
-						Node::Store {
-							name: tok.text.span(tok.span),
-							value: Box::new(self.parse_expr()?),
-						}.span(tok.span.extend(&self.current().span))
+						let value = Box::new(self.parse_expr()?);
+						let span = to.span.extend(&self.current().span);
+						Node::Store { to: Box::new(to), value }.span(span)
+					},
+					_ => {
+						// not a store, so it has to be an expression
+						self.index = start;
+						self.parse_expr()?
 					},
-					_ => todo!()
 				}
 			}
 
 			_ => self.parse_expr()?,
+		})
+	}
+
+	/// `if`, `while`, `loop` and `match` end in a block, so unlike other statements they take no ';'
+	fn parse_control(&mut self) -> Result<Sp<Node<'src>>> {
+		let tok = self.current();
+		self.advance();
+
+		let node = match tok.kind {
+			TokenKind::KWIf => {
+				let cond = Box::new(self.parse_cond()?);
+				let body = self.parse_braced()?;
+
+				let els = match self.advance_if(|t| matches!(t, TokenKind::KWElse)) {
+					true if matches!(self.current().kind, TokenKind::KWIf) => Some(vec![self.parse_control()?]),
+					true  => Some(self.parse_braced()?),
+					false => None,
+				};
+
+				Node::If { cond, body, els }
+			},
+			TokenKind::KWWhile => Node::While {
+				cond: Box::new(self.parse_cond()?),
+				body: self.parse_braced()?,
+			},
+			TokenKind::KWLoop => Node::Loop(self.parse_braced()?),
+			TokenKind::KWMatch => self.parse_match()?,
+			_ => unreachable!(),
 		};
 
-		self.advance_if(|t| matches!(t, TokenKind::Semicolon)).then_some(())
+		Ok(node.span(tok.span))
+	}
+
+	/// `match x { Pat => stmt, Pat => { ... } }`, the keyword is already consumed
+	fn parse_match(&mut self) -> Result<Node<'src>> {
+		let expr = Box::new(self.parse_cond()?);
+
+		self.advance_if(|t| matches!(t, TokenKind::LBrace)).then_some(())
 			.ok_or_else(|| ReportKind::UnexpectedToken
-				.title(format!("Expected ';', got '{:?}'", self.current().kind))
+				.title(format!("Expected '{{', got '{:?}'", self.current().kind))
 				.span(self.current().span))?;
 
-		Ok(ast)
+		let mut arms = Vec::new();
+		loop {
+			let token = self.current();
+			let pat = match token.kind {
+				TokenKind::RBrace => {
+					self.advance();
+					break;
+				},
+				TokenKind::Underscore => {
+					self.advance();
+					Pattern::Wildcard.span(token.span)
+				},
+				TokenKind::Identifier => {
+					self.advance();
+
+					let bind = match self.advance_if(|t| matches!(t, TokenKind::LParen)) {
+						true => {
+							let bind = self.current();
+							self.advance_if(|t| matches!(t, TokenKind::Identifier | TokenKind::Underscore)).then_some(())
+								.ok_or_else(|| ReportKind::UnexpectedToken
+									.title("Expected identifier or '_'")
+									.span(bind.span))?;
+
+							self.advance_if(|t| matches!(t, TokenKind::RParen)).then_some(())
+								.ok_or_else(|| ReportKind::UnexpectedToken
+									.title("Expected ')'")
+									.span(self.current().span))?;
+
+							(bind.kind == TokenKind::Identifier).then(|| bind.text.span(bind.span))
+						},
+						false => None,
+					};
+
+					Pattern::Variant(token.text.span(token.span), bind)
+						.span(token.span.extend(&self.peek(-1).unwrap().span))
+				},
+				TokenKind::EOF => return ReportKind::UnexpectedEOF
+					.title("Expected '}'")
+					.span(self.peek(-1).unwrap().span).as_err(),
+				_ => return ReportKind::UnexpectedToken
+					.title("Expected pattern")
+					.span(token.span).as_err(),
+			};
+
+			self.advance_if(|t| matches!(t, TokenKind::FatArrowRight)).then_some(())
+				.ok_or_else(|| ReportKind::UnexpectedToken
+					.title(format!("Expected '=>', got '{:?}'", self.current().kind))
+					.span(self.current().span))?;
+
+			let body = match self.current().kind {
+				TokenKind::LBrace => {
+					let body = self.parse_braced()?;
+					self.advance_if(|t| matches!(t, TokenKind::Comma));
+					body
+				},
+				TokenKind::KWIf | TokenKind::KWWhile | TokenKind::KWLoop | TokenKind::KWMatch => {
+					let stmt = self.parse_control()?;
+					self.advance_if(|t| matches!(t, TokenKind::Comma));
+					vec![stmt]
+				},
+				_ => {
+					let stmt = self.parse_bare_stmt()?;
+					self.parse_list_sep()?;
+					vec![stmt]
+				},
+			};
+
+			arms.push((pat, body));
+		}
+
+		Ok(Node::Match { expr, arms })
+	}
+
+	/// `if x {` has to open the body rather than a struct literal
+	fn parse_cond(&mut self) -> Result<Sp<Node<'src>>> {
+		self.no_struct = true;
+		let cond = self.parse_expr();
+		self.no_struct = false;
+		cond
+	}
+
+	fn parse_braced(&mut self) -> Result<Vec<Sp<Node<'src>>>> {
+		self.advance_if(|t| matches!(t, TokenKind::LBrace)).then_some(())
+			.ok_or_else(|| ReportKind::UnexpectedToken
+				.title(format!("Expected '{{', got '{:?}'", self.current().kind))
+				.span(self.current().span))?;
+
+		self.parse_block()
 	}
 
 	fn parse_expr(&mut self) -> Result<Sp<Node<'src>>> {
+		self.parse_binary(0)
+	}
+
+	/// Precedence climbing, every operator binds tighter than the ones before it
+	fn parse_binary(&mut self, min_prec: u8) -> Result<Sp<Node<'src>>> {
+		let mut lhs = self.parse_cast()?;
+
+		while let Some((op, prec)) = binop(self.current().kind) {
+			if prec < min_prec { break; }
+
+			let token = self.current();
+			self.advance();
+
+			// all binary operators are left associative
+			let rhs = self.parse_binary(prec + 1)?;
+			let span = lhs.span.extend(&rhs.span);
+
+			lhs = Node::BinOp {
+				op:  op.span(token.span),
+				lhs: Box::new(lhs),
+				rhs: Box::new(rhs),
+			}.span(span);
+		}
+
+		Ok(lhs)
+	}
+
+	/// `x as T` binds tighter than any binary operator, but looser than the unary ones
+	fn parse_cast(&mut self) -> Result<Sp<Node<'src>>> {
+		let mut expr = self.parse_unary()?;
+
+		while self.advance_if(|t| matches!(t, TokenKind::KWAs)) {
+			let ty = self.parse_type()?;
+			let span = expr.span.extend(&ty.span);
+			expr = Node::Cast { expr: Box::new(expr), ty }.span(span);
+		}
+
+		Ok(expr)
+	}
+
+	fn parse_unary(&mut self) -> Result<Sp<Node<'src>>> {
+		let token = self.current();
+
+		let op = match token.kind {
+			TokenKind::Minus => UnOp::Neg,
+			TokenKind::Bang  => UnOp::Not,
+			TokenKind::Tilde => UnOp::BitNot,
+			TokenKind::Ampersand | TokenKind::At => {
+				self.advance();
+				let expr = Box::new(self.parse_unary()?);
+				let span = token.span.extend(&expr.span);
+
+				return Ok(match token.kind {
+					TokenKind::Ampersand => Node::AddrOf(expr),
+					_ => Node::Deref(expr),
+				}.span(span));
+			},
+			_ => return self.parse_postfix(),
+		};
+
+		self.advance();
+		let expr = self.parse_unary()?;
+		let span = token.span.extend(&expr.span);
+
+		Ok(match expr.elem {
+			Node::UIntLit(v) if op == UnOp::Neg => Node::SIntLit(-v),
+			_ => Node::UnOp { op: op.span(token.span), expr: Box::new(expr) },
+		}.span(span))
+	}
+
+	fn parse_postfix(&mut self) -> Result<Sp<Node<'src>>> {
+		let mut expr = self.parse_atom()?;
+
+		loop {
+			let token = self.current();
+			match token.kind {
+				TokenKind::Question => {
+					self.advance();
+					let span = expr.span.extend(&token.span);
+					expr = Node::Unwrap(Box::new(expr)).span(span);
+					continue;
+				},
+				TokenKind::LBracket => {
+					self.advance();
+					let index = self.parse_nested_expr()?;
+
+					self.advance_if(|t| matches!(t, TokenKind::RBracket)).then_some(())
+						.ok_or_else(|| ReportKind::UnexpectedToken
+							.title("Expected ']'")
+							.span(self.current().span))?;
+
+					let span = expr.span.extend(&self.peek(-1).unwrap().span);
+					expr = Node::Index { expr: Box::new(expr), index: Box::new(index) }.span(span);
+					continue;
+				},
+				TokenKind::Dot => self.advance(),
+				_ => break,
+			}
+
+			let token = self.current();
+			self.advance_if(|t| matches!(t, TokenKind::Identifier)).then_some(())
+				.ok_or_else(|| ReportKind::UnexpectedToken
+					.title("Expected field name")
+					.span(token.span))?;
+
+			expr = match self.current().kind {
+				TokenKind::LParen => {
+					let args = self.parse_args()?;
+					let span = expr.span.extend(&self.peek(-1).unwrap().span);
+					Node::MethodCall {
+						expr: Box::new(expr),
+						name: token.text.span(token.span),
+						args,
+					}.span(span)
+				},
+				_ => {
+					let span = expr.span.extend(&token.span);
+					Node::Field {
+						expr:  Box::new(expr),
+						field: token.text.span(token.span),
+					}.span(span)
+				},
+			};
+		}
+
+		Ok(expr)
+	}
+
+	/// Parses an expression that is delimited by the current token, where struct literals are fine again
+	fn parse_nested_expr(&mut self) -> Result<Sp<Node<'src>>> {
+		let no_struct = std::mem::replace(&mut self.no_struct, false);
+		let expr = self.parse_expr();
+		self.no_struct = no_struct;
+		expr
+	}
+
+	/// `(a, b, ...)`, starting at the '('
+	fn parse_args(&mut self) -> Result<Vec<Sp<Node<'src>>>> {
+		self.advance();
+
+		let mut args = Vec::new();
+		loop {
+			match self.current().kind {
+				TokenKind::RParen => {
+					self.advance();
+					break;
+				},
+				TokenKind::Comma => self.advance(),
+				TokenKind::EOF => return ReportKind::UnexpectedEOF
+					.title("Expected ')'")
+					.span(self.peek(-1).unwrap().span).as_err(),
+				_ => args.push(self.parse_nested_expr()?)
+			}
+		}
+		Ok(args)
+	}
+
+	fn parse_atom(&mut self) -> Result<Sp<Node<'src>>> {
 		let token = self.current();
 
 		let ast = match token.kind {
+			TokenKind::LParen => {
+				self.advance();
+				let expr = self.parse_nested_expr()?;
+
+				self.advance_if(|t| matches!(t, TokenKind::RParen)).then_some(())
+					.ok_or_else(|| ReportKind::UnexpectedToken
+						.title("Expected ')'")
+						.span(self.current().span))?;
+
+				return Ok(expr);
+			},
 			TokenKind::Dollar => {
 				self.advance();
 				let token = self.current();
@@ -299,35 +941,16 @@ impl<'src> Parser<'src> {
 						.span(token.span).as_err();
 				}
 
-				let name = token.text.span(token.span);
-
-				// TODO: generic parsing
-
 				self.advance();
+				let name = self.parse_path(token);
+				let ty_args = self.parse_ty_args()?;
 				let args = match self.current().kind {
-					TokenKind::LParen => {
-						self.advance();
-
-						let mut args = Vec::new();
-						loop {
-							match self.current().kind {
-								TokenKind::RParen => {
-									self.advance();
-									break;
-								},
-								TokenKind::Comma => self.advance(),
-								TokenKind::EOF => return ReportKind::UnexpectedEOF
-									.title("Expected ')'")
-									.span(self.peek(-1).unwrap().span).as_err(),
-								_ => args.push(self.parse_expr()?)
-							}
-						}
-						args
-					},
-					_ => vec![self.parse_expr()?],
+					TokenKind::LParen => self.parse_args()?,
+					// `$f x + 1` calls f with x, not x + 1
+					_ => vec![self.parse_unary()?],
 				};
 
-				Node::FuncCall { name, args }
+				Node::FuncCall { name, ty_args, args }
 			},
 			TokenKind::StringLiteral => {
 				let text = token.text;
@@ -337,7 +960,7 @@ impl<'src> Parser<'src> {
 				let mut escape_flag = false;
 				for c in text.chars() {
 					if escape_flag {
-						new_text.push(parse_char(c));
+						new_text.push(parse_char(c).ok_or_else(|| invalid_escape(c, token))?);
 						escape_flag = false; 
 					} else if c == '\\' {
 						escape_flag = true;
@@ -349,14 +972,108 @@ impl<'src> Parser<'src> {
 				self.advance();
 				Node::StrLit(new_text)
 			},
-			TokenKind::DecimalIntLiteral => {
+			TokenKind::DecimalIntLiteral | TokenKind::BinaryIntLiteral
+				| TokenKind::OctalIntLiteral | TokenKind::HexadecimalIntLiteral => {
 				self.advance();
-				Node::UIntLit(token.text.parse::<IBig>()
+
+				let (digits, radix) = match token.kind {
+					TokenKind::BinaryIntLiteral      => (&token.text[2..], 2),
+					TokenKind::OctalIntLiteral       => (&token.text[2..], 8),
+					TokenKind::HexadecimalIntLiteral => (&token.text[2..], 16),
+					_ => (token.text, 10),
+				};
+
+				Node::UIntLit(IBig::from_str_radix(&digits.replace('_', ""), radix)
 					.map_err(|_| ReportKind::InvalidNumber
 						.title("Invalid integer literal")
 						.span(token.span))?)
-			}, // TODO: other int literals + signed
+			}, // TODO: signed
+			TokenKind::KWNone => {
+				self.advance();
+				Node::None
+			},
+			TokenKind::LBracket => {
+				self.advance();
+
+				let mut elems = Vec::new();
+				loop {
+					match self.current().kind {
+						TokenKind::RBracket => {
+							self.advance();
+							break;
+						},
+						TokenKind::EOF => return ReportKind::UnexpectedEOF
+							.title("Expected ']'")
+							.span(self.peek(-1).unwrap().span).as_err(),
+						_ => elems.push(self.parse_nested_expr()?),
+					}
+
+					match self.current().kind {
+						TokenKind::Comma => self.advance(),
+						TokenKind::RBracket => (),
+						_ => return ReportKind::UnexpectedToken
+							.title(format!("Expected ',' or ']', got '{:?}'", self.current().kind))
+							.span(self.current().span).as_err(),
+					}
+				}
+
+				Node::ArrLit(elems)
+			},
+			TokenKind::FloatLiteral => {
+				self.advance();
+				Node::FloatLit(token.text.replace('_', "").parse::<f64>()
+					.map_err(|_| ReportKind::InvalidNumber
+						.title("Invalid float literal")
+						.span(token.span))?)
+			},
+			TokenKind::CharLiteral => {
+				self.advance();
+
+				let mut chars = token.text.chars();
+				Node::CharLit(match (chars.next(), chars.next()) {
+					(Some('\\'), Some(c)) => parse_char(c).ok_or_else(|| invalid_escape(c, token))?,
+					(Some(c), None) => c,
+					_ => return ReportKind::SyntaxError
+						.title("Invalid character literal")
+						.span(token.span).as_err(),
+				})
+			},
+
+			TokenKind::Identifier if !self.no_struct && self.at_struct_lit() => {
+				self.advance();
+				let name = self.parse_path(token);
+				self.advance();
 
+				let mut fields = Vec::new();
+				loop {
+					let field = self.current();
+					match field.kind {
+						TokenKind::RBrace => {
+							self.advance();
+							break;
+						},
+						TokenKind::Identifier => {
+							self.advance();
+
+							self.advance_if(|t| matches!(t, TokenKind::Colon)).then_some(())
+								.ok_or_else(|| ReportKind::UnexpectedToken
+									.title(format!("Expected ':', got '{:?}'", self.current().kind))
+									.span(self.current().span))?;
+
+							fields.push((field.text.span(field.span), self.parse_nested_expr()?));
+							self.parse_list_sep()?;
+						},
+						TokenKind::EOF => return ReportKind::UnexpectedEOF
+							.title("Expected '}'")
+							.span(self.peek(-1).unwrap().span).as_err(),
+						_ => return ReportKind::UnexpectedToken
+							.title("Expected field name")
+							.span(field.span).as_err(),
+					}
+				}
+
+				Node::StructLit { name, fields }
+			},
 			// FIXME: naive impl
 			TokenKind::Identifier => {
 				self.advance();
@@ -368,9 +1085,7 @@ impl<'src> Parser<'src> {
 				.span(token.span).as_err(),
 		};
 
-		// TODO:
-		// Verify span.
-		Ok(ast.span(token.span.extend(&self.current().span)))
+		Ok(ast.span(token.span.extend(&self.peek(-1).unwrap().span)))
 	}
 
 	fn parse_type(&mut self) -> Result<Sp<Type<'src>>> {
@@ -382,33 +1097,50 @@ impl<'src> Parser<'src> {
 			TokenKind::LBracket => {
 				let ty = self.parse_type()?;
 
+				// `[T:N]` has its size inline, `[T]` is a slice
+				let size = match self.advance_if(|t| matches!(t, TokenKind::Colon)) {
+					true => {
+						let size = self.current();
+						self.advance();
+						Some(match size.kind {
+							TokenKind::DecimalIntLiteral => size.text.replace('_', "").parse()
+								.map_err(|_| ReportKind::InvalidNumber
+									.title("Invalid array size")
+									.span(size.span))?,
+							_ => return ReportKind::UnexpectedToken
+								.title("Expected array size")
+								.span(size.span).as_err(),
+						})
+					},
+					false => None,
+				};
+
 				self.advance_if(|t| matches!(t, TokenKind::RBracket)).then_some(())
 					.ok_or_else(|| ReportKind::UnexpectedToken
 						.title("Expected ']'")
 						.span(self.current().span))?;
 
-				// TODO: array size
-				Type::Arr(Box::new(ty), None).span(token.span.extend(&self.current().span))
+				Type::Arr(Box::new(ty), size).span(token.span.extend(&self.peek(-1).unwrap().span))
 			},
 			TokenKind::Identifier => match token.text {
 				"isize" => Type::Isize,
 				"usize" => Type::Usize,
-				n if n.starts_with('u') => Type::U(n[1..].parse()
+				n if is_prim(n, 'u') => Type::U(n[1..].parse()
 					.map_err(|_| ReportKind::InvalidNumber
 						.title("Invalid integer in primitive type")
 						.label("try 'u8'")
 						.span(token.span))?),
-				n if n.starts_with('i') => Type::I(n[1..].parse()
+				n if is_prim(n, 'i') => Type::I(n[1..].parse()
 					.map_err(|_| ReportKind::InvalidNumber
 						.title("Invalid integer in primitive type")
 						.label("try 'i8'")
 						.span(token.span))?),
-				n if n.starts_with('b') => Type::B(n[1..].parse()
+				n if is_prim(n, 'b') => Type::B(n[1..].parse()
 					.map_err(|_| ReportKind::InvalidNumber
 						.title("Invalid integer in primitive type")
 						.label("try 'b8'")
 						.span(token.span))?),
-				n if n.starts_with('f') => Type::F(n[1..].parse()
+				n if is_prim(n, 'f') => Type::F(n[1..].parse()
 					.map_err(|_| ReportKind::InvalidNumber
 						.title("Invalid integer in primitive type")
 						.label("try 'f8'")
@@ -417,7 +1149,13 @@ impl<'src> Parser<'src> {
 				"never" => Type::Never,
 				"opt"   => Type::Opt(Box::new(self.parse_type()?)),
 				"mut"   => Type::Mut(Box::new(self.parse_type()?)),
-				n => Type::Ident(n),
+				_ => {
+					let name = self.parse_path(token);
+					if self.current().kind != TokenKind::LessThan { return Ok(Type::Ident(name.elem).span(name.span)); }
+
+					let args = self.parse_ty_args()?;
+					return Ok(Type::Generic(name.elem, args).span(token.span.extend(&self.peek(-1).unwrap().span)));
+				},
 			}.span(token.span),
 			_ => return ReportKind::UnexpectedToken
 				.title("Expected type")
@@ -426,8 +1164,56 @@ impl<'src> Parser<'src> {
 	}
 }
 
-fn parse_char(chunk: char) -> char {
-	match chunk {
+/// `u32`, `i8`... rather than a type that happens to start with the same letter
+fn is_prim(name: &str, c: char) -> bool {
+	name.strip_prefix(c).is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Names `parse_type` always reads as a builtin type
+fn is_builtin(name: &str) -> bool {
+	matches!(name, "isize" | "usize" | "void" | "never" | "opt" | "mut")
+		|| ['u', 'i', 'b', 'f'].into_iter().any(|c| is_prim(name, c))
+}
+
+/// The operator a token stands for in a binary expression, and its precedence
+fn binop(kind: TokenKind) -> Option<(BinOp, u8)> {
+	Some(match kind {
+		TokenKind::PipePipe           => (BinOp::Or,  1),
+		TokenKind::CaretCaret         => (BinOp::Xor, 2),
+		TokenKind::AmpersandAmpersand => (BinOp::And, 3),
+
+		TokenKind::Equals            => (BinOp::Eq, 4),
+		TokenKind::NotEquals         => (BinOp::Ne, 4),
+		TokenKind::LessThan          => (BinOp::Lt, 4),
+		TokenKind::LessThanEquals    => (BinOp::Le, 4),
+		TokenKind::GreaterThan       => (BinOp::Gt, 4),
+		TokenKind::GreaterThanEquals => (BinOp::Ge, 4),
+
+		TokenKind::Pipe      => (BinOp::BitOr,  5),
+		TokenKind::Caret     => (BinOp::BitXor, 6),
+		TokenKind::Ampersand => (BinOp::BitAnd, 7),
+
+		TokenKind::ShiftLeft  => (BinOp::Shl, 8),
+		TokenKind::ShiftRight => (BinOp::Shr, 8),
+
+		TokenKind::Plus  => (BinOp::Add, 9),
+		TokenKind::Minus => (BinOp::Sub, 9),
+
+		TokenKind::Star    => (BinOp::Mul, 10),
+		TokenKind::Slash   => (BinOp::Div, 10),
+		TokenKind::Percent => (BinOp::Rem, 10),
+		_ => return None,
+	})
+}
+
+fn invalid_escape(c: char, token: Token) -> crate::report::Report {
+	ReportKind::SyntaxError
+		.title(format!("Invalid escape sequence '\\{c}'"))
+		.span(token.span)
+}
+
+fn parse_char(chunk: char) -> Option<char> {
+	Some(match chunk {
 		'0' | '@' => '\x00',
 		'A'       => '\x01',
 		'B'       => '\x02',
@@ -461,7 +1247,9 @@ fn parse_char(chunk: char) -> char {
 		'^'       => '\x1E',
 		'_'       => '\x1F',
 		'?'       => '\x7F',
-		// '"'       => '\\',
-		_ => unreachable!(),
-	}
+		'\\'      => '\\',
+		'\''      => '\'',
+		'"'       => '"',
+		_ => return None,
+	})
 }
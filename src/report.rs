@@ -13,6 +13,13 @@ pub static ERR_COUNT: AtomicUsize = AtomicUsize::new(0);
 pub enum ReportKind {
 	_NOTE_,
 	_WARNING_,
+	LossyCast,
+	UnreachableCode,
+	UnusedVariable,
+	UnusedFunction,
+	UnusedImport,
+	UnusedResult,
+
 	_ERROR_,
 	ArgumentParserError,
 
@@ -30,7 +37,12 @@ pub enum ReportKind {
 	// Analyzer
 	TypeError,
 	UndefinedSym,
+	PrivateSym,
 	InvalidArgCount,
+	CyclicType,
+
+	// Interpreter
+	RuntimeError,
 
 	// General
 	IOError,
@@ -47,6 +59,7 @@ impl ReportKind {
 			title:     None,
 			span:      None,
 			label:     None,
+			related:   Vec::new(),
 			footers:   None,
 		}
 	}
@@ -60,6 +73,7 @@ impl ReportKind {
 			title:     Some(title.to_string()),
 			span:      None,
 			label:     None,
+			related:   Vec::new(),
 			footers:   None,
 		}
 	}
@@ -72,6 +86,7 @@ pub struct Report {
 	title:   Option<String>,
 	span:    Option<Span>,
 	label:   Option<String>,
+	related: Vec<(Span, String)>, // other places the report is about, like a declaration
 	footers: Option<Vec<String>>,
 }
 
@@ -84,6 +99,10 @@ impl Report {
 		self.label = Some(label.to_string()); self
 	}
 
+	pub fn related<T: Display>(mut self, span: Span, label: T) -> Self {
+		self.related.push((span, label.to_string())); self
+	}
+
 	pub fn help<T: Display>(self, help: T) -> Self {
 		self.footer(format!("HELP: {help}"))
 	}
@@ -108,8 +127,10 @@ impl Report {
 		self
 	}
 
+	/// The file the spans are in, a report which already has one is from another module and keeps it
 	pub fn file(mut self, file: &'static str) -> Self {
-		self.file = file; self
+		if self.file.is_empty() { self.file = file; }
+		self
 	}
 }
 
@@ -142,53 +163,67 @@ impl Display for Report {
 			self.title.as_ref().unwrap_or(&String::new()))?;
 
 		let mut padding = String::new();
-		if let Some(span) = &self.span {
-			let file = CACHE.get(self.file);
-
-			let mut line = 1;
-			let mut line_start = 0;
-			while let Some(pos) = file[line_start..].find('\n') {
-				if line_start + pos >= span.start { break; }
-				line_start += pos + 1;
-				line += 1;
+		if let Some(span) = self.span {
+			padding = self.snippet(f, span, self.label.as_deref(), '^', (primary, secondary))?;
+			for (span, label) in &self.related {
+				self.snippet(f, *span, Some(label), '-', (Color::Cyan, Color::Cyan))?;
 			}
+		}
+
+		if let Some(footers) = &self.footers {
+			for footer in footers {
+				writeln!(f, "{}{}", padding, footer.bright_black().italic())?;
+			}
+		}
+
+		Ok(())
+	}
+}
 
-			let col = span.start - line_start + 1;
+impl Report {
+	/// Writes the line `span` is on with it underlined, yielding the padding lines below it should use
+	fn snippet(&self, f: &mut Formatter<'_>, span: Span, label: Option<&str>, mark: char, (primary, secondary): (Color, Color))
+	-> std::result::Result<String, std::fmt::Error> {
+		let file = CACHE.get(self.file);
+
+		let mut line = 1;
+		let mut line_start = 0;
+		while let Some(pos) = file[line_start..].find('\n') {
+			if line_start + pos >= span.start { break; }
+			line_start += pos + 1;
+			line += 1;
+		}
 
-			writeln!(f, " {} {}:{line}:{col}", 
-				"-->".cyan(), self.file)?;
+		let col = span.start - line_start + 1;
 
-			let line_str = line.to_string();
+		writeln!(f, " {} {}:{line}:{col}", 
+			"-->".cyan(), self.file)?;
 
-			padding = format!("{} {} ",
-				" ".repeat(line_str.len()),
-				"|".cyan().dimmed());
+		let line_str = line.to_string();
 
-			let Some(line) = file.lines().nth(line - 1) else {
-				return writeln!(f, "{padding}{}",
-					"Could not fetch line.".color(Color::Red).bold());
-			};
+		let padding = format!("{} {} ",
+			" ".repeat(line_str.len()),
+			"|".cyan().dimmed());
 
-			let trimmed_start = file[line_start..span.start].trim_start();
+		let Some(line) = file.lines().nth(line - 1) else {
+			writeln!(f, "{padding}{}",
+				"Could not fetch line.".color(Color::Red).bold())?;
+			return Ok(padding);
+		};
 
-			writeln!(f, "{padding}{}{}{}",
-				&trimmed_start,
-				file[span.start..=span.end].color(secondary).bold(),
-				&file[span.end+1..line_start + line.len()].trim_end())?;
+		let trimmed_start = file[line_start..span.start].trim_start();
 
-			writeln!(f, "{padding}{}{} {}",
-				" ".repeat(trimmed_start.len()),
-				"^".repeat(span.end+1 - span.start).color(primary).bold(),
-				self.label.as_ref().unwrap_or(&String::new()))?;
-		}
+		writeln!(f, "{padding}{}{}{}",
+			&trimmed_start,
+			file[span.start..=span.end].color(secondary).bold(),
+			&file[span.end+1..line_start + line.len()].trim_end())?;
 
-		if let Some(footers) = &self.footers {
-			for footer in footers {
-				writeln!(f, "{}{}", padding, footer.bright_black().italic())?;
-			}
-		}
+		writeln!(f, "{padding}{}{} {}",
+			" ".repeat(trimmed_start.len()),
+			mark.to_string().repeat(span.end+1 - span.start).color(primary).bold(),
+			label.unwrap_or_default())?;
 
-		Ok(())
+		Ok(padding)
 	}
 }
 
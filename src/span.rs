@@ -1,7 +1,7 @@
 use std::fmt::{self, Display};
 use colored::Colorize;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Span {
 	pub start: usize,
 	pub end:   usize,
@@ -0,0 +1,1161 @@
+//! Runs the compiler on small programs, checking that they build for every target, what they return when
+//! interpreted and what is reported about them
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const TARGETS: [&str; 5] = ["llvm", "qbe", "c", "x86_64", "wasm"];
+
+/// Writes `files` into a directory of their own, the first one is the root module
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("sharc-{}-{name}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	for (file, src) in files {
+		let path = dir.join(file);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(path, src).unwrap();
+	}
+	dir.join(files[0].0)
+}
+
+fn sharc(file: &PathBuf, args: &[&str]) -> Output {
+	Command::new(env!("CARGO_BIN_EXE_sharc"))
+		.args(args)
+		.arg("-f").arg(file)
+		.arg("-o").arg(file.with_extension("out"))
+		.env("NO_COLOR", "1")
+		.output()
+		.unwrap()
+}
+
+fn build(name: &str, src: &str, target: &str) -> Output {
+	sharc(&project(name, &[("main.shd", src)]), &["-t", target])
+}
+
+fn run(name: &str, src: &str) -> Output {
+	sharc(&project(name, &[("main.shd", src)]), &["run"])
+}
+
+/// Builds `src` for `target` and runs it, `None` if the tools the target needs are not installed
+fn exec(name: &str, src: &str, target: &str) -> Option<i32> {
+	exec_file(&project(name, &[("main.shd", src)]), target, &[])
+}
+
+/// Builds the project whose root is `file` for `target`, with `args` given to sharc, and runs it
+fn exec_file(file: &PathBuf, target: &str, args: &[&str]) -> Option<i32> {
+	let out = sharc(file, &[args, &["-t", target]].concat());
+	assert!(out.status.success(), "target {target}:\n{}", stderr(&out));
+
+	let (code, asm, exe) = (file.with_extension("out"), file.with_extension("s"), file.with_extension("exe"));
+	let (src, lang) = match target {
+		// older versions need to be told that pointers are untyped
+		"llvm" => return [&["-opaque-pointers"][..], &[]].into_iter()
+			.filter_map(|flags| Command::new("lli").args(flags).arg(&code).output().ok())
+			.find(|out| out.stderr.is_empty())
+			.and_then(|out| out.status.code()),
+		"qbe" => {
+			let qbe = Command::new("qbe").arg("-o").arg(&asm).arg(&code).status().ok()?;
+			assert!(qbe.success());
+			(&asm, "assembler")
+		},
+		"c" => (&code, "c"),
+		"x86_64" => (&code, "assembler"),
+		_ => return None,
+	};
+
+	let cc = Command::new("cc").args(["-w", "-o"]).arg(&exe).arg("-x").arg(lang).arg(src).status().ok()?;
+	assert!(cc.success());
+	Command::new(&exe).output().unwrap().status.code()
+}
+
+fn stderr(out: &Output) -> String {
+	String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+fn assert_no_panic(out: &Output) {
+	assert!(!stderr(out).contains("panicked"), "the compiler panicked:\n{}", stderr(out));
+}
+
+#[test]
+fn qbe_backend() {
+	let src = "fn fib(n: i32) i32 {\n\tif n < 2 { ret n; }\n\tret $fib(n - 1) + $fib(n - 2);\n}\n\
+		fn count(s: *u8) i32 {\n\tlet n: mut usize = 0;\n\twhile s[n] ~= 0 { n = n + 1; }\n\tret n as i32;\n}\n\
+		export fn main() i32 {\n\tlet i: mut i64 = 0;\n\tlet acc: mut i64 = 0;\n\twhile i < 10 {\n\t\tacc = acc + i * 3;\n\t\ti = i + 1;\n\t}\n\
+		\tret $fib(10) + acc as i32 + $count(\"abc\\0\");\n}\n";
+
+	assert_eq!(run("qbe", src).status.code(), Some(193));
+	let file = project("qbe-il", &[("main.shd", src)]);
+	let out = sharc(&file, &["-t", "qbe"]);
+	assert!(out.status.success(), "{}", stderr(&out));
+
+	let il = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	assert!(il.contains("export function w $main()") && il.contains("b \"abc\", b 0"), "{il}");
+	if let Some(code) = exec("qbe-run", src, "qbe") { assert_eq!(code, 193); }
+
+	let fails = [
+		("fn f(a: f64) f64 { ret a % 2.0; }", "Floating point remainder is not supported on the qbe target"),
+		("fn f(a: u128) u128 { ret a; }", "bit width for integer on the qbe target"),
+		("fn f(a: opt i32) opt i32 { ret a; }", "Optionals are not yet supported on the qbe target"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = build(&format!("qbe-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"), "qbe");
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn x86_64_backend() {
+	// more values are live than there are registers, and args are passed on the stack past the sixth
+	let src = "fn fib(n: i32) i32 {\n\tif n < 2 { ret n; }\n\tret $fib(n - 1) + $fib(n - 2);\n}\n\
+		fn many(a: i64, b: i64, c: i64, d: i64, e: i64, f: i64, g: i64, h: i64) i64 {\n\
+		\tlet x = a * b + c * d;\n\tlet y = e * f + g * h;\n\tlet z = (a + b) * (c + d) - (e + f) * (g + h);\n\tret x + y + z + a + h;\n}\n\
+		export fn main() i32 {\n\tlet s: *u8 = \"abc\\0\";\n\tlet i: mut i64 = 0;\n\tlet acc: mut i64 = 0;\n\
+		\twhile i < 10 {\n\t\tacc = acc + i * 3;\n\t\ti = i + 1;\n\t}\n\
+		\tret $fib(10) + acc as i32 + s[2] as i32 + ($many(1, 2, 3, 4, 5, 6, 7, 8) % 100) as i32;\n}\n";
+
+	assert_eq!(run("x86", src).status.code(), Some(254));
+	if let Some(code) = exec("x86-run", src, "x86_64") { assert_eq!(code, 254); }
+
+	let fails = [
+		("fn f() i32 { let a: [i32:2] = [1, 2]; ret a[0]; }", "Stack arrays are not yet supported on the x86_64 target"),
+		("fn f() *i32 { let a = 1; ret &a; }", "Taking the address of a local is not yet supported on the x86_64 target"),
+		("fn f(a: f64) f64 { ret a; }", "Floating point is not yet supported on the x86_64 target"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = build(&format!("x86-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"), "x86_64");
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn wasm_backend() {
+	let src = "extern fn putchar(c: i32) i32;\nfn fib(n: i32) i32 {\n\tif n < 2 { ret n; }\n\tret $fib(n - 1) + $fib(n - 2);\n}\n\
+		export fn main() i32 {\n\tlet s: *u8 = \"abc\\0\";\n\tlet _ = $putchar(s[0] as i32);\n\tret $fib(10);\n}\n";
+
+	let file = project("wasm", &[("main.shd", src)]);
+	let out = sharc(&file, &["-t", "wasm"]);
+	assert!(out.status.success(), "{}", stderr(&out));
+
+	// externs are imported from the host, which reads strings out of the exported memory
+	let wat = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	for part in ["(import \"env\" \"putchar\" (func $putchar (param i32) (result i32)))", "(memory (export \"memory\") 1)",
+		"\"abc\\00\")", "(func $main (export \"main\") (result i32)"] {
+		assert!(wat.contains(part), "{part} in:\n{wat}");
+	}
+
+	let fails = [
+		("fn g() {}\nfn f() { let h = g; $h(); }", "Function values are not yet supported on the wasm target"),
+		("fn f(a: opt i32) opt i32 { ret a; }", "Optionals are not yet supported on the wasm target"),
+		("fn f() i32 { let a: [i32:2] = [1, 2]; ret a[0]; }", "Stack arrays are not yet supported on the wasm target"),
+		("fn f(a: u128) u128 { ret a; }", "bit width for integer on the wasm target"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = build(&format!("wasm-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"), "wasm");
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn interpreter() {
+	// externs are provided by the host
+	let src = "extern fn puts(s: *u8) i32;\nextern fn exit(c: i32);\nexport fn main() i32 {\n\tlet _ = $puts(\"hi\\0\");\n\t$exit(7);\n\tret 0;\n}\n";
+	let out = run("interp", src);
+	assert_eq!(out.status.code(), Some(7));
+	assert_eq!(String::from_utf8_lossy(&out.stdout), "hi\n");
+
+	let fails = [
+		("fn d(a: i32) i32 { ret 10 / a; }\nexport fn main() i32 { ret $d(0); }", "Division by zero"),
+		("extern fn nope() i32;\nexport fn main() i32 { ret $nope(); }", "Extern function 'nope' is not available in the interpreter"),
+		("extern fn abort();\nexport fn main() i32 { $abort(); ret 0; }", "Program aborted"),
+		("fn _main() i32 { ret 0; }", "No entry point found"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("interp-err-{i}"), &format!("{src}\n"));
+		assert_no_panic(&out);
+		assert_eq!(out.status.code(), Some(1));
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn function_values() {
+	let src = "fn two() i32 { ret 2; }\nexport fn main() i32 { let f = two; ret $f(); }\n";
+
+	assert_eq!(run("fnval", src).status.code(), Some(2));
+	for target in TARGETS {
+		let out = build(&format!("fnval-{target}"), src, target);
+		assert_no_panic(&out);
+
+		// wasm has no function values yet, which it reports
+		assert_eq!(out.status.success(), target != "wasm", "target {target}:\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn arrays() {
+	// sized arrays are copied by value, and decay to a slice which knows its length
+	let src = "fn sum(xs: [i32]) i32 {\n\tlet total: mut i32 = 0;\n\tlet i: mut usize = 0;\n\
+		\twhile i < xs.len {\n\t\ttotal = total + xs[i];\n\t\ti = i + 1;\n\t}\n\tret total;\n}\n\
+		fn fill(xs: [mut i32], v: i32) {\n\tlet i: mut usize = 0;\n\twhile i < xs.len {\n\t\txs[i] = v;\n\t\ti = i + 1;\n\t}\n}\n\
+		fn make() [i32:3] { ret [7, 8, 9]; }\n\
+		export fn main() i32 {\n\tlet a: mut [i32:4] = [1, 2, 3, 4];\n\ta[2] = 5;\n\tlet b: [i32:4] = a;\n\ta[0] = 9;\n\
+		\tlet m = $make();\n\tlet r = $sum(a) + b[0] * 100 + m[1] + m.len as i32;\n\t$fill(a, 1);\n\tret r + $sum(a) * 10;\n}\n";
+
+	assert_eq!(run("array", src).status.code(), Some(171));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("array-{target}"), src, target) { assert_eq!(code, 171, "target {target}"); }
+	}
+
+	let file = project("array-ir", &[("main.shd", src)]);
+	assert!(sharc(&file, &[]).status.success());
+	let ir = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	assert!(ir.contains("define internal i32 @g1({ ptr, i64 } %a1)") && ir.contains("alloca [3 x i32]"), "{ir}");
+
+	// an index past the end stops the program
+	let src = "fn idx(i: usize) usize { ret i; }\nexport fn main() i32 {\n\tlet a: [i32:3] = [1, 2, 3];\n\tret a[$idx(3)];\n}\n";
+	let out = run("array-oob", src);
+	assert_eq!(out.status.code(), Some(1));
+	assert!(stderr(&out).contains("Index out of bounds"), "{}", stderr(&out));
+
+	let fails = [
+		("fn f() { let _a: [i32:2] = [1, 2, 3]; }", "expected '[i32:2]', found '[i32:3]'"),
+		("fn f() { let a: [i32:2] = [1, 2]; let _b = a[2]; }", "Index 2 is out of bounds"),
+		("fn f(a: i32) { let _b = a[0]; }", "Cannot index a 'i32', it is not an array"),
+		("fn f() { let _a: [i32:2] = [1, 1.5]; }", "Type mismatch in array literal"),
+		("fn f() { let a: [i32:2] = [1, 2]; let _b = a[1.5]; }", "Cannot index with a '{float}'"),
+		("fn f() { let a: [i32:2] = [1, 2]; a[0] = 3; }", "Cannot assign to an element of 'a', it is not mutable"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("array-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn bounds_checks() {
+	let src = "fn idx(i: usize) usize { ret i; }\nexport fn main() i32 {\n\tlet a: [i32:3] = [1, 2, 3];\n\tlet s: [i32] = a;\n\tret a[$idx(1)] + s[$idx(2)];\n}\n";
+	let file = project("bounds", &[("main.shd", src)]);
+	let code = |args: &[&str]| {
+		let out = sharc(&file, args);
+		assert!(out.status.success(), "{}", stderr(&out));
+		std::fs::read_to_string(file.with_extension("out")).unwrap()
+	};
+
+	assert!(code(&["-t", "c"]).contains("__builtin_trap"));
+	assert!(!code(&["-t", "c", "--no-bounds-checks"]).contains("__builtin_trap"));
+	assert!(!code(&["--no-bounds-checks"]).contains("llvm.trap"));
+
+	// an index known to be past the end is still an error
+	let src = "export fn main() i32 {\n\tlet a: [i32:3] = [1, 2, 3];\n\tret a[3];\n}\n";
+	let out = sharc(&project("bounds-const", &[("main.shd", src)]), &["--no-bounds-checks"]);
+	assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn literals() {
+	let src = "export fn main() i32 {\n\tlet b: i32 = 0b1010;\n\tlet o: i32 = 0o17;\n\tlet h: i32 = 0xFF;\n\tlet u: i32 = 1_000;\n\
+		\tlet c: u8 = 'A';\n\tlet e: u8 = '\\n';\n\tlet f: f64 = 2.5;\n\
+		\tret (b + o + h + u / 100 + c as i32 + e as i32 + (f * 2.0) as i32) % 256;\n}\n";
+
+	assert_eq!(run("lits", src).status.code(), Some(114));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("lits-{target}"), src, target) { assert_eq!(code, 114, "target {target}"); }
+	}
+
+	let fails = [
+		("0b12", "Invalid Integer Literal"),
+		("0xZ", "Invalid Integer Literal"),
+		("1.2.3", "Invalid Float Literal"),
+		("''", "EmptyLiteral"),
+		("'ab'", "UnterminatedLiteral"),
+		("'\\q'", "Invalid escape sequence '\\q'"),
+	];
+
+	for (i, (lit, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("lits-err-{i}"), &format!("export fn main() i32 {{ let _a = {lit}; ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{lit}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn operators() {
+	// `*` binds tighter than `+`, shifts tighter than `&`, then `^`, then `|`, and `&&` tighter than `||`
+	let src = "fn f(a: i32, b: i32, c: b8) i32 {\n\tlet x = a + b * 3 - 20 / 4 % 3;\n\tlet y = (a + b) * 3;\n\
+		\tlet z = a << 2 | b & 6 ^ 1;\n\tlet n = -a + ~b;\n\tlet r: mut i32 = x * 1000 + y * 100 + z + n;\n\
+		\tif !c && a < b || a = 1 { r = r + 5; }\n\tret r;\n}\n\
+		export fn main() i32 {\n\tret $f(2, 5, 1 = 0) % 256;\n}\n";
+
+	assert_eq!(run("ops", src).status.code(), Some(214));
+	for target in ["llvm", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("ops-{target}"), src, target) { assert_eq!(code, 214, "target {target}"); }
+	}
+
+	let fails = [
+		("let _x: i32 = 1 + 2 as u8;", "Type mismatch in assignment"),
+		("let _x = -(1 as u8);", "Operator '-' cannot be applied to type 'u8'"),
+		("let _x = ~(1.0);", "Operator '~' cannot be applied to type '{float}'"),
+		("let _x = !5;", "Operator '!' cannot be applied to type '{uint}'"),
+		("let _x = 1.0 << 2.0;", "Operator '<<' cannot be applied to type '{float}'"),
+	];
+
+	for (i, (stmt, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("ops-err-{i}"), &format!("export fn main() i32 {{ {stmt} ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{stmt}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn control_flow() {
+	let src = "fn classify(n: i32) i32 {\n\tif n < 0 { ret 1; } else if n = 0 { ret 2; } else { ret 3; }\n}\n\
+		export fn main() i32 {\n\tlet i: mut i32 = 0;\n\tlet s: mut i32 = 0;\n\twhile i < 10 {\n\t\ti = i + 1;\n\
+		\t\tif i % 2 = 0 { continue; }\n\t\ts = s + i;\n\t}\n\tlet j: mut i32 = 0;\n\
+		\tloop {\n\t\tj = j + 1;\n\t\tif j = 7 { break; }\n\t}\n\tret s + j * 10 + $classify(-5) * 100;\n}\n";
+
+	assert_eq!(run("cf", src).status.code(), Some(195));
+	for target in ["llvm", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("cf-{target}"), src, target) { assert_eq!(code, 195, "target {target}"); }
+	}
+
+	let fails = [
+		("break;", "'break' outside of a loop"),
+		("continue;", "'continue' outside of a loop"),
+		("if 1 { }", "Condition is not a bool"),
+		("while 2 { }", "Condition is not a bool"),
+	];
+
+	for (i, (stmt, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("cf-err-{i}"), &format!("export fn main() i32 {{ {stmt} ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{stmt}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn literal_ranges() {
+	let fails = [
+		("let _a: u8 = 300;", "Literal '300' does not fit in a 'u8'"),
+		("let _b: i32 = 3000000000;", "Literal '3000000000' does not fit in a 'i32'"),
+		("let _c: i8 = -129;", "Literal '-129' does not fit in a 'i8'"),
+		// the type comes from its uses
+		("let x = 300; let _d: u8 = x;", "Literal '300' does not fit in a 'u8'"),
+	];
+
+	for (i, (stmt, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("lit-{i}"), &format!("export fn main() i32 {{ {stmt} ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert_eq!(out.status.code(), Some(1), "'{stmt}' is accepted");
+		assert!(stderr(&out).contains(msg), "'{stmt}':\n{}", stderr(&out));
+	}
+
+	let src = "export fn main() i32 {\n\tlet _a: u8 = 255;\n\tlet _b: i8 = -128;\n\tlet _c: u64 = 18446744073709551615;\n\tlet d = 300 as u8;\n\tret d as i32;\n}\n";
+	assert_eq!(run("lit-ok", src).status.code(), Some(44));
+}
+
+#[test]
+fn inferred_uses() {
+	// every use has to expect the same type
+	let src = "export fn main() i32 {\n\tlet x = 5;\n\tlet _a: u8 = x;\n\tlet _b: i64 = x;\n\tret 0;\n}\n";
+	let out = run("infer-conflict", src);
+	assert_no_panic(&out);
+	assert_eq!(out.status.code(), Some(1));
+	assert!(stderr(&out).contains("Cannot infer the type of 'x'"), "{}", stderr(&out));
+
+	// the operand is typed by a local bound after it
+	let src = "export fn main() i32 {\n\tlet x = 5;\n\tlet y: i64 = 2;\n\tlet z = x * y;\n\tret z as i32;\n}\n";
+	assert_eq!(run("infer-operand", src).status.code(), Some(10));
+
+	// the type goes through the bindings of the value, and `x + 100` wraps as a u8
+	let chain = "export fn main() i32 {\n\tlet x = 200;\n\tlet y = x;\n\tlet _z: u8 = y;\n\tlet w = x + 100;\n\tret w as i32;\n}\n";
+	// the elements take the type they are returned as
+	let elems = "export fn main() i32 {\n\tlet a = [1, 2, 3];\n\tret a[0] + a[2];\n}\n";
+	// an overload is picked for the binding as it would be for its literal
+	let call = "fn f(a: i32) i32 { ret a + 1; }\nfn f(_a: *u8) i32 { ret 0; }\nexport fn main() i32 {\n\tlet x = 5;\n\tret $f(x);\n}\n";
+
+	for (name, src, code) in [("infer-chain", chain, 44), ("infer-elems", elems, 4), ("infer-call", call, 6)] {
+		assert_eq!(run(name, src).status.code(), Some(code), "{name}");
+		for target in ["llvm", "c"] {
+			if let Some(got) = exec(&format!("{name}-{target}"), src, target) { assert_eq!(got, code, "{name} on {target}"); }
+		}
+	}
+
+	// what nothing constrains takes the default of its kind
+	let src = "export fn main() i32 {\n\tlet x = 5;\n\tlet y = -2;\n\tlet b = x > 3;\n\tif b { ret x * 10 + y; }\n\tret 0;\n}\n";
+	assert_eq!(run("infer-default", src).status.code(), Some(48));
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("infer-default-{target}"), src, target) { assert_eq!(code, 48, "target {target}"); }
+	}
+	let out = build("infer-default-wasm", src, "wasm");
+	assert!(out.status.success(), "{}", stderr(&out));
+
+	// a literal is checked against the type its binding ends up with
+	let out = run("infer-range", "export fn main() i32 {\n\tlet x = 300;\n\tlet _y: u8 = x;\n\tret 0;\n}\n");
+	assert_eq!(out.status.code(), Some(1));
+	assert!(stderr(&out).contains("Literal '300' does not fit in a 'u8'"), "{}", stderr(&out));
+}
+
+#[test]
+fn unused_functions() {
+	let file = project("unused", &[
+		("main.shd", "import util;\nfn rec(n: u32) { if n > 0 { $rec(n - 1); } }\nfn _quiet(n: u32) { if n > 0 { $_quiet(n - 1); } }\n\
+			fn ping(n: u32) { if n > 0 { $pong(n - 1); } }\nfn pong(n: u32) { $ping(n); }\n\
+			fn id<T>(x: T) T { ret x; }\nfn wrap() i32 { ret $id(1); }\nfn twice<T>(x: T) T { ret x + x; }\n\
+			export fn main() i32 { ret $util.one() - 1 + $twice(0); }\n"),
+		("util.shd", "pub fn one() i32 { ret 1; }\npub fn two() i32 { ret 2; }\nfn hidden() i32 { ret 3; }\n"),
+	]);
+	let out = sharc(&file, &["run"]);
+	assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+
+	// calling itself does not count as a use
+	let err = stderr(&out);
+	assert!(err.contains("Function 'rec' is never used"), "{err}");
+	assert!(err.contains("Function 'ping' is never used") && err.contains("Function 'pong' is never used"), "{err}");
+	assert!(err.contains("Function 'id' is never used") && err.contains("Function 'wrap' is never used"), "{err}");
+	assert!(!err.contains("'twice'"), "{err}");
+	assert!(err.contains("Function 'hidden' is never used"), "{err}");
+	// a module exports what is `pub`, and '_' says it is meant to be unused
+	assert!(!err.contains("'one'") && !err.contains("'two'") && !err.contains("'_quiet'"), "{err}");
+}
+
+#[test]
+fn unused_values() {
+	let file = project("unused-values", &[
+		("main.shd", "import util;\nextern fn putchar(c: i32) i32;\n\
+			fn f(a: i32, b: i32, _c: i32) i32 {\n\tlet x = 1;\n\tlet _y = 2;\n\t$putchar(65);\n\tlet _ = $putchar(66);\n\tret a;\n}\n\
+			export fn main() i32 { ret $f(0, 1, 2); }\n"),
+		("util.shd", "pub fn one() i32 { ret 1; }\n"),
+	]);
+	let out = sharc(&file, &["run"]);
+	assert_eq!(out.status.code(), Some(0), "{}", stderr(&out));
+
+	let err = stderr(&out);
+	for msg in ["Unused argument 'b'", "Unused variable 'x'", "main.shd:4:6", "Result of 'putchar' is unused", "main.shd:6:2",
+		"Module 'util' is imported but never used"] {
+		assert!(err.contains(msg), "{msg} in:\n{err}");
+	}
+	// a leading '_', or assigning to '_', says it is meant to be unused
+	assert!(!err.contains("'a'") && !err.contains("'_c'") && !err.contains("'_y'") && !err.contains("main.shd:7:"), "{err}");
+}
+
+#[test]
+fn generic_errors_in_their_module() {
+	let file = project("generic-module", &[
+		("main.shd", "import util;\nexport fn main() i32 { ret $util.f<i32>(1); }\n"),
+		("util.shd", "// pushes the body of f past the end of main.shd\npub fn f<T>(x: T) T { let _y: u8 = x; ret x; }\n"),
+	]);
+	let out = sharc(&file, &["run"]);
+	assert_eq!(out.status.code(), Some(1));
+
+	// the instance is reported in the file of the generic, not the one calling it
+	let err = stderr(&out);
+	assert!(err.contains("util.shd:2:27"), "{err}");
+	assert!(err.contains("In 'util.f<i32>'"), "{err}");
+}
+
+#[test]
+fn unsupported_on_target() {
+	let src = "struct P { x: i32 }\nexport fn main() i32 {\n\tlet p = P { x: 3 };\n\tret p.x;\n}\n";
+
+	for target in ["qbe", "x86_64", "wasm"] {
+		let out = build(&format!("unsupported-{target}"), src, target);
+		assert_no_panic(&out);
+		assert_eq!(out.status.code(), Some(1));
+
+		// what a backend cannot lower is reported at the declaration it is in
+		let err = stderr(&out);
+		assert!(err.contains(&format!("Structs are not yet supported on the {target} target")), "{err}");
+		assert!(err.contains("main.shd:1:8") && err.contains("main.shd:2:11"), "{err}");
+	}
+}
+
+#[test]
+fn structs() {
+	// nested structs are passed and returned by value
+	let src = "struct V { x: i32, y: i32 }\nstruct R { min: V, max: V, tag: u8 }\n\
+		fn area(r: R) i32 { ret (r.max.x - r.min.x) * (r.max.y - r.min.y); }\n\
+		fn grow(r: R) R { ret R { min: r.min, max: V { x: r.max.x + 1, y: r.max.y + 1 }, tag: r.tag }; }\n\
+		export fn main() i32 {\n\tlet r = R { min: V { x: 1, y: 2 }, max: V { x: 4, y: 6 }, tag: 9 };\n\
+		\tlet g = $grow(r);\n\tret $area(r) + $area(g) * 10 + g.tag as i32;\n}\n";
+
+	assert_eq!(run("struct", src).status.code(), Some(221));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("struct-{target}"), src, target) { assert_eq!(code, 221, "target {target}"); }
+	}
+
+	let fails = [
+		("struct S { a: i32 }\nfn f() i32 { let s = S { a: 1 }; ret s.b; }", "'S' has no field 'b'"),
+		("struct S { a: i32, b: i32 }\nfn f() { let _s = S { a: 1 }; }", "Missing fields in 'S' literal"),
+		("struct S { a: i32, a: i32 }", "Field 'a' is declared twice"),
+		("struct S { s: S }", "Struct 'S' contains itself"),
+		("fn f() { let _s = T { a: 1 }; }", "Type 'T' is not defined"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("struct-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn enums() {
+	let src = "struct V { x: i32, y: i32 }\nenum Shape { Circle(i32), Rect(V), Big(i64), Dot }\n\
+		fn area(s: Shape) i32 {\n\tmatch s {\n\t\tCircle(r) => ret 3 * r * r,\n\t\tRect(v) => { ret v.x * v.y; },\n\t\tDot => ret 0,\n\t\t_ => ret 1,\n\t}\n}\n\
+		fn big(s: Shape) i32 {\n\tmatch s {\n\t\tBig(b) => if b > 4000000000 { ret 50; },\n\t\tDot => ret 7,\n\t\t_ => ret 8,\n\t}\n\tret 0;\n}\n\
+		export fn main() i32 {\n\tlet a = Shape.Circle(2);\n\tlet b = Shape.Rect(V { x: 2, y: 4 });\n\
+		\tret $area(a) + $area(b) * 10 + $area(Shape.Big(5)) * 100 + $big(Shape.Big(5000000000)) + $big(Shape.Dot);\n}\n";
+
+	assert_eq!(run("enum", src).status.code(), Some(249));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("enum-{target}"), src, target) { assert_eq!(code, 249, "target {target}"); }
+	}
+
+	let fails = [
+		("fn f(e: E) i32 { match e { A => ret 0, } }", "Match on 'E' is not exhaustive"),
+		("fn f(e: E) i32 { match e { C => ret 1, _ => ret 2, } }", "'E' has no variant 'C'"),
+		("fn f() { let _e = E.C; }", "'E' has no variant 'C'"),
+		("fn f(e: E) i32 { match e { A(x) => ret x, _ => ret 0, } }", "Variant 'A' has no value to bind"),
+		("fn f(e: E) i32 { match e { A => ret 0, A => ret 0, _ => ret 1, } }", "Variant 'A' is already matched"),
+		("enum F { A, A }", "Variant 'A' is declared twice"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("enum-err-{i}"), &format!("enum E {{ A, B(i32) }}\n{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn generics() {
+	let src = "struct Pair<A, B> { a: A, b: B }\nenum Maybe<T> { Some(T), None }\n\
+		fn first<A, B>(p: Pair<A, B>) A { ret p.a; }\n\
+		fn max<T>(a: T, b: T) T {\n\tif a > b { ret a; }\n\tret b;\n}\n\
+		fn or<T>(m: Maybe<T>, d: T) T {\n\tmatch m {\n\t\tSome(v) => ret v,\n\t\tNone => ret d,\n\t}\n}\n\
+		fn wrap<T>(v: T) Maybe<T> { ret Maybe.Some(v); }\n\
+		fn count<T>(n: T, acc: i32) i32 {\n\tif n > 0 { ret $count<T>(n - 1, acc + 1); }\n\tret acc;\n}\n\
+		export fn main() i32 {\n\tlet p: Pair<i32, u8> = Pair { a: 4, b: 2 };\n\tlet n: Maybe<i32> = Maybe.None;\n\
+		\tlet w: Maybe<i32> = $wrap(6);\n\tlet big = $max<i64>(5000000000, 2);\n\tif big ~= 5000000000 { ret 1; }\n\
+		\tret $first(p) + $max(3, 7) * 10 + $or(n, 5) * 10 + $or(w, 0) + $count<u8>(3, 0) * 2;\n}\n";
+
+	assert_eq!(run("generic", src).status.code(), Some(136));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("generic-{target}"), src, target) { assert_eq!(code, 136, "target {target}"); }
+	}
+
+	// each instance is a function of its own on every backend
+	let src = "fn max<T>(a: T, b: T) T {\n\tif a > b { ret a; }\n\tret b;\n}\n\
+		export fn main() i32 {\n\tlet x: u8 = $max<u8>(200, 9);\n\tret $max(3, 7) + x as i32;\n}\n";
+
+	assert_eq!(run("generic-fn", src).status.code(), Some(207));
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("generic-fn-{target}"), src, target) { assert_eq!(code, 207, "target {target}"); }
+	}
+
+	let file = project("generic-qbe", &[("main.shd", src)]);
+	let out = sharc(&file, &["-t", "qbe"]);
+	assert!(out.status.success(), "{}", stderr(&out));
+	let il = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	assert!(il.contains("function ub $_ZN3maxIu8EE(") && il.contains("function w $_ZN3maxIi32EE("), "{il}");
+
+	let fails = [
+		("export fn f<T>(x: T) T { ret x; }", "Generic functions cannot be exported"),
+		("fn f<T, T>(x: T) T { ret x; }", "Type parameter 'T' is declared twice"),
+		("fn f<T>(x: T) T { ret x; }\nfn f<T>(x: T) T { ret x; }", "Generic function 'f' is already defined"),
+		("struct S<T> { x: T }\nfn f(_s: S<i32, u8>) {}", "Type 'S' takes 1 type arguments, got 2"),
+		("fn f<T>(x: i32) i32 { ret x; }\nfn g() i32 { ret $f(1); }", "Cannot infer type parameter 'T' of 'f'"),
+		("fn f<T>(x: T) T { ret x; }\nfn g() i32 { ret $f<i32, u8>(1); }", "'f' takes 1 type arguments, got 2"),
+		("fn f<T>(x: T, _y: T) T { ret x; }\nfn g(a: i32, b: u8) i32 { ret $f(a, b); }", "Type mismatch in function call"),
+		// the bodies of instances are not analyzed inside each other, so this does not run out of stack
+		("fn f<T>(x: T) i32 {\n\tif 1 = 1 { while 1 = 1 { if 1 = 1 { ret (($f<*T>(&x) + 1) * 2) + 3; } } }\n\tret 0;\n}\n\
+			fn g() i32 { ret $f(1); }", "'f' is instantiated too deeply"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("generic-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn overloads() {
+	let src = "fn show(x: i32) i32 { ret x; }\nfn show(_x: u8) i32 { ret 20; }\n\
+		fn show(x: i32, y: i32) i32 { ret $show(x + y) * 2; }\nfn pick(_x: u64) i32 { ret 1; }\nfn pick(_x: i8) i32 { ret 2; }\n\
+		export fn main() i32 {\n\tlet a: i32 = 3;\n\tlet b: u8 = 2;\n\
+		\tret $show(a) + $show(b) + $show(a, 4) + $show(1, 1) + $pick(5) * 10 + $pick(-1) * 100;\n}\n";
+
+	assert_eq!(run("overload", src).status.code(), Some(251));
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("overload-{target}"), src, target) { assert_eq!(code, 251, "target {target}"); }
+	}
+
+	// overloads get a symbol mangled from their args, the same on every build
+	let file = project("overload-qbe", &[("main.shd", src)]);
+	let out = sharc(&file, &["-t", "qbe"]);
+	assert!(out.status.success(), "{}", stderr(&out));
+	let il = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	for sym in ["$_Z4showi32(", "$_Z4showu8(", "$_Z4showi32i32(", "$_Z4picku64(", "$_Z4picki8("] {
+		assert!(il.contains(&format!("function w {sym}")), "{sym} in:\n{il}");
+	}
+
+	// a float goes to the float overload, and an int never does
+	let src = "fn show(_x: i32) i32 { ret 1; }\nfn show(_x: f64) i32 { ret 2; }\n\
+		export fn main() i32 {\n\tlet c: f64 = 1.5;\n\tret $show(c) * 10 + $show(2) + $show(2.5) * 100;\n}\n";
+	assert_eq!(run("overload-float", src).status.code(), Some(221));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("overload-float-{target}"), src, target) { assert_eq!(code, 221, "target {target}"); }
+	}
+
+	let fails: [(&str, &[&str]); 5] = [
+		("fn f(x: i32) i32 { ret x; }\nfn f(x: i32) i32 { ret x; }", &["Function 'f' is already defined", "Overloads need different argument types"]),
+		("fn f(_x: i32) {}\nfn f(_x: u8) {}\nfn g() { $f(1.5); }",
+			&["No overload of 'f' takes these arguments", "found '({float})'", "Candidate 'fn f(i32)'", "Candidate 'fn f(u8)'"]),
+		("fn f(_x: i32) {}\nfn f(_x: u8) {}\nfn g() { $f(1, 2); }", &["No overload of 'f' takes these arguments", "found '({uint}, {uint})'"]),
+		("fn f(_x: i16) {}\nfn f(_x: u16) {}\nfn g() { $f(1); }",
+			&["Call to 'f' is ambiguous", "Candidate 'fn f(i16)'", "Candidate 'fn f(u16)'"]),
+		("export fn f(_x: i32) {}\nexport fn f(_x: u8) {}", &["Symbol 'f' is already defined"]),
+	];
+
+	for (i, (src, msgs)) in fails.iter().enumerate() {
+		let out = run(&format!("overload-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		for msg in *msgs { assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out)); }
+	}
+}
+
+#[test]
+fn stores() {
+	let src = "fn sum(n: mut i32) i32 {\n\tlet acc: mut i32 = 0;\n\twhile n > 0 {\n\t\tacc = acc + n;\n\t\tn = n - 1;\n\t}\n\tret acc;\n}\n\
+		export fn main() i32 {\n\tlet x: mut i64 = 5;\n\tx = x * 3;\n\tlet y: mut u8 = 250;\n\ty = y + 10;\n\
+		\tret $sum(10) + x as i32 + y as i32;\n}\n";
+
+	assert_eq!(run("store", src).status.code(), Some(74));
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("store-{target}"), src, target) { assert_eq!(code, 74, "target {target}"); }
+	}
+	let out = build("store-wasm", src, "wasm");
+	assert!(out.status.success(), "{}", stderr(&out));
+
+	// pointer targets and elements are written in place
+	let src = "fn set(p: *mut i32, v: i32) {\n\t@p = v;\n}\n\
+		export fn main() i32 {\n\tlet a: mut i32 = 1;\n\tlet arr: mut [i32:3] = [1, 2, 3];\n\t$set(&a, 40);\n\
+		\tlet q: *mut i32 = &a;\n\t@q = @q + 2;\n\tarr[1] = a;\n\tret a + arr[1] + arr[2];\n}\n";
+
+	assert_eq!(run("store-ptr", src).status.code(), Some(87));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("store-ptr-{target}"), src, target) { assert_eq!(code, 87, "target {target}"); }
+	}
+
+	let fails: [(&str, &[&str]); 6] = [
+		("fn f() { let x: i8 = 1; x = 2; }", &["Cannot assign to 'x', it is not mutable", "'x' is declared here", "Declare it as 'mut i8'"]),
+		("fn f() { let x = 1; x = 2; }", &["Cannot assign to 'x', it is not mutable", "like 'let x: mut u64 = ...'"]),
+		("fn f() { let x: mut i32 = 1; x = 1.5; }", &["Type mismatch in assignment", "expected 'i32', found '{float}'"]),
+		("fn f(p: *i32) { @p = 2; }", &["Cannot write through a '*i32'", "'p' is declared here", "like '*mut i32'"]),
+		("fn g() {}\nfn f() { g = 2; }", &["Cannot assign to 'g'", "Only variables can be assigned to"]),
+		("fn f() { y = 2; }", &["'y' is not defined"]),
+	];
+
+	for (i, (src, msgs)) in fails.iter().enumerate() {
+		let out = run(&format!("store-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		for msg in *msgs { assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out)); }
+	}
+}
+
+#[test]
+fn mutability() {
+	// a pointer that allows writes can be passed where one that does not is expected
+	let src = "fn read(p: *i32) i32 { ret @p; }\nfn bump(p: *mut i32) { @p = @p + 1; }\n\
+		export fn main() i32 {\n\tlet a: mut i32 = 5;\n\t$bump(&a);\n\tret $read(&a);\n}\n";
+	assert_eq!(run("mut", src).status.code(), Some(6));
+
+	let src = "extern fn memset(p: *mut u8, c: i32, n: usize) *mut u8;\nfn read(p: *i32) i32 { ret @p; }\n\
+		fn bump(p: *mut i32) { @p = @p + 1; }\nexport fn main() i32 {\n\tlet a: mut i32 = 5;\n\
+		\tlet buf: mut [u8:4] = [0, 0, 0, 0];\n\t$bump(&a);\n\tlet _ = $memset(&buf as *mut u8, 7, 4);\n\
+		\tret $read(&a) + buf[3] as i32 * 10;\n}\n";
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("mut-{target}"), src, target) { assert_eq!(code, 76, "target {target}"); }
+	}
+
+	// the write, or the use needing one, is reported along with where the value was declared
+	let fails: [(&str, &[&str]); 7] = [
+		("fn w(p: *mut i32) { @p = 1; }\nfn f() { let a: i32 = 0; $w(&a); }",
+			&["Cannot use a '*i32' as a '*mut i32'", "main.shd:2:29", "'a' is declared here", "main.shd:2:14"]),
+		("fn w(p: *mut i32) { @p = 1; }\nfn f(q: *i32) { $w(q); }", &["Cannot use a '*i32' as a '*mut i32'", "'q' is declared here"]),
+		("fn f() { let a: i32 = 0; let _p: *mut i32 = &a; }", &["Cannot use a '*i32' as a '*mut i32'", "'a' is declared here"]),
+		("struct P { x: i32 }\nfn f(q: *P) { q.x = 1; }", &["Cannot write through a '*P'", "'q' is declared here", "like '*mut P'"]),
+		("fn f(q: *[i32:2]) { q[0] = 1; }", &["Cannot write through a '*[i32:2]'", "'q' is declared here"]),
+		("struct P { x: i32 }\nfn f() { let p = P { x: 1 }; p.x = 2; }", &["Cannot assign to a field of 'p', it is not mutable", "'p' is declared here"]),
+		("fn f() { let a: [i32:2] = [1, 2]; a[0] = 3; }", &["Cannot assign to an element of 'a', it is not mutable", "Declare it as 'mut [i32:2]'"]),
+	];
+
+	for (i, (src, msgs)) in fails.iter().enumerate() {
+		let out = run(&format!("mut-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		for msg in *msgs { assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out)); }
+	}
+}
+
+#[test]
+fn optionals() {
+	let src = "fn half(n: i32) opt i32 {\n\tif n % 2 = 1 { ret none; }\n\tret n / 2;\n}\n\
+		fn or(v: opt i32, d: i32) i32 {\n\tif v = none { ret d; }\n\tret v?;\n}\n\
+		fn first(s: *u8) opt *u8 {\n\tif @s = 0 { ret none; }\n\tret s;\n}\n\
+		export fn main() i32 {\n\tlet c: mut opt i32 = none;\n\tif c = none { c = 7; }\n\tlet p = $first(\"ab\\0\");\n\
+		\tif p = none || $first(\"\\0\") ~= none { ret 1; }\n\tret $half(8)? + $or($half(7), 9) * 10 + c? * 20 + @p? as i32 - 97;\n}\n";
+
+	assert_eq!(run("opt", src).status.code(), Some(234));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("opt-{target}"), src, target) { assert_eq!(code, 234, "target {target}"); }
+	}
+
+	// an optional pointer is none when it is null, so it needs no tag
+	let file = project("opt-layout", &[("main.shd", src)]);
+	assert!(sharc(&file, &[]).status.success());
+	let ir = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	assert!(ir.contains("define internal { i8, i32 } @g1(i32 %a1)") && ir.contains("define internal ptr @g3(ptr %a1)"), "{ir}");
+
+	let src = "fn first(s: *u8) opt *u8 {\n\tif @s = 0 { ret none; }\n\tret s;\n}\n\
+		export fn main() i32 {\n\tlet p = $first(\"ab\\0\");\n\tif p = none || $first(\"\\0\") ~= none { ret 1; }\n\tret @p? as i32;\n}\n";
+	for target in ["x86_64", "qbe"] {
+		if let Some(code) = exec(&format!("opt-ptr-{target}"), src, target) { assert_eq!(code, 97, "target {target}"); }
+	}
+
+	// unwrapping a none stops the program
+	let src = "fn f() opt i32 { ret none; }\nexport fn main() i32 { ret $f()?; }\n";
+	let out = run("opt-none", src);
+	assert_eq!(out.status.code(), Some(1));
+	assert!(stderr(&out).contains("Unwrapped a none value"), "{}", stderr(&out));
+	let file = project("opt-none-c", &[("main.shd", src)]);
+	assert!(sharc(&file, &["-t", "c"]).status.success());
+	assert!(std::fs::read_to_string(file.with_extension("out")).unwrap().contains("__builtin_trap"));
+
+	let fails: [(&str, &[&str]); 5] = [
+		("fn f(a: opt i32) i32 { ret a; }", &["Cannot use a 'opt i32' as a 'i32'", "it may be none", "Unwrap it with '?' first"]),
+		("fn f(a: opt i32) i32 { ret a + 1; }", &["Type mismatch in binary expression", "Unwrap optionals with '?' first"]),
+		("fn f(a: i32) i32 { ret a?; }", &["Cannot unwrap a 'i32', it is not optional"]),
+		("fn f() i32 { ret none; }", &["'none' is not a 'i32'", "like 'opt i32'"]),
+		("fn f() { let _a = none; }", &["Cannot infer the type of 'none'"]),
+	];
+
+	for (i, (src, msgs)) in fails.iter().enumerate() {
+		let out = run(&format!("opt-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		for msg in *msgs { assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out)); }
+	}
+}
+
+#[test]
+fn casts() {
+	let src = "export fn main() i32 {\n\tlet big: u32 = 300;\n\tlet neg: i8 = -1;\n\tlet w: i64 = 7;\n\
+		\tlet r: mut i32 = big as u8 as i32 + w as i32;\n\tif neg as i32 = -1 { r = r + 100; }\n\
+		\tif neg as u8 as u32 = 255 { r = r + 50; }\n\tlet p: *u8 = \"xyz\\0\";\n\tlet q = (p as usize + 1) as *u8;\n\
+		\tret r + @q as i32 - 121;\n}\n";
+
+	assert_eq!(run("cast", src).status.code(), Some(201));
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("cast-{target}"), src, target) { assert_eq!(code, 201, "target {target}"); }
+	}
+
+	let float = "export fn main() i32 {\n\tlet f: f64 = 66.75;\n\tlet g = 3 as f32;\n\tlet h: f32 = f as f32;\n\tlet n: i8 = -2;\n\
+		\tret f as i32 + g as i32 + (n as f64 * 2.5) as i32 + h as u8 as i32;\n}\n";
+
+	assert_eq!(run("cast-float", float).status.code(), Some(130));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("cast-float-{target}"), float, target) { assert_eq!(code, 130, "target {target}"); }
+	}
+
+	// each kind of conversion has an instruction of its own
+	let ir: String = [("cast-ir", src), ("cast-float-ir", float)].into_iter().map(|(name, src)| {
+		let file = project(name, &[("main.shd", src)]);
+		assert!(sharc(&file, &[]).status.success());
+		std::fs::read_to_string(file.with_extension("out")).unwrap()
+	}).collect();
+	for op in ["zext", "sext", "trunc", "fptosi", "sitofp", "fptrunc", "ptrtoint", "inttoptr"] {
+		assert!(ir.contains(&format!(" = {op} ")), "{op} in:\n{ir}");
+	}
+
+	let warns = [
+		("fn f(a: i64) i32 { ret a as i32; }", "values that do not fit are truncated"),
+		("fn f(a: f64) f32 { ret a as f32; }", "precision is lost"),
+		("fn f(a: i32) u32 { ret a as u32; }", "negative values wrap around"),
+	];
+
+	for (i, (src, msg)) in warns.iter().enumerate() {
+		let out = run(&format!("cast-warn-{i}"), &format!("{src}\nexport fn main() i32 {{ ret $f(1) as i32; }}\n"));
+		assert_eq!(out.status.code(), Some(1), "'{src}':\n{}", stderr(&out));
+		let err = stderr(&out);
+		assert!(err.contains("may change its value") && err.contains(msg), "'{src}':\n{err}");
+	}
+
+	// widening cannot lose anything
+	let out = run("cast-widen", "fn f(a: u8) i32 { ret a as i32; }\nexport fn main() i32 { ret $f(3); }\n");
+	assert_eq!(out.status.code(), Some(3));
+	assert!(!stderr(&out).contains("LossyCast"), "{}", stderr(&out));
+
+	let fails = [
+		("struct S { x: i32 }\nfn f(s: S) i32 { ret s as i32; }", "Cannot cast a 'S' to a 'i32'"),
+		("fn f(a: f64) *u8 { ret a as *u8; }", "Cannot cast a 'f64' to a '*u8'"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("cast-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn declaration_order() {
+	// functions can be used before they are declared, and call each other
+	let src = "export fn main() i32 {\n\tret $is_even(10) * 100 + $is_odd(7) * 10 + $last();\n}\n\
+		fn is_even(n: i32) i32 {\n\tif n = 0 { ret 1; }\n\tret $is_odd(n - 1);\n}\n\
+		fn is_odd(n: i32) i32 {\n\tif n = 0 { ret 0; }\n\tret $is_even(n - 1);\n}\n\
+		fn last() i32 { ret 4; }\n";
+
+	assert_eq!(run("order", src).status.code(), Some(114));
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("order-{target}"), src, target) { assert_eq!(code, 114, "target {target}"); }
+	}
+	let out = build("order-wasm", src, "wasm");
+	assert!(out.status.success(), "{}", stderr(&out));
+
+	// and so can types, methods and aliases, in any order among themselves
+	let src = "export fn main() i32 {\n\tlet o: Outer = Outer { inner: Inner { v: 5 }, tag: 2 };\n\tlet c = Counter { n: 3 };\n\
+		\tlet n: Num = 6;\n\tret o.inner.v + c.twice() * 10 + $area(Shape.Square(4)) + n;\n}\n\
+		impl Counter {\n\tfn twice(self: Counter) i32 { ret self.once() * 2; }\n\tfn once(self: Counter) i32 { ret self.n; }\n}\n\
+		type Num = i32;\nstruct Counter { n: i32 }\nstruct Outer { inner: Inner, tag: i32 }\nstruct Inner { v: i32 }\n\
+		fn area(s: Shape) i32 {\n\tmatch s {\n\t\tSquare(n) => ret n * n,\n\t\tDot => ret 0,\n\t}\n}\n\
+		enum Shape { Square(i32), Dot }\n";
+
+	assert_eq!(run("order-types", src).status.code(), Some(87));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("order-types-{target}"), src, target) { assert_eq!(code, 87, "target {target}"); }
+	}
+
+	let fails = [
+		("fn f() i32 { ret $g(); }", "Function 'g' is not defined"),
+		("fn f() { let _a: T = 1; }", "Type 'T' is not defined"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("order-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn modules() {
+	let files = [
+		("main.shd", "import geo.shapes;\nimport util;\nimport consts;\n\
+			export fn main() i32 {\n\tlet p: shapes.Point = shapes.Point { x: 3, y: -4 };\n\
+			\tlet s: shapes.Shape = shapes.Shape.Square(5);\n\
+			\tret $shapes.manhattan(p) + $util.twice(2) * 10 + $shapes.area(s) + $consts.ten() + p.sum() + $add(1, 1);\n}\n\
+			fn add(a: i32, b: i32) i32 { ret a * b; }\n"),
+		("util.shd", "pub fn twice(x: i32) i32 { ret $add(x, x); }\nfn add(a: i32, b: i32) i32 { ret a + b; }\n"),
+		("geo/shapes.shd", "import inner;\npub struct Point { x: i32, y: i32 }\n\
+			impl Point {\n\tfn sum(self: Point) i32 { ret self.x + self.y; }\n}\npub enum Shape { Square(i32), Dot }\n\
+			pub fn manhattan(p: Point) i32 { ret $inner.abs(p.x) + $inner.abs(p.y); }\n\
+			pub fn area(s: Shape) i32 {\n\tmatch s {\n\t\tSquare(n) => ret $sq(n),\n\t\tDot => ret 0,\n\t}\n}\n\
+			fn sq(n: i32) i32 { ret n * n; }\n"),
+		("geo/inner.shd", "pub fn abs(x: i32) i32 {\n\tif x < 0 { ret 0 - x; }\n\tret x;\n}\n"),
+		("lib/consts.shd", "pub fn ten() i32 { ret 10; }\n"),
+	];
+
+	// modules are found next to the importer and on the include path, and private names do not clash
+	let file = project("modules", &files);
+	let lib = file.with_file_name("lib");
+	let include = ["-I", lib.to_str().unwrap()];
+
+	let out = sharc(&file, &[&include[..], &["run"]].concat());
+	assert_eq!(out.status.code(), Some(82), "{}", stderr(&out));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec_file(&file, target, &include) { assert_eq!(code, 82, "target {target}"); }
+	}
+
+	let file = project("modules-scalar", &[
+		("main.shd", "import m;\nexport fn main() i32 { ret $m.pick(1) + $m.pick(1, 2) * 10 + $m.id<i32>(6) + $m.twice(3) + $twice(); }\n\
+			fn twice() i32 { ret 100; }\n"),
+		("m.shd", "pub fn pick(a: i32) i32 { ret a; }\npub fn pick(_a: i32, b: i32) i32 { ret b; }\n\
+			pub fn id<T>(x: T) T { ret x; }\npub fn twice(x: i32) i32 { ret x * 2; }\n"),
+	]);
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec_file(&file, target, &[]) { assert_eq!(code, 133, "target {target}"); }
+	}
+	assert!(sharc(&file, &["-t", "wasm"]).status.success());
+
+	let m = "fn hidden() i32 { ret 1; }\npub fn shown() i32 { ret 2; }\nstruct Priv { x: i32 }\n";
+	let fails: [(&str, &[&str]); 5] = [
+		("import m;\nexport fn main() i32 { ret $m.hidden(); }", &["'hidden' is private to module 'm'", "Mark it 'pub'"]),
+		("import m;\nexport fn main() i32 { let _p = m.Priv { x: 1 }; ret 0; }", &["'Priv' is private to module 'm'"]),
+		("import m;\nexport fn main() i32 { ret $m.gone(); }", &["Module 'm' has no 'gone'"]),
+		("import nope;\nexport fn main() i32 { ret 0; }", &["Module 'nope' not found", "Looked for 'nope.shd' next to this file"]),
+		("import m;\nimport m;\nexport fn main() i32 { ret $m.shown(); }", &["'m' is already imported"]),
+	];
+
+	for (i, (src, msgs)) in fails.iter().enumerate() {
+		let out = sharc(&project(&format!("modules-err-{i}"), &[("main.shd", src), ("m.shd", m)]), &["run"]);
+		assert_no_panic(&out);
+		assert_eq!(out.status.code(), Some(1));
+		for msg in *msgs { assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out)); }
+	}
+}
+
+#[test]
+fn return_paths() {
+	// a call to a 'never' function ends its path like a 'ret' does
+	let src = "extern fn exit(code: i32) never;\n\
+		fn check(a: i32) i32 {\n\tif a > 3 { ret a; }\n\t$exit(40 + a);\n}\n\
+		fn fail(code: i32) never {\n\t$exit(code);\n}\n\
+		fn pick(a: i32) i32 {\n\tloop {\n\t\tif a > 0 { ret a; }\n\t\t$fail(9);\n\t}\n}\n\
+		export fn main() i32 {\n\tret $check(5) + $pick(3) + $check(2);\n}\n";
+
+	assert_eq!(run("never", src).status.code(), Some(42));
+	for target in ["llvm", "qbe", "c", "x86_64"] {
+		if let Some(code) = exec(&format!("never-{target}"), src, target) { assert_eq!(code, 42, "target {target}"); }
+	}
+
+	let file = project("never-ir", &[("main.shd", src)]);
+	assert!(sharc(&file, &[]).status.success());
+	let ir = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	assert!(ir.contains("declare void @exit(i32) noreturn") && ir.contains("(i32 %a1) noreturn {"), "{ir}");
+
+	let fails: [(&str, &[&str]); 4] = [
+		("fn f(a: i32) i32 { if a > 0 { ret 1; } }", &["Function 'f' does not return on every path", "expected to return 'i32'"]),
+		("fn f(a: i32) i32 { while a > 0 { ret 1; } }", &["Function 'f' does not return on every path"]),
+		("fn f() never { ret; }", &["Cannot return from a function returning 'never'"]),
+		("fn f() never { }", &["Function 'f' can reach its end", "it returns 'never'"]),
+	];
+
+	for (i, (src, msgs)) in fails.iter().enumerate() {
+		let out = run(&format!("never-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		for msg in *msgs { assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out)); }
+	}
+
+	// code after the end of a path is only warned about
+	let warns = [
+		"fn f() i32 {\n\tret 1;\n\tlet _x = 2;\n}\n",
+		"extern fn abort() never;\nfn f() i32 {\n\t$abort();\n\tret 1;\n}\n",
+	];
+
+	for (i, src) in warns.iter().enumerate() {
+		let out = run(&format!("never-warn-{i}"), &format!("{src}export fn main() i32 {{ ret 0; }}\n"));
+		let err = stderr(&out);
+		assert_eq!(out.status.code(), Some(0), "{err}");
+		assert!(err.contains("Unreachable code") && err.contains("main.shd:3:"), "{err}");
+	}
+}
+
+#[test]
+fn field_stores() {
+	let src = "struct P { x: i32, y: i32 }\nfn bump(p: *mut P) { p.y = p.y + 2; }\nexport fn main() i32 {\n\tlet p: mut P = P { x: 1, y: 2 };\n\tp.x = p.x + 10;\n\t$bump(&p);\n\tret p.x + p.y;\n}\n";
+	assert_eq!(run("field-store-ptr", src).status.code(), Some(15));
+	for target in ["llvm", "c"] {
+		let out = build(&format!("field-store-{target}"), src, target);
+		assert!(out.status.success(), "target {target}:\n{}", stderr(&out));
+	}
+
+	let src = "struct P { x: i32 }\nexport fn main() i32 {\n\tlet p: mut P = P { x: 1 };\n\tp.x = p.x + 10;\n\tret p.x;\n}\n";
+	assert_eq!(run("field-store", src).status.code(), Some(11));
+
+	// only mutable structs, and pointers to them, can be written to
+	let src = "struct P { x: i32 }\nexport fn main() i32 {\n\tlet p = P { x: 1 };\n\tlet q: *P = &p;\n\tq.x = 2;\n\tret p.x;\n}\n";
+	let out = run("field-store-const", src);
+	assert_no_panic(&out);
+	assert!(stderr(&out).contains("Cannot write through a '*P'"), "{}", stderr(&out));
+}
+
+#[test]
+fn addressed_locals() {
+	// locals are read and written through pointers to them, also after returning into a deeper call
+	let src = "struct P { x: u8, y: i64 }\nstruct Q { p: P, a: [u16:3] }\n\
+		fn bump(p: *mut P) { p.y = p.y + 2; p.x = p.x + 1; }\nfn set(n: *mut i32, v: i32) { @n = v; }\n\
+		fn rec(n: i32) i32 {\n\tlet x: mut i32 = 0;\n\t$set(&x, n);\n\tif n > 0 { ret x + $rec(n - 1); }\n\tret x;\n}\n\
+		fn sumq(q: *Q) i32 { ret q.p.x as i32 + q.a[2] as i32; }\n\
+		export fn main() i32 {\n\tlet p: mut P = P { x: 1, y: 2 };\n\t$bump(&p);\n\t$bump(&p);\n\tlet n: mut i32 = 3;\n\t$set(&n, n + 4);\n\
+		\tlet q: mut Q = Q { p: p, a: [5, 6, 7] };\n\tlet qp = &q;\n\tq.a = [5, 6, 9];\n\
+		\tret (p.x as i32 + p.y as i32 * 10 + n * 100 + $rec(4) + $sumq(qp) * 1000) % 256;\n}\n";
+
+	// enums and optionals keep their tag in memory
+	let tagged = "enum Shape { Circle(i32), Rect(i64), Dot }\n\
+		fn area(s: *Shape) i32 {\n\tmatch @s {\n\t\tCircle(r) => ret r,\n\t\tRect(v) => ret v as i32,\n\t\tDot => ret 0,\n\t}\n}\n\
+		fn or(v: *opt i16, d: i32) i32 {\n\tlet o = @v;\n\tif o = none { ret d; }\n\tret o? as i32;\n}\n\
+		fn put(o: *mut opt i16, v: i16) { @o = v; }\n\
+		export fn main() i32 {\n\tlet a: mut Shape = Shape.Rect(40);\n\tlet r = $area(&a);\n\ta = Shape.Circle(3);\n\
+		\tlet o: mut opt i16 = none;\n\tlet n = $or(&o, 5);\n\t$put(&o, 7);\n\tlet arr: [Shape:2] = [Shape.Dot, Shape.Circle(100)];\n\
+		\tret r + $area(&a) + n + $or(&o, 0) * 10 + $area(&arr[1]);\n}\n";
+
+	for (name, src, code) in [("addr", src, 229), ("addr-tagged", tagged, 218)] {
+		let out = run(name, src);
+		assert_eq!(out.status.code(), Some(code), "{name}:\n{}", stderr(&out));
+		for target in ["llvm", "c"] {
+			let out = build(&format!("{name}-{target}"), src, target);
+			assert!(out.status.success(), "{name} on {target}:\n{}", stderr(&out));
+		}
+	}
+}
+
+#[test]
+fn type_aliases() {
+	// aliases name other aliases, and can be used before they are declared
+	let src = "type Num = Int;\nfn twice(n: Num) Int { ret n * 2; }\ntype Int = i32;\ntype Ref = *Num;\n\
+		export fn main() i32 {\n\tlet x: Num = 21;\n\tlet p: Ref = &x;\n\tret $twice(@p);\n}\n";
+
+	assert_eq!(run("alias", src).status.code(), Some(42));
+	for target in ["llvm", "c"] {
+		let out = build(&format!("alias-{target}"), src, target);
+		assert!(out.status.success(), "target {target}:\n{}", stderr(&out));
+	}
+
+	// a cycle is reported once, where it is declared rather than where it is used
+	let src = "type A = [B:2];\ntype B = opt A;\nstruct S { a: A }\nfn f(_b: B) {}\nexport fn main() i32 { ret 0; }\n";
+	let out = run("alias-cycle", src);
+	assert_no_panic(&out);
+	assert_eq!(stderr(&out).matches("expands to itself").count(), 1, "{}", stderr(&out));
+	assert!(stderr(&out).contains("'A' -> 'B' -> 'A'"), "{}", stderr(&out));
+	assert!(stderr(&out).contains("Type alias 'A' cannot be used"), "{}", stderr(&out));
+
+	let out = run("alias-builtin", "type i32 = u8;\nexport fn main() i32 { ret 0; }\n");
+	assert_no_panic(&out);
+	assert!(stderr(&out).contains("Type 'i32' is already defined"), "{}", stderr(&out));
+}
+
+#[test]
+fn methods() {
+	// a variable is passed by its address to a method taking a pointer
+	let src = "struct P { x: i32 }\nimpl P {\n\tfn get() i32 { ret 4; }\n\tfn inc(p: *mut P) { p.x = p.x + 1; }\n\tfn sum(p: P) i32 { ret p.x; }\n}\n\
+		export fn main() i32 {\n\tlet p: mut P = P { x: 1 };\n\tp.inc();\n\t$P.inc(&p);\n\tret $P.get() + p.sum();\n}\n";
+
+	assert_eq!(run("method", src).status.code(), Some(7));
+	for target in ["llvm", "c"] {
+		let out = build(&format!("method-{target}"), src, target);
+		assert!(out.status.success(), "target {target}:\n{}", stderr(&out));
+	}
+
+	let fails = [
+		("let p = P { x: 1 };\n\tret p.get();", "Call it as '$P.get(...)'"),
+		("let p = P { x: 1 };\n\tp.inc();\n\tret 0;", "Cannot use a '*P' as a '*mut P'"),
+		("$mk().inc();\n\tret 0;", "Store it in a variable first"),
+	];
+
+	for (i, (body, msg)) in fails.iter().enumerate() {
+		let src = format!("struct P {{ x: i32 }}\nimpl P {{\n\tfn get() i32 {{ ret 4; }}\n\tfn inc(p: *mut P) {{ p.x = p.x + 1; }}\n}}\n\
+			fn mk() P {{ ret P {{ x: 1 }}; }}\nexport fn main() i32 {{\n\t{body}\n}}\n");
+		let out = run(&format!("method-err-{i}"), &src);
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{body}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn unused_expressions() {
+	for (i, stmt) in ["5;", "x;", "x + 1;", "$f() + 1;"].into_iter().enumerate() {
+		let src = format!("fn f() i32 {{ ret 1; }}\nexport fn main() i32 {{\n\tlet x: i32 = $f();\n\t{stmt}\n\tret x;\n}}\n");
+		let out = run(&format!("unused-expr-{i}"), &src);
+		assert_no_panic(&out);
+		assert_eq!(out.status.code(), Some(1));
+
+		let err = stderr(&out);
+		assert!(err.contains("Expression result is unused") && err.contains("main.shd:4:2"), "{err}");
+	}
+}
+
+#[test]
+fn c_backend() {
+	let src = "struct P { x: i32, y: f64 }\nenum E { A(i32), B }\n\
+		fn pick(e: E) i32 {\n\tmatch e {\n\t\tA(v) => ret v,\n\t\tB => ret 1,\n\t}\n}\n\
+		fn half(n: i32) opt i32 {\n\tif n % 2 = 1 { ret none; }\n\tret n / 2;\n}\n\
+		export fn main() i32 {\n\tlet p = P { x: 3, y: 2.5 };\n\tlet a: [i32:3] = [4, 5, 6];\n\tlet h = $half(8);\n\
+		\tlet r: mut i32 = p.x + (p.y * 2.0) as i32 + a[2] + $pick(E.A(10)) + $pick(E.B);\n\
+		\tif h ~= none { r = r + h?; }\n\tif $half(7) = none { r = r + 100; }\n\tret r;\n}\n";
+
+	assert_eq!(run("c", src).status.code(), Some(129));
+	if let Some(code) = exec("c-run", src, "c") { assert_eq!(code, 129); }
+
+	let fails = [
+		("fn f(a: f64) f64 { ret a % 2.0; }", "Floating point remainder is not supported on the c target"),
+		("fn f(a: u128) u128 { ret a; }", "bit width for integer on the c target"),
+		("fn f(a: f16) f16 { ret a; }", "bit width for float on the c target"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = build(&format!("c-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"), "c");
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}
+
+#[test]
+fn c_signed_overflow_wraps() {
+	let src = "fn wraps(x: i32) b8 { ret x + 1 < x; }\nfn mulw(a: u16, b: u16) u16 { ret a * b; }\nexport fn main() i32 {\n\tlet r: mut i32 = 0;\n\tif $wraps(2147483647) { r = r + 1; }\n\tif $mulw(65535, 65535) = 1 { r = r + 2; }\n\tret r;\n}\n";
+	assert_eq!(run("wrap", src).status.code(), Some(3));
+
+	let file = project("wrap-c", &[("main.shd", src)]);
+	let out = sharc(&file, &["-t", "c"]);
+	assert!(out.status.success(), "{}", stderr(&out));
+
+	// the arithmetic is done unsigned, where overflowing is defined
+	let c = std::fs::read_to_string(file.with_extension("out")).unwrap();
+	assert!(c.contains("(int32_t)((uint32_t)"), "{c}");
+
+	// an optimizing compiler would otherwise assume that `x + 1 < x` never holds
+	let exe = file.with_extension("exe");
+	let Ok(cc) = Command::new("cc").args(["-O2", "-w", "-o"]).arg(&exe).arg("-x").arg("c").arg(file.with_extension("out")).status()
+		else { return };
+	assert!(cc.success());
+	assert_eq!(Command::new(&exe).status().unwrap().code(), Some(3));
+}
+
+#[test]
+fn pointer_arithmetic() {
+	// indexing, offsetting from either side, subtracting and ordering pointers
+	let src = "fn len(start: *u8) isize {\n\tlet end: mut *u8 = start;\n\twhile @end ~= 0 { end = 1 + end; }\n\tret end - start;\n}\n\
+		fn count(s: *u8, c: u8) i32 {\n\tlet n: mut i32 = 0;\n\tlet i: mut usize = 0;\n\twhile s[i] ~= 0 {\n\t\tif s[i] = c { n = n + 1; }\n\t\ti = i + 1;\n\t}\n\tret n;\n}\n\
+		export fn main() i32 {\n\tlet s: *u8 = \"hello world\\0\";\n\tlet e = s + 4;\n\tlet r: mut i32 = $count(s, 'o') + $len(s) as i32 * 10;\n\
+		\tif s < e { r = r + 100; }\n\tif e <= s { r = r + 1000; }\n\tret r + ($len(e) - $len(s)) as i32;\n}\n";
+
+	assert_eq!(run("ptr", src).status.code(), Some(208));
+	for target in TARGETS {
+		let out = build(&format!("ptr-{target}"), src, target);
+		assert!(out.status.success(), "target {target}:\n{}", stderr(&out));
+		if let Some(code) = exec(&format!("ptr-run-{target}"), src, target) { assert_eq!(code, 208, "target {target}"); }
+	}
+
+	// pointers into an array write to its elements
+	let src = "fn fill(p: *mut i32, n: usize) {\n\tlet i: mut usize = 0;\n\twhile i < n {\n\t\tp[i] = 5;\n\t\ti = i + 1;\n\t}\n}\n\
+		export fn main() i32 {\n\tlet a: mut [i32:4] = [1, 2, 3, 4];\n\t$fill(&a[1], 2);\n\tret a[0] + a[1] + a[2] + a[3] + (&a[3] - &a[0]) as i32;\n}\n";
+	assert_eq!(run("ptr-elem", src).status.code(), Some(18));
+	for target in ["llvm", "c"] {
+		if let Some(code) = exec(&format!("ptr-elem-{target}"), src, target) { assert_eq!(code, 18, "target {target}"); }
+	}
+
+	let fails = [
+		("fn f(p: *void) { let _a = p[0]; }", "Cannot index a '*void'"),
+		("fn f(p: *i32) { p[0] = 1; }", "Cannot write through a '*i32'"),
+		("fn f(p: *i32, q: *u8) { let _a = p - q; }", "Only pointers to the same type can be subtracted"),
+		("fn f(p: *void) { let _a = p - p; }", "Cannot subtract a '*void'"),
+		("fn g() [i32:2] { ret [1, 2]; }\nfn f() { let _a = &$g()[0]; }", "Cannot take the address of an element of a temporary array"),
+		("fn f(a: i32) i32 { ret @a; }", "Cannot dereference a 'i32', it is not a pointer"),
+		("fn f(a: i32) { @a = 1; }", "Cannot dereference a 'i32', it is not a pointer"),
+		("fn g() i32 { ret 1; }\nfn f() { let _p = &$g(); }", "Cannot take the address of a temporary value"),
+		("fn f(p: *i32) { let _a: *u8 = p; }", "expected '*u8', found '*i32'"),
+	];
+
+	for (i, (src, msg)) in fails.iter().enumerate() {
+		let out = run(&format!("ptr-err-{i}"), &format!("{src}\nexport fn main() i32 {{ ret 0; }}\n"));
+		assert_no_panic(&out);
+		assert!(stderr(&out).contains(msg), "'{src}':\n{}", stderr(&out));
+	}
+}